@@ -1,52 +1,209 @@
 // Copyright (c) 2025 Kevin Thomas
 // Licensed under the MIT License. See LICENSE file in the project root for full license information.
 
-//! Build script for STM32F303RE embedded project
+//! Build script for the STM32 Nucleo blink firmware
 //!
 //! This build script handles the linker memory configuration at compile time by:
-//! - Copying the `memory.x` linker script to the build output directory
-//! - Adding the output directory to the linker search path
+//! - Checking that exactly one `board-*` Cargo feature is enabled
+//! - Parsing that board's `memory/<board>.toml` descriptor
+//! - Validating it against the selected chip (sizes, erase boundaries, overlaps)
+//! - Generating `memory.x` and the Rust partition constants in the build output directory
+//! - With the `bootloader` feature, a second `memory.x` for the serial bootloader
+//! - Recording the git commit, build time, profile and features for `blink::build_info`
+//! - Adding the output directories to each binary's linker search path
+//! - Asking the linker for a per-region memory usage report
 //! - Configuring incremental rebuild behavior
 //!
 //! # Purpose
-//! The `memory.x` file defines the memory layout for the STM32F303RE microcontroller,
-//! specifying FLASH and RAM regions that the linker uses to place code and data.
-//! This script ensures the linker can find this critical configuration file.
+//! Each descriptor declares the FLASH, RAM and optional CCMRAM of one Nucleo
+//! board, plus the flash it reserves for persistent settings and a bootloader.
+//! The generated `memory.x` tells the linker where to place code and data, and
+//! `layout.rs` gives the firmware the same partition offsets, so the two can
+//! never disagree. Any inconsistency fails the build with a clear message.
+//! The `[bootloader]` section only applies with the `bootloader` feature;
+//! without it the application keeps the whole flash before the settings.
+//! `build_info.rs` identifies the build; the firmware keeps it in its own flash
+//! section. The build time honours `SOURCE_DATE_EPOCH` for reproducible builds.
 //!
 //! # Cargo Integration
 //! - Runs automatically before each build
-//! - Only re-runs when `memory.x` changes (for build efficiency)
+//! - Only re-runs when the selected board, the `memory/` files, the sources,
+//!   `Cargo.toml` or the git state (commit, branch, index) change
 //! - Communicates with Cargo via `cargo:` directives
 
 use std::env;
-use std::fs::File;
-use std::io::Write;
+use std::fs;
 use std::path::PathBuf;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[path = "memory/layout.rs"]
+#[allow(dead_code)]
+mod layout;
+
+use layout::{Descriptor, Layout};
+
+/// Supported boards: Cargo feature suffix, chip and descriptor under `memory/`
+const BOARDS: &[(&str, &str, &str)] = &[
+    ("F303RE", "STM32F303RE", "memory/f303re.toml"),
+    ("F401RE", "STM32F401RE", "memory/f401re.toml"),
+    ("L476RG", "STM32L476RG", "memory/l476rg.toml"),
+    ("G431RB", "STM32G431RB", "memory/g431rb.toml"),
+];
 
 /// Build script entry point
 ///
 /// Performs the following tasks:
-/// 1. Copies `memory.x` to the build output directory
-/// 2. Instructs Cargo to add the output directory to linker search paths
-/// 3. Configures rebuild triggers to only watch `memory.x`
+/// 1. Finds the enabled `board-*` feature
+/// 2. Parses and validates its memory descriptor
+/// 3. Writes `memory.x` and `layout.rs` to the build output directory, and
+///    the bootloader's `memory.x` to `bootloader/` under it
+/// 4. Writes `build_info.rs` (see `build_info`)
+/// 5. Instructs Cargo to add those directories to each binary's linker search
+///    path and, for the board, to print the memory usage report at link time
+/// 6. Configures rebuild triggers to only watch `memory/layout.rs` and the
+///    board descriptors, `src/`, `Cargo.toml`, `SOURCE_DATE_EPOCH` and the git
+///    `HEAD`, index and current branch
 ///
 /// # Panics
 /// Panics if:
+/// - No `board-*` feature, or more than one, is enabled
+/// - The board's descriptor cannot be read, does not parse or fails validation
+/// - The `bootloader` feature is enabled for a board without a bootloader `slot`
 /// - `OUT_DIR` environment variable is not set (should never happen in normal builds)
-/// - Unable to create or write to `memory.x` in the output directory
+/// - Unable to write the generated files in the output directory
 fn main() {
-    // Put `memory.x` in our output directory and ensure it's
-    // on the linker search path.
+    // Cargo exposes every enabled feature as `CARGO_FEATURE_<NAME>`
+    let selected: Vec<&(&str, &str, &str)> = BOARDS
+        .iter()
+        .filter(|(board, _, _)| env::var_os(format!("CARGO_FEATURE_BOARD_{board}")).is_some())
+        .collect();
+    let (chip, path) = match selected.as_slice() {
+        [(_, chip, path)] => (chip, path),
+        [] => panic!(
+            "no board selected: enable one of the features board-f303re, board-f401re, \
+             board-l476rg or board-g431rb"
+        ),
+        _ => panic!(
+            "several boards selected ({}): build with `--no-default-features --features board-<name>`",
+            selected
+                .iter()
+                .map(|(board, _, _)| board.to_ascii_lowercase())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+
+    // Parse and validate the descriptor against the selected chip
+    // (the bootloader partitions only count when the bootloader is built)
+    let text = fs::read_to_string(path).unwrap_or_else(|err| panic!("cannot read {path}: {err}"));
+    let bootloader = env::var_os("CARGO_FEATURE_BOOTLOADER").is_some();
+    let layout = Descriptor::parse(&text)
+        .map(|mut descriptor| {
+            if !bootloader {
+                descriptor.bootloader = 0;
+                descriptor.slot = None;
+            }
+            descriptor
+        })
+        .and_then(|descriptor| Layout::new(&descriptor, chip))
+        .unwrap_or_else(|err| panic!("{path}: {err}"));
+    if bootloader && layout.update.is_none() {
+        panic!("{path}: the bootloader feature needs `slot` in the [bootloader] section");
+    }
+
+    // Put the generated linker scripts in our output directory as `memory.x`,
+    // one directory per binary, and put each on that binary's search path
+    // (a shared search path would let either script shadow the other).
     let out = &PathBuf::from(env::var_os("OUT_DIR").unwrap());
-    File::create(out.join("memory.x"))
-        .unwrap()
-        .write_all(include_bytes!("memory.x"))
-        .unwrap();
-    println!("cargo:rustc-link-search={}", out.display());
+    fs::write(out.join("memory.x"), layout.memory_x(path)).unwrap();
+    fs::write(out.join("layout.rs"), layout.constants(path)).unwrap();
+    fs::write(out.join("build_info.rs"), build_info()).unwrap();
+    println!(
+        "cargo:rustc-link-arg-bin=stm32f303re-blink=-L{}",
+        out.display()
+    );
+    if bootloader {
+        let dir = out.join("bootloader");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("memory.x"), layout.bootloader_memory_x(path)).unwrap();
+        println!("cargo:rustc-link-arg-bin=bootloader=-L{}", dir.display());
+    }
+
+    // Have the linker print how much of each MEMORY region the firmware uses
+    // (shown through the `linker_messages` lint enabled in `src/main.rs`)
+    if env::var("CARGO_CFG_TARGET_OS").as_deref() == Ok("none") {
+        println!("cargo:rustc-link-arg-bins=--print-memory-usage");
+    }
 
     // By default, Cargo will re-run a build script whenever
-    // any file in the project changes. By specifying `memory.x`
+    // any file in the project changes. By specifying the descriptors
     // here, we ensure the build script is only re-run when
-    // `memory.x` is changed.
-    println!("cargo:rerun-if-changed=memory.x");
+    // one of them changes (feature changes always re-run it).
+    println!("cargo:rerun-if-changed=memory/layout.rs");
+    for (_, _, path) in BOARDS {
+        println!("cargo:rerun-if-changed={path}");
+    }
+
+    // The build info also goes stale when the sources change (dirty flag)
+    // or a commit is made or checked out
+    println!("cargo:rerun-if-changed=src");
+    println!("cargo:rerun-if-changed=Cargo.toml");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    if let Some(git_dir) = git(&["rev-parse", "--git-dir"]) {
+        println!("cargo:rerun-if-changed={git_dir}/HEAD");
+        println!("cargo:rerun-if-changed={git_dir}/index");
+        if let Some(head) = git(&["symbolic-ref", "-q", "HEAD"]) {
+            println!("cargo:rerun-if-changed={git_dir}/{head}");
+        }
+    }
+}
+
+/// Renders the `blink::build_info::BuildInfo` expression for this build
+///
+/// Outside a git checkout (or without `git`) the commit is `unknown` and the
+/// build counts as clean. Untracked files do not make the tree dirty.
+///
+/// # Returns
+/// A `BuildInfo::new(...)` call, included by `BuildInfo::CURRENT`
+fn build_info() -> String {
+    let commit = git(&["rev-parse", "HEAD"]).unwrap_or_else(|| "unknown".into());
+    let dirty = git(&["status", "--porcelain", "--untracked-files=no"])
+        .is_some_and(|changes| !changes.is_empty());
+
+    // Reproducible builds pin the time with `SOURCE_DATE_EPOCH`
+    let timestamp = match env::var("SOURCE_DATE_EPOCH") {
+        Ok(value) => value
+            .trim()
+            .parse()
+            .unwrap_or_else(|_| panic!("SOURCE_DATE_EPOCH is not a number: {value:?}")),
+        Err(_) => SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs()),
+    };
+
+    // Cargo exposes features as `CARGO_FEATURE_BOARD_F303RE`; report `board-f303re`
+    let mut features: Vec<String> = env::vars()
+        .filter_map(|(name, _)| {
+            let feature = name.strip_prefix("CARGO_FEATURE_")?;
+            (feature != "DEFAULT").then(|| feature.to_ascii_lowercase().replace('_', "-"))
+        })
+        .collect();
+    features.sort();
+
+    format!(
+        "BuildInfo::new({:?}, {commit:?}, {dirty}, {timestamp}, {:?}, {:?})\n",
+        env::var("CARGO_PKG_VERSION").unwrap(),
+        env::var("PROFILE").unwrap(),
+        features.join(",")
+    )
+}
+
+/// Runs `git` with `args` and returns its trimmed output, if it succeeded
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_owned())
 }
@@ -0,0 +1,152 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Decoding of the firmware's build metadata
+//!
+//! The firmware keeps a `BuildInfo` record in its `.build_info` flash section
+//! (see `blink::build_info` in the firmware crate for the layout):
+//! - `BuildInfo::decode` - parses the section bytes
+//! - `BuildInfo::from_elf` - finds and parses the section in a firmware ELF
+//!
+//! # Design Philosophy
+//! Like the telemetry decoder, this does not link the firmware library: it
+//! reads the documented byte layout, so it keeps working on images built from
+//! other revisions. Newer format versions only append fields, so they decode
+//! as long as the magic matches.
+
+use std::fmt;
+
+use crate::elf::{self, ElfError};
+
+/// Name of the firmware's build metadata section
+pub const SECTION: &str = ".build_info";
+
+/// First four bytes of every record
+pub const MAGIC: [u8; 4] = *b"BLDI";
+
+/// Length of a format version 1 record in bytes
+pub const LEN: usize = 152;
+
+/// Reasons a record could not be decoded
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BuildInfoError {
+    /// The ELF file has no readable `.build_info` section
+    Elf(ElfError),
+    /// Fewer than `LEN` bytes
+    TooShort(usize),
+    /// The record does not start with `MAGIC`
+    Magic,
+    /// Format version `0` (never written by the firmware)
+    Version(u8),
+}
+
+impl fmt::Display for BuildInfoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Elf(err) => err.fmt(f),
+            Self::TooShort(len) => write!(f, "build info is {len} bytes, expected {LEN}"),
+            Self::Magic => f.write_str("build info section does not start with BLDI"),
+            Self::Version(version) => write!(f, "unknown build info format {version}"),
+        }
+    }
+}
+
+impl std::error::Error for BuildInfoError {}
+
+impl From<ElfError> for BuildInfoError {
+    fn from(err: ElfError) -> Self {
+        Self::Elf(err)
+    }
+}
+
+/// Decoded build metadata
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BuildInfo {
+    /// Record format version
+    pub format: u8,
+    /// Package version
+    pub version: String,
+    /// Git commit hash (hex), or `unknown`
+    pub commit: String,
+    /// Whether the working tree had uncommitted changes
+    pub dirty: bool,
+    /// Build time in seconds since the Unix epoch
+    pub timestamp: u64,
+    /// Cargo profile
+    pub profile: String,
+    /// Enabled Cargo features
+    pub features: Vec<String>,
+}
+
+impl BuildInfo {
+    /// Parses a record
+    ///
+    /// # Errors
+    /// `TooShort`, `Magic` or `Version`.
+    pub fn decode(bytes: &[u8]) -> Result<Self, BuildInfoError> {
+        if bytes.len() < LEN {
+            return Err(BuildInfoError::TooShort(bytes.len()));
+        }
+        if bytes[..4] != MAGIC {
+            return Err(BuildInfoError::Magic);
+        }
+        if bytes[4] == 0 {
+            return Err(BuildInfoError::Version(0));
+        }
+        let text = |at: usize, size: usize| {
+            let field = &bytes[at..at + size];
+            let end = field.iter().position(|&b| b == 0).unwrap_or(size);
+            String::from_utf8_lossy(&field[..end]).into_owned()
+        };
+        let mut time = [0u8; 8];
+        time.copy_from_slice(&bytes[8..16]);
+        Ok(Self {
+            format: bytes[4],
+            dirty: bytes[5] & 1 != 0,
+            timestamp: u64::from_le_bytes(time),
+            version: text(16, 16),
+            commit: text(32, 40),
+            profile: text(72, 16),
+            features: text(88, 64)
+                .split(',')
+                .filter(|f| !f.is_empty())
+                .map(str::to_owned)
+                .collect(),
+        })
+    }
+
+    /// Finds and parses the record in a firmware ELF file
+    ///
+    /// # Errors
+    /// `Elf` if the section is missing, otherwise as `decode`.
+    pub fn from_elf(elf: &[u8]) -> Result<Self, BuildInfoError> {
+        Self::decode(elf::section(elf, SECTION)?)
+    }
+
+    /// Returns the build time as an ISO-8601 UTC timestamp
+    pub fn built(&self) -> String {
+        iso8601(self.timestamp)
+    }
+}
+
+/// Formats seconds since 1970-01-01 as an ISO-8601 UTC timestamp
+///
+/// This is the form the firmware prints and `time set` accepts.
+pub fn iso8601(secs: u64) -> String {
+    // Howard Hinnant's `civil_from_days`, counting from 0000-03-01
+    let z = secs / 86_400 + 719_468;
+    let (era, doe) = (z / 146_097, z % 146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    let secs = secs % 86_400;
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
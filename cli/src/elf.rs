@@ -0,0 +1,239 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Just enough ELF to find a section by name and to flatten the flash image
+//!
+//! - `section` walks the section headers of a little-endian ELF32 or ELF64 file
+//!   and returns the file bytes of the named section
+//! - `load_image` lays the loadable segments out at their load addresses, as
+//!   a probe would program them, for `blinkctl update`
+//!
+//! # Design Philosophy
+//! The firmware ELF is only ever read for a handful of bytes or its flash
+//! contents, so this parses the section and program header tables and nothing
+//! else: no symbols, no relocations, no dependency. Every offset read from the
+//! file is bounds-checked, so a truncated or foreign file is an error, never a
+//! panic.
+
+use std::fmt;
+
+/// Section header type of sections that occupy no file space (`.bss`)
+const SHT_NOBITS: u32 = 8;
+
+/// Program header type of loadable segments
+const PT_LOAD: u32 = 1;
+
+/// Largest span `load_image` lays out; firmware images are far smaller
+pub const MAX_IMAGE: u64 = 16 * 1024 * 1024;
+
+/// Reasons a section could not be read
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ElfError {
+    /// The file does not start with the ELF magic
+    NotElf,
+    /// Big-endian or an unknown ELF class
+    Unsupported,
+    /// A header or section points past the end of the file
+    Truncated,
+    /// No section has this name
+    Missing(String),
+    /// The section has no bytes in the file (`NOBITS`)
+    NoData(String),
+    /// No loadable segment has bytes in the file
+    NoSegments,
+    /// The loadable segments span more than `MAX_IMAGE` bytes
+    TooSparse(u64),
+}
+
+impl fmt::Display for ElfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotElf => f.write_str("not an ELF file"),
+            Self::Unsupported => f.write_str("only little-endian ELF32/ELF64 files are supported"),
+            Self::Truncated => f.write_str("truncated ELF file"),
+            Self::Missing(name) => write!(f, "no {name} section"),
+            Self::NoData(name) => write!(f, "{name} section has no data in the file"),
+            Self::NoSegments => f.write_str("no loadable segments"),
+            Self::TooSparse(span) => write!(f, "loadable segments span {span} bytes"),
+        }
+    }
+}
+
+impl std::error::Error for ElfError {}
+
+/// Returns the bytes of the section called `name`
+///
+/// # Arguments
+/// * `elf` - Contents of an ELF file
+/// * `name` - Section name, such as `.build_info`
+///
+/// # Errors
+/// Any `ElfError`.
+pub fn section<'a>(elf: &'a [u8], name: &str) -> Result<&'a [u8], ElfError> {
+    let wide = class(elf)?;
+    let (shoff, shentsize, shnum, shstrndx) = if wide {
+        (
+            read(elf, 0x28, 8)?,
+            read(elf, 0x3A, 2)?,
+            read(elf, 0x3C, 2)?,
+            read(elf, 0x3E, 2)?,
+        )
+    } else {
+        (
+            read(elf, 0x20, 4)?,
+            read(elf, 0x2E, 2)?,
+            read(elf, 0x30, 2)?,
+            read(elf, 0x32, 2)?,
+        )
+    };
+
+    // Section header `index`: (name offset, type, file offset, size)
+    let header = |index: u64| -> Result<(u64, u32, u64, u64), ElfError> {
+        let at = index
+            .checked_mul(shentsize)
+            .and_then(|rel| rel.checked_add(shoff))
+            .ok_or(ElfError::Truncated)?;
+        let entry = bytes(elf, at, shentsize)?;
+        let kind = read(entry, 4, 4)? as u32;
+        let (offset, size) = if wide {
+            (read(entry, 0x18, 8)?, read(entry, 0x20, 8)?)
+        } else {
+            (read(entry, 0x10, 4)?, read(entry, 0x14, 4)?)
+        };
+        Ok((read(entry, 0, 4)?, kind, offset, size))
+    };
+
+    // Names live in the section named by `e_shstrndx`
+    let (_, _, names_at, names_len) = header(shstrndx)?;
+    let names = bytes(elf, names_at, names_len)?;
+    for index in 0..shnum {
+        let (name_at, kind, offset, size) = header(index)?;
+        let tail = names.get(name_at as usize..).ok_or(ElfError::Truncated)?;
+        let end = tail
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or(ElfError::Truncated)?;
+        if &tail[..end] != name.as_bytes() {
+            continue;
+        }
+        if kind == SHT_NOBITS {
+            return Err(ElfError::NoData(name.into()));
+        }
+        return bytes(elf, offset, size);
+    }
+    Err(ElfError::Missing(name.into()))
+}
+
+/// Returns the flash contents of an ELF file and the address they start at
+///
+/// Every `PT_LOAD` segment with bytes in the file is placed at its physical
+/// (load) address, so initialised data lands in flash after the code, where
+/// the startup code copies it from. Gaps between segments are `0xFF`, the
+/// erased flash value.
+///
+/// # Arguments
+/// * `elf` - Contents of an ELF file
+///
+/// # Returns
+/// The lowest load address and the bytes from there to the end of the last
+/// segment.
+///
+/// # Errors
+/// Any `ElfError` but `Missing` and `NoData`.
+pub fn load_image(elf: &[u8]) -> Result<(u32, Vec<u8>), ElfError> {
+    let wide = class(elf)?;
+    let (phoff, phentsize, phnum) = if wide {
+        (
+            read(elf, 0x20, 8)?,
+            read(elf, 0x36, 2)?,
+            read(elf, 0x38, 2)?,
+        )
+    } else {
+        (
+            read(elf, 0x1C, 4)?,
+            read(elf, 0x2A, 2)?,
+            read(elf, 0x2C, 2)?,
+        )
+    };
+
+    // Loadable segments with file bytes: (load address, file offset, size)
+    let mut segments = Vec::new();
+    for index in 0..phnum {
+        let at = index
+            .checked_mul(phentsize)
+            .and_then(|rel| rel.checked_add(phoff))
+            .ok_or(ElfError::Truncated)?;
+        let entry = bytes(elf, at, phentsize)?;
+        if read(entry, 0, 4)? as u32 != PT_LOAD {
+            continue;
+        }
+        let (offset, paddr, filesz) = if wide {
+            (
+                read(entry, 0x08, 8)?,
+                read(entry, 0x18, 8)?,
+                read(entry, 0x20, 8)?,
+            )
+        } else {
+            (
+                read(entry, 0x04, 4)?,
+                read(entry, 0x0C, 4)?,
+                read(entry, 0x10, 4)?,
+            )
+        };
+        if filesz != 0 {
+            segments.push((paddr, bytes(elf, offset, filesz)?));
+        }
+    }
+
+    // Lay them out between the lowest and highest load address
+    let start = segments
+        .iter()
+        .map(|&(paddr, _)| paddr)
+        .min()
+        .ok_or(ElfError::NoSegments)?;
+    let end = segments
+        .iter()
+        .map(|&(paddr, data)| paddr.saturating_add(data.len() as u64))
+        .max()
+        .unwrap_or(start);
+    let base = u32::try_from(start).map_err(|_| ElfError::Unsupported)?;
+    if end - start > MAX_IMAGE {
+        return Err(ElfError::TooSparse(end - start));
+    }
+    let mut image = vec![0xFF; (end - start) as usize];
+    for (paddr, data) in segments {
+        let at = (paddr - start) as usize;
+        image[at..at + data.len()].copy_from_slice(data);
+    }
+    Ok((base, image))
+}
+
+/// Checks the ELF magic and returns `true` for ELF64, `false` for ELF32
+///
+/// Field offsets differ between the 32- and 64-bit headers.
+fn class(elf: &[u8]) -> Result<bool, ElfError> {
+    if elf.get(..4) != Some(b"\x7fELF".as_slice()) {
+        return Err(ElfError::NotElf);
+    }
+    match (elf.get(4), elf.get(5)) {
+        (Some(1), Some(1)) => Ok(false),
+        (Some(2), Some(1)) => Ok(true),
+        _ => Err(ElfError::Unsupported),
+    }
+}
+
+/// Reads a little-endian integer of `len` bytes at `at`
+fn read(data: &[u8], at: u64, len: u64) -> Result<u64, ElfError> {
+    Ok(bytes(data, at, len)?
+        .iter()
+        .rev()
+        .fold(0, |value, &b| value << 8 | u64::from(b)))
+}
+
+/// Returns `len` bytes at `at`
+fn bytes(data: &[u8], at: u64, len: u64) -> Result<&[u8], ElfError> {
+    let start = usize::try_from(at).map_err(|_| ElfError::Truncated)?;
+    let len = usize::try_from(len).map_err(|_| ElfError::Truncated)?;
+    data.get(start..start.checked_add(len).ok_or(ElfError::Truncated)?)
+        .ok_or(ElfError::Truncated)
+}
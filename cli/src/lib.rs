@@ -0,0 +1,26 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Host companion for the STM32F303RE blink firmware (Linux)
+//!
+//! The `blinkctl` binary is a thin argument parser over this library:
+//! - `tty` - raw serial port and pseudo-terminal access
+//! - `protocol` - shell commands, replies and telemetry over any byte stream
+//! - `status` - parsing of the `status` report
+//! - `script` - command sequences with host-side waits
+//! - `elf`, `build_info` - build metadata read from a firmware ELF
+//! - `update` - firmware uploads to the serial bootloader
+//!
+//! # Design Philosophy
+//! Everything that understands the wire format is generic over `Read + Write`,
+//! so it is tested against a simulated board behind a pseudo-terminal rather
+//! than needing hardware. Telemetry frames are decoded by `blink-telemetry`,
+//! the same crate test rigs embed.
+
+pub mod build_info;
+pub mod elf;
+pub mod protocol;
+pub mod script;
+pub mod status;
+pub mod tty;
+pub mod update;
@@ -0,0 +1,256 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! `blinkctl` - talk to the STM32F303RE blink firmware from a Linux host
+//!
+//! ```text
+//! blinkctl [-p <port>] [-b <baud>] <command>
+//!
+//!   send <words...>   send one shell command and print the reply
+//!   status            print the parsed status report
+//!   pattern <name>    select a preset blink pattern
+//!   blink <ms>        blink with a <ms> half-period
+//!   script <file>     run a command script (see `blink_cli::script`)
+//!   monitor [<s>]     print text and telemetry records (for <s> seconds)
+//!   version           print the firmware build running on the board
+//!   time [sync]       print the board's RTC time, or set it to the host's UTC time
+//!   info <elf>        print the build metadata embedded in a firmware ELF
+//!   update <elf|bin>  upload a firmware image through the serial bootloader
+//! ```
+//!
+//! The port defaults to `$BLINK_PORT`, then `/dev/ttyACM0` (the ST-Link VCP).
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::{Duration, Instant, SystemTime};
+
+use blink_cli::build_info::{iso8601, BuildInfo};
+use blink_cli::protocol::{Device, Error, Event};
+use blink_cli::script::Script;
+use blink_cli::tty::SerialPort;
+use blink_cli::update::{Image, Uploader};
+use blink_telemetry::Record;
+
+/// Serial device used when neither `-p` nor `$BLINK_PORT` is given
+const DEFAULT_PORT: &str = "/dev/ttyACM0";
+
+/// Baud rate used when `-b` is not given (the firmware default)
+const DEFAULT_BAUD: u32 = 115_200;
+
+/// Read timeout of the serial port
+const READ_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Usage text printed for `-h` and argument errors
+const USAGE: &str = "usage: blinkctl [-p <port>] [-b <baud>] <command>
+
+commands:
+  send <words...>   send one shell command and print the reply
+  status            print the parsed status report
+  pattern <name>    select a preset blink pattern
+  blink <ms>        blink with a <ms> half-period
+  script <file>     run a command script
+  monitor [<s>]     print text and telemetry records (for <s> seconds)
+  version           print the firmware build running on the board
+  time [sync]       print the board's RTC time, or set it to the host's UTC time
+  info <elf>        print the build metadata embedded in a firmware ELF
+  update <elf|bin>  upload a firmware image through the serial bootloader";
+
+/// Parsed command line
+struct Args {
+    port: PathBuf,
+    baud: u32,
+    command: Vec<String>,
+}
+
+/// Parses the process arguments
+fn parse_args() -> Result<Args, String> {
+    let mut port = std::env::var_os("BLINK_PORT")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_PORT));
+    let mut baud = DEFAULT_BAUD;
+    let mut args = std::env::args().skip(1);
+    let mut command = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Err(String::new()),
+            "-p" | "--port" if command.is_empty() => {
+                port = args.next().ok_or("missing value for --port")?.into();
+            }
+            "-b" | "--baud" if command.is_empty() => {
+                let value = args.next().ok_or("missing value for --baud")?;
+                baud = value
+                    .parse()
+                    .map_err(|_| format!("invalid baud rate {value:?}"))?;
+            }
+            _ => command.push(arg),
+        }
+    }
+    if command.is_empty() {
+        return Err("missing command".into());
+    }
+    Ok(Args {
+        port,
+        baud,
+        command,
+    })
+}
+
+/// Formats a telemetry record on one line
+fn format_record(record: &Record) -> String {
+    format!(
+        "#{} t={} ms led={} mode={:?} brightness={} uptime={} ms errors={}",
+        record.seq,
+        record.timestamp_ms,
+        if record.led_on { "on" } else { "off" },
+        record.led_mode,
+        record.brightness,
+        record.uptime_ms,
+        record.errors.total(),
+    )
+}
+
+/// Prints reply lines
+fn print_reply(reply: &[String]) {
+    for line in reply {
+        println!("{line}");
+    }
+}
+
+/// Prints the build metadata of a firmware ELF
+fn print_build_info(file: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let elf = std::fs::read(file).map_err(|err| format!("{file}: {err}"))?;
+    let info = BuildInfo::from_elf(&elf).map_err(|err| format!("{file}: {err}"))?;
+    println!("version:   {}", info.version);
+    println!(
+        "commit:    {}{}",
+        info.commit,
+        if info.dirty { " (dirty)" } else { "" }
+    );
+    println!("built:     {}", info.built());
+    println!("profile:   {}", info.profile);
+    println!("features:  {}", info.features.join(", "));
+    Ok(())
+}
+
+/// Runs the selected command
+fn run(args: Args) -> Result<(), Box<dyn std::error::Error>> {
+    // Commands that work on files only, without opening the port
+    if let [info, file] = args.command.as_slice() {
+        if info == "info" {
+            return print_build_info(file);
+        }
+    }
+
+    let port = SerialPort::open(&args.port, args.baud, READ_TIMEOUT)
+        .map_err(|err| format!("{}: {err}", args.port.display()))?;
+    let mut device = Device::new(port);
+    let words: Vec<&str> = args.command.iter().map(String::as_str).collect();
+    match words.as_slice() {
+        ["send", rest @ ..] if !rest.is_empty() => print_reply(&device.command(&rest.join(" "))?),
+        ["status"] => {
+            let status = device.status()?;
+            if let Some(time) = &status.time {
+                println!("time:      {time}");
+            }
+            println!("led:       {}", status.led);
+            println!("mode:      {}", status.mode);
+            println!("pattern:   {}", status.pattern);
+            println!(
+                "uptime:    {}.{:03} s",
+                status.uptime_ms / 1000,
+                status.uptime_ms % 1000
+            );
+            println!("tx errors: {}", status.tx_errors);
+            println!("rx errors: {}", status.rx_errors);
+            println!("boot:      #{}", status.boot);
+            if let Some(tenths) = status.temperature_tenths_c {
+                let sign = if tenths < 0 { "-" } else { "" };
+                let tenths = tenths.unsigned_abs();
+                println!("temp:      {sign}{}.{} C", tenths / 10, tenths % 10);
+            }
+            if let Some(mv) = status.vdda_mv {
+                println!("vdda:      {}.{:03} V", mv / 1000, mv % 1000);
+            }
+        }
+        ["pattern", name] => print_reply(&device.command(&format!("led pattern {name}"))?),
+        ["blink", ms] => print_reply(&device.command(&format!("led blink {ms}"))?),
+        ["version"] => print_reply(&device.command("version")?),
+        ["time"] => print_reply(&device.command("time get")?),
+        ["time", "sync"] => {
+            let secs = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?;
+            let time = iso8601(secs.as_secs());
+            device.command(&format!("time set {time}"))?;
+            println!("board time set to {time}");
+        }
+        ["script", file] => {
+            let text = std::fs::read_to_string(file).map_err(|err| format!("{file}: {err}"))?;
+            let script = Script::parse(&text)?;
+            script.run(&mut device, |command, reply| {
+                println!("> {command}");
+                print_reply(reply);
+            })?;
+        }
+        ["monitor", rest @ ..] if rest.len() <= 1 => {
+            let limit = match rest.first() {
+                Some(s) => Some(Duration::from_secs(
+                    s.parse().map_err(|_| format!("invalid duration {s:?}"))?,
+                )),
+                None => None,
+            };
+            let start = Instant::now();
+            while limit.is_none_or(|limit| start.elapsed() < limit) {
+                match device.next_event(READ_TIMEOUT)? {
+                    Some(Event::Text(text)) => print!("{text}"),
+                    Some(Event::Record(record)) => println!("{}", format_record(&record)),
+                    Some(Event::Corrupt(err)) => eprintln!("corrupt frame: {err:?}"),
+                    None => {}
+                }
+            }
+        }
+        ["update", file] => {
+            let data = std::fs::read(file).map_err(|err| format!("{file}: {err}"))?;
+            let image = Image::load(&data).map_err(|err| format!("{file}: {err}"))?;
+            // The application answers `update` by resetting without a prompt;
+            // a board already in the bootloader does not answer at all
+            match device.command("update") {
+                Ok(_) | Err(Error::Timeout) => {}
+                Err(err) => return Err(err.into()),
+            }
+            let len = image.data.len();
+            let mut uploader = Uploader::new(device.into_inner());
+            let info = uploader.install(&image, |done| {
+                print!("\rwriting {done}/{len} bytes");
+                let _ = std::io::stdout().flush();
+            })?;
+            println!();
+            println!(
+                "installed {len} bytes at {:#010x}, restarting into the new image",
+                info.slot_address
+            );
+        }
+        _ => return Err(format!("unknown command {:?}\n\n{USAGE}", args.command.join(" ")).into()),
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(err) if err.is_empty() => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(err) => {
+            eprintln!("blinkctl: {err}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("blinkctl: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
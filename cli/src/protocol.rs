@@ -0,0 +1,339 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Conversation with the firmware shell over any byte stream
+//!
+//! This module implements the host side of the UART protocol:
+//! - `Demux` - separates shell text from binary telemetry frames
+//! - `Device` - sends shell commands and collects their replies
+//! - `Error` - what can go wrong talking to the board
+//!
+//! # Design Philosophy
+//! `Device` is generic over `Read + Write`, so the same code drives the real
+//! VCP, a pseudo-terminal looped back to a simulated board, or an in-memory
+//! buffer. A command's reply is everything between the echoed command line
+//! and the next `> ` prompt; asynchronous notices (`LED ON`/`LED OFF`, mode
+//! and schedule changes, sensor and power reports, each possibly behind an RTC
+//! timestamp) and telemetry frames that arrive in the meantime are queued, not
+//! mixed in.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::time::{Duration, Instant};
+
+use blink_telemetry::{decode_frame, DecodeError, Record, MAX_FRAME};
+
+use crate::status::{split_timestamp, Status};
+
+/// Shell prompt written by the firmware after every command
+pub const PROMPT: &str = "> ";
+
+/// Default time to wait for a command's prompt
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// What can go wrong talking to the board
+#[derive(Debug)]
+pub enum Error {
+    /// The serial port failed
+    Io(io::Error),
+    /// No prompt (or record) arrived in time
+    Timeout,
+    /// The firmware rejected the command; holds its `ERR ...` line
+    Rejected(String),
+    /// A reply did not have the expected shape
+    Unexpected(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "serial port: {err}"),
+            Self::Timeout => write!(f, "timed out waiting for the board"),
+            Self::Rejected(line) => write!(f, "board replied: {line}"),
+            Self::Unexpected(text) => write!(f, "unexpected reply: {text:?}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// One item received from the board
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// Shell text, in arrival order
+    Text(String),
+    /// A telemetry record that passed its CRC
+    Record(Record),
+    /// A chunk between delimiters that was neither text nor a valid frame
+    Corrupt(DecodeError),
+}
+
+/// Separates shell text from telemetry frames in the received byte stream
+///
+/// Frames are written as `0x00 | COBS | 0x00` and never contain a zero byte;
+/// shell text never contains one either. A zero therefore opens a candidate
+/// frame, and the next zero closes it. A candidate that fails to decode is
+/// assumed to be text that followed an earlier frame's closing delimiter, and
+/// its closing zero is treated as the opening of the real frame, so a dropped
+/// delimiter costs at most one frame.
+pub struct Demux {
+    frame: Vec<u8>,
+    in_frame: bool,
+}
+
+impl Demux {
+    /// Creates a demultiplexer that starts in shell text
+    pub fn new() -> Self {
+        Self {
+            frame: Vec::with_capacity(MAX_FRAME),
+            in_frame: false,
+        }
+    }
+
+    /// Feeds received bytes, appending what they complete to `out`
+    ///
+    /// Text outside frames is passed through as soon as it arrives.
+    pub fn feed(&mut self, bytes: &[u8], out: &mut Vec<Event>) {
+        let mut text = Vec::new();
+        for &byte in bytes {
+            if byte == 0 {
+                flush_text(&mut text, out);
+                self.delimiter(out);
+            } else if self.in_frame && self.frame.len() < MAX_FRAME {
+                self.frame.push(byte);
+            } else if self.in_frame {
+                // Too long to be a frame: it was text all along
+                self.in_frame = false;
+                text.append(&mut self.frame);
+                text.push(byte);
+            } else {
+                text.push(byte);
+            }
+        }
+        flush_text(&mut text, out);
+    }
+
+    /// Handles a `0x00` delimiter
+    fn delimiter(&mut self, out: &mut Vec<Event>) {
+        if !self.in_frame || self.frame.is_empty() {
+            self.in_frame = true;
+            return;
+        }
+        match decode_frame(&self.frame) {
+            Ok(record) => {
+                out.push(Event::Record(record));
+                self.in_frame = false;
+            }
+            Err(err) => {
+                // Stay in frame: this zero may open the next real frame
+                let chunk = std::mem::take(&mut self.frame);
+                if chunk
+                    .iter()
+                    .all(|b| b.is_ascii_graphic() || b.is_ascii_whitespace())
+                {
+                    out.push(Event::Text(String::from_utf8_lossy(&chunk).into_owned()));
+                } else {
+                    out.push(Event::Corrupt(err));
+                }
+            }
+        }
+        self.frame.clear();
+    }
+}
+
+impl Default for Demux {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Moves accumulated text bytes into `out` as one `Event::Text`
+fn flush_text(text: &mut Vec<u8>, out: &mut Vec<Event>) {
+    if !text.is_empty() {
+        out.push(Event::Text(String::from_utf8_lossy(text).into_owned()));
+        text.clear();
+    }
+}
+
+/// Returns `true` for the firmware's asynchronous notices
+///
+/// A `status` reply also starts with `LED `, but never ends right after it.
+fn is_notice(line: &str) -> bool {
+    let (_, text) = split_timestamp(line);
+    matches!(text, "LED ON" | "LED OFF")
+        || ["mode ", "sensors: ", "power: ", "schedule: "]
+            .iter()
+            .any(|prefix| text.starts_with(prefix))
+}
+
+/// A board reached over a byte stream
+pub struct Device<T: Read + Write> {
+    port: T,
+    demux: Demux,
+    timeout: Duration,
+    text: String,
+    events: VecDeque<Event>,
+}
+
+impl<T: Read + Write> Device<T> {
+    /// Wraps an open port
+    ///
+    /// Reads on `port` must time out (return `Ok(0)` or `WouldBlock`/`TimedOut`)
+    /// rather than block forever; `tty::SerialPort` does.
+    pub fn new(port: T) -> Self {
+        Self {
+            port,
+            demux: Demux::new(),
+            timeout: DEFAULT_TIMEOUT,
+            text: String::new(),
+            events: VecDeque::new(),
+        }
+    }
+
+    /// Sets how long `command` waits for the prompt
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Returns the underlying port
+    pub fn into_inner(self) -> T {
+        self.port
+    }
+
+    /// Sends one shell command and returns its reply lines
+    ///
+    /// # Arguments
+    /// * `line` - Command as typed at the shell, without a line ending
+    ///
+    /// # Errors
+    /// `Rejected` if the first reply line starts with `ERR`, `Timeout` if no
+    /// prompt follows within the timeout.
+    pub fn command(&mut self, line: &str) -> Result<Vec<String>, Error> {
+        // Keep text received before this command for `next_event`
+        if !self.text.is_empty() {
+            let earlier = std::mem::take(&mut self.text);
+            self.events.push_back(Event::Text(earlier));
+        }
+        // One write: the board buffers its input, so no pacing is needed
+        let mut typed = Vec::with_capacity(line.len() + 1);
+        typed.extend_from_slice(line.as_bytes());
+        typed.push(b'\r');
+        self.port.write_all(&typed)?;
+        self.port.flush()?;
+
+        let deadline = Instant::now() + self.timeout;
+        while !self.text.ends_with(PROMPT) || !self.text.contains("\r\n") {
+            if Instant::now() >= deadline {
+                return Err(Error::Timeout);
+            }
+            self.receive()?;
+        }
+        let text = std::mem::take(&mut self.text);
+        let body = &text[..text.len() - PROMPT.len()];
+
+        // The first line that is not a notice is the echo of what we typed
+        let mut echoed = false;
+        let mut reply = Vec::new();
+        for l in body.split("\r\n").filter(|l| !l.is_empty()) {
+            if is_notice(l) {
+                self.events.push_back(Event::Text(format!("{l}\r\n")));
+            } else if echoed {
+                reply.push(l.to_owned());
+            } else {
+                echoed = true;
+            }
+        }
+        match reply.first() {
+            Some(first) if first.starts_with("ERR") => Err(Error::Rejected(first.clone())),
+            _ => Ok(reply),
+        }
+    }
+
+    /// Sends `status` and parses the report
+    pub fn status(&mut self) -> Result<Status, Error> {
+        let reply = self.command("status")?;
+        let line = reply.first().map(String::as_str).unwrap_or_default();
+        line.parse().map_err(|_| Error::Unexpected(line.to_owned()))
+    }
+
+    /// Waits for the next item from the board outside a command
+    ///
+    /// # Returns
+    /// `None` if nothing arrived within `timeout`.
+    pub fn next_event(&mut self, timeout: Duration) -> Result<Option<Event>, Error> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(event) = self.events.pop_front() {
+                return Ok(Some(event));
+            }
+            if !self.text.is_empty() {
+                return Ok(Some(Event::Text(std::mem::take(&mut self.text))));
+            }
+            if Instant::now() >= deadline {
+                return Ok(None);
+            }
+            self.receive()?;
+        }
+    }
+
+    /// Waits for the next telemetry record, skipping text and corrupt frames
+    pub fn next_record(&mut self, timeout: Duration) -> Result<Record, Error> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let left = deadline.saturating_duration_since(Instant::now());
+            match self.next_event(left)? {
+                Some(Event::Record(record)) => return Ok(record),
+                Some(_) => {}
+                None => return Err(Error::Timeout),
+            }
+        }
+    }
+
+    /// Performs one read, routing text to the reply buffer and frames to the queue
+    fn receive(&mut self) -> Result<(), Error> {
+        let mut buf = [0u8; 256];
+        let n = match self.port.read(&mut buf) {
+            Ok(n) => n,
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    io::ErrorKind::WouldBlock
+                        | io::ErrorKind::TimedOut
+                        | io::ErrorKind::Interrupted
+                ) =>
+            {
+                0
+            }
+            Err(err) => return Err(err.into()),
+        };
+        if n == 0 {
+            // Nothing within the port's read timeout; avoid spinning on
+            // ports that return immediately
+            std::thread::sleep(Duration::from_millis(1));
+            return Ok(());
+        }
+        let mut events = Vec::new();
+        self.demux.feed(&buf[..n], &mut events);
+        for event in events {
+            match event {
+                Event::Text(text) => self.text.push_str(&text),
+                other => self.events.push_back(other),
+            }
+        }
+        Ok(())
+    }
+}
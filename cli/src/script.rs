@@ -0,0 +1,143 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Scripted command sequences, e.g. for demos or soak tests
+//!
+//! A script is plain text with one step per line:
+//! - `# comment` and blank lines are ignored
+//! - `wait <ms>` pauses on the host
+//! - anything else is sent to the board as a shell command
+//!
+//! ```text
+//! # cycle through the presets
+//! led pattern heartbeat
+//! wait 3000
+//! led pattern sos
+//! wait 6000
+//! led blink 500
+//! ```
+//!
+//! # Design Philosophy
+//! Commands are not validated on the host: the firmware shell is the single
+//! source of truth, and its `ERR` reply stops the script at the offending line.
+
+use std::fmt;
+use std::io::{Read, Write};
+use std::time::Duration;
+
+use crate::protocol::{Device, Error};
+
+/// One script step
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Step {
+    /// Send a shell command
+    Command(String),
+    /// Pause on the host
+    Wait(Duration),
+}
+
+/// A parsed script: steps paired with their 1-based line numbers
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Script {
+    pub steps: Vec<(usize, Step)>,
+}
+
+/// Error returned for a malformed `wait` line
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SyntaxError {
+    /// 1-based line number
+    pub line: usize,
+    /// The offending text
+    pub text: String,
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}: expected `wait <ms>`, got {:?}",
+            self.line, self.text
+        )
+    }
+}
+
+impl std::error::Error for SyntaxError {}
+
+/// Error returned when a step fails on the board
+#[derive(Debug)]
+pub struct StepError {
+    /// 1-based line number
+    pub line: usize,
+    /// What went wrong
+    pub error: Error,
+}
+
+impl fmt::Display for StepError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.error)
+    }
+}
+
+impl std::error::Error for StepError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl Script {
+    /// Parses script text
+    ///
+    /// # Errors
+    /// A `wait` line without a valid millisecond count.
+    pub fn parse(text: &str) -> Result<Self, SyntaxError> {
+        let mut steps = Vec::new();
+        for (index, raw) in text.lines().enumerate() {
+            let line = raw.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let step = match line.split_once(char::is_whitespace) {
+                Some((word, ms)) if word.eq_ignore_ascii_case("wait") => {
+                    let ms: u64 = ms.trim().parse().map_err(|_| SyntaxError {
+                        line: index + 1,
+                        text: line.to_owned(),
+                    })?;
+                    Step::Wait(Duration::from_millis(ms))
+                }
+                _ if line.eq_ignore_ascii_case("wait") => {
+                    return Err(SyntaxError {
+                        line: index + 1,
+                        text: line.to_owned(),
+                    })
+                }
+                _ => Step::Command(line.to_owned()),
+            };
+            steps.push((index + 1, step));
+        }
+        Ok(Self { steps })
+    }
+
+    /// Runs every step against `device`, stopping at the first failure
+    ///
+    /// # Arguments
+    /// * `device` - Board to drive
+    /// * `on_reply` - Called with each command and its reply lines
+    pub fn run<T, F>(&self, device: &mut Device<T>, mut on_reply: F) -> Result<(), StepError>
+    where
+        T: Read + Write,
+        F: FnMut(&str, &[String]),
+    {
+        for (line, step) in &self.steps {
+            match step {
+                Step::Wait(duration) => std::thread::sleep(*duration),
+                Step::Command(command) => {
+                    let reply = device
+                        .command(command)
+                        .map_err(|error| StepError { line: *line, error })?;
+                    on_reply(command, &reply);
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,195 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Parsing of the firmware's `status` report
+//!
+//! The board answers `status` with one line such as
+//! `LED ON, mode pattern, pattern steady, uptime 12.345 s, tx errors 0, rx errors 0, boot #3`,
+//! followed by `temp 31.4 C, vdda 3.29 V` once the internal sensors have been read.
+//! Firmware with the real-time clock prefixes the line with its ISO-8601 time
+//! (`2025-06-01T12:34:56Z LED ON, ...`), as it does every status line.
+//!
+//! # Design Philosophy
+//! Fields are matched by their label rather than position, and unknown fields
+//! are ignored, so a newer firmware that appends to the report still parses.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// LED level as reported by `status`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Level {
+    /// Fully lit
+    On,
+    /// Dark
+    Off,
+    /// Perceived brightness while an effect runs, in percent
+    Percent(u8),
+}
+
+/// A parsed `status` line
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Status {
+    /// LED level
+    pub led: Level,
+    /// Drive mode (`pattern`, `manual`, an effect name or `morse`)
+    pub mode: String,
+    /// Name of the loaded blink pattern
+    pub pattern: String,
+    /// Time since boot in milliseconds
+    pub uptime_ms: u64,
+    /// UART write failures since boot
+    pub tx_errors: u64,
+    /// UART receive faults (overrun, framing, noise, parity) since boot
+    pub rx_errors: u64,
+    /// Persisted boot counter
+    pub boot: u32,
+    /// Die temperature in tenths of a degree Celsius, if reported
+    pub temperature_tenths_c: Option<i32>,
+    /// Supply voltage (VDDA) in mV, if reported
+    pub vdda_mv: Option<u32>,
+    /// Board RTC time the report was written at (ISO-8601), if stamped
+    pub time: Option<String>,
+}
+
+/// Error returned when a line is not a `status` report
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NotStatus;
+
+impl fmt::Display for NotStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("not a status report")
+    }
+}
+
+impl std::error::Error for NotStatus {}
+
+impl FromStr for Status {
+    type Err = NotStatus;
+
+    fn from_str(line: &str) -> Result<Self, NotStatus> {
+        let (time, line) = split_timestamp(line.trim());
+        let mut led = None;
+        let mut mode = None;
+        let mut pattern = None;
+        let mut uptime_ms = None;
+        let mut tx_errors = None;
+        let mut rx_errors = None;
+        let mut boot = None;
+        let mut temperature_tenths_c = None;
+        let mut vdda_mv = None;
+        for field in line.split(", ") {
+            if let Some(level) = field.strip_prefix("LED ") {
+                led = parse_level(level);
+            } else if let Some(value) = field.strip_prefix("mode ") {
+                mode = Some(value.to_owned());
+            } else if let Some(value) = field.strip_prefix("pattern ") {
+                pattern = Some(value.to_owned());
+            } else if let Some(value) = field.strip_prefix("uptime ") {
+                uptime_ms = value.strip_suffix(" s").and_then(parse_seconds);
+            } else if let Some(value) = field.strip_prefix("tx errors ") {
+                tx_errors = value.parse().ok();
+            } else if let Some(value) = field.strip_prefix("rx errors ") {
+                rx_errors = value.parse().ok();
+            } else if let Some(value) = field.strip_prefix("boot #") {
+                boot = value.parse().ok();
+            } else if let Some(value) = field.strip_prefix("temp ") {
+                temperature_tenths_c = value
+                    .strip_suffix(" C")
+                    .and_then(|t| parse_fixed(t, 1))
+                    .and_then(|t| i32::try_from(t).ok());
+            } else if let Some(value) = field.strip_prefix("vdda ") {
+                vdda_mv = value
+                    .strip_suffix(" V")
+                    .and_then(|v| parse_fixed(v, 3))
+                    .and_then(|v| u32::try_from(v).ok());
+            }
+        }
+        Ok(Self {
+            led: led.ok_or(NotStatus)?,
+            mode: mode.ok_or(NotStatus)?,
+            pattern: pattern.ok_or(NotStatus)?,
+            uptime_ms: uptime_ms.ok_or(NotStatus)?,
+            tx_errors: tx_errors.ok_or(NotStatus)?,
+            rx_errors: rx_errors.ok_or(NotStatus)?,
+            boot: boot.ok_or(NotStatus)?,
+            temperature_tenths_c,
+            vdda_mv,
+            time: time.map(str::to_owned),
+        })
+    }
+}
+
+/// Splits the firmware's `YYYY-MM-DDTHH:MM:SSZ ` timestamp off a status line
+///
+/// # Returns
+/// The timestamp (if the line starts with one) and the rest of the line.
+pub fn split_timestamp(line: &str) -> (Option<&str>, &str) {
+    let stamped = line.as_bytes().get(..21).is_some_and(|head| {
+        head.iter().enumerate().all(|(i, &b)| match i {
+            4 | 7 => b == b'-',
+            10 => b == b'T',
+            13 | 16 => b == b':',
+            19 => b == b'Z',
+            20 => b == b' ',
+            _ => b.is_ascii_digit(),
+        })
+    });
+    if stamped {
+        (Some(&line[..20]), &line[21..])
+    } else {
+        (None, line)
+    }
+}
+
+/// Parses `ON`, `OFF` or `N%`
+fn parse_level(text: &str) -> Option<Level> {
+    match text {
+        "ON" => Some(Level::On),
+        "OFF" => Some(Level::Off),
+        _ => text
+            .strip_suffix('%')
+            .and_then(|p| p.parse().ok())
+            .map(Level::Percent),
+    }
+}
+
+/// Parses `seconds.millis` (exactly three fraction digits) into milliseconds
+fn parse_seconds(text: &str) -> Option<u64> {
+    let (secs, millis) = text.split_once('.')?;
+    if millis.len() != 3 {
+        return None;
+    }
+    Some(secs.parse::<u64>().ok()? * 1000 + millis.parse::<u64>().ok()?)
+}
+
+/// Parses a decimal such as `-12.3` or `3.29` into units of `10^-scale`
+///
+/// At most `scale` fraction digits are accepted; fewer are padded (`3.3` with
+/// scale 3 is 3300).
+fn parse_fixed(text: &str, scale: usize) -> Option<i64> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text),
+    };
+    let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    if fraction.len() > scale || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let mut value = whole.parse::<u32>().ok().map(i64::from)?;
+    for place in 0..scale {
+        let digit = fraction.as_bytes().get(place).map_or(0, |b| b - b'0');
+        value = value * 10 + i64::from(digit);
+    }
+    Some(if negative { -value } else { value })
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::On => f.write_str("on"),
+            Self::Off => f.write_str("off"),
+            Self::Percent(p) => write!(f, "{p}%"),
+        }
+    }
+}
@@ -0,0 +1,190 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Serial port and pseudo-terminal access on Linux
+//!
+//! This module wraps the few termios and pty calls the CLI needs:
+//! - `SerialPort` - a tty (the ST-Link VCP, or a pty slave) in raw mode
+//! - `Pty` - a pseudo-terminal pair, used to loop the CLI back to a simulated board
+//!
+//! # Design Philosophy
+//! The calls are declared directly against the C library that `std` already
+//! links, instead of pulling in `libc` or `nix` for a dozen symbols. The
+//! `termios` layout below is the Linux one (glibc and musl agree).
+
+use std::ffi::{c_char, c_int, CStr};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Linux `struct termios`
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Termios {
+    c_iflag: u32,
+    c_oflag: u32,
+    c_cflag: u32,
+    c_lflag: u32,
+    c_line: u8,
+    c_cc: [u8; 32],
+    c_ispeed: u32,
+    c_ospeed: u32,
+}
+
+extern "C" {
+    fn tcgetattr(fd: c_int, termios: *mut Termios) -> c_int;
+    fn tcsetattr(fd: c_int, action: c_int, termios: *const Termios) -> c_int;
+    fn cfmakeraw(termios: *mut Termios);
+    fn cfsetspeed(termios: *mut Termios, speed: u32) -> c_int;
+    fn posix_openpt(flags: c_int) -> c_int;
+    fn grantpt(fd: c_int) -> c_int;
+    fn unlockpt(fd: c_int) -> c_int;
+    fn ptsname_r(fd: c_int, buf: *mut c_char, len: usize) -> c_int;
+}
+
+/// `tcsetattr` action: apply immediately
+const TCSANOW: c_int = 0;
+/// `open` flag: read and write
+const O_RDWR: c_int = 0o2;
+/// `open` flag: do not become the controlling terminal
+const O_NOCTTY: c_int = 0o400;
+/// `c_cflag`: ignore modem control lines
+const CLOCAL: u32 = 0o4000;
+/// `c_cflag`: enable the receiver
+const CREAD: u32 = 0o200;
+/// `c_cc` index: inter-byte read timeout in tenths of a second
+const VTIME: usize = 5;
+/// `c_cc` index: minimum bytes for a read to return
+const VMIN: usize = 6;
+
+/// Maps a baud rate onto its termios speed constant
+fn speed_constant(baud: u32) -> Option<u32> {
+    Some(match baud {
+        9_600 => 0o15,
+        19_200 => 0o16,
+        38_400 => 0o17,
+        57_600 => 0o10001,
+        115_200 => 0o10002,
+        230_400 => 0o10003,
+        460_800 => 0o10004,
+        _ => return None,
+    })
+}
+
+/// Turns a C return code into an `io::Result`
+fn check(ret: c_int) -> io::Result<c_int> {
+    if ret < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(ret)
+    }
+}
+
+/// A serial tty configured for raw 8N1 I/O
+///
+/// Reads return `Ok(0)` after `read_timeout` without data instead of blocking
+/// forever, so callers can enforce their own deadlines.
+pub struct SerialPort {
+    file: File,
+}
+
+impl SerialPort {
+    /// Opens and configures `path`
+    ///
+    /// # Arguments
+    /// * `path` - Device node, e.g. `/dev/ttyACM0`
+    /// * `baud` - One of the firmware's supported rates (9600 to 460800)
+    /// * `read_timeout` - Longest a read waits for the first byte (0.1 s resolution)
+    pub fn open(path: &Path, baud: u32, read_timeout: Duration) -> io::Result<Self> {
+        let speed = speed_constant(baud).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unsupported baud rate {baud}"),
+            )
+        })?;
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(O_NOCTTY)
+            .open(path)?;
+        let fd = file.as_raw_fd();
+        let deciseconds = (read_timeout.as_millis() / 100).clamp(1, 255) as u8;
+        // SAFETY: `fd` is an open descriptor owned by `file`, and `Termios`
+        // matches the Linux layout the calls expect
+        unsafe {
+            let mut tio: Termios = std::mem::zeroed();
+            check(tcgetattr(fd, &mut tio))?;
+            cfmakeraw(&mut tio);
+            tio.c_cflag |= CLOCAL | CREAD;
+            tio.c_cc[VMIN] = 0;
+            tio.c_cc[VTIME] = deciseconds;
+            check(cfsetspeed(&mut tio, speed))?;
+            check(tcsetattr(fd, TCSANOW, &tio))?;
+        }
+        Ok(Self { file })
+    }
+}
+
+impl Read for SerialPort {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.file.read(buf)
+    }
+}
+
+impl Write for SerialPort {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// A pseudo-terminal pair
+///
+/// The slave behaves like a serial device node and is opened with
+/// `SerialPort::open`; whatever plays the board reads and writes `master`.
+pub struct Pty {
+    /// Controller side of the pair
+    pub master: File,
+    slave_path: PathBuf,
+    // Keeps the slave open (and raw) between `SerialPort` opens, so the
+    // master never sees a hang-up
+    _slave: SerialPort,
+}
+
+impl Pty {
+    /// Allocates a new pseudo-terminal pair with the slave in raw mode
+    pub fn open() -> io::Result<Self> {
+        // SAFETY: plain libc calls on a descriptor we own; `ptsname_r` writes
+        // a NUL-terminated path into `buf`
+        let (master, slave_path) = unsafe {
+            let fd = check(posix_openpt(O_RDWR | O_NOCTTY))?;
+            let master = <File as std::os::fd::FromRawFd>::from_raw_fd(fd);
+            check(grantpt(fd))?;
+            check(unlockpt(fd))?;
+            let mut buf = [0 as c_char; 128];
+            let err = ptsname_r(fd, buf.as_mut_ptr(), buf.len());
+            if err != 0 {
+                return Err(io::Error::from_raw_os_error(err));
+            }
+            let path = CStr::from_ptr(buf.as_ptr()).to_string_lossy().into_owned();
+            (master, PathBuf::from(path))
+        };
+        let slave = SerialPort::open(&slave_path, 115_200, Duration::from_millis(100))?;
+        Ok(Self {
+            master,
+            slave_path,
+            _slave: slave,
+        })
+    }
+
+    /// Returns the slave device node, e.g. `/dev/pts/3`
+    pub fn slave_path(&self) -> &Path {
+        &self.slave_path
+    }
+}
@@ -0,0 +1,458 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Host side of the serial bootloader's update protocol
+//!
+//! This module uploads a firmware image to the bootloader
+//! (`src/bin/bootloader.rs`, protocol in `blink::update`):
+//! - `Image` - the bytes to upload, from an ELF or a raw binary
+//! - `Uploader` - sends requests and waits for their replies, retrying lost frames
+//! - `Info` - the slot geometry the bootloader reports
+//! - `UpdateError` - what can go wrong
+//! - `crc32` - the image checksum `Begin` carries
+//!
+//! # Design Philosophy
+//! Every request waits for its reply before the next one is sent. A reply that
+//! does not arrive, or arrives corrupted, costs one retry of the same request:
+//! the bootloader acknowledges a repeated chunk without programming it twice.
+//! Like `protocol`, the uploader is generic over `Read + Write`, so it is tested
+//! against a bootloader simulated with the firmware library behind a
+//! pseudo-terminal.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::time::{Duration, Instant};
+
+use blink_telemetry::{cobs_decode, crc16};
+
+use crate::elf::{self, ElfError};
+
+/// Protocol version this uploader speaks
+pub const PROTOCOL_VERSION: u8 = 1;
+
+/// Default time to wait for a reply
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Time to wait for `Begin`, which erases up to a whole slot first
+pub const ERASE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Times a request is sent before giving up
+pub const ATTEMPTS: u32 = 5;
+
+/// Every chunk but the last must be a multiple of this many bytes
+const WRITE_ALIGN: usize = 8;
+
+/// Request type codes
+const HELLO: u8 = 0x01;
+const BEGIN: u8 = 0x02;
+const WRITE: u8 = 0x03;
+const FINISH: u8 = 0x04;
+const BOOT: u8 = 0x05;
+
+/// Reply type codes
+const INFO: u8 = 0x81;
+const ACK: u8 = 0x82;
+const NAK: u8 = 0x83;
+
+/// `Nak` code of a corrupt request frame, which is worth a retry
+const NAK_FRAME: u8 = 1;
+
+/// Slot geometry reported by the bootloader
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Info {
+    /// Protocol version of the bootloader
+    pub version: u8,
+    /// Address the application must be linked at
+    pub slot_address: u32,
+    /// Largest image the bootloader accepts
+    pub slot_len: u32,
+    /// Flash erase page size
+    pub page_size: u32,
+    /// Largest chunk per `Write` request
+    pub max_chunk: u16,
+}
+
+/// What can go wrong during an update
+#[derive(Debug)]
+pub enum UpdateError {
+    /// The serial port failed
+    Io(io::Error),
+    /// No valid reply after `ATTEMPTS` tries
+    Timeout,
+    /// The bootloader refused a request; holds its reason
+    Refused(&'static str),
+    /// A reply did not fit the request
+    Unexpected(String),
+    /// The bootloader speaks another protocol version
+    Version(u8),
+    /// The image is larger than the slot
+    TooLarge { len: usize, slot: u32 },
+    /// The image was linked for a different address than the slot
+    WrongAddress { image: u32, slot: u32 },
+}
+
+impl fmt::Display for UpdateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "serial port: {err}"),
+            Self::Timeout => f.write_str("no reply from the bootloader"),
+            Self::Refused(reason) => write!(f, "bootloader refused: {reason}"),
+            Self::Unexpected(reply) => write!(f, "unexpected reply: {reply}"),
+            Self::Version(version) => write!(
+                f,
+                "bootloader speaks protocol version {version}, expected {PROTOCOL_VERSION}"
+            ),
+            Self::TooLarge { len, slot } => {
+                write!(f, "image is {len} bytes but the slot holds {slot}")
+            }
+            Self::WrongAddress { image, slot } => write!(
+                f,
+                "image is linked at {image:#010x} but the slot is at {slot:#010x} \
+                 (build with `--features bootloader`)"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for UpdateError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for UpdateError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// Returns the bootloader's description of a `Nak` code
+fn nak_reason(code: u8) -> &'static str {
+    match code {
+        1 => "corrupt frame",
+        2 => "malformed request",
+        3 => "out of sequence",
+        4 => "image does not fit the slot",
+        5 => "flash error",
+        6 => "image CRC mismatch",
+        7 => "invalid vector table",
+        _ => "unknown error",
+    }
+}
+
+/// A firmware image to upload
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Image {
+    /// Address the image was linked at; unknown for a raw binary
+    pub address: Option<u32>,
+    /// The bytes to program, starting with the vector table
+    pub data: Vec<u8>,
+}
+
+impl Image {
+    /// Loads an image from the contents of an ELF file or a raw binary
+    ///
+    /// # Errors
+    /// `ElfError` if the file looks like an ELF but has no usable segments.
+    pub fn load(file: &[u8]) -> Result<Self, ElfError> {
+        match elf::load_image(file) {
+            Ok((address, data)) => Ok(Self {
+                address: Some(address),
+                data,
+            }),
+            Err(ElfError::NotElf) => Ok(Self {
+                address: None,
+                data: file.to_vec(),
+            }),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// A reply from the bootloader
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Reply {
+    Info(Info),
+    Ack(u32),
+    Nak(u8),
+}
+
+impl Reply {
+    /// Parses a frame body (without its CRC)
+    fn decode(body: &[u8]) -> Option<Self> {
+        let word = |at: usize| Some(u32::from_le_bytes(body.get(at..at + 4)?.try_into().ok()?));
+        match (body.first()?, body.len()) {
+            (&INFO, 16) => Some(Self::Info(Info {
+                version: body[1],
+                slot_address: word(2)?,
+                slot_len: word(6)?,
+                page_size: word(10)?,
+                max_chunk: u16::from_le_bytes([body[14], body[15]]),
+            })),
+            (&ACK, 5) => Some(Self::Ack(word(1)?)),
+            (&NAK, 2) => Some(Self::Nak(body[1])),
+            _ => None,
+        }
+    }
+}
+
+/// Talks to the serial bootloader over a byte stream
+pub struct Uploader<T: Read + Write> {
+    port: T,
+    timeout: Duration,
+    input: VecDeque<u8>,
+}
+
+impl<T: Read + Write> Uploader<T> {
+    /// Wraps an open port
+    ///
+    /// Reads on `port` must time out rather than block forever, as for
+    /// `protocol::Device`.
+    pub fn new(port: T) -> Self {
+        Self {
+            port,
+            timeout: DEFAULT_TIMEOUT,
+            input: VecDeque::new(),
+        }
+    }
+
+    /// Sets how long each request waits for its reply (`Begin` waits longer)
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Returns the underlying port
+    pub fn into_inner(self) -> T {
+        self.port
+    }
+
+    /// Asks for the slot geometry
+    ///
+    /// Also the way to find out whether the bootloader is listening yet.
+    ///
+    /// # Errors
+    /// `Version` if the bootloader speaks another protocol, otherwise as for
+    /// any request.
+    pub fn hello(&mut self) -> Result<Info, UpdateError> {
+        let reply = self.request(&[HELLO], self.timeout, |r| matches!(r, Reply::Info(_)))?;
+        let Reply::Info(info) = reply else {
+            return Err(unexpected(reply));
+        };
+        if info.version != PROTOCOL_VERSION {
+            return Err(UpdateError::Version(info.version));
+        }
+        Ok(info)
+    }
+
+    /// Uploads and verifies `image`, then asks the bootloader to install it
+    ///
+    /// # Arguments
+    /// * `image` - The image to upload
+    /// * `progress` - Called with the number of bytes acknowledged so far
+    ///
+    /// # Returns
+    /// The slot geometry, after the bootloader has acknowledged `Boot`.
+    ///
+    /// # Errors
+    /// `TooLarge` or `WrongAddress` before anything is sent but `Hello`,
+    /// otherwise the first request that failed.
+    pub fn install(
+        &mut self,
+        image: &Image,
+        mut progress: impl FnMut(usize),
+    ) -> Result<Info, UpdateError> {
+        let info = self.hello()?;
+        let len = image.data.len();
+        if len as u64 > u64::from(info.slot_len) || len == 0 {
+            return Err(UpdateError::TooLarge {
+                len,
+                slot: info.slot_len,
+            });
+        }
+        if let Some(address) = image.address.filter(|&a| a != info.slot_address) {
+            return Err(UpdateError::WrongAddress {
+                image: address,
+                slot: info.slot_address,
+            });
+        }
+
+        // Erase the download slot
+        let mut begin = vec![BEGIN];
+        begin.extend_from_slice(&(len as u32).to_le_bytes());
+        begin.extend_from_slice(&crc32(&image.data).to_le_bytes());
+        self.expect_ack(&begin, ERASE_TIMEOUT, 0)?;
+
+        // Program it chunk by chunk
+        let chunk = (usize::from(info.max_chunk) / WRITE_ALIGN * WRITE_ALIGN).max(WRITE_ALIGN);
+        for (index, data) in image.data.chunks(chunk).enumerate() {
+            let offset = index * chunk;
+            let mut write = vec![WRITE];
+            write.extend_from_slice(&(offset as u32).to_le_bytes());
+            write.extend_from_slice(data);
+            let end = offset + data.len();
+            self.expect_ack(&write, self.timeout, end as u32)?;
+            progress(end);
+        }
+
+        // Have it checked, then installed
+        self.expect_ack(&[FINISH], self.timeout, len as u32)?;
+        self.expect_ack(&[BOOT], self.timeout, 0)?;
+        Ok(info)
+    }
+
+    /// Sends `body` and waits for `Ack(value)`
+    fn expect_ack(
+        &mut self,
+        body: &[u8],
+        timeout: Duration,
+        value: u32,
+    ) -> Result<(), UpdateError> {
+        // Other acks are late replies to an earlier attempt
+        match self.request(body, timeout, |r| *r == Reply::Ack(value))? {
+            Reply::Ack(_) => Ok(()),
+            reply => Err(unexpected(reply)),
+        }
+    }
+
+    /// Sends `body` until a reply accepted by `expected` or a `Nak` arrives
+    ///
+    /// A request whose reply does not arrive in `timeout`, or whose frame the
+    /// bootloader found corrupt, is sent again, up to `ATTEMPTS` times.
+    fn request(
+        &mut self,
+        body: &[u8],
+        timeout: Duration,
+        expected: impl Fn(&Reply) -> bool,
+    ) -> Result<Reply, UpdateError> {
+        let frame = encode_frame(body);
+        let mut failure = UpdateError::Timeout;
+        for _ in 0..ATTEMPTS {
+            self.port.write_all(&frame)?;
+            self.port.flush()?;
+            let deadline = Instant::now() + timeout;
+            loop {
+                match self.receive(deadline)? {
+                    None => break,
+                    Some(Reply::Nak(NAK_FRAME)) => {
+                        failure = UpdateError::Refused(nak_reason(NAK_FRAME));
+                        break;
+                    }
+                    Some(Reply::Nak(code)) => return Err(UpdateError::Refused(nak_reason(code))),
+                    Some(reply) if expected(&reply) => return Ok(reply),
+                    Some(_) => {}
+                }
+            }
+        }
+        Err(failure)
+    }
+
+    /// Waits for the next valid reply frame
+    ///
+    /// Anything between delimiters that does not decode (shell text sent
+    /// before the reset, line noise) is skipped.
+    ///
+    /// # Returns
+    /// `None` if no reply arrived before `deadline`.
+    fn receive(&mut self, deadline: Instant) -> Result<Option<Reply>, UpdateError> {
+        let mut raw = Vec::new();
+        loop {
+            // Drain what has already arrived before reading more
+            while let Some(byte) = self.input.pop_front() {
+                if byte != 0 {
+                    raw.push(byte);
+                    continue;
+                }
+                if let Some(reply) = decode_frame(&raw) {
+                    return Ok(Some(reply));
+                }
+                raw.clear();
+            }
+            if Instant::now() >= deadline {
+                // Keep a partial frame for the next call
+                self.input.extend(raw);
+                return Ok(None);
+            }
+            let mut buf = [0u8; 256];
+            let n = match self.port.read(&mut buf) {
+                Ok(n) => n,
+                Err(err)
+                    if matches!(
+                        err.kind(),
+                        io::ErrorKind::WouldBlock
+                            | io::ErrorKind::TimedOut
+                            | io::ErrorKind::Interrupted
+                    ) =>
+                {
+                    0
+                }
+                Err(err) => return Err(err.into()),
+            };
+            self.input.extend(&buf[..n]);
+        }
+    }
+}
+
+/// Describes a reply that does not fit the request
+fn unexpected(reply: Reply) -> UpdateError {
+    UpdateError::Unexpected(format!("{reply:?}"))
+}
+
+/// Decodes the bytes between two delimiters into a reply
+fn decode_frame(raw: &[u8]) -> Option<Reply> {
+    let mut body = [0u8; 32];
+    let len = cobs_decode(raw, &mut body).ok()?;
+    let (body, crc) = body[..len].split_at(len.checked_sub(2)?);
+    if crc16(body).to_le_bytes() != crc {
+        return None;
+    }
+    Reply::decode(body)
+}
+
+/// Frames a request: delimiter, COBS(body | CRC-16 LE), delimiter
+fn encode_frame(body: &[u8]) -> Vec<u8> {
+    let mut raw = body.to_vec();
+    raw.extend_from_slice(&crc16(body).to_le_bytes());
+    let mut frame = vec![0];
+    frame.extend(cobs_encode(&raw));
+    frame.push(0);
+    frame
+}
+
+/// COBS-encodes `data` (without delimiters)
+fn cobs_encode(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0];
+    let mut code_at = 0;
+    for &byte in data {
+        if byte != 0 {
+            out.push(byte);
+        }
+        // A zero, or a full 254-byte block, closes the current block
+        if byte == 0 || out.len() - code_at == 0xFF {
+            out[code_at] = (out.len() - code_at) as u8;
+            code_at = out.len();
+            out.push(0);
+        }
+    }
+    out[code_at] = (out.len() - code_at) as u8;
+    out
+}
+
+/// CRC-32 (ISO-HDLC, as zlib computes it) of `data`, as `Begin` carries
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
@@ -0,0 +1,222 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Build metadata and flash images read back from ELF files
+//!
+//! The records are produced by the firmware library itself, then found by
+//! section name in a hand-built ELF32 image (the firmware's format) and in
+//! this test executable (ELF64). Flash images are assembled from hand-built
+//! program headers.
+
+use blink::build_info::BuildInfo as Record;
+use blink_cli::build_info::{BuildInfo, BuildInfoError, SECTION};
+use blink_cli::elf::{load_image, section, ElfError};
+
+/// The firmware's own record, linked into this test executable
+#[used]
+#[link_section = ".build_info"]
+static CURRENT: [u8; blink::build_info::LEN] = *Record::CURRENT.as_bytes();
+
+/// Builds a little-endian ELF32 file holding `sections` (name, type, bytes)
+///
+/// Section 0 is the null section and the last one the section name table.
+fn elf32(sections: &[(&str, u32, &[u8])]) -> Vec<u8> {
+    let mut names = vec![0u8];
+    let mut data = Vec::new();
+    let mut headers = vec![[0u32; 10]];
+    for &(name, kind, bytes) in sections {
+        let offset = 52 + data.len() as u32;
+        headers.push([
+            names.len() as u32,
+            kind,
+            0,
+            0,
+            offset,
+            bytes.len() as u32,
+            0,
+            0,
+            4,
+            0,
+        ]);
+        names.extend_from_slice(name.as_bytes());
+        names.push(0);
+        data.extend_from_slice(bytes);
+    }
+    let strtab_name = names.len() as u32;
+    names.extend_from_slice(b".shstrtab\0");
+    headers.push([
+        strtab_name,
+        3,
+        0,
+        0,
+        52 + data.len() as u32,
+        names.len() as u32,
+        0,
+        0,
+        1,
+        0,
+    ]);
+    data.extend_from_slice(&names);
+
+    let mut elf = vec![0x7f, b'E', b'L', b'F', 1, 1, 1];
+    elf.resize(16, 0);
+    elf.extend_from_slice(&2u16.to_le_bytes()); // e_type: executable
+    elf.extend_from_slice(&40u16.to_le_bytes()); // e_machine: ARM
+    elf.extend_from_slice(&1u32.to_le_bytes()); // e_version
+    elf.extend_from_slice(&[0; 8]); // e_entry, e_phoff
+    elf.extend_from_slice(&(52 + data.len() as u32).to_le_bytes()); // e_shoff
+    elf.extend_from_slice(&[0; 4]); // e_flags
+    for half in [
+        52u16,
+        0,
+        0,
+        40,
+        headers.len() as u16,
+        headers.len() as u16 - 1,
+    ] {
+        elf.extend_from_slice(&half.to_le_bytes());
+    }
+    elf.extend_from_slice(&data);
+    for header in headers {
+        for word in header {
+            elf.extend_from_slice(&word.to_le_bytes());
+        }
+    }
+    elf
+}
+
+/// Builds a little-endian ELF32 file holding `segments` (type, load address,
+/// file bytes, size in memory)
+fn elf32_segments(segments: &[(u32, u32, &[u8], u32)]) -> Vec<u8> {
+    let mut elf = vec![0x7f, b'E', b'L', b'F', 1, 1, 1];
+    elf.resize(16, 0);
+    elf.extend_from_slice(&2u16.to_le_bytes()); // e_type: executable
+    elf.extend_from_slice(&40u16.to_le_bytes()); // e_machine: ARM
+    elf.extend_from_slice(&1u32.to_le_bytes()); // e_version
+    elf.extend_from_slice(&0x0800_0101u32.to_le_bytes()); // e_entry
+    elf.extend_from_slice(&52u32.to_le_bytes()); // e_phoff
+    elf.extend_from_slice(&[0; 8]); // e_shoff, e_flags
+    for half in [52u16, 32, segments.len() as u16, 40, 0, 0] {
+        elf.extend_from_slice(&half.to_le_bytes());
+    }
+    let mut offset = 52 + 32 * segments.len() as u32;
+    for &(kind, paddr, bytes, memsz) in segments {
+        let vaddr = if memsz > bytes.len() as u32 {
+            0x2000_0000
+        } else {
+            paddr
+        };
+        let header = [kind, offset, vaddr, paddr, bytes.len() as u32, memsz, 0, 4];
+        for word in header {
+            elf.extend_from_slice(&word.to_le_bytes());
+        }
+        offset += bytes.len() as u32;
+    }
+    for &(_, _, bytes, _) in segments {
+        elf.extend_from_slice(bytes);
+    }
+    elf
+}
+
+#[test]
+fn flash_images_follow_the_load_addresses() {
+    // Vector table and code, a gap, initialised data stored after the code,
+    // `.bss` (nothing in the file) and a non-loadable segment
+    let elf = elf32_segments(&[
+        (1, 0x0800_0000, &[0x11; 8], 8),
+        (1, 0x0800_0010, &[0x22; 6], 6),
+        (1, 0x0800_0016, &[0x33; 4], 12),
+        (1, 0x2000_0100, &[], 64),
+        (0x7000_0001, 0x0800_1000, &[0x44; 4], 4),
+    ]);
+    let (address, image) = load_image(&elf).unwrap();
+    assert_eq!(address, 0x0800_0000);
+    let mut expected = vec![0x11; 8];
+    expected.extend_from_slice(&[0xFF; 8]);
+    expected.extend_from_slice(&[0x22; 6]);
+    expected.extend_from_slice(&[0x33; 4]);
+    assert_eq!(image, expected);
+
+    // Nothing to flash, or too far apart to flash as one image
+    let empty = elf32_segments(&[(1, 0x2000_0000, &[], 16)]);
+    assert_eq!(load_image(&empty), Err(ElfError::NoSegments));
+    let sparse = elf32_segments(&[(1, 0x0800_0000, &[1], 1), (1, 0x2000_0000, &[2], 1)]);
+    assert_eq!(load_image(&sparse), Err(ElfError::TooSparse(0x1800_0001)));
+    assert_eq!(load_image(&elf[..elf.len() - 30]), Err(ElfError::Truncated));
+}
+
+#[test]
+fn records_decode_from_a_firmware_image() {
+    let record = Record::new(
+        "0.1.0",
+        "0123456789abcdef0123456789abcdef01234567",
+        true,
+        1_750_000_000,
+        "release",
+        "board-g431rb,telemetry",
+    );
+    let bss = [0u8; 0];
+    let elf = elf32(&[
+        (".text", 1, &[0xAA; 12]),
+        (SECTION, 1, record.as_bytes()),
+        (".bss", 8, &bss),
+    ]);
+    assert_eq!(section(&elf, ".text").unwrap(), [0xAA; 12]);
+
+    let info = BuildInfo::from_elf(&elf).unwrap();
+    assert_eq!(info.format, 1);
+    assert_eq!(info.version, "0.1.0");
+    assert_eq!(info.commit, "0123456789abcdef0123456789abcdef01234567");
+    assert!(info.dirty);
+    assert_eq!(info.built(), "2025-06-15T15:06:40Z");
+    assert_eq!(info.profile, "release");
+    assert_eq!(info.features, ["board-g431rb", "telemetry"]);
+}
+
+#[test]
+fn sections_are_found_in_elf64_files() {
+    let exe = std::fs::read(std::env::current_exe().unwrap()).unwrap();
+    let info = BuildInfo::from_elf(&exe).unwrap();
+    let current = Record::CURRENT;
+    assert_eq!(info.commit, current.commit());
+    assert_eq!(info.dirty, current.dirty());
+    assert_eq!(info.timestamp, current.timestamp());
+    assert_eq!(info.features.join(","), current.features());
+    assert_eq!(CURRENT[..4], *b"BLDI");
+}
+
+#[test]
+fn bad_files_and_records_are_errors() {
+    let record = Record::new("0.1.0", "abc", false, 0, "debug", "");
+    assert_eq!(section(b"MZ\x90\0", SECTION), Err(ElfError::NotElf));
+    let mut big_endian = elf32(&[]);
+    big_endian[5] = 2;
+    assert_eq!(section(&big_endian, SECTION), Err(ElfError::Unsupported));
+
+    let elf = elf32(&[(SECTION, 1, record.as_bytes())]);
+    assert_eq!(
+        section(&elf[..elf.len() - 8], SECTION),
+        Err(ElfError::Truncated)
+    );
+    assert_eq!(
+        section(&elf, ".rodata"),
+        Err(ElfError::Missing(".rodata".into()))
+    );
+    let bss = elf32(&[(SECTION, 8, &[])]);
+    assert_eq!(
+        BuildInfo::from_elf(&bss),
+        Err(BuildInfoError::Elf(ElfError::NoData(SECTION.into())))
+    );
+
+    let bytes = record.as_bytes();
+    assert_eq!(
+        BuildInfo::decode(&bytes[..100]),
+        Err(BuildInfoError::TooShort(100))
+    );
+    let mut bad = *bytes;
+    bad[0] = b'X';
+    assert_eq!(BuildInfo::decode(&bad), Err(BuildInfoError::Magic));
+    bad = *bytes;
+    bad[4] = 0;
+    assert_eq!(BuildInfo::decode(&bad), Err(BuildInfoError::Version(0)));
+}
@@ -0,0 +1,216 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! The protocol layer against a simulated board behind a pseudo-terminal
+//!
+//! The simulator is built from the firmware library itself (shell input,
+//! command parser, LED state, replies and telemetry framing), so the CLI is
+//! exercised against the same bytes the board would send. It takes input in
+//! whatever bursts the pty delivers, the way `uart_rx_task` drains its DMA
+//! ring buffer, rather than one byte per read.
+
+use std::fs::File;
+use std::io::{Read, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use blink::app::{Led, LedMode};
+use blink::event::{AppEvent, ShellInput};
+use blink::messages;
+use blink::mock::MockPin;
+use blink::monitor::ErrorCounters;
+use blink::pattern::presets;
+use blink::settings::Settings;
+use blink::shell::{parse, Command, ParseError, MAX_LINE};
+use blink::telemetry::{Record, Telemetry};
+use blink_cli::protocol::{Device, Error};
+use blink_cli::script::Script;
+use blink_cli::status::{Level, Status};
+use blink_cli::tty::{Pty, SerialPort};
+use blink_telemetry::LedMode as WireMode;
+
+/// Pretend uptime reported by the simulator, in ms
+const UPTIME_MS: u64 = 12_345;
+
+/// Pretend RTC time that stamps the simulator's status lines
+const NOW: &str = "2025-06-01T12:00:00Z";
+
+/// Runs a simulated board on the master side of `pty` until it hangs up
+///
+/// With `noisy` set, every command is followed by a stamped `LED ON` notice and a
+/// telemetry frame written between the echo and the reply, as the firmware's
+/// pattern and telemetry output may interleave with a command. The size of the
+/// longest read is kept in `longest_read`.
+fn simulate(mut master: File, noisy: bool, longest_read: Arc<AtomicUsize>) {
+    let mut led = Led::new(MockPin::new(), presets::STEADY, &Settings::default());
+    let mut input = ShellInput::new();
+    let mut telemetry = Telemetry::new();
+    let mut buf = [0u8; MAX_LINE];
+    while let Ok(len @ 1..) = master.read(&mut buf) {
+        longest_read.fetch_max(len, Ordering::Relaxed);
+        for &byte in &buf[..len] {
+            // Echo typing the way the firmware shell does
+            let (echo, event) = input.push(byte);
+            let mut out = echo.to_vec();
+            let text = match event {
+                Some(AppEvent::Line(text)) if !text.is_empty() => text,
+                _ => {
+                    let _ = master.write_all(&out);
+                    continue;
+                }
+            };
+            if noisy {
+                out.extend_from_slice(format!("{NOW} ").as_bytes());
+                out.extend_from_slice(messages::LED_ON);
+            }
+            let cmd = parse(&text);
+            out.extend_from_slice(&execute(&mut led, cmd));
+            if noisy {
+                let record = Record::sample(&led, UPTIME_MS, UPTIME_MS, ErrorCounters::default());
+                let frame = telemetry.frame(&record);
+                // Frame lands between the echo and the reply
+                let at = out.len() - 4;
+                out.splice(at..at, frame.iter().copied());
+            }
+            out.extend_from_slice(messages::PROMPT);
+            if master.write_all(&out).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Executes the subset of shell commands the tests use
+fn execute(led: &mut Led<MockPin>, cmd: Result<Command<'_>, ParseError>) -> Vec<u8> {
+    let reply: &[u8] = match cmd {
+        Ok(Command::LedOn) => {
+            led.hold(true);
+            messages::OK
+        }
+        Ok(Command::LedOff) => {
+            led.hold(false);
+            messages::OK
+        }
+        Ok(Command::LedPattern(index)) => {
+            led.select_preset(index);
+            messages::OK
+        }
+        Ok(Command::Status) => {
+            let mode = match led.mode() {
+                LedMode::Manual => "manual",
+                _ => "pattern",
+            };
+            let level = if led.is_on() { "ON" } else { "OFF" };
+            return format!(
+                "{NOW} LED {level}, mode {mode}, pattern {}, uptime 12.345 s, tx errors 0, rx errors 0, boot #7\r\n",
+                led.pattern_name()
+            )
+            .into_bytes();
+        }
+        Ok(Command::TimeGet) => return format!("{NOW}\r\n").into_bytes(),
+        Ok(_) => messages::OK,
+        Err(ParseError::UnknownCommand) => messages::ERR_UNKNOWN,
+        Err(ParseError::MissingArgument) => messages::ERR_MISSING,
+        Err(ParseError::TooManyArguments) => messages::ERR_TOO_MANY,
+        Err(_) => messages::ERR_INVALID,
+    };
+    reply.to_vec()
+}
+
+/// Starts a simulated board and connects a `Device` to it over a pty
+///
+/// # Returns
+/// The device, the pty (closing it stops the board) and the size of the
+/// longest burst the board has read so far.
+fn connect(noisy: bool) -> (Device<SerialPort>, Pty, Arc<AtomicUsize>) {
+    let pty = Pty::open().unwrap();
+    let master = pty.master.try_clone().unwrap();
+    let longest_read = Arc::new(AtomicUsize::new(0));
+    let board = Arc::clone(&longest_read);
+    std::thread::spawn(move || simulate(master, noisy, board));
+    let port = SerialPort::open(pty.slave_path(), 115_200, Duration::from_millis(100)).unwrap();
+    (Device::new(port), pty, longest_read)
+}
+
+#[test]
+fn commands_and_status_round_trip() {
+    let (mut device, _pty, _) = connect(false);
+    assert_eq!(device.command("led pattern sos").unwrap(), ["OK"]);
+    assert_eq!(device.command("led on").unwrap(), ["OK"]);
+    assert_eq!(
+        device.status().unwrap(),
+        Status {
+            led: Level::On,
+            mode: "manual".into(),
+            pattern: "sos".into(),
+            uptime_ms: UPTIME_MS,
+            tx_errors: 0,
+            rx_errors: 0,
+            boot: 7,
+            temperature_tenths_c: None,
+            vdda_mv: None,
+            time: Some(NOW.into()),
+        }
+    );
+    assert_eq!(device.command("time").unwrap(), [NOW]);
+}
+
+#[test]
+fn command_lines_arrive_in_one_burst() {
+    let (mut device, _pty, longest_read) = connect(false);
+    let line = "led pattern double-flash";
+    assert_eq!(device.command(line).unwrap(), ["OK"]);
+    assert_eq!(longest_read.load(Ordering::Relaxed), line.len() + 1);
+    assert_eq!(device.status().unwrap().pattern, "double-flash");
+}
+
+#[test]
+fn error_replies_become_rejections() {
+    let (mut device, _pty, _) = connect(false);
+    match device.command("led pattern disco") {
+        Err(Error::Rejected(line)) => assert_eq!(line, "ERR invalid argument"),
+        other => panic!("expected a rejection, got {other:?}"),
+    }
+    // The shell is still in step afterwards
+    assert_eq!(device.command("led off").unwrap(), ["OK"]);
+}
+
+#[test]
+fn notices_and_frames_are_kept_out_of_replies() {
+    let (mut device, _pty, _) = connect(true);
+    assert_eq!(device.command("led on").unwrap(), ["OK"]);
+    let record = device.next_record(Duration::from_secs(1)).unwrap();
+    assert_eq!(record.led_mode, WireMode::Manual);
+    assert!(record.led_on);
+    assert_eq!(record.seq, 0);
+
+    assert_eq!(device.command("led pattern heartbeat").unwrap(), ["OK"]);
+    let record = device.next_record(Duration::from_secs(1)).unwrap();
+    assert_eq!(record.led_mode, WireMode::Pattern);
+    assert_eq!(record.seq, 1);
+}
+
+#[test]
+fn scripts_run_until_the_first_rejected_command() {
+    let (mut device, _pty, _) = connect(false);
+    let script = Script::parse(
+        "# demo\n\
+         led pattern heartbeat\n\
+         wait 10\n\
+         led pattern sos\n\
+         led strobe\n\
+         led off\n",
+    )
+    .unwrap();
+    let mut sent = Vec::new();
+    let err = script
+        .run(&mut device, |cmd, reply| {
+            sent.push((cmd.to_owned(), reply.to_vec()));
+        })
+        .unwrap_err();
+    assert_eq!(err.line, 5);
+    assert!(matches!(err.error, Error::Rejected(_)));
+    assert_eq!(sent.len(), 2);
+    assert_eq!(device.status().unwrap().pattern, "sos");
+}
@@ -0,0 +1,154 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Status reports, scripts and stream demultiplexing, without a port
+
+use std::time::Duration;
+
+use blink::app::Led;
+use blink::mock::MockPin;
+use blink::monitor::ErrorCounters;
+use blink::pattern::presets;
+use blink::settings::Settings;
+use blink::telemetry::{Record, Telemetry};
+use blink_cli::protocol::{Demux, Event};
+use blink_cli::script::{Script, Step};
+use blink_cli::status::{split_timestamp, Level, Status};
+
+/// Frames one telemetry record the way the firmware does
+fn frame(telemetry: &mut Telemetry) -> Vec<u8> {
+    let led = Led::new(MockPin::new(), presets::STEADY, &Settings::default());
+    let record = Record::sample(&led, 1, 2, ErrorCounters::default());
+    telemetry.frame(&record).to_vec()
+}
+
+#[test]
+fn status_lines_parse_by_label() {
+    let status: Status =
+        "LED 42%, mode breathe, pattern steady, uptime 3.007 s, tx errors 2, rx errors 5, boot #11"
+            .parse()
+            .unwrap();
+    assert_eq!(status.led, Level::Percent(42));
+    assert_eq!(status.mode, "breathe");
+    assert_eq!(status.uptime_ms, 3_007);
+    assert_eq!(status.tx_errors, 2);
+    assert_eq!(status.rx_errors, 5);
+    assert_eq!(status.boot, 11);
+
+    // Appended fields are ignored; missing ones are not
+    assert!(
+        "LED OFF, mode manual, pattern sos, uptime 0.000 s, tx errors 0, rx errors 0, boot #1, temp 25 C"
+            .parse::<Status>()
+            .is_ok()
+    );
+    assert!("LED OFF, mode manual".parse::<Status>().is_err());
+}
+
+#[test]
+fn status_lines_carry_sensor_readings() {
+    let line =
+        "LED ON, mode pattern, pattern steady, uptime 1.000 s, tx errors 0, rx errors 0, boot #2";
+    let status: Status = format!("{line}, temp -12.3 C, vdda 3.29 V")
+        .parse()
+        .unwrap();
+    assert_eq!(status.temperature_tenths_c, Some(-123));
+    assert_eq!(status.vdda_mv, Some(3290));
+
+    // Older firmware (or no reading yet) leaves them out
+    let status: Status = line.parse().unwrap();
+    assert_eq!(status.temperature_tenths_c, None);
+    assert_eq!(status.vdda_mv, None);
+
+    // A malformed reading is dropped, not fatal
+    let status: Status = format!("{line}, temp hot C, vdda 3.2999 V")
+        .parse()
+        .unwrap();
+    assert_eq!(status.temperature_tenths_c, None);
+    assert_eq!(status.vdda_mv, None);
+    assert!("OK".parse::<Status>().is_err());
+}
+
+#[test]
+fn status_lines_may_carry_an_rtc_timestamp() {
+    let line =
+        "LED OFF, mode manual, pattern sos, uptime 0.500 s, tx errors 0, rx errors 0, boot #4";
+    let status: Status = format!("2025-06-01T12:34:56Z {line}").parse().unwrap();
+    assert_eq!(status.time.as_deref(), Some("2025-06-01T12:34:56Z"));
+    assert_eq!(status.led, Level::Off);
+    assert_eq!(line.parse::<Status>().unwrap().time, None);
+
+    assert_eq!(
+        split_timestamp("2025-06-01T12:34:56Z LED ON"),
+        (Some("2025-06-01T12:34:56Z"), "LED ON")
+    );
+    assert_eq!(
+        split_timestamp("2025-06-01 12:34:56 LED ON"),
+        (None, "2025-06-01 12:34:56 LED ON")
+    );
+    assert_eq!(
+        split_timestamp("2025-06-01T12:34:56Z"),
+        (None, "2025-06-01T12:34:56Z")
+    );
+}
+
+#[test]
+fn scripts_skip_comments_and_parse_waits() {
+    let script = Script::parse("# x\n\n  led blink 100  \nWAIT 250\n").unwrap();
+    assert_eq!(
+        script.steps,
+        [
+            (3, Step::Command("led blink 100".into())),
+            (4, Step::Wait(Duration::from_millis(250))),
+        ]
+    );
+    assert_eq!(Script::parse("led on\nwait soon\n").unwrap_err().line, 2);
+    assert_eq!(Script::parse("wait\n").unwrap_err().line, 1);
+}
+
+#[test]
+fn demux_separates_text_from_frames() {
+    let mut telemetry = Telemetry::new();
+    let mut stream = b"LED ON\r\n".to_vec();
+    stream.extend(frame(&mut telemetry));
+    stream.extend_from_slice(b"OK\r\n");
+    stream.extend(frame(&mut telemetry));
+
+    // Byte-at-a-time delivery must give the same result as one read
+    let mut events = Vec::new();
+    let mut demux = Demux::new();
+    for byte in &stream {
+        demux.feed(std::slice::from_ref(byte), &mut events);
+    }
+    let text: String = events
+        .iter()
+        .filter_map(|e| match e {
+            Event::Text(t) => Some(t.as_str()),
+            _ => None,
+        })
+        .collect();
+    let seqs: Vec<u32> = events
+        .iter()
+        .filter_map(|e| match e {
+            Event::Record(r) => Some(r.seq),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(text, "LED ON\r\nOK\r\n");
+    assert_eq!(seqs, [0, 1]);
+}
+
+#[test]
+fn demux_resynchronises_after_a_lost_delimiter() {
+    let mut telemetry = Telemetry::new();
+    let first = frame(&mut telemetry);
+    let second = frame(&mut telemetry);
+    // Drop the first frame's closing delimiter so its bytes run into the text
+    let mut stream = first[..first.len() - 1].to_vec();
+    stream.extend_from_slice(b"hi");
+    stream.extend(&second);
+
+    let mut events = Vec::new();
+    Demux::new().feed(&stream, &mut events);
+    assert!(matches!(events[0], Event::Corrupt(_)));
+    assert!(matches!(events.last(), Some(Event::Record(r)) if r.seq == 1));
+}
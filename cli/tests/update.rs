@@ -0,0 +1,213 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Firmware uploads against a simulated bootloader behind a pseudo-terminal
+//!
+//! The simulator runs the firmware library's bootloader logic (frame reader,
+//! request handling, slot swap) on a model flash, so the uploader is checked
+//! against the same replies and the same flash contents as on the board.
+
+use std::fs::File;
+use std::io::{Read, Write};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use blink::boot::{request_update, BootState, Bootloader, Partitions, Start};
+use blink::messages;
+use blink::mock::MockFlash;
+use blink::settings::SettingsFlash;
+use blink::update::{FrameReader, Reply, Request};
+use blink_cli::tty::{Pty, SerialPort};
+use blink_cli::update::{crc32, Image, UpdateError, Uploader};
+
+/// 1 KB pages: 4 for the bootloader, 2 of state, two 16 KB slots and scratch
+const PARTS: Partitions = Partitions {
+    flash_origin: 0x0800_0000,
+    state: 0x1000,
+    active: 0x1800,
+    download: 0x5800,
+    scratch: 0x9800,
+    slot_len: 0x4000,
+    page_size: 0x400,
+    ram: [(0x2000_0000, 0x1_0000), (0, 0)],
+};
+
+type Flash = MockFlash<0x9C00>;
+
+/// Ways the simulator mangles one of its replies
+#[derive(Clone, Copy)]
+enum Fault {
+    /// The reply never arrives
+    Drop,
+    /// A bit of the reply flips on the way
+    Corrupt,
+}
+
+/// Builds an image of `len` bytes linked for the active slot
+fn image(len: usize, seed: u8) -> Vec<u8> {
+    let mut image: Vec<u8> = (0..len).map(|i| (i as u8).wrapping_mul(seed)).collect();
+    image[..4].copy_from_slice(&0x2001_0000u32.to_le_bytes());
+    image[4..8].copy_from_slice(&(PARTS.app_address() + 0x201).to_le_bytes());
+    image
+}
+
+/// Runs a simulated bootloader on the master side of a pty until `Boot`
+///
+/// Starts with the text the application sends before it resets, then mangles
+/// the replies numbered (from 1) in `faults`.
+///
+/// # Returns
+/// The flash as the bootloader left it.
+fn simulate(mut master: File, mut flash: Flash, faults: &[(usize, Fault)]) -> Flash {
+    let mut bootloader = Bootloader::new(&mut flash, PARTS);
+    assert_eq!(bootloader.start(), Start::Update);
+    let mut reader = FrameReader::new();
+    let mut replies = 0;
+    let mut byte = [0u8; 1];
+    master.write_all(messages::UPDATING).unwrap();
+    while let Ok(1) = master.read(&mut byte) {
+        let reply = match reader.push(byte[0]) {
+            None => continue,
+            Some(Ok(frame)) => match Request::decode(frame) {
+                Ok(request) => bootloader.handle(request),
+                Err(nak) => Reply::Nak(nak),
+            },
+            Some(Err(nak)) => Reply::Nak(nak),
+        };
+        let mut frame = reply.frame().to_vec();
+        replies += 1;
+        match faults.iter().find(|(n, _)| *n == replies) {
+            Some((_, Fault::Drop)) => frame.clear(),
+            Some((_, Fault::Corrupt)) => frame[3] ^= 0x10,
+            None => {}
+        }
+        master.write_all(&frame).unwrap();
+        if bootloader.reset_requested() {
+            break;
+        }
+    }
+    flash
+}
+
+/// Starts a simulated bootloader over `flash` and connects an `Uploader`
+fn connect(
+    flash: Flash,
+    faults: Vec<(usize, Fault)>,
+) -> (Uploader<SerialPort>, Pty, JoinHandle<Flash>) {
+    let pty = Pty::open().unwrap();
+    let master = pty.master.try_clone().unwrap();
+    let board = std::thread::spawn(move || simulate(master, flash, &faults));
+    let port = SerialPort::open(pty.slave_path(), 115_200, Duration::from_millis(50)).unwrap();
+    let mut uploader = Uploader::new(port);
+    uploader.set_timeout(Duration::from_millis(300));
+    (uploader, pty, board)
+}
+
+/// Returns a flash holding `installed` whose application asked for an update
+fn flash_with(installed: &[u8]) -> Flash {
+    let mut flash = Flash::new(0, PARTS.page_size);
+    flash.write(PARTS.active, installed).unwrap();
+    request_update(&mut flash, &PARTS).unwrap();
+    flash
+}
+
+/// Returns `len` bytes of flash at `offset`
+fn contents(flash: &mut Flash, offset: u32, len: usize) -> Vec<u8> {
+    let mut buf = vec![0u8; len];
+    flash.read(offset, &mut buf).unwrap();
+    buf
+}
+
+/// Resets the simulated board and checks that `expected` runs on trial
+fn assert_installed(mut flash: Flash, expected: &[u8]) {
+    let mut bootloader = Bootloader::new(&mut flash, PARTS);
+    assert_eq!(bootloader.start(), Start::Run);
+    assert!(matches!(bootloader.state(), BootState::Testing { .. }));
+    assert_eq!(contents(&mut flash, PARTS.active, expected.len()), expected);
+}
+
+#[test]
+fn crc32_matches_the_bootloader() {
+    let data = image(3000, 7);
+    assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    assert_eq!(crc32(&data), blink::crc::crc32(&data));
+}
+
+#[test]
+fn an_image_is_uploaded_and_installed() {
+    let new = image(5000, 7);
+    let (mut uploader, _pty, board) = connect(flash_with(&image(9000, 3)), Vec::new());
+    let mut progress = Vec::new();
+    let image = Image::load(&new).unwrap();
+    assert_eq!(image.address, None);
+    let info = uploader
+        .install(&image, |done| progress.push(done))
+        .unwrap();
+    assert_eq!(
+        (
+            info.slot_address,
+            info.slot_len,
+            info.page_size,
+            info.max_chunk
+        ),
+        (0x0800_1800, 0x4000, 0x400, 128)
+    );
+    assert_eq!(progress.len(), 40);
+    assert_eq!(progress.last(), Some(&5000));
+    assert_installed(board.join().unwrap(), &new);
+}
+
+#[test]
+fn lost_and_corrupt_replies_are_retried() {
+    let new = image(1000, 9);
+    // Reply 4 (to `Begin`) is left alone: it waits out `ERASE_TIMEOUT`
+    let faults = vec![
+        (1, Fault::Corrupt), // Info
+        (2, Fault::Drop),    // Info to the repeated `Hello`
+        (5, Fault::Drop),    // Ack to the first chunk
+        (6, Fault::Corrupt), // Ack to the repeated first chunk
+    ];
+    let (mut uploader, _pty, board) = connect(flash_with(&image(2000, 3)), faults);
+    let image = Image::load(&new).unwrap();
+    uploader.install(&image, |_| {}).unwrap();
+    assert_installed(board.join().unwrap(), &new);
+}
+
+#[test]
+fn mismatched_images_are_refused() {
+    let installed = image(2000, 3);
+    let (mut uploader, _pty, board) = connect(flash_with(&installed), Vec::new());
+
+    // Checked against the slot before anything is written
+    let foreign = Image {
+        address: Some(0x0800_0000),
+        data: image(1000, 5),
+    };
+    assert!(matches!(
+        uploader.install(&foreign, |_| {}),
+        Err(UpdateError::WrongAddress {
+            image: 0x0800_0000,
+            slot: 0x0800_1800
+        })
+    ));
+    let huge = Image::load(&image(0x4001, 5)).unwrap();
+    assert!(matches!(
+        uploader.install(&huge, |_| {}),
+        Err(UpdateError::TooLarge { len: 0x4001, .. })
+    ));
+
+    // Checked by the bootloader once uploaded
+    let mut data = image(1000, 5);
+    data[4..8].copy_from_slice(&0x0800_0101u32.to_le_bytes());
+    let err = uploader
+        .install(&Image::load(&data).unwrap(), |_| {})
+        .unwrap_err();
+    assert_eq!(err.to_string(), "bootloader refused: invalid vector table");
+
+    // The board still takes a good image afterwards
+    let new = image(1000, 11);
+    uploader
+        .install(&Image::load(&new).unwrap(), |_| {})
+        .unwrap();
+    assert_installed(board.join().unwrap(), &new);
+}
@@ -0,0 +1,930 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Memory layout descriptors and linker script generation
+//!
+//! Shared by `build.rs` (which turns the selected board's descriptor into
+//! `memory.x`) and the host tests (which check the validation rules):
+//! - `Descriptor` - a parsed `memory/<board>.toml`
+//! - `Chip` / `CHIPS` - the real flash, RAM and erase geometry of each supported chip
+//! - `Layout` - a descriptor validated against its chip, with the flash split into
+//!   bootloader, application and settings partitions (plus the update slots of
+//!   the serial bootloader, `UpdateSlots`)
+//!
+//! # Descriptor Format
+//! A small TOML subset: `key = value` lines, `[section]` headers and `#`
+//! comments. Values are integers (decimal or `0x` hex) or quoted sizes with a
+//! `K` or `M` suffix.
+//!
+//! ```toml
+//! chip = "STM32F303RE"
+//!
+//! [flash]
+//! origin = 0x08000000
+//! length = "512K"
+//!
+//! [ram]
+//! origin = 0x20000000
+//! length = "64K"
+//!
+//! [ccmram]            # optional
+//! origin = 0x10000000
+//! length = "16K"
+//! stack = true        # optional; put the main stack at the top of CCM RAM
+//!
+//! [settings]          # optional; reserved at the end of flash
+//! pages = 2
+//! page_size = "2K"
+//! offset = "32K"      # optional; reserve these pages inside flash instead
+//!
+//! [bootloader]        # optional; reserved at the start of flash
+//! length = "32K"
+//! slot = "232K"       # optional; the application slot size for `src/bin/bootloader.rs`
+//! ```
+//!
+//! With a `slot`, the flash after the bootloader holds two boot state pages,
+//! the active slot (where the application is linked), a download slot of the
+//! same size and a scratch page for swapping the two. Slots need a chip with
+//! uniform erase pages.
+//!
+//! With a settings `offset`, the pages sit inside the application's flash, after
+//! the vector table: `memory.x` starts `.text` (`_stext`) past them. This lets a
+//! chip with small sectors at the start of flash and large ones at the end (the
+//! F4) keep its settings in small, quickly erased sectors.
+//!
+//! # Design Philosophy
+//! The descriptor states what the board needs; the chip table states what the
+//! silicon has. Keeping them apart means a typo in a descriptor (a 256K RAM, a
+//! settings page that straddles two F4 sectors) fails the build with a message
+//! instead of producing a binary that faults or erases its own code at runtime.
+//! The file is plain `std` Rust with no dependencies so the build script can
+//! include it directly.
+
+use std::fmt;
+
+/// One kibibyte
+pub const K: u32 = 1024;
+
+/// A contiguous address range
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Region {
+    /// First address
+    pub origin: u32,
+    /// Size in bytes
+    pub length: u32,
+}
+
+impl Region {
+    /// Creates a region of `length` bytes starting at `origin`
+    pub const fn new(origin: u32, length: u32) -> Self {
+        Self { origin, length }
+    }
+
+    /// Returns the first address past the region
+    pub fn end(&self) -> u64 {
+        u64::from(self.origin) + u64::from(self.length)
+    }
+
+    /// Returns `true` if the two regions share at least one address
+    pub fn overlaps(&self, other: &Region) -> bool {
+        u64::from(self.origin) < other.end() && u64::from(other.origin) < self.end()
+    }
+}
+
+/// How a chip's flash is divided into erasable units
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Erase {
+    /// Equal pages of the given size
+    Uniform(u32),
+    /// Sectors of the given sizes, in address order (STM32F4)
+    Sectors(&'static [u32]),
+}
+
+impl Erase {
+    /// Returns the erasable unit containing `offset` as `(start offset, size)`
+    pub fn unit_at(&self, offset: u32) -> Option<(u32, u32)> {
+        match *self {
+            Self::Uniform(page) => Some((offset - offset % page, page)),
+            Self::Sectors(sizes) => {
+                let mut start = 0u32;
+                for &size in sizes {
+                    if offset < start + size {
+                        return Some((start, size));
+                    }
+                    start += size;
+                }
+                None
+            }
+        }
+    }
+
+    /// Returns `true` if `offset` is the start of an erasable unit
+    pub fn is_boundary(&self, offset: u32) -> bool {
+        self.unit_at(offset)
+            .is_some_and(|(start, _)| start == offset)
+    }
+}
+
+/// Memory geometry of a supported chip
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Chip {
+    /// Part name without package suffix, as used in descriptors
+    pub name: &'static str,
+    /// Main flash
+    pub flash: Region,
+    /// SRAM mapped contiguously at 0x20000000
+    pub ram: Region,
+    /// Core-coupled RAM (CPU-only, no DMA), if the chip has any
+    pub ccmram: Option<Region>,
+    /// Flash erase geometry
+    pub erase: Erase,
+}
+
+/// Chips with a board in `memory/`
+///
+/// Reference: the memory mapping section of each chip's datasheet.
+pub const CHIPS: &[Chip] = &[
+    Chip {
+        name: "STM32F303RE",
+        flash: Region::new(0x0800_0000, 512 * K),
+        ram: Region::new(0x2000_0000, 64 * K),
+        ccmram: Some(Region::new(0x1000_0000, 16 * K)),
+        erase: Erase::Uniform(2 * K),
+    },
+    Chip {
+        name: "STM32F401RE",
+        flash: Region::new(0x0800_0000, 512 * K),
+        ram: Region::new(0x2000_0000, 96 * K),
+        ccmram: None,
+        erase: Erase::Sectors(&[
+            16 * K,
+            16 * K,
+            16 * K,
+            16 * K,
+            64 * K,
+            128 * K,
+            128 * K,
+            128 * K,
+        ]),
+    },
+    Chip {
+        name: "STM32L476RG",
+        flash: Region::new(0x0800_0000, 1024 * K),
+        ram: Region::new(0x2000_0000, 96 * K),
+        ccmram: None,
+        erase: Erase::Uniform(2 * K),
+    },
+    Chip {
+        name: "STM32G431RB",
+        flash: Region::new(0x0800_0000, 128 * K),
+        ram: Region::new(0x2000_0000, 22 * K),
+        ccmram: Some(Region::new(0x1000_0000, 10 * K)),
+        erase: Erase::Uniform(2 * K),
+    },
+];
+
+/// Looks up a chip by its descriptor name
+pub fn chip(name: &str) -> Option<&'static Chip> {
+    CHIPS.iter().find(|c| c.name.eq_ignore_ascii_case(name))
+}
+
+/// Settings pages reserved in flash
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SettingsPages {
+    /// Number of pages (the settings store needs two)
+    pub pages: u32,
+    /// Size of each page in bytes; must equal the chip's erasable unit
+    pub page_size: u32,
+    /// Flash offset of the first page, if not at the end of flash
+    pub offset: Option<u32>,
+}
+
+/// A parsed board memory descriptor
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Descriptor {
+    /// Chip the board carries
+    pub chip: String,
+    /// Main flash
+    pub flash: Region,
+    /// Main RAM
+    pub ram: Region,
+    /// Core-coupled RAM, if the linker should be given it
+    pub ccmram: Option<Region>,
+    /// Whether the main stack lives at the top of `ccmram` instead of RAM
+    pub ccmram_stack: bool,
+    /// Settings pages, if any
+    pub settings: Option<SettingsPages>,
+    /// Bytes reserved for a bootloader at the start of flash
+    pub bootloader: u32,
+    /// Application slot size for the serial bootloader, if it is used
+    pub slot: Option<u32>,
+}
+
+/// Reasons a descriptor is rejected
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LayoutError {
+    /// A line could not be parsed
+    Syntax { line: usize, message: String },
+    /// A required key is absent
+    Missing(&'static str),
+    /// The descriptor names a chip missing from `CHIPS`
+    UnknownChip(String),
+    /// The descriptor is for a different chip than the one selected
+    WrongChip {
+        descriptor: String,
+        selected: String,
+    },
+    /// A region does not match the chip's
+    Mismatch {
+        region: &'static str,
+        declared: Region,
+        chip: Option<Region>,
+    },
+    /// A partition boundary does not fall on an erasable unit
+    Misaligned { what: &'static str, offset: u32 },
+    /// The settings page size differs from the chip's erasable unit there
+    PageSize { declared: u32, erase: u32 },
+    /// Bootloader and settings leave no flash for the application
+    NoRoom { needed: u64, flash: u32 },
+    /// Update slots on a chip whose erasable units differ in size
+    UnevenErase,
+    /// Two regions share addresses
+    Overlap(&'static str, &'static str),
+}
+
+impl fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Syntax { line, message } => write!(f, "line {line}: {message}"),
+            Self::Missing(key) => write!(f, "missing `{key}`"),
+            Self::UnknownChip(name) => {
+                let known: Vec<&str> = CHIPS.iter().map(|c| c.name).collect();
+                write!(f, "unknown chip `{name}` (known: {})", known.join(", "))
+            }
+            Self::WrongChip {
+                descriptor,
+                selected,
+            } => write!(
+                f,
+                "descriptor is for {descriptor} but the selected board has a {selected}"
+            ),
+            Self::Mismatch {
+                region,
+                declared,
+                chip: Some(chip),
+            } => write!(
+                f,
+                "[{region}] is {} at {:#010x}, but the chip has {} at {:#010x}",
+                size(declared.length),
+                declared.origin,
+                size(chip.length),
+                chip.origin
+            ),
+            Self::Mismatch { region, .. } => write!(f, "the chip has no {region}"),
+            Self::Misaligned { what, offset } => write!(
+                f,
+                "{what} at flash offset {offset:#x} is not on an erase boundary"
+            ),
+            Self::PageSize { declared, erase } => write!(
+                f,
+                "settings page_size is {} but the chip erases {} there",
+                size(*declared),
+                size(*erase)
+            ),
+            Self::NoRoom { needed, flash } => write!(
+                f,
+                "the reserved partitions need {needed} bytes, leaving no application in the {} flash",
+                size(*flash)
+            ),
+            Self::UnevenErase => write!(
+                f,
+                "bootloader slots need equal erase pages, but the chip's sectors differ in size"
+            ),
+            Self::Overlap(a, b) => write!(f, "{a} overlaps {b}"),
+        }
+    }
+}
+
+/// Formats a byte count the way linker scripts do (`508K`, `1M`, `100`)
+pub fn size(bytes: u32) -> String {
+    if bytes != 0 && bytes.is_multiple_of(1024 * K) {
+        format!("{}M", bytes / (1024 * K))
+    } else if bytes.is_multiple_of(K) {
+        format!("{}K", bytes / K)
+    } else {
+        bytes.to_string()
+    }
+}
+
+impl Descriptor {
+    /// Parses a descriptor
+    ///
+    /// # Errors
+    /// `Syntax` for malformed lines or unknown keys, `Missing` for absent
+    /// required keys.
+    pub fn parse(text: &str) -> Result<Self, LayoutError> {
+        let mut section = String::new();
+        let mut values: Vec<(String, u32, usize)> = Vec::new();
+        let mut chip = None;
+        for (index, raw) in text.lines().enumerate() {
+            let line_no = index + 1;
+            let syntax = |message: String| LayoutError::Syntax {
+                line: line_no,
+                message,
+            };
+            // Strip comments and blank lines
+            let line = raw.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[') {
+                let name = name
+                    .strip_suffix(']')
+                    .ok_or_else(|| syntax("unterminated section header".into()))?;
+                section = name.trim().to_owned();
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| syntax(format!("expected `key = value`, found `{line}`")))?;
+            let key = if section.is_empty() {
+                key.trim().to_owned()
+            } else {
+                format!("{section}.{}", key.trim())
+            };
+            let value = value.trim();
+            if key == "chip" {
+                chip = Some(unquote(value).to_owned());
+                continue;
+            }
+            if !KEYS.contains(&key.as_str()) {
+                return Err(syntax(format!("unknown key `{key}`")));
+            }
+            if key == "ccmram.stack" {
+                let flag = match value {
+                    "true" => 1,
+                    "false" => 0,
+                    _ => return Err(syntax(format!("`{key}`: expected true or false"))),
+                };
+                values.push((key, flag, line_no));
+                continue;
+            }
+            let number = parse_number(unquote(value))
+                .ok_or_else(|| syntax(format!("`{key}`: invalid number `{value}`")))?;
+            values.push((key, number, line_no));
+        }
+
+        let get = |key: &str| values.iter().find(|(k, _, _)| k == key).map(|(_, v, _)| *v);
+        let region = |origin, length| match (get(origin), get(length)) {
+            (Some(o), Some(l)) => Ok(Some(Region::new(o, l))),
+            (None, None) => Ok(None),
+            (None, Some(_)) => Err(LayoutError::Missing(origin)),
+            (Some(_), None) => Err(LayoutError::Missing(length)),
+        };
+        let settings = match (get("settings.pages"), get("settings.page_size")) {
+            (Some(pages), Some(page_size)) => Some(SettingsPages {
+                pages,
+                page_size,
+                offset: get("settings.offset"),
+            }),
+            (None, None) => None,
+            (None, Some(_)) => return Err(LayoutError::Missing("settings.pages")),
+            (Some(_), None) => return Err(LayoutError::Missing("settings.page_size")),
+        };
+        let ccmram = region("ccmram.origin", "ccmram.length")?;
+        let ccmram_stack = get("ccmram.stack") == Some(1);
+        if ccmram_stack && ccmram.is_none() {
+            return Err(LayoutError::Missing("ccmram.origin"));
+        }
+        Ok(Self {
+            chip: chip.ok_or(LayoutError::Missing("chip"))?,
+            flash: region("flash.origin", "flash.length")?
+                .ok_or(LayoutError::Missing("flash.origin"))?,
+            ram: region("ram.origin", "ram.length")?.ok_or(LayoutError::Missing("ram.origin"))?,
+            ccmram,
+            ccmram_stack,
+            settings,
+            bootloader: get("bootloader.length").unwrap_or(0),
+            slot: get("bootloader.slot"),
+        })
+    }
+}
+
+/// Numeric keys a descriptor may contain
+const KEYS: &[&str] = &[
+    "flash.origin",
+    "flash.length",
+    "ram.origin",
+    "ram.length",
+    "ccmram.origin",
+    "ccmram.length",
+    "ccmram.stack",
+    "settings.pages",
+    "settings.page_size",
+    "settings.offset",
+    "bootloader.length",
+    "bootloader.slot",
+];
+
+/// Removes surrounding double quotes, if any
+fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value)
+}
+
+/// Parses `123`, `0x1F000`, `2K` or `1M` (underscores allowed)
+fn parse_number(text: &str) -> Option<u32> {
+    let text = text.replace('_', "");
+    let (digits, scale) = match text.as_bytes().last()? {
+        b'K' | b'k' => (&text[..text.len() - 1], K),
+        b'M' | b'm' => (&text[..text.len() - 1], 1024 * K),
+        _ => (text.as_str(), 1),
+    };
+    let value = match digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+        None => digits.parse().ok()?,
+    };
+    value.checked_mul(scale)
+}
+
+/// Boot state pages of the serial bootloader
+pub const BOOT_STATE_PAGES: u32 = 2;
+
+/// RAM the bootloader links into, at the top of RAM
+///
+/// The application's `.uninit` crash record sits low in RAM and survives the
+/// bootloader running in between.
+pub const BOOTLOADER_RAM: u32 = 8 * K;
+
+/// Partitions the serial bootloader adds around the application slot
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UpdateSlots {
+    /// Boot state log (`BOOT_STATE_PAGES` pages) right after the bootloader
+    pub state: Region,
+    /// Where an uploaded image is received, the size of the application slot
+    pub download: Region,
+    /// One page used while exchanging the slots
+    pub scratch: Region,
+    /// Erase page size of all of the above
+    pub page_size: u32,
+}
+
+/// A descriptor validated against its chip
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Layout {
+    /// The chip the layout was validated against
+    pub chip: &'static Chip,
+    /// Bootloader partition at the start of flash (empty if none)
+    pub bootloader: Region,
+    /// Flash given to the linker (`FLASH` in `memory.x`); the active slot
+    /// when there are update slots
+    pub app: Region,
+    /// Update slots of the serial bootloader, if the descriptor has a `slot`
+    pub update: Option<UpdateSlots>,
+    /// Settings pages, at the end of flash or at their `offset` (empty if none)
+    pub settings: Region,
+    /// Start of `.text` (`_stext`) when the settings sit inside `app`, after
+    /// the vector table
+    pub text: Option<u32>,
+    /// Settings page size (0 if none)
+    pub settings_page_size: u32,
+    /// RAM given to the linker
+    pub ram: Region,
+    /// CCM RAM given to the linker, if declared
+    pub ccmram: Option<Region>,
+    /// Whether the main stack lives at the top of `ccmram`
+    pub ccmram_stack: bool,
+}
+
+impl Layout {
+    /// Validates `descriptor` against `selected`, the chip of the board being built
+    ///
+    /// # Errors
+    /// Any `LayoutError` other than `Syntax` and `Missing`.
+    pub fn new(descriptor: &Descriptor, selected: &str) -> Result<Self, LayoutError> {
+        // The descriptor must describe the selected chip, and the chip must be known
+        let chip = chip(&descriptor.chip)
+            .ok_or_else(|| LayoutError::UnknownChip(descriptor.chip.clone()))?;
+        if !chip.name.eq_ignore_ascii_case(selected) {
+            return Err(LayoutError::WrongChip {
+                descriptor: chip.name.into(),
+                selected: selected.into(),
+            });
+        }
+
+        // Declared regions must be the chip's
+        let check = |region: &'static str, declared: Region, actual: Option<Region>| {
+            if Some(declared) == actual {
+                Ok(())
+            } else {
+                Err(LayoutError::Mismatch {
+                    region,
+                    declared,
+                    chip: actual,
+                })
+            }
+        };
+        check("flash", descriptor.flash, Some(chip.flash))?;
+        check("ram", descriptor.ram, Some(chip.ram))?;
+        if let Some(ccmram) = descriptor.ccmram {
+            check("ccmram", ccmram, chip.ccmram)?;
+        }
+
+        // Split the flash: bootloader | application | settings, or with a
+        // settings offset: bootloader | vector table | settings | application
+        let settings_len = descriptor
+            .settings
+            .map_or(0, |s| u64::from(s.pages) * u64::from(s.page_size));
+        let reserved = u64::from(descriptor.bootloader) + settings_len;
+        if reserved >= u64::from(chip.flash.length) {
+            return Err(LayoutError::NoRoom {
+                needed: reserved,
+                flash: chip.flash.length,
+            });
+        }
+        let inside = descriptor.settings.and_then(|s| s.offset);
+        let settings_offset = match inside {
+            Some(offset) if u64::from(offset) + settings_len >= u64::from(chip.flash.length) => {
+                return Err(LayoutError::NoRoom {
+                    needed: u64::from(offset) + settings_len,
+                    flash: chip.flash.length,
+                });
+            }
+            Some(offset) if offset < descriptor.bootloader => {
+                return Err(LayoutError::Overlap("SETTINGS", "BOOTLOADER"));
+            }
+            // The vector table must come first
+            Some(offset) if offset == descriptor.bootloader => {
+                return Err(LayoutError::Overlap("SETTINGS", "FLASH"));
+            }
+            Some(offset) => offset,
+            None => chip.flash.length - settings_len as u32,
+        };
+        let flash = chip.flash.origin;
+        let bootloader = Region::new(flash, descriptor.bootloader);
+        let app_end = match inside {
+            Some(_) => chip.flash.length,
+            None => settings_offset,
+        };
+        let mut app = Region::new(
+            flash + descriptor.bootloader,
+            app_end - descriptor.bootloader,
+        );
+        let settings = Region::new(flash + settings_offset, settings_len as u32);
+        let text = inside.map(|_| settings.end() as u32);
+
+        // With update slots the application only gets the active slot:
+        // bootloader | state | active | download | scratch | (unused) | settings
+        let update = match descriptor.slot {
+            // The bootloader swaps whole slots, so settings in one would be lost
+            Some(_) if inside.is_some() => {
+                return Err(LayoutError::Overlap("SETTINGS", "FLASH"));
+            }
+            Some(slot) => {
+                let slots = update_slots(chip, descriptor.bootloader, slot, reserved)?;
+                app = Region::new(slots.state.end() as u32, slot);
+                Some(slots)
+            }
+            None => None,
+        };
+
+        // Partitions must start on erase boundaries so updating one never erases another
+        if !chip.erase.is_boundary(descriptor.bootloader) {
+            return Err(LayoutError::Misaligned {
+                what: "the application",
+                offset: descriptor.bootloader,
+            });
+        }
+        if let Some(pages) = descriptor.settings {
+            let mut offset = settings_offset;
+            for _ in 0..pages.pages {
+                let (start, erase) = chip.erase.unit_at(offset).unwrap_or((offset, 0));
+                if start != offset {
+                    return Err(LayoutError::Misaligned {
+                        what: "the settings page",
+                        offset,
+                    });
+                }
+                if erase != pages.page_size {
+                    return Err(LayoutError::PageSize {
+                        declared: pages.page_size,
+                        erase,
+                    });
+                }
+                offset += pages.page_size;
+            }
+        }
+
+        // No two regions may share addresses; settings inside the application
+        // only share its flash with the gap between the vector table and `.text`
+        let mut regions = vec![
+            ("BOOTLOADER", bootloader),
+            ("SETTINGS", settings),
+            ("RAM", descriptor.ram),
+        ];
+        let code = match text {
+            Some(text) => Region::new(text, (app.end() - u64::from(text)) as u32),
+            None => app,
+        };
+        regions.push(("FLASH", code));
+        if let Some(slots) = &update {
+            regions.push(("BOOT_STATE", slots.state));
+            regions.push(("DOWNLOAD", slots.download));
+            regions.push(("SCRATCH", slots.scratch));
+        }
+        if let Some(ccmram) = descriptor.ccmram {
+            regions.push(("CCMRAM", ccmram));
+        }
+        for (i, (a, ra)) in regions.iter().enumerate() {
+            for (b, rb) in &regions[i + 1..] {
+                if ra.length != 0 && rb.length != 0 && ra.overlaps(rb) {
+                    return Err(LayoutError::Overlap(a, b));
+                }
+            }
+        }
+
+        Ok(Self {
+            chip,
+            bootloader,
+            app,
+            update,
+            settings,
+            text,
+            settings_page_size: descriptor.settings.map_or(0, |s| s.page_size),
+            ram: descriptor.ram,
+            ccmram: descriptor.ccmram,
+            ccmram_stack: descriptor.ccmram_stack,
+        })
+    }
+
+    /// Renders the `MEMORY` block for `cortex-m-rt`'s `link.x`
+    ///
+    /// # Arguments
+    /// * `source` - Descriptor path, recorded in the header comment
+    pub fn memory_x(&self, source: &str) -> String {
+        let mut out = format!(
+            "/* Generated by build.rs from {source} for the {} -- do not edit */\n\n",
+            self.chip.name
+        );
+        let line = |name: &str, r: &Region| {
+            format!(
+                "  {name} : ORIGIN = {:#010x}, LENGTH = {}\n",
+                r.origin,
+                size(r.length)
+            )
+        };
+        out.push_str("MEMORY\n{\n");
+        out.push_str(&line("FLASH", &self.app));
+        out.push_str(&line("RAM", &self.ram));
+        if let Some(ccmram) = &self.ccmram {
+            out.push_str(&line("CCMRAM", ccmram));
+        }
+        out.push_str("}\n\n");
+
+        // Partition symbols for code that needs the raw addresses
+        let symbols = [
+            ("__bootloader_start", self.bootloader.origin),
+            ("__bootloader_end", self.bootloader.end() as u32),
+            ("__settings_start", self.settings.origin),
+            ("__settings_end", self.settings.end() as u32),
+        ];
+        for (name, value) in symbols {
+            out.push_str(&format!("{name} = {value:#010x};\n"));
+        }
+        if let Some(text) = self.text {
+            // Code starts past the settings; only the vector table sits before them
+            out.push_str(&format!(
+                "\n/* Settings inside FLASH: .text starts after them */\n\
+                 _stext = {text:#010x};\n\
+                 ASSERT(ADDR(.vector_table) + SIZEOF(.vector_table) <= __settings_start, \"\n\
+                 ERROR(blink): the vector table runs into the settings pages\");\n"
+            ));
+        }
+        out.push('\n');
+        out.push_str(&self.ccm_sections());
+        out.push('\n');
+        out.push_str(BUILD_INFO_SECTION);
+        out
+    }
+
+    /// Renders the `MEMORY` block of the serial bootloader (`src/bin/bootloader.rs`)
+    ///
+    /// The bootloader links into its own partition and the top `BOOTLOADER_RAM`
+    /// bytes of RAM; it places nothing in CCM RAM.
+    ///
+    /// # Arguments
+    /// * `source` - Descriptor path, recorded in the header comment
+    pub fn bootloader_memory_x(&self, source: &str) -> String {
+        let ram = Region::new(
+            (self.ram.end() - u64::from(BOOTLOADER_RAM)) as u32,
+            BOOTLOADER_RAM,
+        );
+        let mut out = format!(
+            "/* Generated by build.rs from {source} for the {} bootloader -- do not edit */\n\n",
+            self.chip.name
+        );
+        out.push_str("MEMORY\n{\n");
+        for (name, r) in [("FLASH", &self.bootloader), ("RAM", &ram)] {
+            out.push_str(&format!(
+                "  {name} : ORIGIN = {:#010x}, LENGTH = {}\n",
+                r.origin,
+                size(r.length)
+            ));
+        }
+        out.push_str("}\n\n");
+        out.push_str(NO_CCM_SECTIONS);
+        out.push('\n');
+        out.push_str(BUILD_INFO_SECTION);
+        out
+    }
+
+    /// Renders the sections behind `blink::ccm!`
+    ///
+    /// With a CCMRAM region, `.ccmram.bss` and `.ccmram.data` go there and
+    /// `blink::ccm::init` zeroes and loads them at boot. Without one, they are
+    /// appended to `.bss` and `.data` in RAM, which cortex-m-rt initialises
+    /// itself, and the `init` ranges are left empty.
+    fn ccm_sections(&self) -> String {
+        let Some(ccmram) = &self.ccmram else {
+            return NO_CCM_SECTIONS.to_owned();
+        };
+        let mut out = CCM_SECTIONS.to_owned();
+        if self.ccmram_stack {
+            // The stack grows down from the top of CCM RAM towards the statics
+            out.push_str(&format!(
+                "\n/* Main stack at the top of CCM RAM */\n\
+                 _stack_start = {:#010x};\n\
+                 _stack_end = __eccmdata;\n",
+                ccmram.end()
+            ));
+        }
+        out
+    }
+}
+
+/// Places the serial bootloader's partitions after a `bootloader`-byte bootloader
+///
+/// # Arguments
+/// * `reserved` - Bytes already taken by the bootloader and settings
+///
+/// # Errors
+/// `UnevenErase` on a chip with sectors, `Missing` without a bootloader,
+/// `Misaligned` for a slot that is not whole pages, `NoRoom` if the slots
+/// do not fit between the bootloader and the settings.
+fn update_slots(
+    chip: &Chip,
+    bootloader: u32,
+    slot: u32,
+    reserved: u64,
+) -> Result<UpdateSlots, LayoutError> {
+    let Erase::Uniform(page) = chip.erase else {
+        return Err(LayoutError::UnevenErase);
+    };
+    if bootloader == 0 {
+        return Err(LayoutError::Missing("bootloader.length"));
+    }
+    let state = bootloader;
+    let active = state + BOOT_STATE_PAGES * page;
+    if slot == 0 || !slot.is_multiple_of(page) {
+        return Err(LayoutError::Misaligned {
+            what: "the download slot",
+            offset: u64::from(active).saturating_add(u64::from(slot)) as u32,
+        });
+    }
+    let needed =
+        reserved + u64::from(BOOT_STATE_PAGES * page) + 2 * u64::from(slot) + u64::from(page);
+    if needed > u64::from(chip.flash.length) {
+        return Err(LayoutError::NoRoom {
+            needed,
+            flash: chip.flash.length,
+        });
+    }
+    let download = active + slot;
+    let flash = chip.flash.origin;
+    Ok(UpdateSlots {
+        state: Region::new(flash + state, BOOT_STATE_PAGES * page),
+        download: Region::new(flash + download, slot),
+        scratch: Region::new(flash + download + slot, page),
+        page_size: page,
+    })
+}
+
+/// `blink::ccm!` sections for chips with a CCMRAM region
+const CCM_SECTIONS: &str = r#"/* Statics placed with `blink::ccm!`, initialised by `blink::ccm::init` */
+SECTIONS
+{
+  .ccmram.bss (NOLOAD) : ALIGN(4)
+  {
+    __sccmbss = .;
+    *(.ccmram.bss .ccmram.bss.*);
+    . = ALIGN(4);
+    __eccmbss = .;
+  } > CCMRAM
+
+  .ccmram.data : ALIGN(4)
+  {
+    __sccmdata = .;
+    *(.ccmram.data .ccmram.data.*);
+    . = ALIGN(4);
+    __eccmdata = .;
+  } > CCMRAM AT > FLASH
+
+  __siccmdata = LOADADDR(.ccmram.data);
+}
+INSERT AFTER .uninit;
+"#;
+
+/// `blink::ccm!` sections for chips without CCM RAM: plain RAM, initialised by
+/// cortex-m-rt, with empty ranges for `blink::ccm::init`
+const NO_CCM_SECTIONS: &str = r#"/* No CCM RAM: `blink::ccm!` statics join .bss and .data in RAM */
+SECTIONS
+{
+  .ccmram.bss (NOLOAD) : ALIGN(4) { *(.ccmram.bss .ccmram.bss.*); . = ALIGN(4); } > RAM
+}
+INSERT AFTER .bss;
+SECTIONS
+{
+  .ccmram.data : ALIGN(4) { *(.ccmram.data .ccmram.data.*); . = ALIGN(4); } > RAM AT > FLASH
+}
+INSERT AFTER .data;
+__sccmbss = 0;
+__eccmbss = 0;
+__sccmdata = 0;
+__eccmdata = 0;
+__siccmdata = 0;
+"#;
+
+/// Flash section for `blink::build_info`, found by name by host tools
+const BUILD_INFO_SECTION: &str = r#"/* Build metadata (`blink::build_info`), read from the ELF by host tools */
+SECTIONS
+{
+  .build_info : ALIGN(4)
+  {
+    KEEP(*(.build_info .build_info.*));
+  } > FLASH
+}
+INSERT AFTER .rodata;
+"#;
+
+impl Layout {
+    /// Renders Rust constants describing the flash partitions
+    ///
+    /// Offsets are relative to the start of flash, as taken by the flash driver.
+    pub fn constants(&self, source: &str) -> String {
+        let flash = self.chip.flash.origin;
+        let offset = |r: Option<Region>| r.map_or((0, 0), |r| (r.origin - flash, r.length));
+        let slots = |pick: fn(&UpdateSlots) -> Region| offset(self.update.as_ref().map(pick));
+        let (state, download, scratch) = (
+            slots(|u| u.state),
+            slots(|u| u.download),
+            slots(|u| u.scratch),
+        );
+        let ccmram = self.ccmram.map_or((0, 0), |r| (r.origin, r.length));
+        format!(
+            "// Generated by build.rs from {source} -- do not edit\n\n\
+             /// Chip the layout was validated against\n\
+             pub const CHIP: &str = {:?};\n\
+             /// Start of flash\n\
+             pub const FLASH_ORIGIN: u32 = {flash:#x};\n\
+             /// Bootloader partition offset and size (0 if none)\n\
+             pub const BOOTLOADER: (u32, u32) = (0, {:#x});\n\
+             /// Application partition offset and size\n\
+             pub const APP: (u32, u32) = ({:#x}, {:#x});\n\
+             /// Offset of the first settings page\n\
+             pub const SETTINGS_OFFSET: u32 = {:#x};\n\
+             /// Settings page size (0 if none)\n\
+             pub const SETTINGS_PAGE_SIZE: u32 = {:#x};\n\
+             /// Boot state pages offset and size (0 without update slots)\n\
+             pub const BOOT_STATE: (u32, u32) = ({:#x}, {:#x});\n\
+             /// Download slot offset and size (0 without update slots)\n\
+             pub const DOWNLOAD: (u32, u32) = ({:#x}, {:#x});\n\
+             /// Scratch page offset and size (0 without update slots)\n\
+             pub const SCRATCH: (u32, u32) = ({:#x}, {:#x});\n\
+             /// RAM address and size\n\
+             pub const RAM: (u32, u32) = ({:#x}, {:#x});\n\
+             /// CCM RAM address and size (0 if not declared)\n\
+             pub const CCMRAM: (u32, u32) = ({:#x}, {:#x});\n",
+            self.chip.name,
+            self.bootloader.length,
+            self.app.origin - flash,
+            self.app.length,
+            self.settings.origin - flash,
+            self.settings_page_size,
+            state.0,
+            state.1,
+            download.0,
+            download.1,
+            scratch.0,
+            scratch.1,
+            self.ram.origin,
+            self.ram.length,
+            ccmram.0,
+            ccmram.1,
+        )
+    }
+}
@@ -0,0 +1,394 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Application state shared by the firmware main loop and the host tests
+//!
+//! This module holds the logic that used to live directly in `main`:
+//! - `Led` - LED output plus pattern playback, brightness effects, Morse
+//!   messages, manual hold and gesture handling
+//! - `Persisted` - settings together with the store that writes them back
+//! - `stored_pattern` - rebuilds the blink pattern selected by persisted settings
+//!
+//! # Design Philosophy
+//! Nothing here awaits or reads a clock. The main loop passes the current time
+//! in and sends whatever message comes back, so the same sequencing is verified
+//! on a host with `mock::MockPin` and a fake clock.
+
+use crate::brightness::{self, Effect, EFFECT_STEP_MS};
+use crate::button::Gesture;
+use crate::messages;
+use crate::morse::{self, MorseError, MorsePlayer, MorseTick, MorseTiming};
+use crate::pattern::{presets, BlinkPattern, LedPin, PatternPlayer, Tick};
+use crate::settings::{Settings, SettingsFlash, SettingsStore, PATTERN_CUSTOM};
+use crate::shell;
+
+/// How the LED is currently being driven
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub enum LedMode {
+    /// The pattern player owns the LED
+    Pattern,
+    /// A brightness effect owns the LED (PWM)
+    Effect,
+    /// A Morse message is being keyed
+    Morse,
+    /// The LED is held at a fixed level by a shell command or gesture
+    Manual,
+}
+
+/// LED output together with the state needed to drive and report it
+pub struct Led<P: LedPin> {
+    pin: P,
+    mode: LedMode,
+    on: bool,
+    player: PatternPlayer,
+    next_ms: u64,
+    preset: usize,
+    default: BlinkPattern,
+    effect: Effect,
+    effect_start_ms: Option<u64>,
+    level: Option<u8>,
+    settled: bool,
+    morse: Option<MorsePlayer>,
+    morse_timing: MorseTiming,
+}
+
+impl<P: LedPin> Led<P> {
+    /// Creates the LED state selected by persisted settings
+    ///
+    /// # Arguments
+    /// * `pin` - LED output, assumed off
+    /// * `default` - Pattern used when the settings hold no valid selection,
+    ///   and after a finite pattern completes
+    /// * `settings` - Persisted settings (pattern index or custom blink period)
+    pub fn new(pin: P, default: BlinkPattern, settings: &Settings) -> Self {
+        let (player, preset) = stored_pattern(settings, default);
+        Self {
+            pin,
+            mode: LedMode::Pattern,
+            on: false,
+            player,
+            next_ms: 0,
+            preset,
+            default,
+            effect: Effect::Dim { level: 0 },
+            effect_start_ms: None,
+            level: None,
+            settled: false,
+            morse: None,
+            morse_timing: DEFAULT_MORSE_TIMING,
+        }
+    }
+
+    /// Advances the pattern if its current step has elapsed
+    ///
+    /// # Arguments
+    /// * `now_ms` - Current time in milliseconds
+    ///
+    /// # Returns
+    /// `messages::LED_ON` or `messages::LED_OFF` if a pattern switched the LED.
+    /// Brightness effects change the level too often to report. Morse messages
+    /// are mirrored instead: each character as it starts, a space between
+    /// words and `messages::NEWLINE` at the end.
+    pub fn poll(&mut self, now_ms: u64) -> Option<&'static [u8]> {
+        if self.next_deadline().is_none_or(|next_ms| now_ms < next_ms) {
+            return None;
+        }
+        match self.mode {
+            LedMode::Effect => {
+                self.step_effect(now_ms);
+                return None;
+            }
+            LedMode::Morse => return self.step_morse(now_ms),
+            LedMode::Pattern | LedMode::Manual => {}
+        }
+        match self.player.poll(now_ms, &mut self.pin) {
+            // LED changed level: report it
+            Tick::Edge { on, until_ms } => {
+                self.on = on;
+                self.next_ms = until_ms;
+                Some(if on {
+                    messages::LED_ON
+                } else {
+                    messages::LED_OFF
+                })
+            }
+            // Step still running: remember when it ends
+            Tick::Wait { until_ms } => {
+                self.next_ms = until_ms;
+                None
+            }
+            // Finite pattern complete: fall back to the default pattern
+            Tick::Finished => {
+                self.play(PatternPlayer::new(self.default));
+                None
+            }
+        }
+    }
+
+    /// Applies the effect level for `now_ms` and schedules the next update
+    fn step_effect(&mut self, now_ms: u64) {
+        let start_ms = *self.effect_start_ms.get_or_insert(now_ms);
+        let elapsed_ms = u32::try_from(now_ms - start_ms).unwrap_or(u32::MAX);
+        let level = self.effect.level_at(elapsed_ms);
+        // Only touch the timer when the level actually changes
+        if self.level != Some(level) {
+            self.pin.set_level(brightness::gamma(level));
+            self.level = Some(level);
+            self.on = level > 0;
+        }
+        self.settled = self
+            .effect
+            .settle_ms()
+            .is_some_and(|settle_ms| elapsed_ms >= settle_ms);
+        self.next_ms = now_ms + u64::from(EFFECT_STEP_MS);
+    }
+
+    /// Keys the Morse message up to `now_ms`
+    fn step_morse(&mut self, now_ms: u64) -> Option<&'static [u8]> {
+        let tick = match self.morse.as_mut() {
+            Some(player) => player.poll(now_ms, &mut self.pin),
+            None => MorseTick::Finished,
+        };
+        match tick {
+            MorseTick::Key { on, until_ms, echo } => {
+                self.on = on;
+                self.next_ms = until_ms;
+                echo.map(morse::as_static)
+            }
+            MorseTick::Wait { until_ms } => {
+                self.next_ms = until_ms;
+                None
+            }
+            // Message sent: end the mirrored line, resume the default pattern
+            MorseTick::Finished => {
+                self.on = false;
+                self.morse = None;
+                self.play(PatternPlayer::new(self.default));
+                Some(messages::NEWLINE)
+            }
+        }
+    }
+
+    /// Returns when `poll` next needs to run, or `None` while held manually or
+    /// once an effect has reached its final level
+    pub fn next_deadline(&self) -> Option<u64> {
+        match self.mode {
+            LedMode::Pattern | LedMode::Morse => Some(self.next_ms),
+            LedMode::Effect if !self.settled => Some(self.next_ms),
+            LedMode::Effect | LedMode::Manual => None,
+        }
+    }
+
+    /// Plays a brightness effect from the next `poll` onwards
+    pub fn fade(&mut self, effect: Effect) {
+        #[cfg(target_os = "none")]
+        defmt::info!("brightness effect: {}", effect);
+        self.effect = effect;
+        self.effect_start_ms = None;
+        self.level = None;
+        self.settled = false;
+        self.mode = LedMode::Effect;
+        self.next_ms = 0;
+    }
+
+    /// Keys `text` in Morse from the next `poll` onwards
+    ///
+    /// The default pattern resumes once the message has been sent.
+    ///
+    /// # Errors
+    /// See `morse::validate`; the LED is left unchanged.
+    pub fn send_morse(&mut self, text: &str) -> Result<(), MorseError> {
+        let player = MorsePlayer::new(text, self.morse_timing)?;
+        #[cfg(target_os = "none")]
+        defmt::info!("morse: {=str}", player.text());
+        self.pin.set_off();
+        self.on = false;
+        self.morse = Some(player);
+        self.mode = LedMode::Morse;
+        self.next_ms = 0;
+        Ok(())
+    }
+
+    /// Sets the speeds used by the next `send_morse`
+    pub fn set_morse_timing(&mut self, timing: MorseTiming) {
+        self.morse_timing = timing;
+    }
+
+    /// Returns the speeds used by `send_morse`
+    pub fn morse_timing(&self) -> MorseTiming {
+        self.morse_timing
+    }
+
+    /// Plays `player` from the next `poll` onwards
+    pub fn play(&mut self, player: PatternPlayer) {
+        #[cfg(target_os = "none")]
+        defmt::info!("blink pattern: {}", player.name());
+        self.player = player;
+        self.mode = LedMode::Pattern;
+        self.next_ms = 0;
+    }
+
+    /// Plays the preset `offset` places away from the current one in `presets::ALL`
+    pub fn step_preset(&mut self, offset: isize) {
+        let count = presets::ALL.len() as isize;
+        self.select_preset((self.preset as isize + offset).rem_euclid(count) as usize);
+    }
+
+    /// Plays the preset at `index` in `presets::ALL` (wrapping out-of-range indices)
+    pub fn select_preset(&mut self, index: usize) {
+        self.preset = index % presets::ALL.len();
+        self.play(PatternPlayer::new(presets::ALL[self.preset]));
+    }
+
+    /// Plays the pattern selected by `settings` again (see `stored_pattern`)
+    ///
+    /// Used when a scheduled window closes, so the LED returns to what was
+    /// chosen by the last command or gesture that was saved.
+    pub fn play_stored(&mut self, settings: &Settings) {
+        let (player, preset) = stored_pattern(settings, self.default);
+        self.preset = preset;
+        self.play(player);
+    }
+
+    /// Holds the LED at `on`, pausing pattern playback
+    pub fn hold(&mut self, on: bool) {
+        if on {
+            self.pin.set_on();
+        } else {
+            self.pin.set_off();
+        }
+        self.on = on;
+        self.mode = LedMode::Manual;
+    }
+
+    /// Applies a button gesture
+    ///
+    /// Short = next preset, double = previous preset, long = pause (LED off)
+    /// or resume the current preset.
+    pub fn apply(&mut self, gesture: Gesture) {
+        match gesture {
+            Gesture::Short => self.step_preset(1),
+            Gesture::Double => self.step_preset(-1),
+            Gesture::Long if self.mode != LedMode::Manual => self.hold(false),
+            Gesture::Long => self.step_preset(0),
+        }
+    }
+
+    /// Returns how the LED is being driven
+    pub fn mode(&self) -> LedMode {
+        self.mode
+    }
+
+    /// Returns whether the LED is lit (at any brightness)
+    pub fn is_on(&self) -> bool {
+        self.on
+    }
+
+    /// Returns the perceived brightness set by the running effect, if any
+    pub fn level(&self) -> Option<u8> {
+        match self.mode {
+            LedMode::Effect => self.level,
+            LedMode::Pattern | LedMode::Morse | LedMode::Manual => None,
+        }
+    }
+
+    /// Returns the index of the current preset in `presets::ALL`
+    pub fn preset(&self) -> usize {
+        self.preset
+    }
+
+    /// Returns the name of the loaded pattern (kept while held manually)
+    pub fn pattern_name(&self) -> &'static str {
+        self.player.name()
+    }
+
+    /// Returns a short description of how the LED is being driven
+    pub fn mode_name(&self) -> &'static str {
+        match self.mode {
+            LedMode::Pattern => self.player.name(),
+            LedMode::Effect => self.effect.name(),
+            LedMode::Morse => "morse",
+            LedMode::Manual if self.on => "manual (on)",
+            LedMode::Manual => "manual (off)",
+        }
+    }
+
+    /// Returns the LED output
+    pub fn pin(&self) -> &P {
+        &self.pin
+    }
+}
+
+/// Morse speeds used until `Led::set_morse_timing` is called
+///
+/// 20 WPM characters with 10 WPM Farnsworth spacing: readable by ear or eye
+/// without knowing Morse at speed.
+pub const DEFAULT_MORSE_TIMING: MorseTiming = MorseTiming {
+    wpm: 20,
+    farnsworth_wpm: 10,
+};
+
+/// Persisted settings together with the store that writes them back
+pub struct Persisted<F: SettingsFlash> {
+    store: SettingsStore<F>,
+    settings: Settings,
+}
+
+impl<F: SettingsFlash> Persisted<F> {
+    /// Wraps a loaded store, starting from its current settings
+    pub fn new(store: SettingsStore<F>) -> Self {
+        Self {
+            settings: store.current(),
+            store,
+        }
+    }
+
+    /// Returns the settings as last updated
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    /// Returns the flash driver behind the store
+    pub fn flash(&mut self) -> &mut F {
+        self.store.flash()
+    }
+
+    /// Applies `change` and writes the result to flash
+    ///
+    /// # Returns
+    /// `true` if the settings were saved (or were already up to date).
+    pub fn update(&mut self, change: impl FnOnce(&mut Settings)) -> bool {
+        change(&mut self.settings);
+        match self.store.save(&self.settings) {
+            Ok(()) => true,
+            Err(_err) => {
+                #[cfg(target_os = "none")]
+                defmt::warn!("settings save failed: {}", _err);
+                false
+            }
+        }
+    }
+}
+
+/// Builds the player selected by persisted settings
+///
+/// A corrupt custom period is clamped to the range `led blink` accepts, and an
+/// unknown preset index falls back to `default`.
+///
+/// # Returns
+/// The player and the preset index used for button cycling.
+pub fn stored_pattern(settings: &Settings, default: BlinkPattern) -> (PatternPlayer, usize) {
+    let index = usize::from(settings.pattern);
+    let default_index = presets::ALL.iter().position(|p| *p == default).unwrap_or(0);
+    if settings.pattern == PATTERN_CUSTOM {
+        let ms = settings
+            .blink_ms
+            .clamp(shell::MIN_BLINK_MS, shell::MAX_BLINK_MS);
+        (PatternPlayer::square(ms), default_index)
+    } else if let Some(pattern) = presets::ALL.get(index) {
+        (PatternPlayer::new(*pattern), index)
+    } else {
+        (PatternPlayer::new(default), default_index)
+    }
+}
@@ -0,0 +1,182 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Serial bootloader for firmware updates over the ST-Link VCP
+//!
+//! Lives in the first partition of flash (`[bootloader]` in `memory/<board>.toml`)
+//! and runs at every reset before the application:
+//! 1. Loads the boot state log and carries out whatever it asks for: finishes an
+//!    interrupted swap, installs a verified upload or rolls back an image that
+//!    reset before confirming (`blink::boot::Bootloader::start`)
+//! 2. Jumps to the application in the active slot, straight from reset, with
+//!    no clock or peripheral touched
+//! 3. Otherwise (the application asked for an update with the `update` shell
+//!    command, or the slot holds no valid image) receives a new image over
+//!    USART2 at 115200 baud with the `blink::update` protocol, then resets
+//!
+//! # Hardware
+//! - Boards: NUCLEO-F303RE and NUCLEO-L476RG (the boards with a bootloader `slot`)
+//! - UART: USART2 on PA2 (TX) and PA3 (RX) via ST-Link VCP
+//!
+//! # Design Philosophy
+//! Everything that decides what happens to the flash is in the library and
+//! tested on the host; this binary only adds the UART loop and the jump. It
+//! initialises the HAL only when it has to talk to the host, so the application
+//! always starts from the same reset state whether or not a bootloader ran.
+//!
+//! # Usage
+//! Build: `cargo build --release --features bootloader`
+//! Flash both: `probe-rs download` the `bootloader` and `stm32f303re-blink` ELFs
+//! Upload: `cargo cli update target/thumbv7em-none-eabihf/release/stm32f303re-blink`
+
+#![cfg_attr(target_os = "none", no_std)]
+#![cfg_attr(target_os = "none", no_main)]
+// Surface the linker's `--print-memory-usage` report (requested by `build.rs`)
+#![cfg_attr(target_os = "none", warn(linker_messages))]
+
+#[cfg(target_os = "none")]
+mod firmware {
+    use core::panic::PanicInfo;
+
+    use blink::boot::{Bootloader, Partitions, Start};
+    use blink::update::{FrameReader, Reply, Request};
+    use cortex_m::peripheral::SCB;
+    use cortex_m_rt::entry;
+    use defmt_rtt as _;
+    use embassy_stm32::flash::Flash;
+    use embassy_stm32::peripherals::FLASH;
+    use embassy_stm32::usart::{Config, Uart};
+
+    /// Flash partitions generated by `build.rs` from the board's descriptor
+    mod layout {
+        include!(concat!(env!("OUT_DIR"), "/layout.rs"));
+    }
+
+    /// Update baud rate, the shell's default
+    const BAUD_RATE: u32 = 115_200;
+
+    /// Partitions of the board, as the application sees them in `board::BOOT`
+    const PARTS: Partitions = Partitions {
+        flash_origin: layout::FLASH_ORIGIN,
+        state: layout::BOOT_STATE.0,
+        active: layout::APP.0,
+        download: layout::DOWNLOAD.0,
+        scratch: layout::SCRATCH.0,
+        slot_len: layout::APP.1,
+        page_size: layout::SCRATCH.1,
+        ram: [layout::RAM, layout::CCMRAM],
+    };
+
+    /// Bootloader entry point
+    ///
+    /// Resolves the boot state and either starts the application or serves
+    /// uploads until the host sends `Boot`.
+    #[entry]
+    fn main() -> ! {
+        // The blocking flash driver needs neither clocks nor interrupts
+        // SAFETY: nothing else uses FLASH before the jump or the `init` below
+        let flash = Flash::new_blocking(unsafe { FLASH::steal() });
+        let mut bootloader = Bootloader::new(flash, PARTS);
+        defmt::info!("bootloader: state {}", bootloader.state());
+        if bootloader.start() == Start::Run {
+            defmt::info!(
+                "bootloader: starting application at {:#x}",
+                PARTS.app_address()
+            );
+            run()
+        }
+
+        // Stay and wait for an image
+        defmt::info!("bootloader: waiting for an upload on USART2");
+        let p = embassy_stm32::init(Default::default());
+        let mut config = Config::default();
+        config.baudrate = BAUD_RATE;
+        let Ok(mut uart) = Uart::new_blocking(p.USART2, p.PA3, p.PA2, config) else {
+            defmt::panic!("USART2 configuration rejected");
+        };
+        let mut reader = FrameReader::new();
+        loop {
+            // Line errors drop the byte; the frame CRC catches what is lost
+            let mut byte = [0u8];
+            if uart.blocking_read(&mut byte).is_err() {
+                continue;
+            }
+            let reply = match reader.push(byte[0]) {
+                None => continue,
+                Some(Ok(frame)) => match Request::decode(frame) {
+                    Ok(request) => bootloader.handle(request),
+                    Err(nak) => Reply::Nak(nak),
+                },
+                Some(Err(nak)) => Reply::Nak(nak),
+            };
+            if let Reply::Nak(nak) = reply {
+                defmt::warn!("bootloader: refused, {}", nak.name());
+            }
+            let _ = uart.blocking_write(&reply.frame());
+
+            // `Boot` was acknowledged: reset into whatever the state log says
+            if bootloader.reset_requested() {
+                let _ = uart.blocking_flush();
+                SCB::sys_reset();
+            }
+        }
+    }
+
+    /// Jumps to the application in the active slot
+    fn run() -> ! {
+        flush_caches();
+        let address = PARTS.app_address();
+        // SAFETY: `Bootloader::start` only returns `Run` for a slot whose
+        // vector table passed `valid_vectors`, and nothing has been set up
+        // since reset that the application would not expect
+        unsafe {
+            (*SCB::PTR).vtor.write(address);
+            cortex_m::asm::bootload(address as *const u32)
+        }
+    }
+
+    /// Drops cached flash contents from before a swap (the L4 instruction
+    /// and data caches; the F303 has none)
+    fn flush_caches() {
+        #[cfg(feature = "board-l476rg")]
+        {
+            use embassy_stm32::pac::FLASH;
+            FLASH.acr().modify(|w| {
+                w.set_icen(false);
+                w.set_dcen(false);
+            });
+            FLASH.acr().modify(|w| {
+                w.set_icrst(true);
+                w.set_dcrst(true);
+            });
+            FLASH.acr().modify(|w| {
+                w.set_icrst(false);
+                w.set_dcrst(false);
+            });
+            FLASH.acr().modify(|w| {
+                w.set_icen(true);
+                w.set_dcen(true);
+            });
+        }
+    }
+
+    /// Panic handler: logs over defmt and resets
+    ///
+    /// The boot state log is only advanced after each completed step, so the
+    /// next attempt picks up where this one stopped.
+    #[panic_handler]
+    fn panic(info: &PanicInfo) -> ! {
+        cortex_m::interrupt::disable();
+        defmt::error!("bootloader: {}", defmt::Display2Format(info));
+        SCB::sys_reset()
+    }
+}
+
+/// Host stand-in for the bootloader entry point
+///
+/// The bootloader only runs on the boards; this keeps host builds with the
+/// `bootloader` feature working.
+#[cfg(not(target_os = "none"))]
+fn main() {
+    eprintln!("the bootloader runs on STM32 Nucleo boards; flash it with probe-rs");
+}
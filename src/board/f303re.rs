@@ -0,0 +1,250 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! NUCLEO-F303RE (STM32F303RET6, Cortex-M4F, 72 MHz, 512 KB flash, 64 KB RAM)
+//!
+//! - LED LD2: PA5, TIM2_CH1
+//! - Button B1: PC13, EXTI13
+//! - VCP: USART2, PA2 TX / PA3 RX, DMA1 channel 7 (TX) and 6 (RX)
+//!   (RM0316, DMA1 request mapping)
+//! - Sensors: ADC1 channels 16 (temperature) and 18 (VREFINT), interrupt-driven
+//! - Clock: 8 MHz ST-Link MCO (HSE bypass), HSI fallback
+
+use embassy_stm32::adc::{self, Adc, SampleTime, Temperature, Vref};
+use embassy_stm32::mode::Async;
+use embassy_stm32::peripherals::{self, ADC1, DMA1_CH6, DMA1_CH7, PA2, PA3, USART2};
+use embassy_stm32::rcc::{
+    AHBPrescaler, APBPrescaler, Hse, HseMode, Pll, PllMul, PllPreDiv, PllSource, Sysclk,
+};
+use embassy_stm32::time::Hertz;
+use embassy_stm32::timer::simple_pwm::SimplePwmChannel;
+use embassy_stm32::usart::{self, Uart};
+use embassy_stm32::{bind_interrupts, Peri, Peripherals};
+
+use blink::brightness::PwmLed;
+#[cfg(feature = "low-power")]
+use blink::power::CurrentModel;
+use blink::sensors::{Calibration, RawSample};
+use blink::supervisor::ResetFlags;
+
+#[cfg(feature = "low-power")]
+use super::LowPower;
+use super::{
+    hse_bypass_ready, system_word, Board, BoardInfo, ClockSource, Parts, Sensors, Vcp,
+    HSE_BYPASS_HZ,
+};
+use crate::config;
+
+/// Onboard LED (LD2) driven by TIM2 channel 1
+pub type Led = PwmLed<SimplePwmChannel<'static, peripherals::TIM2>>;
+
+// USART2 and ADC1 interrupt bindings required by the async UART and ADC drivers
+bind_interrupts!(struct Irqs {
+    USART2 => usart::InterruptHandler<peripherals::USART2>;
+    ADC1_2 => adc::InterruptHandler<peripherals::ADC1>;
+});
+
+/// The NUCLEO-F303RE
+pub struct NucleoF303re;
+
+/// USART2 on the ST-Link VCP pins, with its DMA channels
+pub struct Usart2Vcp {
+    usart: Peri<'static, USART2>,
+    tx: Peri<'static, PA2>,
+    rx: Peri<'static, PA3>,
+    tx_dma: Peri<'static, DMA1_CH7>,
+    rx_dma: Peri<'static, DMA1_CH6>,
+}
+
+impl Vcp for Usart2Vcp {
+    fn into_async(self, config: usart::Config) -> Result<Uart<'static, Async>, usart::ConfigError> {
+        Uart::new(
+            self.usart,
+            self.rx,
+            self.tx,
+            Irqs,
+            self.tx_dma,
+            self.rx_dma,
+            config,
+        )
+    }
+}
+
+/// ADC1 with the internal temperature sensor and VREFINT channels
+pub struct Adc1Sensors {
+    adc: Adc<'static, ADC1>,
+    vref: Vref,
+    temperature: Temperature,
+}
+
+impl Sensors for Adc1Sensors {
+    /// Calibration values from the chip's datasheet (engineering bytes)
+    fn calibration() -> Calibration {
+        Calibration {
+            ts_cal1: system_word(0x1FFF_F7B8),
+            ts_cal2: system_word(0x1FFF_F7C2),
+            ts_cal1_c: 30,
+            ts_cal2_c: 110,
+            vrefint_cal: system_word(0x1FFF_F7BA),
+            cal_vdda_mv: 3300,
+        }
+    }
+
+    /// Converts both channels, interrupt-driven
+    async fn sample(&mut self) -> RawSample {
+        RawSample {
+            vrefint: self.adc.read(&mut self.vref).await,
+            temperature: self.adc.read(&mut self.temperature).await,
+        }
+    }
+}
+
+impl Board for NucleoF303re {
+    const INFO: BoardInfo = BoardInfo {
+        name: "NUCLEO-F303RE",
+        chip: "STM32F303RETx",
+        sysclk_hz: 72_000_000,
+        led: "PA5 (TIM2_CH1)",
+        button: "PC13",
+        vcp: "USART2 (PA2 TX, PA3 RX)",
+    };
+
+    // 2 KB page erase: 40 ms maximum (datasheet, flash memory characteristics)
+    const SETTINGS_ERASE_MS: u32 = 40;
+
+    type Vcp = Usart2Vcp;
+    type Sensors = Adc1Sensors;
+
+    /// Builds the HAL configuration for a 72 MHz clock tree
+    ///
+    /// Clock tree:
+    /// - PLL input: 8 MHz HSE bypass (ST-Link MCO), or 8 MHz HSI if the MCO is absent
+    /// - PLL: /1 x9 = 72 MHz SYSCLK
+    /// - AHB: /1 = 72 MHz HCLK
+    /// - APB1: /2 = 36 MHz PCLK1 (36 MHz maximum; timers run at 72 MHz)
+    /// - APB2: /1 = 72 MHz PCLK2
+    ///
+    /// Flash wait states are derived from HCLK by `embassy_stm32::init` (2 WS above
+    /// 48 MHz, RM0316 section 4.5.1) and reported at boot by `config::flash_wait_states`.
+    fn clocks() -> (embassy_stm32::Config, ClockSource) {
+        let mut config = embassy_stm32::Config::default();
+        let source = if hse_bypass_ready() {
+            ClockSource::HseBypass
+        } else {
+            ClockSource::Hsi
+        };
+
+        let rcc = &mut config.rcc;
+        match source {
+            ClockSource::HseBypass => {
+                rcc.hse = Some(Hse {
+                    freq: Hertz(HSE_BYPASS_HZ),
+                    mode: HseMode::Bypass,
+                });
+                rcc.pll = Some(Pll {
+                    src: PllSource::HSE,
+                    prediv: PllPreDiv::DIV1,
+                    mul: PllMul::MUL9,
+                });
+            }
+            ClockSource::Hsi => {
+                // The F303xE can feed HSI/PREDIV to the PLL, so no HSI/2 penalty
+                rcc.pll = Some(Pll {
+                    src: PllSource::HSI,
+                    prediv: PllPreDiv::DIV1,
+                    mul: PllMul::MUL9,
+                });
+            }
+        }
+        rcc.sys = Sysclk::PLL1_P;
+        rcc.ahb_pre = AHBPrescaler::DIV1;
+        rcc.apb1_pre = APBPrescaler::DIV2;
+        rcc.apb2_pre = APBPrescaler::DIV1;
+
+        (config, source)
+    }
+
+    fn reset_flags() -> ResetFlags {
+        use embassy_stm32::pac::RCC;
+
+        let csr = RCC.csr().read();
+        let flags = ResetFlags {
+            iwdg: csr.iwdgrstf(),
+            wwdg: csr.wwdgrstf(),
+            software: csr.sftrstf(),
+            low_power: csr.lpwrrstf(),
+            pin: csr.pinrstf(),
+            power_on: csr.porrstf(),
+        };
+        RCC.csr().modify(|w| w.set_rmvf(true));
+        flags
+    }
+
+    fn unlock_backup_domain() {
+        use embassy_stm32::pac::{PWR, RCC};
+
+        RCC.apb1enr().modify(|w| w.set_pwren(true));
+        PWR.cr().modify(|w| w.set_dbp(true));
+        while !PWR.cr().read().dbp() {}
+    }
+
+    fn split(p: Peripherals) -> Parts<Usart2Vcp, Adc1Sensors> {
+        // The sensors need the longest sample time to settle (several µs)
+        let mut adc = Adc::new(p.ADC1, Irqs);
+        adc.set_sample_time(SampleTime::CYCLES601_5);
+        let sensors = Adc1Sensors {
+            vref: adc.enable_vref(),
+            temperature: adc.enable_temperature(),
+            adc,
+        };
+
+        Parts {
+            led: config::init_led(p.TIM2, p.PA5),
+            button: config::init_button(p.PC13, p.EXTI13),
+            vcp: Usart2Vcp {
+                usart: p.USART2,
+                tx: p.PA2,
+                rx: p.PA3,
+                tx_dma: p.DMA1_CH7,
+                rx_dma: p.DMA1_CH6,
+            },
+            sensors,
+            flash: p.FLASH,
+            iwdg: p.IWDG,
+            rtc: p.RTC,
+        }
+    }
+}
+
+/// STOP with the regulator in low-power mode (RM0316, low-power modes)
+///
+/// About 32 mA running at 72 MHz (datasheet typical, with the peripherals used
+/// here enabled) and 7 µA in STOP with the LSE and RTC running.
+#[cfg(feature = "low-power")]
+impl LowPower for NucleoF303re {
+    const CURRENT: CurrentModel = CurrentModel {
+        run_ua: 32_000,
+        stop_ua: 7,
+    };
+
+    // EXTI line 20 is the RTC wake-up event (RM0316, EXTI line connections)
+    const RTC_WAKEUP_LINE: usize = 20;
+
+    fn select_stop() {
+        use embassy_stm32::pac::pwr::vals::Pdds;
+        use embassy_stm32::pac::PWR;
+
+        PWR.cr().modify(|w| {
+            w.set_pdds(Pdds::STOP_MODE);
+            w.set_lpds(true);
+        });
+    }
+
+    fn clear_wakeup_flag() {
+        embassy_stm32::pac::RTC.isr().modify(|w| w.set_wutf(false));
+    }
+
+    fn wakeup_timer_writable() -> bool {
+        embassy_stm32::pac::RTC.isr().read().wutwf()
+    }
+}
@@ -0,0 +1,256 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! NUCLEO-F401RE (STM32F401RET6, Cortex-M4F, 84 MHz, 512 KB flash, 96 KB RAM)
+//!
+//! - LED LD2: PA5, TIM2_CH1
+//! - Button B1: PC13, EXTI13
+//! - VCP: USART2, PA2 TX / PA3 RX, DMA1 stream 6 (TX) and 5 (RX), channel 4
+//!   (RM0368, DMA1 request mapping)
+//! - Sensors: ADC1 channels 18 (temperature) and 17 (VREFINT)
+//! - Clock: 8 MHz ST-Link MCO (HSE bypass), HSI fallback
+//! - Settings: the F401 erases flash in sectors of 16 KB to 128 KB, so the two
+//!   settings "pages" are the 16 KB sectors 2 and 3, between the vector table
+//!   and the code (RM0368, embedded flash memory)
+
+use embassy_stm32::adc::{Adc, SampleTime, Temperature, VrefInt};
+use embassy_stm32::mode::Async;
+use embassy_stm32::peripherals::{self, ADC1, DMA1_CH5, DMA1_CH6, PA2, PA3, USART2};
+use embassy_stm32::rcc::{
+    AHBPrescaler, APBPrescaler, Hse, HseMode, Pll, PllMul, PllPDiv, PllPreDiv, PllQDiv, PllSource,
+    Sysclk,
+};
+use embassy_stm32::time::Hertz;
+use embassy_stm32::timer::simple_pwm::SimplePwmChannel;
+use embassy_stm32::usart::{self, Uart};
+use embassy_stm32::{bind_interrupts, Peri, Peripherals};
+
+use blink::brightness::PwmLed;
+#[cfg(feature = "low-power")]
+use blink::power::CurrentModel;
+use blink::sensors::{Calibration, RawSample};
+use blink::supervisor::ResetFlags;
+
+#[cfg(feature = "low-power")]
+use super::LowPower;
+use super::{
+    hse_bypass_ready, system_word, Board, BoardInfo, ClockSource, Parts, Sensors, Vcp,
+    HSE_BYPASS_HZ,
+};
+use crate::config;
+
+/// Onboard LED (LD2) driven by TIM2 channel 1
+pub type Led = PwmLed<SimplePwmChannel<'static, peripherals::TIM2>>;
+
+// USART2 interrupt binding required by the async (DMA) UART driver
+bind_interrupts!(struct Irqs {
+    USART2 => usart::InterruptHandler<peripherals::USART2>;
+});
+
+/// The NUCLEO-F401RE
+pub struct NucleoF401re;
+
+/// USART2 on the ST-Link VCP pins, with its DMA streams
+pub struct Usart2Vcp {
+    usart: Peri<'static, USART2>,
+    tx: Peri<'static, PA2>,
+    rx: Peri<'static, PA3>,
+    tx_dma: Peri<'static, DMA1_CH6>,
+    rx_dma: Peri<'static, DMA1_CH5>,
+}
+
+impl Vcp for Usart2Vcp {
+    fn into_async(self, config: usart::Config) -> Result<Uart<'static, Async>, usart::ConfigError> {
+        Uart::new(
+            self.usart,
+            self.rx,
+            self.tx,
+            Irqs,
+            self.tx_dma,
+            self.rx_dma,
+            config,
+        )
+    }
+}
+
+/// ADC1 with the internal temperature sensor and VREFINT channels
+pub struct Adc1Sensors {
+    adc: Adc<'static, ADC1>,
+    vref: VrefInt,
+    temperature: Temperature,
+}
+
+impl Sensors for Adc1Sensors {
+    /// Calibration values from the chip's datasheet (engineering bytes)
+    fn calibration() -> Calibration {
+        Calibration {
+            ts_cal1: system_word(0x1FFF_7A2C),
+            ts_cal2: system_word(0x1FFF_7A2E),
+            ts_cal1_c: 30,
+            ts_cal2_c: 110,
+            vrefint_cal: system_word(0x1FFF_7A2A),
+            cal_vdda_mv: 3300,
+        }
+    }
+
+    /// Converts both channels, blocking (a conversion takes a few µs)
+    async fn sample(&mut self) -> RawSample {
+        RawSample {
+            vrefint: self.adc.blocking_read(&mut self.vref),
+            temperature: self.adc.blocking_read(&mut self.temperature),
+        }
+    }
+}
+
+impl Board for NucleoF401re {
+    const INFO: BoardInfo = BoardInfo {
+        name: "NUCLEO-F401RE",
+        chip: "STM32F401RETx",
+        sysclk_hz: 84_000_000,
+        led: "PA5 (TIM2_CH1)",
+        button: "PC13",
+        vcp: "USART2 (PA2 TX, PA3 RX)",
+    };
+
+    // 16 KB sector erase: 500 ms maximum at the x32 parallelism embassy uses
+    // (datasheet, flash memory characteristics)
+    const SETTINGS_ERASE_MS: u32 = 500;
+
+    type Vcp = Usart2Vcp;
+    type Sensors = Adc1Sensors;
+
+    /// Builds the HAL configuration for an 84 MHz clock tree
+    ///
+    /// Clock tree:
+    /// - PLL input: 8 MHz HSE bypass /4, or 16 MHz HSI /8 = 2 MHz (recommended VCO input)
+    /// - PLL: x168 = 336 MHz VCO, /4 = 84 MHz SYSCLK (P), /7 = 48 MHz (Q, USB)
+    /// - AHB: /1 = 84 MHz HCLK
+    /// - APB1: /2 = 42 MHz PCLK1 (42 MHz maximum; timers run at 84 MHz)
+    /// - APB2: /1 = 84 MHz PCLK2
+    ///
+    /// Flash wait states are derived from HCLK by `embassy_stm32::init` (2 WS at
+    /// 84 MHz and 3.3 V, RM0368 section 3.4).
+    fn clocks() -> (embassy_stm32::Config, ClockSource) {
+        let mut config = embassy_stm32::Config::default();
+        let source = if hse_bypass_ready() {
+            ClockSource::HseBypass
+        } else {
+            ClockSource::Hsi
+        };
+
+        let rcc = &mut config.rcc;
+        let prediv = match source {
+            ClockSource::HseBypass => {
+                rcc.hse = Some(Hse {
+                    freq: Hertz(HSE_BYPASS_HZ),
+                    mode: HseMode::Bypass,
+                });
+                rcc.pll_src = PllSource::HSE;
+                PllPreDiv::DIV4
+            }
+            ClockSource::Hsi => {
+                rcc.pll_src = PllSource::HSI;
+                PllPreDiv::DIV8
+            }
+        };
+        rcc.pll = Some(Pll {
+            prediv,
+            mul: PllMul::MUL168,
+            divp: Some(PllPDiv::DIV4),
+            divq: Some(PllQDiv::DIV7),
+            divr: None,
+        });
+        rcc.sys = Sysclk::PLL1_P;
+        rcc.ahb_pre = AHBPrescaler::DIV1;
+        rcc.apb1_pre = APBPrescaler::DIV2;
+        rcc.apb2_pre = APBPrescaler::DIV1;
+
+        (config, source)
+    }
+
+    fn reset_flags() -> ResetFlags {
+        use embassy_stm32::pac::RCC;
+
+        let csr = RCC.csr().read();
+        let flags = ResetFlags {
+            iwdg: csr.iwdgrstf(),
+            wwdg: csr.wwdgrstf(),
+            software: csr.sftrstf(),
+            low_power: csr.lpwrrstf(),
+            pin: csr.pinrstf(),
+            power_on: csr.porrstf(),
+        };
+        RCC.csr().modify(|w| w.set_rmvf(true));
+        flags
+    }
+
+    fn unlock_backup_domain() {
+        use embassy_stm32::pac::{PWR, RCC};
+
+        RCC.apb1enr().modify(|w| w.set_pwren(true));
+        PWR.cr1().modify(|w| w.set_dbp(true));
+        while !PWR.cr1().read().dbp() {}
+    }
+
+    fn split(p: Peripherals) -> Parts<Usart2Vcp, Adc1Sensors> {
+        // The sensors need the longest sample time to settle (several µs)
+        let mut adc = Adc::new(p.ADC1);
+        adc.set_sample_time(SampleTime::CYCLES480);
+        let sensors = Adc1Sensors {
+            vref: adc.enable_vrefint(),
+            temperature: adc.enable_temperature(),
+            adc,
+        };
+
+        Parts {
+            led: config::init_led(p.TIM2, p.PA5),
+            button: config::init_button(p.PC13, p.EXTI13),
+            vcp: Usart2Vcp {
+                usart: p.USART2,
+                tx: p.PA2,
+                rx: p.PA3,
+                tx_dma: p.DMA1_CH6,
+                rx_dma: p.DMA1_CH5,
+            },
+            sensors,
+            flash: p.FLASH,
+            iwdg: p.IWDG,
+            rtc: p.RTC,
+        }
+    }
+}
+
+/// STOP with the regulator in low-power mode and the flash powered down
+/// (RM0368, low-power modes)
+///
+/// About 12 mA running at 84 MHz (datasheet typical) and 12 µA in STOP with the
+/// LSE and RTC running.
+#[cfg(feature = "low-power")]
+impl LowPower for NucleoF401re {
+    const CURRENT: CurrentModel = CurrentModel {
+        run_ua: 12_000,
+        stop_ua: 12,
+    };
+
+    // EXTI line 22 is the RTC wake-up event (RM0368, EXTI line connections)
+    const RTC_WAKEUP_LINE: usize = 22;
+
+    fn select_stop() {
+        use embassy_stm32::pac::pwr::vals::Pdds;
+        use embassy_stm32::pac::PWR;
+
+        PWR.cr1().modify(|w| {
+            w.set_pdds(Pdds::STOP_MODE);
+            w.set_lpds(true);
+            w.set_fpds(true);
+        });
+    }
+
+    fn clear_wakeup_flag() {
+        embassy_stm32::pac::RTC.isr().modify(|w| w.set_wutf(false));
+    }
+
+    fn wakeup_timer_writable() -> bool {
+        embassy_stm32::pac::RTC.isr().read().wutwf()
+    }
+}
@@ -0,0 +1,255 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! NUCLEO-G431RB (STM32G431RBT6, Cortex-M4F, 170 MHz, 128 KB flash, 32 KB RAM)
+//!
+//! - LED LD2: PA5, TIM2_CH1
+//! - Button B1: PC13, EXTI13
+//! - VCP: LPUART1, PA2 TX / PA3 RX, DMA1 channel 1 (TX) and 2 (RX) through the DMAMUX
+//! - Sensors: ADC1 channels 16 (temperature) and 18 (VREFINT), clocked from SYSCLK
+//! - Clock: 8 MHz ST-Link MCO (HSE bypass), HSI16 fallback
+//!
+//! Only the 16 KB SRAM1 and 6 KB SRAM2 are mapped contiguously at 0x20000000;
+//! the 10 KB CCM SRAM is left free.
+
+use embassy_stm32::adc::{Adc, SampleTime, Temperature, VrefInt};
+use embassy_stm32::mode::Async;
+use embassy_stm32::peripherals::{self, ADC1, DMA1_CH1, DMA1_CH2, LPUART1, PA2, PA3};
+use embassy_stm32::rcc::{
+    mux, AHBPrescaler, APBPrescaler, Hse, HseMode, Pll, PllMul, PllPreDiv, PllRDiv, PllSource,
+    Sysclk,
+};
+use embassy_stm32::time::Hertz;
+use embassy_stm32::timer::simple_pwm::SimplePwmChannel;
+use embassy_stm32::usart::{self, Uart};
+use embassy_stm32::{bind_interrupts, Peri, Peripherals};
+
+use blink::brightness::PwmLed;
+#[cfg(feature = "low-power")]
+use blink::power::CurrentModel;
+use blink::sensors::{Calibration, RawSample};
+use blink::supervisor::ResetFlags;
+
+#[cfg(feature = "low-power")]
+use super::LowPower;
+use super::{
+    hse_bypass_ready, system_word, Board, BoardInfo, ClockSource, Parts, Sensors, Vcp,
+    HSE_BYPASS_HZ,
+};
+use crate::config;
+
+/// Onboard LED (LD2) driven by TIM2 channel 1
+pub type Led = PwmLed<SimplePwmChannel<'static, peripherals::TIM2>>;
+
+// LPUART1 interrupt binding required by the async (DMA) UART driver
+bind_interrupts!(struct Irqs {
+    LPUART1 => usart::InterruptHandler<peripherals::LPUART1>;
+});
+
+/// The NUCLEO-G431RB
+pub struct NucleoG431rb;
+
+/// LPUART1 on the ST-Link VCP pins, with its DMA channels
+pub struct Lpuart1Vcp {
+    usart: Peri<'static, LPUART1>,
+    tx: Peri<'static, PA2>,
+    rx: Peri<'static, PA3>,
+    tx_dma: Peri<'static, DMA1_CH1>,
+    rx_dma: Peri<'static, DMA1_CH2>,
+}
+
+impl Vcp for Lpuart1Vcp {
+    fn into_async(self, config: usart::Config) -> Result<Uart<'static, Async>, usart::ConfigError> {
+        Uart::new(
+            self.usart,
+            self.rx,
+            self.tx,
+            Irqs,
+            self.tx_dma,
+            self.rx_dma,
+            config,
+        )
+    }
+}
+
+/// ADC1 with the internal temperature sensor and VREFINT channels
+pub struct Adc1Sensors {
+    adc: Adc<'static, ADC1>,
+    vref: VrefInt,
+    temperature: Temperature,
+}
+
+impl Sensors for Adc1Sensors {
+    /// Calibration values from the chip's datasheet (engineering bytes)
+    fn calibration() -> Calibration {
+        Calibration {
+            ts_cal1: system_word(0x1FFF_75A8),
+            ts_cal2: system_word(0x1FFF_75CA),
+            ts_cal1_c: 30,
+            ts_cal2_c: 130,
+            vrefint_cal: system_word(0x1FFF_75AA),
+            cal_vdda_mv: 3000,
+        }
+    }
+
+    /// Converts both channels, blocking (a conversion takes a few µs)
+    async fn sample(&mut self) -> RawSample {
+        RawSample {
+            vrefint: self.adc.blocking_read(&mut self.vref),
+            temperature: self.adc.blocking_read(&mut self.temperature),
+        }
+    }
+}
+
+impl Board for NucleoG431rb {
+    const INFO: BoardInfo = BoardInfo {
+        name: "NUCLEO-G431RB",
+        chip: "STM32G431RBTx",
+        sysclk_hz: 170_000_000,
+        led: "PA5 (TIM2_CH1)",
+        button: "PC13",
+        vcp: "LPUART1 (PA2 TX, PA3 RX)",
+    };
+
+    // 2 KB page erase: 24.5 ms maximum (datasheet, flash memory characteristics)
+    const SETTINGS_ERASE_MS: u32 = 25;
+
+    type Vcp = Lpuart1Vcp;
+    type Sensors = Adc1Sensors;
+
+    /// Builds the HAL configuration for a 170 MHz clock tree
+    ///
+    /// Clock tree:
+    /// - PLL input: 8 MHz HSE bypass /2, or 16 MHz HSI16 /4 = 4 MHz
+    /// - PLL: x85 = 340 MHz VCO, /2 = 170 MHz SYSCLK (R)
+    /// - AHB, APB1, APB2: /1 = 170 MHz
+    /// - ADC: SYSCLK, /4 = 42.5 MHz (prescaler chosen by the ADC driver)
+    /// - Range 1 boost mode, required above 150 MHz
+    ///
+    /// Flash wait states are derived from HCLK by `embassy_stm32::init` (4 WS at
+    /// 170 MHz in boost mode, RM0440 section 3.3.3).
+    fn clocks() -> (embassy_stm32::Config, ClockSource) {
+        let mut config = embassy_stm32::Config::default();
+        let source = if hse_bypass_ready() {
+            ClockSource::HseBypass
+        } else {
+            ClockSource::Hsi
+        };
+
+        let rcc = &mut config.rcc;
+        let (pll_source, prediv) = match source {
+            ClockSource::HseBypass => {
+                rcc.hse = Some(Hse {
+                    freq: Hertz(HSE_BYPASS_HZ),
+                    mode: HseMode::Bypass,
+                });
+                (PllSource::HSE, PllPreDiv::DIV2)
+            }
+            ClockSource::Hsi => (PllSource::HSI, PllPreDiv::DIV4),
+        };
+        rcc.pll = Some(Pll {
+            source: pll_source,
+            prediv,
+            mul: PllMul::MUL85,
+            divp: None,
+            divq: None,
+            divr: Some(PllRDiv::DIV2),
+        });
+        rcc.sys = Sysclk::PLL1_R;
+        rcc.boost = true;
+        rcc.ahb_pre = AHBPrescaler::DIV1;
+        rcc.apb1_pre = APBPrescaler::DIV1;
+        rcc.apb2_pre = APBPrescaler::DIV1;
+        // The ADC kernel clock is off after reset: run it from SYSCLK
+        rcc.mux.adc12sel = mux::Adcsel::SYS;
+
+        (config, source)
+    }
+
+    fn reset_flags() -> ResetFlags {
+        use embassy_stm32::pac::RCC;
+
+        // The G4 has no separate power-on flag; BORRSTF is set on every power-up
+        let csr = RCC.csr().read();
+        let flags = ResetFlags {
+            iwdg: csr.iwdgrstf(),
+            wwdg: csr.wwdgrstf(),
+            software: csr.sftrstf(),
+            low_power: csr.lpwrrstf(),
+            pin: csr.pinrstf(),
+            power_on: csr.borrstf(),
+        };
+        RCC.csr().modify(|w| w.set_rmvf(true));
+        flags
+    }
+
+    fn unlock_backup_domain() {
+        use embassy_stm32::pac::{PWR, RCC};
+
+        RCC.apb1enr1().modify(|w| w.set_pwren(true));
+        PWR.cr1().modify(|w| w.set_dbp(true));
+        while !PWR.cr1().read().dbp() {}
+    }
+
+    fn split(p: Peripherals) -> Parts<Lpuart1Vcp, Adc1Sensors> {
+        // The sensors need the longest sample time to settle (several µs)
+        let mut adc = Adc::new(p.ADC1);
+        adc.set_sample_time(SampleTime::CYCLES640_5);
+        let sensors = Adc1Sensors {
+            vref: adc.enable_vrefint(),
+            temperature: adc.enable_temperature(),
+            adc,
+        };
+
+        Parts {
+            led: config::init_led(p.TIM2, p.PA5),
+            button: config::init_button(p.PC13, p.EXTI13),
+            vcp: Lpuart1Vcp {
+                usart: p.LPUART1,
+                tx: p.PA2,
+                rx: p.PA3,
+                tx_dma: p.DMA1_CH1,
+                rx_dma: p.DMA1_CH2,
+            },
+            sensors,
+            flash: p.FLASH,
+            iwdg: p.IWDG,
+            rtc: p.RTC,
+        }
+    }
+}
+
+/// STOP 1, main regulator off (RM0440, low-power modes)
+///
+/// About 25 mA running at 170 MHz (datasheet typical) and 90 µA in STOP 1 with
+/// the LSE and RTC running.
+#[cfg(feature = "low-power")]
+impl LowPower for NucleoG431rb {
+    const CURRENT: CurrentModel = CurrentModel {
+        run_ua: 25_000,
+        stop_ua: 90,
+    };
+
+    // EXTI line 20 is the RTC wake-up event (RM0440, EXTI line connections)
+    const RTC_WAKEUP_LINE: usize = 20;
+
+    fn select_stop() {
+        use embassy_stm32::pac::pwr::vals::Lpms;
+        use embassy_stm32::pac::PWR;
+
+        PWR.cr1().modify(|w| w.set_lpms(Lpms::STOP1));
+    }
+
+    fn clear_wakeup_flag() {
+        use embassy_stm32::pac::rtc::vals::Calrf;
+
+        // The RTC v3 flags are cleared through a separate register
+        embassy_stm32::pac::RTC
+            .scr()
+            .write(|w| w.set_cwutf(Calrf::CLEAR));
+    }
+
+    fn wakeup_timer_writable() -> bool {
+        embassy_stm32::pac::RTC.icsr().read().wutwf()
+    }
+}
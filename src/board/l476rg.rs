@@ -0,0 +1,251 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! NUCLEO-L476RG (STM32L476RGT6, Cortex-M4F, 80 MHz, 1 MB flash, 96 KB + 32 KB RAM)
+//!
+//! - LED LD2: PA5, TIM2_CH1
+//! - Button B1: PC13, EXTI13
+//! - VCP: USART2, PA2 TX / PA3 RX, DMA1 channel 7 (TX) and 6 (RX), request 2
+//!   (RM0351, DMA1 request mapping)
+//! - Sensors: ADC1 channels 17 (temperature) and 0 (VREFINT), clocked from SYSCLK
+//! - Clock: 8 MHz ST-Link MCO (HSE bypass), HSI16 fallback
+//!
+//! Only the 96 KB SRAM1 is used; SRAM2 (32 KB at 0x10000000) is left free.
+
+use embassy_stm32::adc::{Adc, SampleTime, Temperature, VrefInt};
+use embassy_stm32::mode::Async;
+use embassy_stm32::peripherals::{self, ADC1, DMA1_CH6, DMA1_CH7, PA2, PA3, USART2};
+use embassy_stm32::rcc::{
+    mux, AHBPrescaler, APBPrescaler, Hse, HseMode, Pll, PllMul, PllPreDiv, PllRDiv, PllSource,
+    Sysclk,
+};
+use embassy_stm32::time::Hertz;
+use embassy_stm32::timer::simple_pwm::SimplePwmChannel;
+use embassy_stm32::usart::{self, Uart};
+use embassy_stm32::{bind_interrupts, Peri, Peripherals};
+
+use blink::brightness::PwmLed;
+#[cfg(feature = "low-power")]
+use blink::power::CurrentModel;
+use blink::sensors::{Calibration, RawSample};
+use blink::supervisor::ResetFlags;
+
+#[cfg(feature = "low-power")]
+use super::LowPower;
+use super::{
+    hse_bypass_ready, system_word, Board, BoardInfo, ClockSource, Parts, Sensors, Vcp,
+    HSE_BYPASS_HZ,
+};
+use crate::config;
+
+/// Onboard LED (LD2) driven by TIM2 channel 1
+pub type Led = PwmLed<SimplePwmChannel<'static, peripherals::TIM2>>;
+
+// USART2 interrupt binding required by the async (DMA) UART driver
+bind_interrupts!(struct Irqs {
+    USART2 => usart::InterruptHandler<peripherals::USART2>;
+});
+
+/// The NUCLEO-L476RG
+pub struct NucleoL476rg;
+
+/// USART2 on the ST-Link VCP pins, with its DMA channels
+pub struct Usart2Vcp {
+    usart: Peri<'static, USART2>,
+    tx: Peri<'static, PA2>,
+    rx: Peri<'static, PA3>,
+    tx_dma: Peri<'static, DMA1_CH7>,
+    rx_dma: Peri<'static, DMA1_CH6>,
+}
+
+impl Vcp for Usart2Vcp {
+    fn into_async(self, config: usart::Config) -> Result<Uart<'static, Async>, usart::ConfigError> {
+        Uart::new(
+            self.usart,
+            self.rx,
+            self.tx,
+            Irqs,
+            self.tx_dma,
+            self.rx_dma,
+            config,
+        )
+    }
+}
+
+/// ADC1 with the internal temperature sensor and VREFINT channels
+pub struct Adc1Sensors {
+    adc: Adc<'static, ADC1>,
+    vref: VrefInt,
+    temperature: Temperature,
+}
+
+impl Sensors for Adc1Sensors {
+    /// Calibration values from the chip's datasheet (engineering bytes)
+    fn calibration() -> Calibration {
+        Calibration {
+            ts_cal1: system_word(0x1FFF_75A8),
+            ts_cal2: system_word(0x1FFF_75CA),
+            ts_cal1_c: 30,
+            ts_cal2_c: 110,
+            vrefint_cal: system_word(0x1FFF_75AA),
+            cal_vdda_mv: 3000,
+        }
+    }
+
+    /// Converts both channels, blocking (a conversion takes a few µs)
+    async fn sample(&mut self) -> RawSample {
+        RawSample {
+            vrefint: self.adc.blocking_read(&mut self.vref),
+            temperature: self.adc.blocking_read(&mut self.temperature),
+        }
+    }
+}
+
+impl Board for NucleoL476rg {
+    const INFO: BoardInfo = BoardInfo {
+        name: "NUCLEO-L476RG",
+        chip: "STM32L476RGTx",
+        sysclk_hz: 80_000_000,
+        led: "PA5 (TIM2_CH1)",
+        button: "PC13",
+        vcp: "USART2 (PA2 TX, PA3 RX)",
+    };
+
+    // 2 KB page erase: 24.5 ms maximum (datasheet, flash memory characteristics)
+    const SETTINGS_ERASE_MS: u32 = 25;
+
+    type Vcp = Usart2Vcp;
+    type Sensors = Adc1Sensors;
+
+    /// Builds the HAL configuration for an 80 MHz clock tree
+    ///
+    /// Clock tree:
+    /// - PLL input: 8 MHz HSE bypass /1, or 16 MHz HSI16 /2 = 8 MHz
+    /// - PLL: x20 = 160 MHz VCO, /2 = 80 MHz SYSCLK (R)
+    /// - AHB, APB1, APB2: /1 = 80 MHz
+    /// - ADC: SYSCLK = 80 MHz
+    ///
+    /// Flash wait states are derived from HCLK by `embassy_stm32::init` (4 WS at
+    /// 80 MHz in range 1, RM0351 section 3.3.3).
+    fn clocks() -> (embassy_stm32::Config, ClockSource) {
+        let mut config = embassy_stm32::Config::default();
+        let source = if hse_bypass_ready() {
+            ClockSource::HseBypass
+        } else {
+            ClockSource::Hsi
+        };
+
+        let rcc = &mut config.rcc;
+        let (pll_source, prediv) = match source {
+            ClockSource::HseBypass => {
+                rcc.hse = Some(Hse {
+                    freq: Hertz(HSE_BYPASS_HZ),
+                    mode: HseMode::Bypass,
+                });
+                (PllSource::HSE, PllPreDiv::DIV1)
+            }
+            ClockSource::Hsi => {
+                rcc.hsi = true;
+                (PllSource::HSI, PllPreDiv::DIV2)
+            }
+        };
+        rcc.pll = Some(Pll {
+            source: pll_source,
+            prediv,
+            mul: PllMul::MUL20,
+            divp: None,
+            divq: None,
+            divr: Some(PllRDiv::DIV2),
+        });
+        rcc.sys = Sysclk::PLL1_R;
+        rcc.ahb_pre = AHBPrescaler::DIV1;
+        rcc.apb1_pre = APBPrescaler::DIV1;
+        rcc.apb2_pre = APBPrescaler::DIV1;
+        // The ADC kernel clock is off after reset: run it from SYSCLK
+        rcc.mux.adcsel = mux::Adcsel::SYS;
+
+        (config, source)
+    }
+
+    fn reset_flags() -> ResetFlags {
+        use embassy_stm32::pac::RCC;
+
+        // The L4 has no separate power-on flag; BORRSTF is set on every power-up
+        let csr = RCC.csr().read();
+        let flags = ResetFlags {
+            iwdg: csr.iwdgrstf(),
+            wwdg: csr.wwdgrstf(),
+            software: csr.sftrstf(),
+            low_power: csr.lpwrrstf(),
+            pin: csr.pinrstf(),
+            power_on: csr.borrstf(),
+        };
+        RCC.csr().modify(|w| w.set_rmvf(true));
+        flags
+    }
+
+    fn unlock_backup_domain() {
+        use embassy_stm32::pac::{PWR, RCC};
+
+        RCC.apb1enr1().modify(|w| w.set_pwren(true));
+        PWR.cr1().modify(|w| w.set_dbp(true));
+        while !PWR.cr1().read().dbp() {}
+    }
+
+    fn split(p: Peripherals) -> Parts<Usart2Vcp, Adc1Sensors> {
+        // The sensors need the longest sample time to settle (several µs)
+        let mut adc = Adc::new(p.ADC1);
+        adc.set_sample_time(SampleTime::CYCLES640_5);
+        let sensors = Adc1Sensors {
+            vref: adc.enable_vrefint(),
+            temperature: adc.enable_temperature(),
+            adc,
+        };
+
+        Parts {
+            led: config::init_led(p.TIM2, p.PA5),
+            button: config::init_button(p.PC13, p.EXTI13),
+            vcp: Usart2Vcp {
+                usart: p.USART2,
+                tx: p.PA2,
+                rx: p.PA3,
+                tx_dma: p.DMA1_CH7,
+                rx_dma: p.DMA1_CH6,
+            },
+            sensors,
+            flash: p.FLASH,
+            iwdg: p.IWDG,
+            rtc: p.RTC,
+        }
+    }
+}
+
+/// STOP 2, the deepest STOP mode that keeps every register (RM0351, low-power modes)
+///
+/// About 10 mA running at 80 MHz (datasheet typical) and 3 µA in STOP 2 with
+/// the LSE and RTC running.
+#[cfg(feature = "low-power")]
+impl LowPower for NucleoL476rg {
+    const CURRENT: CurrentModel = CurrentModel {
+        run_ua: 10_000,
+        stop_ua: 3,
+    };
+
+    // EXTI line 20 is the RTC wake-up event (RM0351, EXTI line connections)
+    const RTC_WAKEUP_LINE: usize = 20;
+
+    fn select_stop() {
+        use embassy_stm32::pac::pwr::vals::Lpms;
+        use embassy_stm32::pac::PWR;
+
+        PWR.cr1().modify(|w| w.set_lpms(Lpms::STOP2));
+    }
+
+    fn clear_wakeup_flag() {
+        embassy_stm32::pac::RTC.isr().modify(|w| w.set_wutf(false));
+    }
+
+    fn wakeup_timer_writable() -> bool {
+        embassy_stm32::pac::RTC.isr().read().wutwf()
+    }
+}
@@ -0,0 +1,318 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Board support for the Nucleo-64 boards the firmware runs on
+//!
+//! Everything that differs between boards lives behind the `Board` trait:
+//! - `BoardInfo` - names and pin mapping for reports
+//! - `layout` / `SETTINGS` / `BOOT` - flash partitions generated from `memory/<board>.toml`
+//! - `Board::clocks` - the chip's clock tree (and PLL source probe)
+//! - `Board::reset_flags` - the chip's RCC reset flags
+//! - `Board::unlock_backup_domain` - write access to the RTC clock selection (LSE probe)
+//! - `Board::split` - LED, button, VCP and ADC peripherals, ready for `config`
+//! - `Sensors` - the chip's ADC driver and factory calibration addresses
+//! - `LowPower` - the chip's STOP mode bits and currents (`low-power` feature)
+//!
+//! One module per board, compiled only when its Cargo feature is enabled:
+//!
+//! | Feature        | Board          | LED (LD2)    | Button (B1) | VCP                 | Sensors |
+//! |----------------|----------------|--------------|-------------|---------------------|---------|
+//! | `board-f303re` | NUCLEO-F303RE  | PA5 TIM2_CH1 | PC13        | USART2 (PA2/PA3)    | ADC1    |
+//! | `board-f401re` | NUCLEO-F401RE  | PA5 TIM2_CH1 | PC13        | USART2 (PA2/PA3)    | ADC1    |
+//! | `board-l476rg` | NUCLEO-L476RG  | PA5 TIM2_CH1 | PC13        | USART2 (PA2/PA3)    | ADC1    |
+//! | `board-g431rb` | NUCLEO-G431RB  | PA5 TIM2_CH1 | PC13        | LPUART1 (PA2/PA3)   | ADC1    |
+//!
+//! `build.rs` validates the matching `memory/<board>.toml` and generates both
+//! `memory.x` and `layout` from it.
+//!
+//! # Design Philosophy
+//! The trait hands out peripheral-erased drivers (`Uart<'static, Async>`,
+//! `ExtiInput`, the PWM channel) or small local traits (`Vcp`, `Sensors`), so
+//! `config` and `firmware` are written once and never name a board-specific
+//! peripheral. A new board is one module and one feature.
+
+use embassy_stm32::exti::ExtiInput;
+use embassy_stm32::mode::Async;
+use embassy_stm32::peripherals::{FLASH, IWDG, RTC};
+use embassy_stm32::usart::{self, Uart};
+use embassy_stm32::{Peri, Peripherals};
+
+use blink::boot::Partitions;
+#[cfg(feature = "low-power")]
+use blink::power::CurrentModel;
+use blink::sensors::{Calibration, RawSample};
+use blink::settings::Geometry;
+use blink::supervisor::ResetFlags;
+
+#[cfg(feature = "board-f303re")]
+mod f303re;
+#[cfg(feature = "board-f303re")]
+pub use f303re::{Led, NucleoF303re as Active};
+
+#[cfg(feature = "board-f401re")]
+mod f401re;
+#[cfg(feature = "board-f401re")]
+pub use f401re::{Led, NucleoF401re as Active};
+
+#[cfg(feature = "board-l476rg")]
+mod l476rg;
+#[cfg(feature = "board-l476rg")]
+pub use l476rg::{Led, NucleoL476rg as Active};
+
+#[cfg(feature = "board-g431rb")]
+mod g431rb;
+#[cfg(feature = "board-g431rb")]
+pub use g431rb::{Led, NucleoG431rb as Active};
+
+/// Static description of a board, for boot reports and configuration
+#[derive(Clone, Copy, Debug)]
+pub struct BoardInfo {
+    /// Board name as printed on the silkscreen
+    pub name: &'static str,
+    /// Chip name as understood by probe-rs (`--chip`)
+    pub chip: &'static str,
+    /// System clock the board's `clocks` configures, in Hz
+    pub sysclk_hz: u32,
+    /// LED pin and timer channel
+    pub led: &'static str,
+    /// User button pin
+    pub button: &'static str,
+    /// Virtual COM port UART and pins
+    pub vcp: &'static str,
+}
+
+/// Flash partitions generated by `build.rs` from `memory/<board>.toml`
+///
+/// Offsets are relative to the start of flash, as taken by the flash driver.
+#[allow(dead_code)]
+pub mod layout {
+    include!(concat!(env!("OUT_DIR"), "/layout.rs"));
+}
+
+/// Settings pages reserved at the end of flash by the board's memory descriptor
+pub const SETTINGS: Geometry = Geometry {
+    base: layout::SETTINGS_OFFSET,
+    page_size: layout::SETTINGS_PAGE_SIZE,
+};
+
+/// Serial bootloader partitions, in builds with the `bootloader` feature
+pub const BOOT: Option<Partitions> = match layout::BOOT_STATE.1 {
+    0 => None,
+    _ => Some(Partitions {
+        flash_origin: layout::FLASH_ORIGIN,
+        state: layout::BOOT_STATE.0,
+        active: layout::APP.0,
+        download: layout::DOWNLOAD.0,
+        scratch: layout::SCRATCH.0,
+        slot_len: layout::APP.1,
+        page_size: layout::SCRATCH.1,
+        ram: [layout::RAM, layout::CCMRAM],
+    }),
+};
+
+/// Clock source feeding the PLL, as chosen by `Board::clocks`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, defmt::Format)]
+pub enum ClockSource {
+    /// 8 MHz from the ST-Link MCO via HSE bypass
+    HseBypass,
+    /// Internal RC oscillator (fallback when no MCO is present)
+    Hsi,
+}
+
+impl ClockSource {
+    /// Short human-readable name for boot reports
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::HseBypass => "HSE bypass (ST-Link MCO)",
+            Self::Hsi => "HSI",
+        }
+    }
+}
+
+/// Clock source of the RTC, as chosen by `config::clocks`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, defmt::Format)]
+pub enum RtcClock {
+    /// 32.768 kHz crystal (X2)
+    Lse,
+    /// Internal RC oscillator (fallback when the crystal does not start)
+    Lsi,
+}
+
+impl RtcClock {
+    /// Short human-readable name for boot reports
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Lse => "LSE 32.768 kHz crystal",
+            Self::Lsi => "LSI (no LSE, time will drift)",
+        }
+    }
+}
+
+/// Peripherals the firmware uses, split off `Peripherals` by `Board::split`
+pub struct Parts<V: Vcp, S: Sensors> {
+    /// PWM-driven LD2, enabled and off
+    pub led: Led,
+    /// User button B1 (active LOW, external pull-up)
+    pub button: ExtiInput<'static>,
+    /// Virtual COM port UART, not yet configured
+    pub vcp: V,
+    /// ADC with the temperature sensor and VREFINT enabled
+    pub sensors: S,
+    /// Flash controller, for the settings store
+    pub flash: Peri<'static, FLASH>,
+    /// Independent watchdog
+    pub iwdg: Peri<'static, IWDG>,
+    /// Real-time clock, not yet configured
+    pub rtc: Peri<'static, RTC>,
+}
+
+/// Small trait to abstract a board's virtual COM port.
+///
+/// We define a local trait so each board can keep its UART instance, pins and
+/// DMA channels as concrete types and still hand `config` a peripheral-erased
+/// `Uart`.
+pub trait Vcp {
+    /// Creates the DMA-driven UART
+    fn into_async(self, config: usart::Config) -> Result<Uart<'static, Async>, usart::ConfigError>;
+}
+
+/// Small trait to abstract a chip's internal ADC channels.
+///
+/// We define a local trait because the ADC drivers differ per family (an
+/// interrupt-driven one on the F3, blocking ones elsewhere, different channel
+/// types and sample times), while the firmware only ever wants one conversion
+/// of each internal channel.
+pub trait Sensors {
+    /// Reads the factory calibration from system memory
+    fn calibration() -> Calibration;
+
+    /// Converts VREFINT and the temperature sensor once each
+    async fn sample(&mut self) -> RawSample;
+}
+
+/// A Nucleo board the firmware can run on
+pub trait Board {
+    /// Names and pin mapping
+    const INFO: BoardInfo;
+
+    /// Longest erase of one settings page in milliseconds (datasheet maximum)
+    ///
+    /// The core stalls on flash reads for the whole erase, so this bounds how
+    /// long a settings save keeps every task from running.
+    const SETTINGS_ERASE_MS: u32;
+
+    /// The board's virtual COM port
+    type Vcp: Vcp;
+
+    /// The board's ADC with its internal channels
+    type Sensors: Sensors;
+
+    /// Builds the HAL configuration for the board's clock tree
+    ///
+    /// Must be called before `embassy_stm32::init`, as it may probe the HSE directly.
+    ///
+    /// # Returns
+    /// The HAL configuration and the selected PLL clock source.
+    fn clocks() -> (embassy_stm32::Config, ClockSource);
+
+    /// Reads and clears the reset flags latched in RCC_CSR
+    fn reset_flags() -> ResetFlags;
+
+    /// Enables the PWR clock and lifts the backup domain write protection
+    ///
+    /// Needed before `embassy_stm32::init` to probe the LSE (`lse_ready`); the
+    /// PWR register holding DBP differs between families.
+    fn unlock_backup_domain();
+
+    /// Configures the LED, button and ADC and splits off the remaining peripherals
+    fn split(p: Peripherals) -> Parts<Self::Vcp, Self::Sensors>;
+}
+
+/// Small trait to abstract a chip's STOP mode.
+///
+/// We define a local trait so `low_power` can enter and leave STOP the same way
+/// on every board; only the PWR mode bits, the RTC wake-up flag registers and
+/// the datasheet currents differ between families.
+#[cfg(feature = "low-power")]
+pub trait LowPower {
+    /// Typical run and STOP supply currents, for the power report
+    const CURRENT: CurrentModel;
+
+    /// EXTI line carrying the RTC wake-up event
+    const RTC_WAKEUP_LINE: usize;
+
+    /// Makes the next deep sleep STOP (not STANDBY), regulator in low-power mode
+    fn select_stop();
+
+    /// Clears the RTC wake-up timer flag (WUTF)
+    fn clear_wakeup_flag();
+
+    /// Returns `true` once the disabled wake-up timer may be reloaded (WUTWF)
+    fn wakeup_timer_writable() -> bool;
+}
+
+/// Reads a 16-bit factory calibration value from system memory
+///
+/// # Arguments
+/// * `address` - Address from the chip's datasheet (always readable, read-only)
+pub(crate) fn system_word(address: usize) -> u16 {
+    // SAFETY: the engineering bytes are part of system memory, which is
+    // mapped and readable on every supported chip; the address is aligned
+    unsafe { core::ptr::read_volatile(address as *const u16) }
+}
+
+/// Frequency of the ST-Link MCO output fed to OSC_IN (HSE bypass)
+pub(crate) const HSE_BYPASS_HZ: u32 = 8_000_000;
+
+/// Polls of `HSERDY` before giving up on the external clock
+///
+/// Roughly 25 ms at an 8-16 MHz reset clock; the MCO is either present
+/// immediately or not connected at all (SB16/SB50 solder bridges).
+const HSE_READY_POLLS: u32 = 50_000;
+
+/// Checks whether an external clock is present on OSC_IN
+///
+/// Briefly enables the HSE in bypass mode and waits a bounded time for `HSERDY`,
+/// then switches it off again so `embassy_stm32::init` starts from reset state.
+/// The RCC_CR bits used here are at the same place on every supported family.
+pub(crate) fn hse_bypass_ready() -> bool {
+    use embassy_stm32::pac::RCC;
+
+    // HSEBYP may only be changed while the HSE is off
+    RCC.cr().modify(|w| w.set_hsebyp(true));
+    RCC.cr().modify(|w| w.set_hseon(true));
+    let ready = (0..HSE_READY_POLLS).any(|_| RCC.cr().read().hserdy());
+    RCC.cr().modify(|w| w.set_hseon(false));
+    while RCC.cr().read().hserdy() {}
+    RCC.cr().modify(|w| w.set_hsebyp(false));
+    ready
+}
+
+/// Polls of `LSERDY` before falling back to the LSI
+///
+/// Roughly 2 s at an 8-16 MHz reset clock, the crystal's worst-case start-up
+/// time. Only spent when the crystal is not already running.
+const LSE_READY_POLLS: u32 = 4_000_000;
+
+/// Checks whether the 32.768 kHz crystal oscillates
+///
+/// The backup domain survives resets, so after the first boot the LSE is
+/// usually running already and this returns at once. Otherwise the LSE is
+/// started and given a bounded time to become ready; on success it is left
+/// running for `embassy_stm32::init`, which still resets the backup domain once
+/// to select the RTC clock, so a cold boot waits for the crystal twice.
+/// The RCC_BDCR bits used here are at the same place on every supported family.
+pub(crate) fn lse_ready<B: Board>() -> bool {
+    use embassy_stm32::pac::RCC;
+
+    if RCC.bdcr().read().lserdy() {
+        return true;
+    }
+    B::unlock_backup_domain();
+    RCC.bdcr().modify(|w| w.set_lseon(true));
+    let ready = (0..LSE_READY_POLLS).any(|_| RCC.bdcr().read().lserdy());
+    if !ready {
+        RCC.bdcr().modify(|w| w.set_lseon(false));
+    }
+    ready
+}
@@ -0,0 +1,733 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Boot state, slot swapping and rollback for the serial bootloader
+//!
+//! This module decides what the bootloader does at each reset and carries it out:
+//! - `Partitions` - where the state log and the two application slots are in flash
+//! - `BootState` / `Swap` - what is pending: an upload, a swap step, a trial run
+//! - `StateLog` - CRC-protected record log of the boot state over two flash pages
+//! - `Bootloader` - resumes swaps, rolls back unconfirmed images and serves uploads
+//! - `request_update` / `confirm` - the application's side of the handshake
+//! - `valid_vectors` - sanity check of an image's initial stack pointer and reset vector
+//!
+//! # Flash Layout
+//! `bootloader | state (2 pages) | active slot | download slot | scratch page | ... | settings`
+//!
+//! The application is linked for and runs from the active slot; uploads are
+//! written to the download slot, which has the same size.
+//!
+//! # Swap
+//! Installing an upload exchanges the two slots one page at a time through the
+//! scratch page, in three steps per page:
+//! 1. Active page to scratch
+//! 2. Download page to active
+//! 3. Scratch to download page
+//!
+//! The next step is logged after each one. A step only reads a page that no
+//! logged step has erased yet, so after a power cut the bootloader repeats the
+//! step it finds in the log and carries on. When it is done the download slot
+//! holds the previous image.
+//!
+//! # Rollback
+//! A new image first runs in `Testing` state and calls `confirm` once it is up.
+//! A reset before that (a crash, the watchdog, a power cut) finds `Testing`
+//! still logged, so the bootloader swaps the slots back and records
+//! `RolledBack` for the previous image to report.
+//!
+//! # Design Philosophy
+//! Like the settings store, everything here runs against `SettingsFlash` with
+//! absolute flash offsets, so every swap step, and a power cut between any two
+//! of them, is tested on the host with `mock::MockFlash`. The bootloader
+//! binary only adds the UART and the jump to the application.
+
+use crate::crc::{crc16, crc32_update};
+use crate::settings::{SettingsFlash, StoreError};
+use crate::update::{Info, Nak, Reply, Request, MAX_CHUNK, PROTOCOL_VERSION, WRITE_ALIGN};
+
+/// Size in bytes of one boot state record (a multiple of every flash write size)
+pub const RECORD_SIZE: usize = 16;
+
+/// Record magic marking a programmed slot
+const MAGIC: u16 = 0x5442;
+
+/// Offset of the CRC within a record
+const CRC_OFFSET: usize = RECORD_SIZE - 2;
+
+/// Bytes copied per flash read while swapping or checking an image
+const COPY_CHUNK: usize = 256;
+
+/// Flash partitions used by the bootloader, as generated from `memory/<board>.toml`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub struct Partitions {
+    /// Address of the start of flash
+    pub flash_origin: u32,
+    /// Offset of the first of the two boot state pages
+    pub state: u32,
+    /// Offset of the slot the application runs from
+    pub active: u32,
+    /// Offset of the slot uploads are written to
+    pub download: u32,
+    /// Offset of the page the slots are exchanged through
+    pub scratch: u32,
+    /// Size of each slot in bytes (a whole number of pages)
+    pub slot_len: u32,
+    /// Erase page size in bytes
+    pub page_size: u32,
+    /// RAM the initial stack pointer may point into, as (address, length);
+    /// `(0, 0)` for an unused entry
+    pub ram: [(u32, u32); 2],
+}
+
+impl Partitions {
+    /// Returns the address the application is linked at
+    pub fn app_address(&self) -> u32 {
+        self.flash_origin + self.active
+    }
+}
+
+/// Checks the first two vector table entries of an image built for the active slot
+///
+/// The initial stack pointer must be word aligned and point into (or just
+/// past the end of) one of the RAM ranges, and the reset vector must be a
+/// Thumb address inside the slot. An erased or foreign image fails both.
+///
+/// # Arguments
+/// * `vectors` - At least the first 8 bytes of the image
+/// * `parts` - Partitions the image is meant to run from
+pub fn valid_vectors(vectors: &[u8], parts: &Partitions) -> bool {
+    let Some(words) = vectors.get(..8) else {
+        return false;
+    };
+    let sp = u32::from_le_bytes([words[0], words[1], words[2], words[3]]);
+    let reset = u32::from_le_bytes([words[4], words[5], words[6], words[7]]);
+    let stack_ok = sp.is_multiple_of(4)
+        && parts.ram.iter().any(|&(origin, len)| {
+            len != 0 && sp > origin && u64::from(sp) <= u64::from(origin) + u64::from(len)
+        });
+    let start = u64::from(parts.app_address());
+    let entry = u64::from(reset & !1);
+    let reset_ok = reset & 1 == 1 && entry > start && entry < start + u64::from(parts.slot_len);
+    stack_ok && reset_ok
+}
+
+/// Progress of a slot exchange
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub struct Swap {
+    /// Number of pages to exchange, from the start of the slots
+    pub pages: u16,
+    /// Page being exchanged
+    pub page: u16,
+    /// Next step for `page`: 0 active to scratch, 1 download to active,
+    /// 2 scratch to download
+    pub step: u8,
+    /// `true` when putting the previous image back
+    pub revert: bool,
+}
+
+impl Swap {
+    /// Creates a swap at its first step
+    pub fn start(pages: u16, revert: bool) -> Self {
+        Self {
+            pages,
+            page: 0,
+            step: 0,
+            revert,
+        }
+    }
+
+    /// Returns the swap after the current step, or `None` once it was the last
+    pub fn next(self) -> Option<Self> {
+        if self.step < 2 {
+            return Some(Self {
+                step: self.step + 1,
+                ..self
+            });
+        }
+        (self.page + 1 < self.pages).then_some(Self {
+            page: self.page + 1,
+            step: 0,
+            ..self
+        })
+    }
+}
+
+/// What the bootloader has to do at the next reset
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub enum BootState {
+    /// Run the application in the active slot (also an erased log)
+    Idle,
+    /// The application asked for an upload: stay in the bootloader
+    Requested,
+    /// Exchange the slots, continuing at the recorded step
+    Swap(Swap),
+    /// A new image is on its first run and has not confirmed yet
+    Testing {
+        /// Pages the install exchanged, for the rollback
+        pages: u16,
+    },
+    /// The new image never confirmed and the previous one was put back
+    RolledBack,
+}
+
+impl BootState {
+    /// Encodes the state into record bytes 2..4 and 8..14
+    fn encode(&self, rec: &mut [u8; RECORD_SIZE]) {
+        let (tag, swap) = match *self {
+            Self::Idle => (0, Swap::start(0, false)),
+            Self::Requested => (1, Swap::start(0, false)),
+            Self::Swap(swap) => (2, swap),
+            Self::Testing { pages } => (3, Swap::start(pages, false)),
+            Self::RolledBack => (4, Swap::start(0, false)),
+        };
+        rec[2] = tag;
+        rec[3] = u8::from(swap.revert);
+        rec[8..10].copy_from_slice(&swap.pages.to_le_bytes());
+        rec[10..12].copy_from_slice(&swap.page.to_le_bytes());
+        rec[12] = swap.step;
+        rec[13] = 0;
+    }
+
+    /// Decodes a state written by `encode`
+    fn decode(rec: &[u8; RECORD_SIZE]) -> Option<Self> {
+        let pages = u16::from_le_bytes([rec[8], rec[9]]);
+        let swap = Swap {
+            pages,
+            page: u16::from_le_bytes([rec[10], rec[11]]),
+            step: rec[12],
+            revert: rec[3] != 0,
+        };
+        match rec[2] {
+            0 => Some(Self::Idle),
+            1 => Some(Self::Requested),
+            2 if swap.step <= 2 && swap.page < swap.pages => Some(Self::Swap(swap)),
+            3 => Some(Self::Testing { pages }),
+            4 => Some(Self::RolledBack),
+            _ => None,
+        }
+    }
+}
+
+/// Write position of the state log
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Cursor {
+    /// Page holding the newest record
+    page: u32,
+    /// First unprogrammed slot in `page` (`slots()` when full)
+    next_slot: u32,
+    /// Sequence number of the newest record
+    seq: u32,
+}
+
+/// Boot state record log over two flash pages
+///
+/// The same scheme as `settings::SettingsStore`: records are appended to one
+/// page, the other page is erased when it is full, and the valid record with
+/// the highest sequence number wins, so a power cut during a save leaves the
+/// previous state in force. A swap logs three records per page it exchanges.
+///
+/// # Record Format
+///
+/// | Offset | Size | Field                                          |
+/// |--------|------|------------------------------------------------|
+/// | 0      | 2    | Magic `0x5442` (little-endian)                 |
+/// | 2      | 1    | State: 0 idle, 1 requested, 2 swap, 3 testing, 4 rolled back |
+/// | 3      | 1    | Swap direction: 1 when reverting              |
+/// | 4      | 4    | Sequence number                                |
+/// | 8      | 2    | Pages to exchange                              |
+/// | 10     | 2    | Page being exchanged                           |
+/// | 12     | 1    | Next step for that page                        |
+/// | 13     | 1    | Reserved (0)                                   |
+/// | 14     | 2    | CRC-16/CCITT-FALSE of bytes 0..14              |
+pub struct StateLog {
+    base: u32,
+    page_size: u32,
+    cursor: Option<Cursor>,
+}
+
+impl StateLog {
+    /// Creates a log over the state pages of `parts`; call `load` before `save`
+    pub fn new(parts: &Partitions) -> Self {
+        Self {
+            base: parts.state,
+            page_size: parts.page_size,
+            cursor: None,
+        }
+    }
+
+    /// Number of record slots per page
+    fn slots(&self) -> u32 {
+        self.page_size / RECORD_SIZE as u32
+    }
+
+    /// Offset of record `slot` in `page`
+    fn slot_offset(&self, page: u32, slot: u32) -> u32 {
+        self.base + page * self.page_size + slot * RECORD_SIZE as u32
+    }
+
+    /// Scans both pages and returns the newest valid state
+    ///
+    /// Falls back to `BootState::Idle` if no valid record exists.
+    pub fn load<F: SettingsFlash>(&mut self, flash: &mut F) -> BootState {
+        let mut newest: Option<(Cursor, BootState)> = None;
+        for page in 0..2 {
+            let mut next_slot = self.slots();
+            for slot in 0..self.slots() {
+                let mut rec = [0u8; RECORD_SIZE];
+                if flash.read(self.slot_offset(page, slot), &mut rec).is_err() {
+                    continue;
+                }
+                if rec.iter().all(|&b| b == 0xFF) {
+                    next_slot = slot;
+                    break;
+                }
+                if let Some((seq, state)) = parse_record(&rec) {
+                    if newest.is_none_or(|(c, _)| seq > c.seq) {
+                        let cursor = Cursor {
+                            page,
+                            next_slot: 0,
+                            seq,
+                        };
+                        newest = Some((cursor, state));
+                    }
+                }
+            }
+            if let Some((c, _)) = newest.as_mut() {
+                if c.page == page {
+                    c.next_slot = next_slot;
+                }
+            }
+        }
+        self.cursor = newest.map(|(c, _)| c);
+        newest.map_or(BootState::Idle, |(_, state)| state)
+    }
+
+    /// Appends `state` to the log
+    ///
+    /// # Errors
+    /// Returns `StoreError` if programming or verification fails; the previous
+    /// state remains in force in that case.
+    pub fn save<F: SettingsFlash>(
+        &mut self,
+        flash: &mut F,
+        state: BootState,
+    ) -> Result<(), StoreError> {
+        let (page, slot, seq) = match self.cursor {
+            Some(c) if c.next_slot < self.slots() => (c.page, c.next_slot, c.seq + 1),
+            Some(c) => (1 - c.page, 0, c.seq + 1),
+            None => (0, 0, 1),
+        };
+        // Starting a page: erase it first (the other page keeps the old record)
+        if slot == 0 {
+            flash.erase(self.slot_offset(page, 0), self.page_size)?;
+        }
+
+        let rec = build_record(seq, &state);
+        let offset = self.slot_offset(page, slot);
+        let written = flash.write(offset, &rec);
+
+        // Whatever happened, the slot may now be partially programmed
+        self.cursor = Some(Cursor {
+            page,
+            next_slot: slot + 1,
+            seq: self.cursor.map_or(0, |c| c.seq),
+        });
+        written?;
+
+        let mut check = [0u8; RECORD_SIZE];
+        flash.read(offset, &mut check)?;
+        if check != rec {
+            return Err(StoreError::Verify);
+        }
+        self.cursor = Some(Cursor {
+            page,
+            next_slot: slot + 1,
+            seq,
+        });
+        Ok(())
+    }
+}
+
+/// Builds a complete record for `state` with sequence number `seq`
+fn build_record(seq: u32, state: &BootState) -> [u8; RECORD_SIZE] {
+    let mut rec = [0u8; RECORD_SIZE];
+    rec[0..2].copy_from_slice(&MAGIC.to_le_bytes());
+    state.encode(&mut rec);
+    rec[4..8].copy_from_slice(&seq.to_le_bytes());
+    let crc = crc16(&rec[..CRC_OFFSET]);
+    rec[CRC_OFFSET..].copy_from_slice(&crc.to_le_bytes());
+    rec
+}
+
+/// Validates a record and decodes its sequence number and state
+fn parse_record(rec: &[u8; RECORD_SIZE]) -> Option<(u32, BootState)> {
+    if u16::from_le_bytes([rec[0], rec[1]]) != MAGIC {
+        return None;
+    }
+    let crc = u16::from_le_bytes([rec[CRC_OFFSET], rec[CRC_OFFSET + 1]]);
+    if crc16(&rec[..CRC_OFFSET]) != crc {
+        return None;
+    }
+    let seq = u32::from_le_bytes([rec[4], rec[5], rec[6], rec[7]]);
+    Some((seq, BootState::decode(rec)?))
+}
+
+/// Asks the bootloader to wait for an upload at the next reset
+///
+/// Called by the application's `update` command before it resets.
+///
+/// # Errors
+/// `StoreError` if the state could not be saved.
+pub fn request_update<F: SettingsFlash>(
+    flash: &mut F,
+    parts: &Partitions,
+) -> Result<(), StoreError> {
+    let mut log = StateLog::new(parts);
+    log.load(flash);
+    log.save(flash, BootState::Requested)
+}
+
+/// Confirms that the running image started, once the application is up
+///
+/// A `Testing` image is kept from then on; a `RolledBack` note is cleared
+/// after the application has had the chance to report it.
+///
+/// # Returns
+/// The state found: `Testing` if a new image has just been confirmed,
+/// `RolledBack` if the previous update failed, otherwise `Idle`.
+///
+/// # Errors
+/// `StoreError` if the state could not be saved; a `Testing` image is then
+/// rolled back at the next reset.
+pub fn confirm<F: SettingsFlash>(
+    flash: &mut F,
+    parts: &Partitions,
+) -> Result<BootState, StoreError> {
+    let mut log = StateLog::new(parts);
+    let state = log.load(flash);
+    if matches!(state, BootState::Testing { .. } | BootState::RolledBack) {
+        log.save(flash, BootState::Idle)?;
+    }
+    Ok(state)
+}
+
+/// What the bootloader does after `Bootloader::start`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub enum Start {
+    /// Jump to the application in the active slot
+    Run,
+    /// Stay and serve upload requests over the UART
+    Update,
+}
+
+/// An upload in progress
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Upload {
+    /// Image length announced by `Begin`
+    len: u32,
+    /// Image CRC-32 announced by `Begin`
+    crc: u32,
+    /// Bytes programmed so far
+    received: u32,
+    /// Offset of the last chunk programmed
+    last: u32,
+    /// `Finish` checked the image
+    verified: bool,
+}
+
+/// The bootloader's decisions and flash work, independent of the UART
+pub struct Bootloader<F: SettingsFlash> {
+    flash: F,
+    parts: Partitions,
+    log: StateLog,
+    state: BootState,
+    upload: Option<Upload>,
+    reset: bool,
+}
+
+impl<F: SettingsFlash> Bootloader<F> {
+    /// Loads the boot state from flash
+    pub fn new(mut flash: F, parts: Partitions) -> Self {
+        let mut log = StateLog::new(&parts);
+        let state = log.load(&mut flash);
+        Self {
+            flash,
+            parts,
+            log,
+            state,
+            upload: None,
+            reset: false,
+        }
+    }
+
+    /// Returns the boot state as last loaded or saved
+    pub fn state(&self) -> BootState {
+        self.state
+    }
+
+    /// Carries out whatever the boot state asks for at reset
+    ///
+    /// Finishes an interrupted swap, rolls back an image that reset before
+    /// confirming, and installs a verified upload.
+    ///
+    /// # Returns
+    /// `Run` if the active slot holds a plausible image and no upload was
+    /// requested, `Update` otherwise (also when the flash fails, so a new
+    /// image can still be uploaded).
+    pub fn start(&mut self) -> Start {
+        loop {
+            let state = self.state;
+            match state {
+                BootState::Idle | BootState::RolledBack if self.app_valid() => return Start::Run,
+                BootState::Idle | BootState::RolledBack | BootState::Requested => {
+                    return Start::Update
+                }
+                BootState::Testing { pages } => {
+                    // The new image reset before confirming: put the previous one back
+                    if self.set(BootState::Swap(Swap::start(pages, true))).is_err() {
+                        return Start::Update;
+                    }
+                }
+                BootState::Swap(swap) => {
+                    let next = match swap.revert {
+                        true => BootState::RolledBack,
+                        false => BootState::Testing { pages: swap.pages },
+                    };
+                    if self.swap(swap).and_then(|()| self.set(next)).is_err() {
+                        return Start::Update;
+                    }
+                    // Run the new image now; `Testing` found at a later reset
+                    // means it never confirmed
+                    if !swap.revert {
+                        return Start::Run;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns `true` once a reply asked for a reset (after `Boot`)
+    pub fn reset_requested(&self) -> bool {
+        self.reset
+    }
+
+    /// Handles one upload request
+    ///
+    /// # Returns
+    /// The reply to send; after an `Ack` to `Boot`, `reset_requested` is set
+    /// and the caller resets once the reply is out.
+    pub fn handle(&mut self, request: Request<'_>) -> Reply {
+        match request {
+            Request::Hello => Reply::Info(Info {
+                version: PROTOCOL_VERSION,
+                slot_address: self.parts.app_address(),
+                slot_len: self.parts.slot_len,
+                page_size: self.parts.page_size,
+                max_chunk: MAX_CHUNK as u16,
+            }),
+            Request::Begin { len, crc } => self.begin(len, crc),
+            Request::Write { offset, data } => self.write(offset, data),
+            Request::Finish => self.finish(),
+            Request::Boot => self.boot(),
+        }
+    }
+
+    /// Erases the download slot for an image of `len` bytes
+    fn begin(&mut self, len: u32, crc: u32) -> Reply {
+        self.upload = None;
+        if len == 0 || len > self.parts.slot_len {
+            return Reply::Nak(Nak::TooLarge);
+        }
+        // Also erase whatever an earlier upload left beyond this image
+        let pages = len
+            .div_ceil(self.parts.page_size)
+            .max(self.used_pages(self.parts.download));
+        let erase = self
+            .flash
+            .erase(self.parts.download, pages * self.parts.page_size);
+        if erase.is_err() {
+            return Reply::Nak(Nak::Flash);
+        }
+        self.upload = Some(Upload {
+            len,
+            crc,
+            received: 0,
+            last: 0,
+            verified: false,
+        });
+        Reply::Ack(0)
+    }
+
+    /// Programs the next chunk of the upload
+    fn write(&mut self, offset: u32, data: &[u8]) -> Reply {
+        let Some(mut upload) = self.upload.filter(|u| !u.verified) else {
+            return Reply::Nak(Nak::Sequence);
+        };
+        let end = offset + data.len() as u32;
+        // The host did not see the previous Ack and sent the chunk again
+        if offset == upload.last && end == upload.received && offset != end {
+            return Reply::Ack(upload.received);
+        }
+        if offset != upload.received || end > upload.len {
+            return Reply::Nak(Nak::Sequence);
+        }
+        if !data.len().is_multiple_of(WRITE_ALIGN) && end != upload.len {
+            return Reply::Nak(Nak::Request);
+        }
+
+        // Pad the last chunk to a whole number of flash write units
+        let mut chunk = [0xFF; MAX_CHUNK];
+        chunk[..data.len()].copy_from_slice(data);
+        let padded = data.len().next_multiple_of(WRITE_ALIGN);
+        if self
+            .flash
+            .write(self.parts.download + offset, &chunk[..padded])
+            .is_err()
+        {
+            // The slot is in an unknown state: start over with `Begin`
+            self.upload = None;
+            return Reply::Nak(Nak::Flash);
+        }
+        upload.last = offset;
+        upload.received = end;
+        self.upload = Some(upload);
+        Reply::Ack(end)
+    }
+
+    /// Checks the complete upload against its CRC-32 and vector table
+    fn finish(&mut self) -> Reply {
+        let Some(mut upload) = self.upload.filter(|u| u.received == u.len) else {
+            return Reply::Nak(Nak::Sequence);
+        };
+        // Read back what was programmed rather than trusting the received bytes
+        let mut crc = 0;
+        let mut buf = [0u8; COPY_CHUNK];
+        let mut at = 0;
+        while at < upload.len {
+            let n = (upload.len - at).min(COPY_CHUNK as u32) as usize;
+            if self
+                .flash
+                .read(self.parts.download + at, &mut buf[..n])
+                .is_err()
+            {
+                return Reply::Nak(Nak::Flash);
+            }
+            crc = crc32_update(crc, &buf[..n]);
+            at += n as u32;
+        }
+        if crc != upload.crc {
+            return Reply::Nak(Nak::Crc);
+        }
+        let mut vectors = [0u8; 8];
+        if self.flash.read(self.parts.download, &mut vectors).is_err()
+            || !valid_vectors(&vectors, &self.parts)
+        {
+            return Reply::Nak(Nak::Vectors);
+        }
+        upload.verified = true;
+        self.upload = Some(upload);
+        Reply::Ack(upload.len)
+    }
+
+    /// Schedules the install of a verified upload, or leaves the bootloader
+    fn boot(&mut self) -> Reply {
+        let upload = self.upload;
+        let next = match upload {
+            Some(upload) if upload.verified => {
+                // Exchange every page either image occupies
+                let pages = upload
+                    .len
+                    .div_ceil(self.parts.page_size)
+                    .max(self.used_pages(self.parts.active));
+                BootState::Swap(Swap::start(pages as u16, false))
+            }
+            _ if self.app_valid() => BootState::Idle,
+            _ => return Reply::Nak(Nak::Sequence),
+        };
+        if self.set(next).is_err() {
+            return Reply::Nak(Nak::Flash);
+        }
+        self.reset = true;
+        Reply::Ack(0)
+    }
+
+    /// Saves and adopts a new boot state
+    fn set(&mut self, state: BootState) -> Result<(), StoreError> {
+        self.log.save(&mut self.flash, state)?;
+        self.state = state;
+        Ok(())
+    }
+
+    /// Runs `swap` to completion, logging each step
+    fn swap(&mut self, mut swap: Swap) -> Result<(), StoreError> {
+        if swap.page >= swap.pages {
+            return Ok(());
+        }
+        loop {
+            let page = u32::from(swap.page) * self.parts.page_size;
+            let (from, to) = match swap.step {
+                0 => (self.parts.active + page, self.parts.scratch),
+                1 => (self.parts.download + page, self.parts.active + page),
+                _ => (self.parts.scratch, self.parts.download + page),
+            };
+            self.copy_page(from, to)?;
+            match swap.next() {
+                Some(next) => {
+                    self.set(BootState::Swap(next))?;
+                    swap = next;
+                }
+                None => return Ok(()),
+            }
+        }
+    }
+
+    /// Erases the page at `to` and copies the page at `from` into it
+    fn copy_page(&mut self, from: u32, to: u32) -> Result<(), StoreError> {
+        self.flash.erase(to, self.parts.page_size)?;
+        let mut buf = [0u8; COPY_CHUNK];
+        let mut at = 0;
+        while at < self.parts.page_size {
+            let n = (self.parts.page_size - at).min(COPY_CHUNK as u32) as usize;
+            self.flash.read(from + at, &mut buf[..n])?;
+            // Erased bytes are already 0xFF
+            if buf[..n].iter().any(|&b| b != 0xFF) {
+                self.flash.write(to + at, &buf[..n])?;
+            }
+            at += n as u32;
+        }
+        Ok(())
+    }
+
+    /// Returns the number of pages of the slot at `slot` up to the last one
+    /// holding any programmed byte
+    fn used_pages(&mut self, slot: u32) -> u32 {
+        let page_size = self.parts.page_size;
+        let mut buf = [0u8; COPY_CHUNK];
+        for page in (0..self.parts.slot_len / page_size).rev() {
+            let mut at = 0;
+            while at < page_size {
+                let n = (page_size - at).min(COPY_CHUNK as u32) as usize;
+                let start = slot + page * page_size + at;
+                // An unreadable page counts as used, so it is swapped or erased
+                if self.flash.read(start, &mut buf[..n]).is_err()
+                    || buf[..n].iter().any(|&b| b != 0xFF)
+                {
+                    return page + 1;
+                }
+                at += n as u32;
+            }
+        }
+        0
+    }
+
+    /// Returns `true` if the active slot starts with a plausible vector table
+    fn app_valid(&mut self) -> bool {
+        let mut vectors = [0u8; 8];
+        self.flash.read(self.parts.active, &mut vectors).is_ok()
+            && valid_vectors(&vectors, &self.parts)
+    }
+}
@@ -0,0 +1,194 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Gamma-corrected LED brightness effects for the PWM-driven LED
+//!
+//! This module provides everything above the timer driver:
+//! - `GAMMA` / `gamma` - maps perceived brightness (0-255) onto PWM duty
+//! - `Effect` - breathing, fade-in, fade-out and fixed dim level curves
+//! - `PwmLed` - `LedPin` over any `embedded-hal` PWM channel
+//!
+//! # Design Philosophy
+//! The eye responds to light roughly logarithmically, so a linear duty ramp spends
+//! most of its time looking fully bright. Effects are therefore described in
+//! perceived brightness and converted to duty with a lookup table computed at
+//! compile time from the CIE 1931 lightness curve. Effects are pure functions of
+//! elapsed time, so curves are verified on a host without a timer.
+
+use embedded_hal::pwm::SetDutyCycle;
+
+use crate::pattern::LedPin;
+
+/// Number of perceived brightness levels (`0` = off, `255` = full)
+pub const LEVELS: usize = 256;
+
+/// Full duty as a fraction of `u16::MAX`, the scale used by `GAMMA`
+pub const FULL_DUTY: u16 = u16::MAX;
+
+/// Interval in milliseconds between brightness updates while an effect runs
+///
+/// 100 updates per second is smooth to the eye and costs one register write.
+pub const EFFECT_STEP_MS: u32 = 10;
+
+/// Perceived brightness level to duty lookup table (`0..=FULL_DUTY`)
+pub const GAMMA: [u16; LEVELS] = gamma_table();
+
+/// Returns the duty (as a fraction of `FULL_DUTY`) for a perceived brightness
+///
+/// # Arguments
+/// * `level` - Perceived brightness, `0` (off) to `255` (full)
+pub fn gamma(level: u8) -> u16 {
+    GAMMA[usize::from(level)]
+}
+
+/// Builds `GAMMA` from the CIE 1931 lightness curve
+///
+/// With lightness `L = 100 * level / 255`, relative luminance is
+/// `((L + 16) / 116)^3` above `L = 8` and `L / 903.3` below it (the linear
+/// segment avoids an infinite slope near black). Integer-only, so it runs in
+/// `const` context without floating point.
+const fn gamma_table() -> [u16; LEVELS] {
+    let mut table = [0u16; LEVELS];
+    let full = FULL_DUTY as u64;
+    let mut level = 0;
+    while level < LEVELS {
+        let l = level as u64;
+        table[level] = if l * 100 <= 8 * 255 {
+            // Y = (100 * l / 255) / 903.3
+            ((full * 1000 * l + 2_303_415 / 2) / 2_303_415) as u16
+        } else {
+            // Y = ((100 * l / 255 + 16) / 116)^3 = ((100 * l + 4080) / 29580)^3
+            let num = 100 * l + 4080;
+            let den = 29_580u64 * 29_580 * 29_580;
+            ((full * num * num * num + den / 2) / den) as u16
+        };
+        level += 1;
+    }
+    table
+}
+
+/// A brightness curve played on the PWM-driven LED
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub enum Effect {
+    /// Rise from off to full and back down again, repeating every `period_ms`
+    Breathe {
+        /// Duration of one full breath in milliseconds
+        period_ms: u32,
+    },
+    /// Ramp from off to full over `ms`, then stay on
+    FadeIn {
+        /// Ramp duration in milliseconds
+        ms: u32,
+    },
+    /// Ramp from full to off over `ms`, then stay off
+    FadeOut {
+        /// Ramp duration in milliseconds
+        ms: u32,
+    },
+    /// Hold a fixed perceived brightness
+    Dim {
+        /// Perceived brightness, `0` (off) to `255` (full)
+        level: u8,
+    },
+}
+
+impl Effect {
+    /// Returns a fixed dim level from a percentage of perceived brightness
+    ///
+    /// Values above 100 are treated as 100.
+    pub fn dim_percent(percent: u8) -> Self {
+        let percent = u32::from(percent.min(100));
+        Self::Dim {
+            level: ((percent * 255 + 50) / 100) as u8,
+        }
+    }
+
+    /// Short human-readable name for status reports
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Breathe { .. } => "breathe",
+            Self::FadeIn { .. } => "fade-in",
+            Self::FadeOut { .. } => "fade-out",
+            Self::Dim { .. } => "dim",
+        }
+    }
+
+    /// Returns the perceived brightness `elapsed_ms` after the effect started
+    pub fn level_at(&self, elapsed_ms: u32) -> u8 {
+        match *self {
+            Self::Breathe { period_ms } => {
+                // Triangle wave: inhale over the first half, exhale over the rest
+                let period = period_ms.max(2);
+                let phase = elapsed_ms % period;
+                let half = period / 2;
+                if phase < half {
+                    ramp(phase, half)
+                } else {
+                    255 - ramp(phase - half, period - half)
+                }
+            }
+            Self::FadeIn { ms } => ramp(elapsed_ms, ms),
+            Self::FadeOut { ms } => 255 - ramp(elapsed_ms, ms),
+            Self::Dim { level } => level,
+        }
+    }
+
+    /// Returns how long the effect changes brightness for
+    ///
+    /// # Returns
+    /// `Some(ms)` for effects that settle at a final level, `None` for effects
+    /// that repeat forever.
+    pub fn settle_ms(&self) -> Option<u32> {
+        match *self {
+            Self::Breathe { .. } => None,
+            Self::FadeIn { ms } | Self::FadeOut { ms } => Some(ms),
+            Self::Dim { .. } => Some(0),
+        }
+    }
+}
+
+/// Linear ramp from `0` at `t = 0` to `255` at `t >= span`
+fn ramp(t: u32, span: u32) -> u8 {
+    if t >= span {
+        return 255;
+    }
+    (u64::from(t) * 255 / u64::from(span)) as u8
+}
+
+/// LED driven by a PWM channel instead of a plain GPIO
+///
+/// Blink patterns switch between full and zero duty; brightness effects use the
+/// intermediate duty cycles set through `LedPin::set_level`.
+pub struct PwmLed<C: SetDutyCycle> {
+    channel: C,
+}
+
+impl<C: SetDutyCycle> PwmLed<C> {
+    /// Wraps an enabled PWM channel, starting with the LED off
+    pub fn new(mut channel: C) -> Self {
+        let _ = channel.set_duty_cycle_fully_off();
+        Self { channel }
+    }
+
+    /// Returns the PWM channel
+    pub fn channel(&self) -> &C {
+        &self.channel
+    }
+}
+
+impl<C: SetDutyCycle> LedPin for PwmLed<C> {
+    fn set_on(&mut self) {
+        // The timer driver cannot fail (`Infallible`); see the `OutputPin` impl
+        let _ = self.channel.set_duty_cycle_fully_on();
+    }
+
+    fn set_off(&mut self) {
+        let _ = self.channel.set_duty_cycle_fully_off();
+    }
+
+    fn set_level(&mut self, duty: u16) {
+        // Rescale from `FULL_DUTY` to the timer's auto-reload range
+        let _ = self.channel.set_duty_cycle_fraction(duty, FULL_DUTY);
+    }
+}
@@ -0,0 +1,191 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Build metadata embedded in the firmware image
+//!
+//! This module identifies which firmware is on a board:
+//! - `BuildInfo` - git commit, dirty flag, build time, profile and features
+//! - `BuildInfo::CURRENT` - the values `build.rs` recorded for this build
+//! - `Iso8601` - formats a Unix timestamp as `YYYY-MM-DDTHH:MM:SSZ`
+//!
+//! # Section Format
+//! The firmware keeps `BuildInfo::CURRENT` in the `.build_info` flash section
+//! (placed after `.rodata` by the generated `memory.x`). Its `LEN` bytes are:
+//!
+//! | Offset | Size | Field                                                |
+//! |--------|------|------------------------------------------------------|
+//! | 0      | 4    | magic `BLDI`                                         |
+//! | 4      | 1    | format version (`FORMAT_VERSION`)                    |
+//! | 5      | 1    | flags: bit 0 = working tree had uncommitted changes  |
+//! | 6      | 2    | reserved, zero                                       |
+//! | 8      | 8    | build time, seconds since the Unix epoch (LE)        |
+//! | 16     | 16   | package version                                      |
+//! | 32     | 40   | git commit (hex), or `unknown` outside a checkout    |
+//! | 72     | 16   | Cargo profile (`debug`, `release`)                   |
+//! | 88     | 64   | enabled Cargo features, comma-separated              |
+//!
+//! Text fields are ASCII, padded with zero bytes (and cut short if too long).
+//!
+//! # Design Philosophy
+//! The record is a plain byte array rather than a `#[repr(C)]` struct, so its
+//! layout is fixed by the table above and not by a compiler. Host tools
+//! (`blinkctl info`) find it by section name in the ELF, with no symbol lookup
+//! or debug info; the magic lets them check they found the right bytes.
+
+use core::fmt;
+
+use crate::clock::DateTime;
+
+/// Name of the linker section holding the firmware's `BuildInfo`
+pub const SECTION: &str = ".build_info";
+
+/// First four bytes of every record
+pub const MAGIC: [u8; 4] = *b"BLDI";
+
+/// Record format version written in byte 4
+pub const FORMAT_VERSION: u8 = 1;
+
+/// Length of a format version 1 record in bytes
+pub const LEN: usize = 152;
+
+/// Byte offsets and sizes of the text fields
+const VERSION: (usize, usize) = (16, 16);
+const COMMIT: (usize, usize) = (32, 40);
+const PROFILE: (usize, usize) = (72, 16);
+const FEATURES: (usize, usize) = (88, 64);
+
+/// Bit in the flags byte set for builds from a modified working tree
+const FLAG_DIRTY: u8 = 1 << 0;
+
+/// Identification of one firmware build
+///
+/// Wraps the section bytes described in the module docs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BuildInfo([u8; LEN]);
+
+impl BuildInfo {
+    /// This build, as recorded by `build.rs`
+    pub const CURRENT: Self = include!(concat!(env!("OUT_DIR"), "/build_info.rs"));
+
+    /// Creates a record
+    ///
+    /// # Arguments
+    /// * `version` - Package version
+    /// * `commit` - Git commit hash (hex)
+    /// * `dirty` - Whether the working tree had uncommitted changes
+    /// * `timestamp` - Build time in seconds since the Unix epoch
+    /// * `profile` - Cargo profile
+    /// * `features` - Enabled Cargo features, comma-separated
+    pub const fn new(
+        version: &str,
+        commit: &str,
+        dirty: bool,
+        timestamp: u64,
+        profile: &str,
+        features: &str,
+    ) -> Self {
+        let mut bytes = [0u8; LEN];
+        let mut i = 0;
+        while i < MAGIC.len() {
+            bytes[i] = MAGIC[i];
+            i += 1;
+        }
+        bytes[4] = FORMAT_VERSION;
+        bytes[5] = if dirty { FLAG_DIRTY } else { 0 };
+        let time = timestamp.to_le_bytes();
+        let mut i = 0;
+        while i < time.len() {
+            bytes[8 + i] = time[i];
+            i += 1;
+        }
+        put(&mut bytes, VERSION, version);
+        put(&mut bytes, COMMIT, commit);
+        put(&mut bytes, PROFILE, profile);
+        put(&mut bytes, FEATURES, features);
+        Self(bytes)
+    }
+
+    /// Returns the record as stored in the `.build_info` section
+    pub const fn as_bytes(&self) -> &[u8; LEN] {
+        &self.0
+    }
+
+    /// Returns the package version
+    pub fn version(&self) -> &str {
+        text(&self.0, VERSION)
+    }
+
+    /// Returns the git commit hash, or `unknown`
+    pub fn commit(&self) -> &str {
+        text(&self.0, COMMIT)
+    }
+
+    /// Returns `true` if the working tree had uncommitted changes
+    pub fn dirty(&self) -> bool {
+        self.0[5] & FLAG_DIRTY != 0
+    }
+
+    /// Returns the build time in seconds since the Unix epoch
+    pub fn timestamp(&self) -> u64 {
+        let mut time = [0u8; 8];
+        time.copy_from_slice(&self.0[8..16]);
+        u64::from_le_bytes(time)
+    }
+
+    /// Returns the Cargo profile
+    pub fn profile(&self) -> &str {
+        text(&self.0, PROFILE)
+    }
+
+    /// Returns the enabled Cargo features, comma-separated
+    pub fn features(&self) -> &str {
+        text(&self.0, FEATURES)
+    }
+}
+
+/// One line for banners and the `version` command
+///
+/// `v0.1.0 1a2b3c4d5e6f-dirty release 2025-06-01T12:00:00Z [board-f303re]`,
+/// with the commit shortened to 12 digits.
+impl fmt::Display for BuildInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let commit = self.commit();
+        write!(
+            f,
+            "v{} {}{} {} {} [{}]",
+            self.version(),
+            commit.get(..12).unwrap_or(commit),
+            if self.dirty() { "-dirty" } else { "" },
+            self.profile(),
+            Iso8601(self.timestamp()),
+            self.features()
+        )
+    }
+}
+
+/// Formats seconds since the Unix epoch as an ISO-8601 UTC date and time
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Iso8601(pub u64);
+
+impl fmt::Display for Iso8601 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        DateTime::from_unix(self.0).fmt(f)
+    }
+}
+
+/// Copies `value` into a zero-padded field, truncating it to the field size
+const fn put(bytes: &mut [u8; LEN], (offset, size): (usize, usize), value: &str) {
+    let value = value.as_bytes();
+    let mut i = 0;
+    while i < size && i < value.len() {
+        bytes[offset + i] = value[i];
+        i += 1;
+    }
+}
+
+/// Returns a zero-padded field up to its first zero byte
+fn text(bytes: &[u8; LEN], (offset, size): (usize, usize)) -> &str {
+    let field = &bytes[offset..offset + size];
+    let end = field.iter().position(|&b| b == 0).unwrap_or(size);
+    core::str::from_utf8(&field[..end]).unwrap_or("?")
+}
@@ -0,0 +1,170 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Push-button debouncing and gesture classification
+//!
+//! This module turns raw button samples into user gestures:
+//! - `ButtonTiming` - debounce, long-press and double-click thresholds
+//! - `Gesture` - short press, long press or double click
+//! - `GestureDetector` - pure state machine fed with `(time, pressed)` samples
+//!
+//! # Design Philosophy
+//! Like the pattern player, the detector never waits on hardware. The caller
+//! samples the pin on every edge (and whenever `next_deadline` elapses) and passes
+//! the current time in milliseconds, so the logic is identical on the board and
+//! on a host driven by a fake clock.
+
+/// Timing thresholds for gesture classification, all in milliseconds
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub struct ButtonTiming {
+    /// Time a raw level must stay unchanged before it is accepted
+    pub debounce_ms: u64,
+    /// Hold time after which a press is reported as `Gesture::Long`
+    pub long_press_ms: u64,
+    /// Maximum gap between release and second press for `Gesture::Double`
+    pub double_click_ms: u64,
+}
+
+/// A classified button gesture
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub enum Gesture {
+    /// Pressed and released once, with no second press following
+    Short,
+    /// Held for at least `long_press_ms` (reported while still held)
+    Long,
+    /// Two presses in quick succession
+    Double,
+}
+
+/// Gesture recognition state after debouncing
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum State {
+    /// Button released, nothing pending
+    Idle,
+    /// First press in progress since `since`; `long` once `Gesture::Long` fired
+    Pressed { since: u64, long: bool },
+    /// First press released at `at`; waiting to see if a second press follows
+    Released { at: u64 },
+    /// Second press in progress; reported as `Gesture::Double` on release
+    SecondPress,
+}
+
+/// Debouncing gesture state machine
+pub struct GestureDetector {
+    timing: ButtonTiming,
+    raw: bool,
+    raw_since: u64,
+    stable: bool,
+    state: State,
+}
+
+impl GestureDetector {
+    /// Creates a detector with the button released
+    pub const fn new(timing: ButtonTiming) -> Self {
+        Self {
+            timing,
+            raw: false,
+            raw_since: 0,
+            stable: false,
+            state: State::Idle,
+        }
+    }
+
+    /// Feeds one raw sample of the button
+    ///
+    /// # Arguments
+    /// * `now_ms` - Current time in milliseconds from any monotonic clock
+    /// * `pressed` - Raw (possibly bouncing) button level, `true` when pressed
+    ///
+    /// # Returns
+    /// A gesture if one was completed by this sample.
+    pub fn update(&mut self, now_ms: u64, pressed: bool) -> Option<Gesture> {
+        // Restart the debounce window on every raw change
+        if pressed != self.raw {
+            self.raw = pressed;
+            self.raw_since = now_ms;
+        }
+
+        // Accept the raw level once it has been stable long enough
+        let settled = now_ms.saturating_sub(self.raw_since) >= self.timing.debounce_ms;
+        if settled && self.raw != self.stable {
+            self.stable = self.raw;
+            if let Some(gesture) = self.on_edge(now_ms, self.stable) {
+                return Some(gesture);
+            }
+        }
+
+        self.on_time(now_ms)
+    }
+
+    /// Returns the debounced button level, `true` when pressed
+    pub fn is_pressed(&self) -> bool {
+        self.stable
+    }
+
+    /// Returns the time at which `update` must be called even without an edge
+    ///
+    /// `None` means the detector is idle and only a pin edge can change its state.
+    pub fn next_deadline(&self) -> Option<u64> {
+        let debounce = (self.raw != self.stable).then(|| self.raw_since + self.timing.debounce_ms);
+        let gesture = match self.state {
+            State::Pressed { since, long: false } => Some(since + self.timing.long_press_ms),
+            State::Released { at } => Some(at + self.timing.double_click_ms),
+            _ => None,
+        };
+        match (debounce, gesture) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    /// Handles a debounced press (`pressed`) or release (`!pressed`)
+    fn on_edge(&mut self, now_ms: u64, pressed: bool) -> Option<Gesture> {
+        match (self.state, pressed) {
+            (State::Idle, true) => {
+                self.state = State::Pressed {
+                    since: now_ms,
+                    long: false,
+                };
+                None
+            }
+            (State::Pressed { long: true, .. }, false) => {
+                self.state = State::Idle;
+                None
+            }
+            (State::Pressed { long: false, .. }, false) => {
+                self.state = State::Released { at: now_ms };
+                None
+            }
+            (State::Released { .. }, true) => {
+                self.state = State::SecondPress;
+                None
+            }
+            (State::SecondPress, false) => {
+                self.state = State::Idle;
+                Some(Gesture::Double)
+            }
+            // Repeated level (cannot happen after debouncing): ignore
+            _ => None,
+        }
+    }
+
+    /// Handles timeouts: long-press threshold and double-click window
+    fn on_time(&mut self, now_ms: u64) -> Option<Gesture> {
+        match self.state {
+            State::Pressed { since, long: false }
+                if now_ms.saturating_sub(since) >= self.timing.long_press_ms =>
+            {
+                self.state = State::Pressed { since, long: true };
+                Some(Gesture::Long)
+            }
+            State::Released { at } if now_ms.saturating_sub(at) >= self.timing.double_click_ms => {
+                self.state = State::Idle;
+                Some(Gesture::Short)
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,128 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Statics in core-coupled memory (CCM RAM)
+//!
+//! This module places selected statics in the CCMRAM region declared by the
+//! board's `memory/<board>.toml`:
+//! - `ccm!` - declares statics in `.ccmram.data` (copied from flash) or, with
+//!   `#[zeroed]`, in `.ccmram.bss` (zero-filled, no flash cost)
+//! - `is_zeroed` - the compile-time check behind `#[zeroed]`
+//! - `init` - zeroes and loads both sections at boot (board builds only)
+//!
+//! # Design Philosophy
+//! CCM RAM sits on the CPU's D-bus only: zero wait states and no contention with
+//! DMA, but also invisible to DMA. It suits data the CPU alone touches (task
+//! queues, lookup tables, the stack) and must never hold a buffer handed to a
+//! DMA transfer. `build.rs` maps the sections onto CCMRAM when the chip has it
+//! and onto ordinary RAM otherwise, so code using `ccm!` builds for every board.
+//! cortex-m-rt only initializes `.data` and `.bss`, hence `init`.
+
+/// Places statics in CCM RAM
+///
+/// `static` items are put in `.ccmram.data` and their initial value is copied
+/// from flash by `init`. Items marked `#[zeroed]` (the first attribute) go to
+/// `.ccmram.bss` instead and are zero-filled, which costs no flash; their
+/// initializer must be all zero bytes, which is checked at compile time.
+///
+/// Placement only applies on the board (`target_os = "none"`); on a host the
+/// statics are ordinary.
+///
+/// # Usage
+/// ```ignore
+/// blink::ccm! {
+///     /// Events for the application task
+///     static EVENTS: Channel<CriticalSectionRawMutex, AppEvent, 8> = Channel::new();
+/// }
+/// blink::ccm! {
+///     #[zeroed]
+///     /// Scratch space for formatting
+///     static SCRATCH: Mutex<RefCell<[u8; 256]>> = Mutex::new(RefCell::new([0; 256]));
+/// }
+/// ```
+///
+/// # Safety
+/// The statics must not be read before `init` has run, and must not be used as
+/// DMA buffers on chips with real CCM RAM.
+#[macro_export]
+macro_rules! ccm {
+    (#[zeroed] $(#[$attr:meta])* $vis:vis static $name:ident: $ty:ty = $init:expr;) => {
+        $(#[$attr])*
+        #[cfg_attr(target_os = "none", link_section = ".ccmram.bss")]
+        $vis static $name: $ty = $init;
+
+        // `.ccmram.bss` is zero-filled, so anything else would be lost
+        // (`ManuallyDrop` has the same bytes and lets const code skip the drop)
+        const _: () = {
+            let value = core::mem::ManuallyDrop::<$ty>::new($init);
+            assert!(
+                $crate::ccm::is_zeroed(&value),
+                concat!(
+                    "`",
+                    stringify!($name),
+                    "` is #[zeroed] but its initializer is not all zero bytes"
+                )
+            );
+        };
+    };
+    ($(#[$attr:meta])* $vis:vis static $name:ident: $ty:ty = $init:expr;) => {
+        $(#[$attr])*
+        #[cfg_attr(target_os = "none", link_section = ".ccmram.data")]
+        $vis static $name: $ty = $init;
+    };
+}
+
+/// Returns `true` if every byte of `value` is zero
+///
+/// Usable in constants. Types containing padding or pointers cannot be
+/// inspected at compile time and fail to build; use the plain (copied) form of
+/// `ccm!` for those.
+pub const fn is_zeroed<T>(value: &T) -> bool {
+    // SAFETY: `value` is a valid reference, so `size_of::<T>()` bytes are readable
+    let bytes = unsafe {
+        core::slice::from_raw_parts((value as *const T).cast::<u8>(), core::mem::size_of::<T>())
+    };
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] != 0 {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Zeroes `.ccmram.bss` and copies `.ccmram.data` from flash
+///
+/// A no-op on chips without CCM RAM, where the linker script leaves both
+/// ranges empty and cortex-m-rt initializes the sections with `.bss`/`.data`.
+///
+/// # Safety
+/// Must be called once at boot, before any `ccm!` static is accessed.
+#[cfg(target_os = "none")]
+pub unsafe fn init() {
+    extern "C" {
+        static mut __sccmbss: u32;
+        static mut __eccmbss: u32;
+        static mut __sccmdata: u32;
+        static mut __eccmdata: u32;
+        static __siccmdata: u32;
+    }
+
+    // SAFETY: the linker script defines word-aligned ranges inside CCMRAM (or
+    // empty ones) and the caller guarantees nothing uses them yet
+    unsafe {
+        let mut dst = core::ptr::addr_of_mut!(__sccmbss);
+        while dst < core::ptr::addr_of_mut!(__eccmbss) {
+            dst.write_volatile(0);
+            dst = dst.add(1);
+        }
+        let mut dst = core::ptr::addr_of_mut!(__sccmdata);
+        let mut src = core::ptr::addr_of!(__siccmdata);
+        while dst < core::ptr::addr_of_mut!(__eccmdata) {
+            dst.write_volatile(src.read());
+            dst = dst.add(1);
+            src = src.add(1);
+        }
+    }
+}
@@ -0,0 +1,263 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Calendar dates and times for the real-time clock
+//!
+//! This module gives the RTC's calendar a host-testable representation:
+//! - `DateTime` - a validated UTC date and time, formatted as ISO-8601
+//! - `DateTime::parse` - reads `YYYY-MM-DDTHH:MM:SS[Z]` (the `time set` argument)
+//! - `DateTime::from_unix` / `to_unix` - conversion to and from seconds since 1970
+//! - `RTC_YEARS` - the years the RTC's two-digit BCD calendar can hold
+//!
+//! # Design Philosophy
+//! The RTC keeps a broken-down calendar (BCD fields, including the weekday),
+//! while arithmetic is easiest on a single count of seconds. Everything here is
+//! plain integer code on `DateTime`, so leap years and month lengths are
+//! tested on the host; the firmware only copies fields to and from the RTC.
+//! Times are always UTC: the board has no notion of a time zone.
+
+use core::fmt;
+use core::ops::RangeInclusive;
+
+/// Years the RTC can store (the calendar holds the year as two BCD digits)
+pub const RTC_YEARS: RangeInclusive<u16> = 2000..=2099;
+
+/// Seconds in one day
+pub const DAY_SECS: u32 = 86_400;
+
+/// Reasons a date and time was rejected
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub enum DateTimeError {
+    /// The text is not `YYYY-MM-DDTHH:MM:SS`, optionally followed by `Z`
+    Format,
+    /// A field is outside its range (month 13, February 30th, hour 24, ...)
+    OutOfRange,
+}
+
+/// A UTC calendar date and time, to the second
+///
+/// Fields are only reachable through the accessors, so every value is a real
+/// date (`new` and `parse` validate, `from_unix` cannot produce an invalid one).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub struct DateTime {
+    year: u16,
+    month: u8,
+    day: u8,
+    hour: u8,
+    minute: u8,
+    second: u8,
+}
+
+impl DateTime {
+    /// Creates a date and time after checking every field
+    ///
+    /// # Errors
+    /// `OutOfRange` for a year before 1970, a month outside 1-12, a day past
+    /// the end of its month, or a time outside 00:00:00-23:59:59.
+    pub fn new(
+        year: u16,
+        month: u8,
+        day: u8,
+        hour: u8,
+        minute: u8,
+        second: u8,
+    ) -> Result<Self, DateTimeError> {
+        if year < 1970
+            || !(1..=12).contains(&month)
+            || day == 0
+            || day > days_in_month(year, month)
+            || hour > 23
+            || minute > 59
+            || second > 59
+        {
+            return Err(DateTimeError::OutOfRange);
+        }
+        Ok(Self {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+        })
+    }
+
+    /// Parses `YYYY-MM-DDTHH:MM:SS`, with an optional trailing `Z`
+    ///
+    /// A lowercase `t` or `z` is accepted, since the shell is case-insensitive
+    /// elsewhere. Offsets other than `Z` are rejected rather than converted.
+    ///
+    /// # Errors
+    /// `Format` if the text has the wrong shape, `OutOfRange` as for `new`.
+    pub fn parse(text: &str) -> Result<Self, DateTimeError> {
+        let text = text.strip_suffix(['Z', 'z']).unwrap_or(text).as_bytes();
+        if text.len() != 19 {
+            return Err(DateTimeError::Format);
+        }
+        // Separators sit at fixed positions; everything else must be a digit
+        for (i, &b) in text.iter().enumerate() {
+            let ok = match i {
+                4 | 7 => b == b'-',
+                10 => b == b'T' || b == b't',
+                13 | 16 => b == b':',
+                _ => b.is_ascii_digit(),
+            };
+            if !ok {
+                return Err(DateTimeError::Format);
+            }
+        }
+        let number = |at: usize, len: usize| {
+            text[at..at + len]
+                .iter()
+                .fold(0u16, |n, &b| n * 10 + u16::from(b - b'0'))
+        };
+        Self::new(
+            number(0, 4),
+            number(5, 2) as u8,
+            number(8, 2) as u8,
+            number(11, 2) as u8,
+            number(14, 2) as u8,
+            number(17, 2) as u8,
+        )
+    }
+
+    /// Converts seconds since 1970-01-01T00:00:00Z
+    ///
+    /// Years past 65535 wrap; every timestamp a build or the RTC produces is
+    /// far below that.
+    pub fn from_unix(secs: u64) -> Self {
+        let (year, month, day) = civil_from_days(secs / u64::from(DAY_SECS));
+        let time = (secs % u64::from(DAY_SECS)) as u32;
+        Self {
+            year: year as u16,
+            month: month as u8,
+            day: day as u8,
+            hour: (time / 3600) as u8,
+            minute: (time / 60 % 60) as u8,
+            second: (time % 60) as u8,
+        }
+    }
+
+    /// Returns the seconds since 1970-01-01T00:00:00Z
+    pub fn to_unix(&self) -> u64 {
+        days_from_civil(self.year, self.month, self.day) * u64::from(DAY_SECS)
+            + u64::from(self.seconds_of_day())
+    }
+
+    /// Returns the date and time `secs` seconds later
+    pub fn plus_secs(&self, secs: u64) -> Self {
+        Self::from_unix(self.to_unix() + secs)
+    }
+
+    /// Returns the day of the week, 1 (Monday) to 7 (Sunday) as in ISO-8601
+    ///
+    /// This is also the RTC's weekday encoding.
+    pub fn weekday(&self) -> u8 {
+        // 1970-01-01 was a Thursday (4)
+        let days = days_from_civil(self.year, self.month, self.day);
+        ((days + 3) % 7 + 1) as u8
+    }
+
+    /// Returns the seconds since midnight
+    pub fn seconds_of_day(&self) -> u32 {
+        (u32::from(self.hour) * 60 + u32::from(self.minute)) * 60 + u32::from(self.second)
+    }
+
+    /// Returns `true` if the RTC calendar can hold this date (`RTC_YEARS`)
+    pub fn fits_rtc(&self) -> bool {
+        RTC_YEARS.contains(&self.year)
+    }
+
+    /// Returns the year (1970 or later)
+    pub fn year(&self) -> u16 {
+        self.year
+    }
+
+    /// Returns the month, 1 (January) to 12
+    pub fn month(&self) -> u8 {
+        self.month
+    }
+
+    /// Returns the day of the month, from 1
+    pub fn day(&self) -> u8 {
+        self.day
+    }
+
+    /// Returns the hour, 0 to 23
+    pub fn hour(&self) -> u8 {
+        self.hour
+    }
+
+    /// Returns the minute, 0 to 59
+    pub fn minute(&self) -> u8 {
+        self.minute
+    }
+
+    /// Returns the second, 0 to 59
+    pub fn second(&self) -> u8 {
+        self.second
+    }
+}
+
+/// `2025-06-01T12:34:56Z`
+impl fmt::Display for DateTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+/// Returns `true` for Gregorian leap years
+pub fn is_leap_year(year: u16) -> bool {
+    year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400))
+}
+
+/// Returns the number of days in a month (1-12) of a given year
+///
+/// # Returns
+/// 28 to 31, or 0 for a month outside 1-12.
+pub fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        _ => 0,
+    }
+}
+
+/// Converts days since 1970-01-01 to a (year, month, day) Gregorian date
+///
+/// Howard Hinnant's `civil_from_days`, restricted to dates after the epoch.
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    // Count from 0000-03-01 so the leap day ends each 400-year era
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Converts a Gregorian date (1970 or later) to days since 1970-01-01
+///
+/// The inverse of `civil_from_days`, also from Howard Hinnant.
+fn days_from_civil(year: u16, month: u8, day: u8) -> u64 {
+    let (month, day) = (u64::from(month), u64::from(day));
+    let year = u64::from(year) - u64::from(month <= 2);
+    let era = year / 400;
+    let yoe = year % 400;
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
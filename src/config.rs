@@ -1,76 +1,395 @@
 // Copyright (c) 2025 Kevin Thomas
 // Licensed under the MIT License. See LICENSE file in the project root for full license information.
 
-//! Hardware configuration and initialization for STM32F303RE Nucleo board
+//! Hardware configuration and initialization for the supported Nucleo boards
 //!
 //! This module encapsulates all hardware-specific configuration including:
-//! - Pin definitions and peripheral mappings
-//! - Timing constants
-//! - UART message definitions
+//! - Board selection (`board::Active`, chosen by the `board-*` Cargo feature)
+//! - Timing constants (default blink pattern, button gesture thresholds)
+//! - UART message definitions (status and command shell responses)
+//! - Clock tree configuration (delegated to the board) and the RTC clock (LSE or LSI)
+//! - Persistent settings location in flash
+//! - Independent watchdog timing and reset-cause readout
+//! - STOP mode policy and report interval (`low-power` feature)
+//! - Internal sensor sampling and report intervals
 //! - Hardware initialization routines
 //!
 //! # Design Philosophy
 //! Configuration is centralized here to separate hardware concerns from application
-//! logic, making the codebase more maintainable and portable.
+//! logic, making the codebase more maintainable and portable. Everything that
+//! differs between boards lives in `board`; this module only sees the `Board` trait.
 
-use embassy_stm32::gpio::{Level, Output, Speed};
-use embassy_stm32::usart::UartTx;
-use embassy_stm32::{mode::Blocking, Peripherals};
+use embassy_stm32::exti::ExtiInput;
+use embassy_stm32::flash::{Blocking as FlashBlocking, Flash};
+use embassy_stm32::gpio::{self, OutputType, Pull};
+use embassy_stm32::mode::Async;
+use embassy_stm32::rcc::LsConfig;
+use embassy_stm32::rtc::{Rtc, RtcConfig};
+use embassy_stm32::time::Hertz;
+use embassy_stm32::timer::low_level::CountingMode;
+use embassy_stm32::timer::simple_pwm::{PwmPin, SimplePwm, SimplePwmChannel};
+use embassy_stm32::timer::{Ch1, GeneralInstance4Channel, TimerPin};
+use embassy_stm32::usart::{self, RingBufferedUartRx, UartRx, UartTx};
+use embassy_stm32::wdg::IndependentWatchdog;
+use embassy_stm32::{peripherals, Peri, Peripherals};
 
-/// Small trait to abstract a blocking-write-capable UART transmitter.
+use blink::boot::Partitions;
+use blink::brightness::PwmLed;
+use blink::button::ButtonTiming;
+use blink::pattern::{presets, BlinkPattern};
+use blink::power::StopPlanner;
+use blink::settings::{Geometry, Settings, SettingsStore};
+use blink::shell;
+use blink::supervisor::ResetCause;
+
+use crate::board::{lse_ready, Active, Board, BoardInfo, Parts, Vcp};
+pub use crate::board::{ClockSource, Led, RtcClock};
+
+/// Blink pattern played at boot
 ///
-/// We define a local trait and implement it for the concrete `UartTx` type
-/// so the public API avoids exposing newer/unstable generic parameters.
-pub trait WriteBlocking {
-    fn blocking_write(&mut self, bytes: &[u8]) -> Result<(), ()>;
-}
+/// The steady preset reproduces the original 500 ms on / 500 ms off blink.
+pub const DEFAULT_PATTERN: BlinkPattern = presets::STEADY;
+
+/// PWM frequency for the LED timer channel
+///
+/// Far above flicker fusion, and low enough that the timer (72-170 MHz on the
+/// supported boards) keeps a resolution of at least 36,000 steps, so the dimmest
+/// gamma levels stay distinct.
+pub const LED_PWM_HZ: u32 = 1_000;
+
+/// DMA-driven VCP transmitter returned by `init_async`
+pub type Tx = UartTx<'static, Async>;
+
+/// VCP receiver returned by `init_async`: DMA fills a ring buffer in the background
+pub type Rx = RingBufferedUartRx<'static>;
+
+/// The board's ADC with the temperature sensor and VREFINT channels
+pub type Sensors = <Active as Board>::Sensors;
+
+/// Blocking on-chip flash driver behind the settings store
+pub type FlashDriver = Flash<'static, FlashBlocking>;
 
-impl WriteBlocking for UartTx<'static, Blocking> {
-    fn blocking_write(&mut self, bytes: &[u8]) -> Result<(), ()> {
-        // Use the inherent blocking_write method provided by the blocking UartTx
-        self.blocking_write(bytes).map_err(|_| ())
+/// Board the firmware was built for (selected by the `board-*` Cargo feature)
+pub const BOARD: BoardInfo = Active::INFO;
+
+/// System clock frequency in Hz configured by `clocks()`
+pub const SYSCLK_HZ: u32 = BOARD.sysclk_hz;
+
+/// Builds the HAL configuration for the board's clock tree and RTC
+///
+/// The clock tree itself is documented on each board's `Board::clocks`. The
+/// RTC runs from the 32.768 kHz LSE crystal (X2, fitted on every supported
+/// Nucleo), or from the LSI if the crystal does not start.
+/// Must be called before `embassy_stm32::init`, as it probes the HSE and LSE directly.
+///
+/// # Returns
+/// The HAL configuration, the selected PLL clock source and the RTC clock.
+pub fn clocks() -> (embassy_stm32::Config, ClockSource, RtcClock) {
+    let (mut config, source) = Active::clocks();
+
+    let rtc = if lse_ready::<Active>() {
+        config.rcc.ls = LsConfig::default_lse();
+        RtcClock::Lse
+    } else {
+        config.rcc.ls = LsConfig::default_lsi();
+        RtcClock::Lsi
+    };
+
+    // Debug in STOP would keep the core supply and clocks up, so it is
+    // switched off; the RTC wake-up timer ends each stop
+    #[cfg(feature = "low-power")]
+    {
+        config.enable_debug_during_sleep = false;
     }
+
+    (config, source, rtc)
+}
+
+/// Returns the flash latency (wait states) currently programmed in FLASH_ACR
+pub fn flash_wait_states() -> u8 {
+    embassy_stm32::pac::FLASH.acr().read().latency().to_bits()
 }
 
-/// UART serial message definitions
+/// Location of the two settings pages
 ///
-/// Pre-formatted messages sent over UART2 to the ST-Link virtual COM port.
-/// Messages use CR+LF (\r\n) line endings for proper terminal display.
-pub mod messages {
-    /// LED state: ON - Sent when LED is activated
-    #[allow(dead_code)]
-    pub const LED_ON: &[u8] = b"LED ON\r\n";
+/// Reserved by `memory/<board>.toml`: `build.rs` shrinks the linker's FLASH
+/// region so no code is placed here. Page sizes differ per chip; the
+/// `SettingsFlash` impl for the flash driver lives in `blink::hal`.
+pub const SETTINGS_GEOMETRY: Geometry = crate::board::SETTINGS;
 
-    /// LED state: OFF - Sent when LED is deactivated
-    #[allow(dead_code)]
-    pub const LED_OFF: &[u8] = b"LED OFF\r\n";
+/// Longest time in milliseconds a settings save stalls the core
+///
+/// One page erase, from the board's datasheet: 25-40 ms for the 2 KB pages,
+/// 500 ms for the F401's 16 KB sectors. Reads from flash wait for the erase, so
+/// no task (and no interrupt handler) runs until it completes.
+pub const SETTINGS_ERASE_MS: u32 = Active::SETTINGS_ERASE_MS;
+
+/// Boot state log and slots of the serial bootloader (`bootloader` feature)
+///
+/// `None` when the application owns the start of flash; the `update` command
+/// then has nothing to restart into.
+pub const BOOT_PARTITIONS: Option<Partitions> = crate::board::BOOT;
+
+/// User button (B1) gesture thresholds
+///
+/// 20 ms comfortably covers the tactile switch bounce; 800 ms separates a
+/// deliberate hold from a tap; 300 ms is a typical double-click window.
+pub const BUTTON_TIMING: ButtonTiming = ButtonTiming {
+    debounce_ms: 20,
+    long_press_ms: 800,
+    double_click_ms: 300,
+};
+
+/// Independent watchdog timeout in microseconds
+///
+/// The IWDG runs from the ~40 kHz LSI, whose frequency may be off by up to 50%,
+/// so the effective timeout can be as short as about 1.3 s. That still covers
+/// the slowest legitimate stall, a settings erase (`SETTINGS_ERASE_MS`, at most
+/// 500 ms), starting just before a feed round and followed by one more round.
+pub const IWDG_TIMEOUT_US: u32 = 2_000_000;
+
+/// Interval in milliseconds between watchdog feed attempts
+///
+/// Several attempts fit in the shortest possible IWDG timeout, so one missed
+/// round (a task checking in just after the poll) does not reset the board.
+pub const WATCHDOG_FEED_MS: u64 = 250;
+
+/// Longest time in milliseconds a supervised task may wait without checking in
+///
+/// Must be shorter than `WATCHDOG_FEED_MS` so an idle task is never mistaken
+/// for a hung one.
+pub const WATCHDOG_CHECKIN_MS: u64 = 100;
+
+/// Reads and clears the reset flags latched in RCC_CSR
+///
+/// The flags accumulate across resets until cleared, so they are cleared here to
+/// make the next boot report only its own cause.
+///
+/// # Returns
+/// The most specific cause of the reset that started this run.
+pub fn reset_cause() -> ResetCause {
+    ResetCause::from_flags(Active::reset_flags())
 }
 
+/// Whether the core drops into STOP between LED edges (`low-power` feature)
+///
+/// STOP halts the debug connection and every peripheral clock, so the default
+/// build stays awake; see `low_power` for what keeps the core running.
+pub const LOW_POWER: bool = cfg!(feature = "low-power");
+
+/// When STOP is entered and how long it may last
+///
+/// A stop shorter than 10 ms saves less than the PLL relock costs. Each stop
+/// ends within 1 s of the last watchdog feed: with the IWDG as short as 1.3 s
+/// that leaves one `WATCHDOG_FEED_MS` round, and some slack, to feed it again.
+/// Shell input keeps the core awake for 10 s, since the UART cannot receive in
+/// STOP.
+pub const STOP_PLANNER: StopPlanner = StopPlanner {
+    min_ms: 10,
+    watchdog_ms: 1_000,
+    wake_margin_ms: 2,
+    input_grace_ms: 10_000,
+};
+
+/// Interval in milliseconds between power reports (`low-power` feature)
+pub const POWER_REPORT_MS: u64 = 10_000;
+
+/// Interval in milliseconds between internal sensor samples
+///
+/// The die temperature and supply change over seconds; one pair of
+/// conversions per second costs a few microseconds of blocking ADC time.
+pub const SENSOR_MS: u64 = 1_000;
+
+/// Interval in milliseconds between `sensors:` report lines on the UART
+///
+/// The latest reading is also part of every `status` reply.
+pub const SENSOR_REPORT_MS: u64 = 30_000;
+
+/// Size in bytes of the DMA ring buffer behind the shell receiver
+///
+/// DMA keeps filling it while `uart_rx_task` waits for the executor, so it holds
+/// the input of the longest stall (`SETTINGS_ERASE_MS`) at the default 115200
+/// baud, in whole 512-byte blocks: 512 bytes (44 ms) for the 2 KB flash pages
+/// and 6 KB (533 ms) for the F401's 16 KB sectors. Faster rates cover less.
+pub const RX_BUFFER_LEN: usize =
+    (115_200 / 10 * SETTINGS_ERASE_MS as usize / 1000).div_ceil(512) * 512;
+
+/// Whether LED changes are reported as binary telemetry frames (`telemetry` feature)
+///
+/// When enabled, `LED ON`/`LED OFF` lines are replaced by `blink::telemetry`
+/// frames; shell replies and Morse text stay ASCII.
+pub const TELEMETRY: bool = cfg!(feature = "telemetry");
+
+/// Interval in milliseconds between heartbeat ticks
+///
+/// Each tick sends a telemetry sample (when enabled), which keeps records
+/// flowing while the LED is held or running a brightness effect.
+pub const HEARTBEAT_MS: u64 = 1_000;
+
+/// Time in milliseconds the UART task is given to send `RESETTING` before
+/// `reset` restarts the board
+///
+/// The 14-byte notice takes about 15 ms at the slowest supported rate (9600 baud).
+pub const RESET_FLUSH_MS: u64 = 50;
+
+/// UART serial message definitions (shared with the host tests)
+pub use blink::messages;
+
 /// Hardware abstraction containing all initialized peripherals
 ///
-/// This structure owns the GPIO and UART peripherals after initialization,
+/// This structure owns the GPIO, UART, flash, watchdog, ADC and RTC peripherals after initialization,
 /// providing a clean interface for the main application logic.
 ///
+/// Built by `init_async`, whose DMA-driven UART writes are awaited and yield to
+/// the executor.
+///
+/// # Ownership
+/// `main_task` destructures the structure and moves every field into exactly one
+/// task, which owns it until reset. Nothing is shared through a mutex; other
+/// tasks reach a peripheral only by sending its owner a message:
+///
+/// | Field         | Owner            | Reached through                             |
+/// |---------------|------------------|---------------------------------------------|
+/// | `led`         | `app_task`       | `EVENTS` (gestures, shell lines, heartbeat) |
+/// | `store`       | `app_task`       | `EVENTS` (`led blink`, `schedule`, gestures) |
+/// | `usart`       | `uart_tx_task`   | `OUTPUT`; error counters via `TX_ERRORS`    |
+/// | `rx`          | `uart_rx_task`   | (input only); fault counters via `RX_ERRORS` |
+/// | `button`      | `button_task`    | (input only)                                |
+/// | `watchdog`    | `watchdog_task`  | `SUPERVISOR` check-ins                      |
+/// | `rtc`         | `app_task`       | `EVENTS` (`time set`, schedule heartbeat)   |
+/// | `sensors`     | `sensor_task`    | readings published in `SENSORS`             |
+/// | `reset_cause` | `main_task`      | (boot report only)                          |
+///
+/// With the `low-power` feature `power_task` replaces `watchdog_task` and owns
+/// the watchdog and the RTC wake-up timer. Reading the calendar needs no
+/// ownership: `main_task` takes read-only `RtcTimeProvider`s from the driver for
+/// the UART timestamps and for measuring stops.
+///
+/// Before the tasks are spawned `main_task` borrows `usart` for the blocking boot
+/// banner and `store` to count the boot.
+///
 /// # Lifetimes
 /// Uses 'static lifetime as peripherals are owned for the program duration.
+pub struct Hardware<T, R> {
+    /// PWM-driven onboard LED (LD2)
+    pub led: Led,
+    /// UART transmitter for serial communication (ST-Link VCP)
+    pub usart: T,
+    /// UART receiver for the command shell (ST-Link VCP)
+    pub rx: R,
+    /// EXTI-capable input for the user button (B1)
+    pub button: ExtiInput<'static>,
+    /// Persistent settings store in the reserved flash pages
+    pub store: SettingsStore<FlashDriver>,
+    /// Independent watchdog, configured but not yet started (`unleash`)
+    pub watchdog: IndependentWatchdog<'static, peripherals::IWDG>,
+    /// ADC with the internal temperature sensor and VREFINT enabled
+    pub sensors: Sensors,
+    /// Why the previous run ended, read from RCC before the flags were cleared
+    pub reset_cause: ResetCause,
+    /// Real-time clock, running from the clock chosen by `clocks`
+    pub rtc: Rtc,
+}
+
+/// Initializes the hardware with a DMA-driven UART transmitter and receiver
 ///
-/// # Fields
-/// * `led` - GPIO output for the onboard LED (PA5)
-/// * `usart` - UART transmitter for serial communication (USART2)
+/// Persisted settings are loaded first so the stored baud rate can be applied.
 ///
-/// Returns an initialized LED `Output` and a blocking-write capable UART transmitter.
-pub fn init(p: Peripherals) -> (Output<'static>, impl WriteBlocking) {
-    // Initialize UART2 TX (connected to ST-Link VCP on PA2)
-    // Configuration: 115200 baud, 8 data bits, no parity, 1 stop bit (8N1)
-    let uart_config = embassy_stm32::usart::Config::default();
+/// The DMA channels of each board's VCP are listed in its `board` module.
+/// The transmitter is usable through either `WriteAsync` or `WriteBlocking`;
+/// the receiver (`ReadAsync`) collects input in a `RX_BUFFER_LEN` ring buffer.
+/// Concrete types are returned (rather than `impl Trait`) because embassy tasks
+/// cannot be generic, and the halves are moved into tasks.
+pub fn init_async(p: Peripherals) -> Hardware<Tx, Rx> {
+    // Capture the reset cause before anything else can reset the board
+    let reset_cause = reset_cause();
+
+    // Take the board's LED, button, VCP and ADC peripherals
+    let Parts {
+        led,
+        button,
+        vcp,
+        sensors,
+        flash,
+        iwdg,
+        rtc,
+    } = Active::split(p);
+
+    // Load persisted settings (baud rate is needed before the UART is created)
+    let mut store = init_store(flash);
+    let settings = store.load();
 
-    // Create a blocking UART transmitter (no DMA) using the convenience constructor
-    let usart = UartTx::new_blocking(p.USART2, p.PA2, uart_config).unwrap();
+    // Create a DMA-backed UART and split it into independent halves
+    let uart = vcp.into_async(uart_config(&settings)).unwrap();
+    let (usart, rx): (Tx, UartRx<'static, Async>) = uart.split();
 
-    // Configure PA5 as push-pull output for the onboard LED (LD2)
-    // Initial state: LOW (LED off), Speed: Low (2MHz slew rate)
-    let led = Output::new(p.PA5, Level::Low, Speed::Low);
+    // Receive into a ring buffer in ordinary RAM (DMA cannot reach CCM RAM)
+    let buffer = cortex_m::singleton!(: [u8; RX_BUFFER_LEN] = [0; RX_BUFFER_LEN]).unwrap();
+    let rx: Rx = rx.into_ring_buffered(buffer);
 
     // Return initialized peripherals
-    (led, usart)
+    Hardware {
+        led,
+        usart,
+        rx,
+        button,
+        store,
+        watchdog: IndependentWatchdog::new(iwdg, IWDG_TIMEOUT_US),
+        sensors,
+        reset_cause,
+        rtc: Rtc::new(rtc, RtcConfig::default()),
+    }
+}
+
+/// VCP UART configuration
+///
+/// Configuration: persisted baud rate (115200 by default), 8 data bits, no parity,
+/// 1 stop bit (8N1). An unsupported stored rate falls back to the default so a
+/// corrupt setting can never lock out the console.
+fn uart_config(settings: &Settings) -> usart::Config {
+    let mut config = usart::Config::default();
+    if shell::BAUD_RATES.contains(&settings.baud_rate) {
+        config.baudrate = settings.baud_rate;
+    }
+    config
+}
+
+/// Creates the settings store over the reserved flash pages
+fn init_store(flash: Peri<'static, peripherals::FLASH>) -> SettingsStore<FlashDriver> {
+    SettingsStore::new(Flash::new_blocking(flash), SETTINGS_GEOMETRY)
+}
+
+/// Configures a timer channel 1 pin as the PWM output for the onboard LED (LD2)
+///
+/// The timer runs edge-aligned at `LED_PWM_HZ`; the channel starts at zero duty
+/// (LED off). Full and zero duty behave exactly like the former GPIO output, so
+/// blink patterns are unaffected. Called by each board's `Board::split`.
+pub(crate) fn init_led<T: GeneralInstance4Channel>(
+    tim: Peri<'static, T>,
+    pin: Peri<'static, impl TimerPin<T, Ch1>>,
+) -> PwmLed<SimplePwmChannel<'static, T>> {
+    let pwm = SimplePwm::new(
+        tim,
+        Some(PwmPin::new(pin, OutputType::PushPull)),
+        None,
+        None,
+        None,
+        Hertz(LED_PWM_HZ),
+        CountingMode::EdgeAlignedUp,
+    );
+    let mut channel = pwm.split().ch1;
+    channel.enable();
+    PwmLed::new(channel)
+}
+
+/// Configures the user button (B1) pin as an EXTI input
+///
+/// Every supported Nucleo fits an external pull-up and the button pulls the pin
+/// LOW when pressed, so no internal pull is enabled. Called by each board's
+/// `Board::split`.
+pub(crate) fn init_button<T: gpio::Pin>(
+    pin: Peri<'static, T>,
+    ch: Peri<'static, T::ExtiChannel>,
+) -> ExtiInput<'static> {
+    ExtiInput::new(pin, ch, Pull::None)
 }
@@ -0,0 +1,275 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Crash capture across resets
+//!
+//! This module records why the firmware died so the next boot can report it:
+//! - `CrashRecord` - panic message, source location and fault registers
+//! - `FaultRegisters` - stacked core registers and SCB fault status at a HardFault
+//! - `store` - used by the firmware's panic and HardFault handlers
+//! - `take` - returns (and clears) the record left by the previous run
+//!
+//! # Design Philosophy
+//! `panic_probe` only reports over RTT, which is lost without a probe attached.
+//! The record lives in the `.uninit` RAM section, which cortex-m-rt neither zeroes
+//! nor initializes, so it survives a system reset (but not a power cycle). A magic
+//! word and CRC-16 tell a genuine record apart from the random contents of RAM
+//! after power-on.
+
+use core::fmt::{self, Write};
+#[cfg(target_os = "none")]
+use core::mem::MaybeUninit;
+#[cfg(target_os = "none")]
+use core::ptr::{addr_of, addr_of_mut};
+
+use crate::crc::{crc16_update, CRC16_INIT};
+
+/// Marks a record written by one of the handlers
+const MAGIC: u32 = 0xDEAD_C0DE;
+
+/// Bytes of the panic message kept (longer messages are truncated)
+pub const MESSAGE_LEN: usize = 96;
+
+/// Bytes of the source file path kept (the tail is kept when truncating)
+pub const FILE_LEN: usize = 48;
+
+/// What ended the previous run
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub enum CrashKind {
+    /// A Rust `panic!` (including failed `unwrap`/`expect` and bounds checks)
+    Panic,
+    /// A processor fault escalated to HardFault
+    HardFault,
+}
+
+/// Core and SCB registers captured by the HardFault handler
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+#[repr(C)]
+pub struct FaultRegisters {
+    /// Stacked program counter (faulting instruction)
+    pub pc: u32,
+    /// Stacked link register (caller of the faulting function)
+    pub lr: u32,
+    /// Stacked program status register
+    pub xpsr: u32,
+    /// Stacked R0-R3
+    pub r0_r3: [u32; 4],
+    /// Stacked R12
+    pub r12: u32,
+    /// Configurable Fault Status Register (MMFSR, BFSR, UFSR)
+    pub cfsr: u32,
+    /// HardFault Status Register
+    pub hfsr: u32,
+    /// MemManage Fault Address Register (valid if CFSR.MMARVALID)
+    pub mmfar: u32,
+    /// BusFault Address Register (valid if CFSR.BFARVALID)
+    pub bfar: u32,
+}
+
+/// Crash report stored in `.uninit` RAM
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct CrashRecord {
+    magic: u32,
+    kind: u8,
+    message_len: u8,
+    file_len: u8,
+    message: [u8; MESSAGE_LEN],
+    file: [u8; FILE_LEN],
+    line: u32,
+    column: u32,
+    registers: FaultRegisters,
+    crc: u16,
+}
+
+impl CrashRecord {
+    /// Creates an empty record of the given kind
+    fn empty(kind: CrashKind) -> Self {
+        Self {
+            magic: MAGIC,
+            kind: kind as u8,
+            message_len: 0,
+            file_len: 0,
+            message: [0; MESSAGE_LEN],
+            file: [0; FILE_LEN],
+            line: 0,
+            column: 0,
+            registers: FaultRegisters::default(),
+            crc: 0,
+        }
+    }
+
+    /// Builds a record for a panic
+    ///
+    /// # Arguments
+    /// * `message` - Panic message, formatted into the record and truncated to `MESSAGE_LEN`
+    /// * `location` - Source file, line and column of the panic, if known
+    pub fn panic(message: fmt::Arguments, location: Option<(&str, u32, u32)>) -> Self {
+        let mut rec = Self::empty(CrashKind::Panic);
+        let mut w = Truncating {
+            buf: &mut rec.message,
+            len: 0,
+            full: false,
+        };
+        // Truncation is not an error for `Truncating`, so this cannot fail
+        let _ = w.write_fmt(message);
+        rec.message_len = w.len as u8;
+        if let Some((file, line, column)) = location {
+            // Keep the end of long paths: the file name matters most
+            let tail = tail_at_char_boundary(file, FILE_LEN);
+            rec.file[..tail.len()].copy_from_slice(tail.as_bytes());
+            rec.file_len = tail.len() as u8;
+            rec.line = line;
+            rec.column = column;
+        }
+        rec.seal();
+        rec
+    }
+
+    /// Builds a record for a HardFault
+    pub fn fault(registers: FaultRegisters) -> Self {
+        let mut rec = Self::empty(CrashKind::HardFault);
+        rec.registers = registers;
+        rec.seal();
+        rec
+    }
+
+    /// Returns what ended the run
+    pub fn kind(&self) -> CrashKind {
+        if self.kind == CrashKind::HardFault as u8 {
+            CrashKind::HardFault
+        } else {
+            CrashKind::Panic
+        }
+    }
+
+    /// Returns the (possibly truncated) panic message
+    pub fn message(&self) -> &str {
+        as_str(&self.message, self.message_len)
+    }
+
+    /// Returns the panic location as `(file, line, column)`, if one was recorded
+    pub fn location(&self) -> Option<(&str, u32, u32)> {
+        (self.file_len > 0).then(|| (as_str(&self.file, self.file_len), self.line, self.column))
+    }
+
+    /// Returns the registers captured by the HardFault handler
+    pub fn registers(&self) -> &FaultRegisters {
+        &self.registers
+    }
+
+    /// Checks the magic word, lengths and CRC
+    pub fn is_valid(&self) -> bool {
+        self.magic == MAGIC
+            && usize::from(self.message_len) <= MESSAGE_LEN
+            && usize::from(self.file_len) <= FILE_LEN
+            && self.checksum() == self.crc
+    }
+
+    /// Stores the checksum of the record contents
+    fn seal(&mut self) {
+        self.crc = self.checksum();
+    }
+
+    /// CRC-16 over every field except `crc`, independent of struct padding
+    fn checksum(&self) -> u16 {
+        let r = &self.registers;
+        let mut crc = CRC16_INIT;
+        crc = crc16_update(crc, &self.magic.to_le_bytes());
+        crc = crc16_update(crc, &[self.kind, self.message_len, self.file_len]);
+        crc = crc16_update(crc, &self.message);
+        crc = crc16_update(crc, &self.file);
+        for word in [self.line, self.column, r.pc, r.lr, r.xpsr]
+            .into_iter()
+            .chain(r.r0_r3)
+            .chain([r.r12, r.cfsr, r.hfsr, r.mmfar, r.bfar])
+        {
+            crc = crc16_update(crc, &word.to_le_bytes());
+        }
+        crc
+    }
+}
+
+/// Returns the first `len` bytes of `buf` as text
+///
+/// Only whole UTF-8 characters are ever stored, so this cannot fail on a record
+/// that passed `is_valid`.
+fn as_str(buf: &[u8], len: u8) -> &str {
+    core::str::from_utf8(&buf[..usize::from(len)]).unwrap_or("")
+}
+
+/// Returns the longest suffix of `s` of at most `max` bytes that starts on a
+/// character boundary
+fn tail_at_char_boundary(s: &str, max: usize) -> &str {
+    let mut start = s.len().saturating_sub(max);
+    while !s.is_char_boundary(start) {
+        start += 1;
+    }
+    &s[start..]
+}
+
+/// `fmt::Write` sink that silently drops whatever does not fit
+///
+/// Unlike `uprint::FmtWriter`, the panic path cannot report an overflow, so the
+/// message is cut at the last whole character that fits.
+struct Truncating<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+    full: bool,
+}
+
+impl Write for Truncating<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        // Once a character has been dropped, drop everything after it too
+        if self.full {
+            return Ok(());
+        }
+        for ch in s.chars() {
+            let mut utf8 = [0u8; 4];
+            let bytes = ch.encode_utf8(&mut utf8).as_bytes();
+            let end = self.len + bytes.len();
+            if end > self.buf.len() {
+                self.full = true;
+                break;
+            }
+            self.buf[self.len..end].copy_from_slice(bytes);
+            self.len = end;
+        }
+        Ok(())
+    }
+}
+
+/// The crash record, preserved across system resets
+#[cfg(target_os = "none")]
+#[link_section = ".uninit.CRASH"]
+static mut CRASH: MaybeUninit<CrashRecord> = MaybeUninit::uninit();
+
+/// Returns the crash record left by the previous run and clears it
+///
+/// Must be called once at boot, before anything can panic again.
+///
+/// # Returns
+/// `None` after a clean reset or a power cycle.
+#[cfg(target_os = "none")]
+pub fn take() -> Option<CrashRecord> {
+    // SAFETY: `.uninit` RAM holds arbitrary bytes, which are valid for every
+    // field type of `CrashRecord` (integers only); the executor has not started,
+    // so nothing else accesses `CRASH` concurrently
+    let rec = unsafe { addr_of!(CRASH).read_volatile().assume_init() };
+    // Invalidate it so the same crash is not reported again
+    unsafe { addr_of_mut!(CRASH).write_volatile(MaybeUninit::zeroed()) };
+    rec.is_valid().then_some(rec)
+}
+
+/// Stores `rec` for the next boot to report
+///
+/// # Safety
+/// Must only be called from the panic or HardFault handler, with interrupts
+/// disabled, immediately before resetting.
+#[cfg(target_os = "none")]
+pub unsafe fn store(rec: CrashRecord) {
+    // SAFETY: the caller guarantees exclusive access
+    unsafe { addr_of_mut!(CRASH).write_volatile(MaybeUninit::new(rec)) };
+}
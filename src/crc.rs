@@ -0,0 +1,69 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! CRC-16/CCITT-FALSE and CRC-32 checksums
+//!
+//! `crc16` protects records written to flash and frames sent over UART.
+//! Parameters: polynomial 0x1021, initial value 0xFFFF, no reflection, no final XOR.
+//! Check value: `crc16(b"123456789") == 0x29B1`.
+//! `crc32` covers whole firmware images received by the bootloader.
+//!
+//! # Design Philosophy
+//! A bitwise implementation is used instead of a 512-byte lookup table: the data
+//! volumes are tiny and flash space is better spent elsewhere.
+
+/// Initial CRC register value
+pub const CRC16_INIT: u16 = 0xFFFF;
+
+/// Computes the CRC-16/CCITT-FALSE of `data`
+pub fn crc16(data: &[u8]) -> u16 {
+    crc16_update(CRC16_INIT, data)
+}
+
+/// Continues a CRC-16/CCITT-FALSE computation over another chunk of data
+///
+/// # Arguments
+/// * `crc` - Running CRC (start with `CRC16_INIT`)
+/// * `data` - Next chunk of data
+pub fn crc16_update(mut crc: u16, data: &[u8]) -> u16 {
+    for &byte in data {
+        crc ^= u16::from(byte) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+/// Computes the CRC-32/ISO-HDLC (zlib, Ethernet) of `data`
+///
+/// Reflected polynomial 0xEDB88320, initial value and final XOR 0xFFFFFFFF.
+/// Check value: `crc32(b"123456789") == 0xCBF43926`. Used for whole firmware
+/// images, where a 16-bit check is too weak.
+pub fn crc32(data: &[u8]) -> u32 {
+    crc32_update(0, data)
+}
+
+/// Continues a CRC-32 computation over another chunk of data
+///
+/// # Arguments
+/// * `crc` - CRC-32 of the data so far (start with `0`, as zlib's `crc32`)
+/// * `data` - Next chunk of data
+pub fn crc32_update(crc: u32, data: &[u8]) -> u32 {
+    let mut crc = !crc;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
@@ -0,0 +1,134 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Messages passed between the firmware tasks
+//!
+//! The firmware is split into tasks that each own one peripheral and talk
+//! through `embassy-sync` channels carrying these types:
+//! - `AppEvent` - everything the application task reacts to (gestures, shell
+//!   lines, heartbeat ticks, the end of a stop), sent by the input tasks
+//! - `Output` - everything written to USART2, sent to the UART output task
+//! - `ShellInput` - turns received bytes into echoes and `AppEvent`s
+//!
+//! # Design Philosophy
+//! Events are plain owned values: no references into a task's state cross a
+//! channel, so each task can be reasoned about (and its logic tested) alone.
+//! Only the application task mutates LED and settings state; the others
+//! translate between hardware and events.
+
+use heapless::String;
+
+use crate::button::Gesture;
+use crate::shell::{LineBuffer, LineError, MAX_LINE};
+use crate::telemetry::Record;
+
+/// Capacity of `Output::Text` in bytes (fits the `status` report with a sensor
+/// reading and 10-digit error counters)
+pub const OUTPUT_TEXT: usize = 192;
+
+/// One complete shell line
+pub type Line = String<MAX_LINE>;
+
+/// Something the application task must react to
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AppEvent {
+    /// A debounced button gesture (button task)
+    Gesture(Gesture),
+    /// A terminated shell line, possibly empty (UART input task)
+    Line(Line),
+    /// A shell line overflowed `MAX_LINE` and was dropped (UART input task)
+    LineTooLong,
+    /// Periodic tick (heartbeat task), used for telemetry samples
+    Heartbeat,
+    /// The core left STOP (power task, `low-power` feature); LED deadlines
+    /// are re-evaluated against the advanced wall clock
+    Resumed,
+}
+
+/// Something to write to USART2
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Output {
+    /// Fixed text: replies, prompts, echoes, LED notices
+    Bytes(&'static [u8]),
+    /// Formatted text (shell replies)
+    Text(String<OUTPUT_TEXT>),
+    /// A status line (LED notices, mode changes, status and periodic reports);
+    /// the UART task writes the RTC date and time in front of it
+    Notice(String<OUTPUT_TEXT>),
+    /// A telemetry sample; the UART task fills in its error counters,
+    /// numbers it and frames it
+    Record(Record),
+}
+
+impl Output {
+    /// Creates a `Notice` from fixed ASCII text such as `messages::LED_ON`
+    ///
+    /// Text longer than `OUTPUT_TEXT` is cut at the capacity.
+    pub fn notice(text: &[u8]) -> Self {
+        let mut line = String::new();
+        for &b in text {
+            if line.push(char::from(b)).is_err() {
+                break;
+            }
+        }
+        Self::Notice(line)
+    }
+}
+
+/// Line assembly for the UART input task
+///
+/// Wraps a `LineBuffer` and reports, for every received byte, what to echo and
+/// which event (if any) the byte completes.
+pub struct ShellInput {
+    line: LineBuffer<MAX_LINE>,
+    after_cr: bool,
+}
+
+impl ShellInput {
+    /// Creates an empty input line
+    pub const fn new() -> Self {
+        Self {
+            line: LineBuffer::new(),
+            after_cr: false,
+        }
+    }
+
+    /// Feeds one received byte
+    ///
+    /// # Returns
+    /// The bytes to echo (possibly empty) and, on CR or LF, the completed line
+    /// (empty lines included, so the prompt is repeated as a terminal expects).
+    /// An LF right after a CR is swallowed, so CR+LF ends a single line.
+    pub fn push(&mut self, byte: u8) -> (&'static [u8], Option<AppEvent>) {
+        // Terminals that send CR+LF for Enter would otherwise get two prompts
+        let after_cr = core::mem::replace(&mut self.after_cr, byte == b'\r');
+        if after_cr && byte == b'\n' {
+            return (b"", None);
+        }
+        let event = match self.line.push(byte) {
+            Ok(Some(text)) => Some(AppEvent::Line(String::try_from(text).unwrap_or_default())),
+            Ok(None) if byte == b'\r' || byte == b'\n' => Some(AppEvent::Line(String::new())),
+            Ok(None) => None,
+            Err(LineError::TooLong) => Some(AppEvent::LineTooLong),
+        };
+        (echo(byte), event)
+    }
+}
+
+impl Default for ShellInput {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns what to echo for a received byte so the terminal shows what was typed
+///
+/// Enter becomes CR+LF, backspace and delete erase the last character on
+/// screen, other control characters are not echoed.
+pub fn echo(byte: u8) -> &'static [u8] {
+    match byte {
+        b'\r' | b'\n' => crate::messages::NEWLINE,
+        0x08 | 0x7f => b"\x08 \x08",
+        _ => crate::morse::as_static(byte),
+    }
+}
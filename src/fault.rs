@@ -0,0 +1,63 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Panic and HardFault handlers
+//!
+//! Both handlers log over defmt, fill a `blink::crash::CrashRecord` and reset the
+//! board; `main_task` reports the record over USART2 on the next boot.
+//!
+//! # Design Philosophy
+//! The handlers live in the binary rather than the library: they only make sense
+//! on the board, and an exception handler defined in a library is silently
+//! replaced by the default one unless something else pulls in its object file.
+
+use core::panic::PanicInfo;
+
+use blink::crash::{self, CrashRecord, FaultRegisters};
+use cortex_m::peripheral::{DCB, SCB};
+use cortex_m_rt::{exception, ExceptionFrame};
+
+/// Stores `rec` and restarts the board
+///
+/// With a debugger attached the core first stops on a breakpoint, so the crash
+/// can be inspected in place before resuming into the reset.
+fn store_and_reset(rec: CrashRecord) -> ! {
+    // SAFETY: called with interrupts disabled from a handler that never returns
+    unsafe { crash::store(rec) };
+    if DCB::is_debugger_attached() {
+        cortex_m::asm::bkpt();
+    }
+    SCB::sys_reset()
+}
+
+/// Panic handler: logs over defmt, records the panic and resets
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    cortex_m::interrupt::disable();
+    defmt::error!("{}", defmt::Display2Format(info));
+    let location = info.location().map(|l| (l.file(), l.line(), l.column()));
+    store_and_reset(CrashRecord::panic(
+        format_args!("{}", info.message()),
+        location,
+    ))
+}
+
+/// HardFault handler: records the stacked registers and fault status, then resets
+#[exception]
+unsafe fn HardFault(frame: &ExceptionFrame) -> ! {
+    // SAFETY: read-only access to the SCB fault status registers
+    let scb = unsafe { &*SCB::PTR };
+    let registers = FaultRegisters {
+        pc: frame.pc(),
+        lr: frame.lr(),
+        xpsr: frame.xpsr(),
+        r0_r3: [frame.r0(), frame.r1(), frame.r2(), frame.r3()],
+        r12: frame.r12(),
+        cfsr: scb.cfsr.read(),
+        hfsr: scb.hfsr.read(),
+        mmfar: scb.mmfar.read(),
+        bfar: scb.bfar.read(),
+    };
+    defmt::error!("HardFault: {}", registers);
+    store_and_reset(CrashRecord::fault(registers))
+}
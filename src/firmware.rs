@@ -0,0 +1,1077 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Firmware entry point and tasks
+//!
+//! Takes the peripherals returned by `config::init_async`, gives each one to
+//! a single task, and drives the hardware-independent state in `blink::app`
+//! from the embassy executor. Tasks communicate only through the statics below:
+//!
+//! ```text
+//! button_task ───── Gesture ───┐
+//! uart_rx_task ──── Line ──────┼─► EVENTS ─► app_task ──┐
+//! heartbeat_task ── Heartbeat ─┘                        ├─► OUTPUT ─► uart_tx_task ─► USART2
+//! uart_rx_task ──── echo ───────────────────────────────┘
+//! supervised tasks ── check-in ─► SUPERVISOR ─► watchdog_task ─► IWDG
+//! sensor_task ──── SENSORS (latest reading, for status) and report lines ─► OUTPUT
+//! RTC ── calendar ─► uart_tx_task (timestamps in front of notices)
+//! RTC ── calendar ─► app_task (schedule rules, checked on every heartbeat)
+//! app_task ──── IDLE ─────┐
+//! all tasks ─── ACTIVITY ─┴─► power_task ─► STOP, then Resumed ─► EVENTS
+//! ```
+//!
+//! With the `low-power` feature `power_task` takes over from `watchdog_task`:
+//! it feeds the IWDG the same way and, whenever `app_task` waits for an LED
+//! edge and nothing else is going on, stops the core until just before it.
+//! Time spent in STOP does not advance `embassy_time`, so deadlines and
+//! reports use `now_ms` (awake time plus `STOPPED_MS`).
+//!
+//! # Design Philosophy
+//! Decisions (what the LED does next, what a command means, what gets saved)
+//! are made by the library; this module only awaits timers, edges and UART
+//! transfers and passes the results along. Every peripheral has exactly one
+//! owning task (see `config::Hardware`), so no peripheral sits behind a mutex;
+//! the only shared state is the channels, the supervisor, snapshots of the
+//! UART error counters and the latest sensor reading, and the power hints
+//! (`STOPPED_MS`, `ACTIVITY`, `IDLE`).
+
+use core::cell::Cell;
+
+use crate::board::Sensors as _;
+use crate::config::{self, messages, ClockSource, RtcClock};
+#[cfg(feature = "low-power")]
+use crate::low_power;
+use blink::app::{Led, LedMode, Persisted};
+use blink::boot::{self, BootState, Partitions};
+use blink::build_info::BuildInfo;
+use blink::button::GestureDetector;
+use blink::clock::DateTime;
+use blink::crash::{self, CrashKind, CrashRecord};
+use blink::event::{AppEvent, Output, ShellInput, OUTPUT_TEXT};
+use blink::monitor::{ErrorCounters, RxMonitor, TxMonitor};
+use blink::pattern::{LedPin, PatternPlayer};
+use blink::power::Activity;
+#[cfg(feature = "low-power")]
+use blink::power::DutyCycle;
+use blink::schedule::{Scheduler, Switch, MAX_RULES};
+use blink::sensors::{Calibration, Reading};
+use blink::serial::{WriteAsync, WriteBlocking};
+use blink::settings::{Settings, SettingsFlash, PATTERN_CUSTOM};
+use blink::shell::{self, Command, ParseError, MAX_LINE};
+use blink::supervisor::{Checkin, Supervisor};
+use blink::telemetry::{Record, Telemetry};
+use blink::{uformat, uprintln};
+use defmt_rtt as _;
+use embassy_executor::{Executor, Spawner};
+use embassy_futures::select::{select, Either};
+use embassy_stm32::exti::ExtiInput;
+use embassy_stm32::peripherals::IWDG;
+use embassy_stm32::rcc::Clocks;
+use embassy_stm32::rtc::{self, DayOfWeek, Rtc, RtcError, RtcTimeProvider};
+use embassy_stm32::time::MaybeHertz;
+use embassy_stm32::wdg::IndependentWatchdog;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_sync::channel::Channel;
+use embassy_sync::signal::Signal;
+use embassy_time::{Duration, Instant, Timer};
+use heapless::String;
+
+// The queues and shared state below are only touched by the CPU (payloads are
+// moved out of the channels before any DMA transfer), so they live in CCM RAM
+blink::ccm! {
+    /// Events for `app_task`: gestures, shell lines and heartbeat ticks
+    static EVENTS: Channel<CriticalSectionRawMutex, AppEvent, 8> = Channel::new();
+}
+
+blink::ccm! {
+    /// Everything written to the VCP, in order, for `uart_tx_task`
+    static OUTPUT: Channel<CriticalSectionRawMutex, Output, 16> = Channel::new();
+}
+
+blink::ccm! {
+    /// Latest UART write failure counters, published by `uart_tx_task` for `status`
+    static TX_ERRORS: Mutex<CriticalSectionRawMutex, Cell<ErrorCounters>> =
+        Mutex::new(Cell::new(ErrorCounters::new()));
+}
+
+blink::ccm! {
+    /// Latest UART receive fault counters, published by `uart_rx_task` for `status`
+    static RX_ERRORS: Mutex<CriticalSectionRawMutex, Cell<ErrorCounters>> =
+        Mutex::new(Cell::new(ErrorCounters::new()));
+}
+
+blink::ccm! {
+    /// Latest die temperature and VDDA, published by `sensor_task` for `status`
+    static SENSORS: Mutex<CriticalSectionRawMutex, Cell<Option<Reading>>> =
+        Mutex::new(Cell::new(None));
+}
+
+blink::ccm! {
+    /// Tasks that must keep checking in for `watchdog_task` to feed the IWDG
+    static SUPERVISOR: Supervisor = Supervisor::new();
+}
+
+blink::ccm! {
+    /// Total time the core has spent in STOP, in milliseconds (`low-power`)
+    static STOPPED_MS: Mutex<CriticalSectionRawMutex, Cell<u64>> = Mutex::new(Cell::new(0));
+}
+
+blink::ccm! {
+    /// What the tasks are doing, for `power_task` (only updated in `low-power` builds)
+    static ACTIVITY: Mutex<CriticalSectionRawMutex, Cell<Activity>> =
+        Mutex::new(Cell::new(Activity::new()));
+}
+
+blink::ccm! {
+    /// Set by `app_task` before it waits: wall time of the next LED edge, if any
+    static IDLE: Signal<CriticalSectionRawMutex, Option<u64>> = Signal::new();
+}
+
+/// Identification of this build, in its own flash section so host tools can
+/// read it from the ELF (`blinkctl info`)
+#[used]
+#[link_section = ".build_info"]
+static BUILD_INFO: BuildInfo = BuildInfo::CURRENT;
+
+/// Reset entry point
+///
+/// Loads the `ccm!` statics, then runs the executor with `main_task` as its
+/// first task. The executor is started here rather than by
+/// `#[embassy_executor::main]` so that no task exists before CCM RAM holds its
+/// initial values.
+#[cortex_m_rt::entry]
+fn main() -> ! {
+    // SAFETY: runs once, before the executor is created, so no `ccm!` static
+    // has been used yet
+    unsafe { blink::ccm::init() };
+
+    let executor = cortex_m::singleton!(: Executor = Executor::new()).unwrap();
+    executor.run(|spawner| spawner.must_spawn(main_task(spawner)))
+}
+
+/// Main application task
+///
+/// Initializes the STM32 peripherals, loads persisted settings (counting this boot),
+/// reports the clocks, the reset cause and any crash left by the previous run, then
+/// hands every peripheral to the task that owns it from then on and returns. The
+/// executor keeps running the tasks:
+/// 1. `app_task` - LED state, shell commands, gestures and settings
+/// 2. `uart_tx_task` - all USART2 output, including telemetry framing
+/// 3. `uart_rx_task` - echo and line assembly for the shell
+/// 4. `button_task` - debounce and gesture classification
+/// 5. `heartbeat_task` - periodic tick (telemetry samples)
+/// 6. `sensor_task` - die temperature and VDDA from the internal ADC channels
+/// 7. `watchdog_task` - feeds the IWDG while the supervised tasks check in
+///    (`power_task` in `low-power` builds, which also enters STOP)
+///
+/// # Arguments
+/// * `spawner` - Embassy task spawner used to start the tasks
+#[embassy_executor::task]
+async fn main_task(spawner: Spawner) {
+    // Collect the crash record (if any) before anything can overwrite it
+    let crash = crash::take();
+
+    // Initialize STM32 peripherals with the board's clock tree
+    let (hal_config, clock_source, rtc_clock) = config::clocks();
+    #[cfg(feature = "low-power")]
+    let sysclk = hal_config.rcc.sys;
+    let p = embassy_stm32::init(hal_config);
+    let freqs = *embassy_stm32::rcc::clocks(&p.RCC);
+
+    // Initialize hardware (LED, DMA-backed UART, button, settings flash, watchdog, ADC, RTC)
+    let hw = config::init_async(p);
+    let board = config::BOARD;
+    defmt::info!(
+        "board: {} ({}), LED {}, button {}, VCP {}",
+        board.name,
+        board.chip,
+        board.led,
+        board.button,
+        board.vcp
+    );
+    defmt::info!("build: {}", defmt::Display2Format(&BUILD_INFO));
+    defmt::info!("reset cause: {}", hw.reset_cause);
+
+    // Count and report UART write failures instead of discarding them
+    let mut usart = TxMonitor::new(hw.usart);
+
+    // Count this boot in the persisted settings
+    let mut persisted = Persisted::new(hw.store);
+    persisted.update(|s| s.boot_count = s.boot_count.wrapping_add(1));
+    defmt::info!("settings: {}", persisted.settings());
+
+    // Boot banner (blocking is harmless before the tasks start)
+    let _ = uprintln!(
+        &mut usart,
+        "{} v{} ready on {}",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION"),
+        config::BOARD.name
+    );
+    let _ = uprintln!(&mut usart, "build {}", BUILD_INFO);
+    report_clocks(&mut usart, clock_source, &freqs);
+    report_rtc(&mut usart, rtc_clock, &hw.rtc.time_provider());
+    let _ = uprintln!(
+        &mut usart,
+        "boot #{}, reset cause: {}",
+        persisted.settings().boot_count,
+        hw.reset_cause.name()
+    );
+    if let Some(record) = &crash {
+        report_crash(&mut usart, record);
+    }
+    if let Some(parts) = &config::BOOT_PARTITIONS {
+        report_update(&mut usart, persisted.flash(), parts);
+    }
+    if config::LOW_POWER {
+        let _ = uprintln!(
+            &mut usart,
+            "low-power: STOP between LED edges, debug probe detached while stopped"
+        );
+    }
+
+    // Sensor readings are only as good as the factory calibration
+    let calibration = <config::Sensors>::calibration();
+    defmt::info!("sensor calibration: {}", calibration);
+    if let Err(error) = calibration.check() {
+        defmt::warn!("sensor calibration unusable: {}", error);
+        let _ = uprintln!(&mut usart, "sensors: no factory calibration, disabled");
+    }
+
+    // Load the persisted blink pattern
+    let led = Led::new(hw.led, config::DEFAULT_PATTERN, persisted.settings());
+    defmt::info!("blink pattern: {}", led.pattern_name());
+
+    // Hand each peripheral to its owning task (see `config::Hardware`),
+    // registering every long-running task with the supervisor first
+    spawner.must_spawn(uart_tx_task(usart, hw.rtc.time_provider(), register_task()));
+    spawner.must_spawn(uart_rx_task(RxMonitor::new(hw.rx), register_task()));
+    spawner.must_spawn(button_task(hw.button, register_task()));
+    #[cfg(feature = "low-power")]
+    let stop = low_power::Stop::new(
+        hw.rtc.time_provider(),
+        freqs.rtc.to_hertz().map_or(32_768, |hz| hz.0),
+        clock_source,
+        sysclk,
+    );
+    spawner.must_spawn(app_task(led, persisted, hw.rtc, register_task()));
+    spawner.must_spawn(heartbeat_task());
+    if calibration.check().is_ok() {
+        spawner.must_spawn(sensor_task(hw.sensors, calibration));
+    }
+    #[cfg(not(feature = "low-power"))]
+    spawner.must_spawn(watchdog_task(hw.watchdog));
+    #[cfg(feature = "low-power")]
+    spawner.must_spawn(power_task(hw.watchdog, stop));
+}
+
+/// Returns milliseconds since boot, including time spent in STOP
+///
+/// `Instant` stands still while the core is stopped; this is the clock for
+/// LED deadlines, uptime and telemetry timestamps.
+fn now_ms() -> u64 {
+    Instant::now().as_millis() + STOPPED_MS.lock(Cell::get)
+}
+
+/// Converts a `now_ms` deadline into an `Instant` for `Timer::at`
+fn instant_at(wall_ms: u64) -> Instant {
+    Instant::from_millis(wall_ms.saturating_sub(STOPPED_MS.lock(Cell::get)))
+}
+
+/// Updates the shared `ACTIVITY` in `low-power` builds (a no-op otherwise)
+fn note(update: impl FnOnce(&mut Activity)) {
+    if config::LOW_POWER {
+        ACTIVITY.lock(|cell| {
+            let mut activity = cell.get();
+            update(&mut activity);
+            cell.set(activity);
+        });
+    }
+}
+
+/// Application task: the only owner of the LED, the persisted settings and the RTC
+///
+/// Runs a loop that:
+/// 1. Advances the blink pattern or brightness effect once its deadline has passed
+/// 2. Queues an "LED ON" or "LED OFF" notice (or a telemetry record) whenever
+///    a pattern switches the LED, or the Morse character being keyed
+/// 3. Checks in with the watchdog supervisor
+/// 4. In `low-power` builds, tells `power_task` when the LED changes next
+/// 5. Waits for the next `AppEvent` or LED deadline, whichever comes first
+/// 6. Applies the event: gesture, shell line, heartbeat (which also checks the
+///    schedule rules) or end of a stop
+///
+/// # Arguments
+/// * `led` - LED state, with the persisted pattern loaded
+/// * `persisted` - Settings updated by gestures and shell commands
+/// * `rtc` - Real-time clock, set by `time set`
+/// * `checkin` - Supervisor handle for this task
+#[embassy_executor::task]
+async fn app_task(
+    mut led: Led<config::Led>,
+    mut persisted: Persisted<config::FlashDriver>,
+    mut rtc: Rtc,
+    checkin: Checkin,
+) {
+    let mut scheduler = Scheduler::new();
+    OUTPUT.send(Output::Bytes(messages::PROMPT)).await;
+    loop {
+        let now = now_ms();
+
+        // Advance the pattern (or brightness effect) once its deadline has
+        // passed; notify pattern level changes via UART
+        if let Some(msg) = led.poll(now) {
+            let notice = msg == messages::LED_ON || msg == messages::LED_OFF;
+            if config::TELEMETRY && notice {
+                OUTPUT.send(Output::Record(sample(&led, now))).await;
+            } else if notice {
+                OUTPUT.send(Output::notice(msg)).await;
+            } else {
+                OUTPUT.send(Output::Bytes(msg)).await;
+            }
+        }
+
+        // Report that this task is still making progress
+        SUPERVISOR.check_in(checkin);
+
+        // A partial PWM level would freeze in STOP; otherwise the core may
+        // stop until the next step boundary
+        if config::LOW_POWER {
+            let dimmed = led
+                .level()
+                .is_some_and(|level| level != 0 && level != u8::MAX);
+            note(|a| a.led_dimmed = dimmed);
+            IDLE.signal(led.next_deadline());
+        }
+
+        // Sleep until the next event, step boundary or check-in, whichever comes first
+        let mut wake_ms = now + config::WATCHDOG_CHECKIN_MS;
+        if let Some(next_ms) = led.next_deadline() {
+            wake_ms = wake_ms.min(next_ms);
+        }
+        let wake = Timer::at(instant_at(wake_ms));
+        if let Either::First(event) = select(EVENTS.receive(), wake).await {
+            handle(event, &mut led, &mut persisted, &mut rtc, &mut scheduler).await;
+        }
+    }
+}
+
+/// Applies one event to the LED and settings, queueing any reply
+///
+/// # Arguments
+/// * `event` - Event received from an input task
+/// * `led` - LED state to act on
+/// * `persisted` - Settings updated by gestures and shell commands
+/// * `rtc` - Real-time clock, for `time set` and the schedule
+/// * `scheduler` - Schedule rule that was active at the last heartbeat
+async fn handle(
+    event: AppEvent,
+    led: &mut Led<config::Led>,
+    persisted: &mut Persisted<config::FlashDriver>,
+    rtc: &mut Rtc,
+    scheduler: &mut Scheduler,
+) {
+    match event {
+        // Short = next preset, double = previous preset,
+        // long = pause (LED off) or resume the current preset
+        AppEvent::Gesture(gesture) => {
+            defmt::info!("button: {}", gesture);
+            led.apply(gesture);
+            if led.mode() == LedMode::Pattern {
+                persisted.update(|s| s.pattern = led.preset() as u8);
+            }
+            let text = uformat!(OUTPUT_TEXT, "mode {}\r\n", led.mode_name());
+            OUTPUT.send(Output::Notice(text)).await;
+        }
+        AppEvent::Line(line) => {
+            execute(shell::parse(&line), led, persisted, rtc).await;
+            OUTPUT.send(Output::Bytes(messages::PROMPT)).await;
+        }
+        AppEvent::LineTooLong => {
+            OUTPUT.send(Output::Bytes(messages::ERR_TOO_LONG)).await;
+            OUTPUT.send(Output::Bytes(messages::PROMPT)).await;
+        }
+        AppEvent::Heartbeat => {
+            let now = now_ms();
+            defmt::trace!("heartbeat at {} ms, mode {=str}", now, led.mode_name());
+            if config::TELEMETRY {
+                OUTPUT.send(Output::Record(sample(led, now))).await;
+            }
+            follow_schedule(led, persisted.settings(), rtc, scheduler).await;
+        }
+        // Nothing to apply: the loop re-evaluates the LED at the new wall time
+        AppEvent::Resumed => {}
+    }
+}
+
+/// Switches the LED when a schedule window opens or closes
+///
+/// The rules are checked against the RTC's minute of the day; only a change
+/// of the active rule touches the LED, so commands given inside a window are
+/// left alone until it closes.
+///
+/// # Arguments
+/// * `led` - LED state to switch
+/// * `settings` - Persisted settings holding the rules and the stored pattern
+/// * `rtc` - Real-time clock
+/// * `scheduler` - Rule that was active at the last check
+async fn follow_schedule(
+    led: &mut Led<config::Led>,
+    settings: &Settings,
+    rtc: &Rtc,
+    scheduler: &mut Scheduler,
+) {
+    let Some(now) = rtc_now(&rtc.time_provider()) else {
+        return;
+    };
+    let minute = (now.seconds_of_day() / 60) as u16;
+    let text = match scheduler.update(&settings.schedule, minute) {
+        Some(Switch::Enter { slot, preset }) => {
+            led.select_preset(preset);
+            uformat!(
+                OUTPUT_TEXT,
+                "schedule: rule {} {}\r\n",
+                slot + 1,
+                led.pattern_name()
+            )
+        }
+        Some(Switch::Leave) => {
+            led.play_stored(settings);
+            uformat!(OUTPUT_TEXT, "schedule: back to {}\r\n", led.pattern_name())
+        }
+        None => return,
+    };
+    defmt::info!("{=str}", text.trim_end());
+    OUTPUT.send(Output::Notice(text)).await;
+}
+
+/// Samples the LED for a telemetry record
+///
+/// Uptime and error counters are filled in by `uart_tx_task` when it sends
+/// the record.
+fn sample(led: &Led<config::Led>, timestamp_ms: u64) -> Record {
+    Record::sample(led, timestamp_ms, timestamp_ms, ErrorCounters::new())
+}
+
+/// UART output task: the only owner of the USART2 transmitter
+///
+/// Writes every `Output` in the order it was queued. Notices are preceded by
+/// the RTC date and time, read as they are written. Telemetry records get the
+/// current error counters and uptime, a sequence number and COBS framing here,
+/// so the counters describe the transmitter at the moment of sending. After
+/// each write the counters are published to `TX_ERRORS` for `status`.
+///
+/// # Arguments
+/// * `usart` - Monitored, DMA-driven UART transmitter
+/// * `time` - Calendar reader of the RTC, for notice timestamps
+/// * `checkin` - Supervisor handle for this task
+#[embassy_executor::task]
+async fn uart_tx_task(mut usart: TxMonitor<config::Tx>, time: RtcTimeProvider, checkin: Checkin) {
+    let mut telemetry = Telemetry::new();
+    loop {
+        SUPERVISOR.check_in(checkin);
+        let wake = Timer::after(Duration::from_millis(config::WATCHDOG_CHECKIN_MS));
+        let Either::First(output) = select(OUTPUT.receive(), wake).await else {
+            continue;
+        };
+        note(|a| a.uart_busy = true);
+        match output {
+            Output::Bytes(bytes) => usart.send(bytes).await,
+            Output::Text(text) => usart.send(text.as_bytes()).await,
+            Output::Notice(text) => {
+                if let Some(now) = rtc_now(&time) {
+                    usart.send(uformat!(24, "{} ", now).as_bytes()).await;
+                }
+                usart.send(text.as_bytes()).await;
+            }
+            Output::Record(mut record) => {
+                record.uptime_ms = now_ms();
+                record.errors = *usart.errors();
+                usart.send(&telemetry.frame(&record)).await;
+            }
+        }
+        let errors = *usart.errors();
+        TX_ERRORS.lock(|cell| cell.set(errors));
+        note(|a| a.uart_busy = false);
+    }
+}
+
+/// UART input task: the only owner of the USART2 receiver
+///
+/// Sleeps until input arrives, echoes what was typed and forwards completed
+/// lines to `app_task`. DMA keeps filling the ring buffer while the task waits
+/// for a channel, so a pasted line or a CLI command arriving in one burst is
+/// received whole. Each read is bounded by `config::WATCHDOG_CHECKIN_MS` so
+/// the task keeps checking in; an abandoned read loses nothing. Line faults
+/// are counted and published to `RX_ERRORS` for `status`.
+///
+/// # Arguments
+/// * `rx` - Monitored, DMA ring-buffered UART receiver
+/// * `checkin` - Supervisor handle for this task
+#[embassy_executor::task]
+async fn uart_rx_task(mut rx: RxMonitor<config::Rx>, checkin: Checkin) {
+    let mut input = ShellInput::new();
+    let mut buf = [0u8; MAX_LINE];
+    loop {
+        SUPERVISOR.check_in(checkin);
+        let wake = Timer::after(Duration::from_millis(config::WATCHDOG_CHECKIN_MS));
+        let Either::First(len) = select(rx.receive(&mut buf), wake).await else {
+            continue;
+        };
+        // A line fault was counted; the next read restarts reception
+        if len == 0 {
+            let errors = *rx.errors();
+            RX_ERRORS.lock(|cell| cell.set(errors));
+            continue;
+        }
+        note(|a| a.last_input_ms = Some(now_ms()));
+        for &byte in &buf[..len] {
+            let (echo, event) = input.push(byte);
+            if !echo.is_empty() {
+                OUTPUT.send(Output::Bytes(echo)).await;
+            }
+            if let Some(event) = event {
+                EVENTS.send(event).await;
+            }
+        }
+    }
+}
+
+/// Heartbeat task: posts `AppEvent::Heartbeat` every `config::HEARTBEAT_MS`
+///
+/// Ticks are dropped rather than queued while `app_task` is busy, so a stall
+/// never turns into a burst of samples afterwards.
+#[embassy_executor::task]
+async fn heartbeat_task() {
+    loop {
+        Timer::after(Duration::from_millis(config::HEARTBEAT_MS)).await;
+        let _ = EVENTS.try_send(AppEvent::Heartbeat);
+    }
+}
+
+/// Sensor task: samples the temperature sensor and VREFINT every `config::SENSOR_MS`
+///
+/// Publishes each reading in `SENSORS` for `status` and queues a `sensors:`
+/// line every `config::SENSOR_REPORT_MS`. Not supervised: the conversions
+/// block the executor for microseconds, so a stuck ADC would stop every
+/// supervised task too.
+///
+/// # Arguments
+/// * `sensors` - ADC with the internal channels enabled
+/// * `calibration` - Factory calibration, already checked
+#[embassy_executor::task]
+async fn sensor_task(mut sensors: config::Sensors, calibration: Calibration) {
+    let mut reported_ms = now_ms();
+    loop {
+        // Convert both channels and publish the result
+        let sample = sensors.sample().await;
+        let Some(reading) = calibration.convert(sample) else {
+            defmt::warn!("sensors: VREFINT read as zero");
+            Timer::after(Duration::from_millis(config::SENSOR_MS)).await;
+            continue;
+        };
+        SENSORS.lock(|cell| cell.set(Some(reading)));
+        defmt::debug!("sensors: {} -> {}", sample, reading);
+
+        // Report on the UART now and then; drop the line if the queue is full
+        let now = now_ms();
+        if now - reported_ms >= config::SENSOR_REPORT_MS {
+            reported_ms = now;
+            let text = uformat!(OUTPUT_TEXT, "sensors: {}\r\n", reading);
+            let _ = OUTPUT.try_send(Output::Notice(text));
+        }
+        Timer::after(Duration::from_millis(config::SENSOR_MS)).await;
+    }
+}
+
+/// User button task: debounces PC13 and classifies gestures
+///
+/// Samples the button on every EXTI edge and whenever the detector has a pending
+/// deadline (debounce window, long-press threshold, double-click window), then
+/// forwards completed gestures to `app_task` through `EVENTS`. Every wait
+/// is bounded by `config::WATCHDOG_CHECKIN_MS` so the task keeps checking in.
+///
+/// # Arguments
+/// * `button` - EXTI input for B1 (active LOW)
+/// * `checkin` - Supervisor handle for this task
+#[embassy_executor::task]
+async fn button_task(mut button: ExtiInput<'static>, checkin: Checkin) {
+    let mut detector = GestureDetector::new(config::BUTTON_TIMING);
+    loop {
+        let now = Instant::now().as_millis();
+        SUPERVISOR.check_in(checkin);
+        if let Some(gesture) = detector.update(now, button.is_low()) {
+            // Drop the gesture if the application task has fallen behind
+            let _ = EVENTS.try_send(AppEvent::Gesture(gesture));
+        }
+        let busy = detector.is_pressed() || detector.next_deadline().is_some();
+        note(|a| a.button_busy = busy);
+        let checkin_ms = now + config::WATCHDOG_CHECKIN_MS;
+        match detector.next_deadline() {
+            Some(deadline) => {
+                let wake = Instant::from_millis(deadline.min(checkin_ms));
+                select(button.wait_for_any_edge(), Timer::at(wake)).await;
+            }
+            // Idle: wait for the opposite level, which also catches an edge
+            // that arrived between sampling and arming the EXTI line
+            None if detector.is_pressed() => {
+                let wake = Instant::from_millis(checkin_ms);
+                select(button.wait_for_high(), Timer::at(wake)).await;
+            }
+            None => {
+                let wake = Instant::from_millis(checkin_ms);
+                select(button.wait_for_low(), Timer::at(wake)).await;
+            }
+        }
+    }
+}
+
+/// Watchdog task: starts the IWDG and feeds it while all supervised tasks live
+///
+/// Every `config::WATCHDOG_FEED_MS` the supervisor is polled; the watchdog is
+/// only fed if every registered task has checked in since the previous feed.
+/// A task that stays silent therefore lets the IWDG expire and reset the board,
+/// and the next boot reports the cause as a watchdog reset.
+///
+/// # Arguments
+/// * `watchdog` - Configured, not yet started, independent watchdog
+#[cfg(not(feature = "low-power"))]
+#[embassy_executor::task]
+async fn watchdog_task(mut watchdog: IndependentWatchdog<'static, IWDG>) {
+    watchdog.unleash();
+    loop {
+        Timer::after(Duration::from_millis(config::WATCHDOG_FEED_MS)).await;
+        match SUPERVISOR.poll() {
+            Ok(()) => watchdog.pet(),
+            Err(missing) => defmt::warn!("watchdog: tasks {=u32:#b} not checking in", missing),
+        }
+    }
+}
+
+/// Power task (`low-power` feature): feeds the IWDG and enters STOP
+///
+/// Feeds the watchdog every `config::WATCHDOG_FEED_MS` exactly like
+/// `watchdog_task`. In between, each `IDLE` hint from `app_task` is a chance
+/// to stop:
+/// 1. Waits for queued events and output to drain, then uses the newest hint
+/// 2. The watchdog is fed early if every supervised task has checked in, so
+///    a hung task still starves it and the stops shrink to nothing
+/// 3. `config::STOP_PLANNER` picks a duration from the next LED edge, the
+///    last feed and `ACTIVITY`
+/// 4. The core stops; the measured time is added to `STOPPED_MS` and the duty
+///    cycle, and `AppEvent::Resumed` wakes `app_task`
+///
+/// Every `config::POWER_REPORT_MS` the duty cycle and estimated average
+/// current are reported over the UART.
+///
+/// # Arguments
+/// * `watchdog` - Configured, not yet started, independent watchdog
+/// * `stop` - RTC wake-up timer and the clock tree to restore
+#[cfg(feature = "low-power")]
+#[embassy_executor::task]
+async fn power_task(mut watchdog: IndependentWatchdog<'static, IWDG>, mut stop: low_power::Stop) {
+    watchdog.unleash();
+    let mut fed_ms = now_ms();
+    let mut next_feed = Instant::now() + Duration::from_millis(config::WATCHDOG_FEED_MS);
+    let mut duty = DutyCycle::new(fed_ms);
+    loop {
+        match select(Timer::at(next_feed), IDLE.wait()).await {
+            Either::First(()) => {
+                next_feed += Duration::from_millis(config::WATCHDOG_FEED_MS);
+                match SUPERVISOR.poll() {
+                    Ok(()) => {
+                        watchdog.pet();
+                        fed_ms = now_ms();
+                    }
+                    Err(missing) => {
+                        defmt::warn!("watchdog: tasks {=u32:#b} not checking in", missing)
+                    }
+                }
+            }
+            Either::Second(next_edge) => {
+                // Let queued events and output drain first (a reply takes a few
+                // ms), then act on the newest hint
+                let busy = || {
+                    !EVENTS.is_empty() || !OUTPUT.is_empty() || ACTIVITY.lock(Cell::get).uart_busy
+                };
+                while busy() && Instant::now() < next_feed {
+                    Timer::after(Duration::from_millis(1)).await;
+                }
+                if busy() || IDLE.signaled() {
+                    continue;
+                }
+                if SUPERVISOR.poll().is_ok() {
+                    watchdog.pet();
+                    fed_ms = now_ms();
+                }
+                let activity = ACTIVITY.lock(Cell::get);
+                if let Some(ms) = config::STOP_PLANNER.plan(now_ms(), next_edge, fed_ms, &activity)
+                {
+                    let stopped = u64::from(stop.enter(ms));
+                    STOPPED_MS.lock(|cell| cell.set(cell.get() + stopped));
+                    duty.record_stop(stopped);
+                    let _ = EVENTS.try_send(AppEvent::Resumed);
+                }
+            }
+        }
+
+        // Report the last window's duty cycle
+        let now = now_ms();
+        if now - duty.start_ms() >= config::POWER_REPORT_MS {
+            let report = duty.take(now, &low_power::CURRENT);
+            defmt::info!("{}", defmt::Display2Format(&report));
+            let _ = OUTPUT.try_send(Output::Notice(uformat!(OUTPUT_TEXT, "{}\r\n", report)));
+        }
+    }
+}
+
+/// Registers a task with `SUPERVISOR`
+///
+/// # Panics
+/// If more tasks are registered than the supervisor can track, which is a
+/// programming error caught on the first boot.
+fn register_task() -> Checkin {
+    SUPERVISOR.register().expect("too many supervised tasks")
+}
+
+/// Writes the boot-time clock report: PLL source, bus frequencies and flash latency
+///
+/// # Arguments
+/// * `usart` - Monitored UART transmitter
+/// * `source` - PLL clock source chosen by `config::clocks`
+/// * `freqs` - Frequencies computed by the HAL during `embassy_stm32::init`
+fn report_clocks<W: WriteAsync + WriteBlocking>(
+    usart: &mut TxMonitor<W>,
+    source: ClockSource,
+    freqs: &Clocks,
+) {
+    let mhz = |f: MaybeHertz| f.to_hertz().map_or(0, |hz| hz.0 / 1_000_000);
+    let _ = uprintln!(
+        usart,
+        "clocks: {}, SYSCLK {} MHz, HCLK {} MHz, PCLK1 {} MHz, PCLK2 {} MHz, flash {} WS",
+        source.name(),
+        mhz(freqs.sys),
+        mhz(freqs.hclk1),
+        mhz(freqs.pclk1),
+        mhz(freqs.pclk2),
+        config::flash_wait_states()
+    );
+    if freqs.sys.to_hertz().map(|hz| hz.0) != Some(config::SYSCLK_HZ) {
+        defmt::warn!("SYSCLK is not {} Hz", config::SYSCLK_HZ);
+    }
+}
+
+/// Writes the boot-time RTC report: clock source and current date and time
+///
+/// The calendar survives resets (it lives in the backup domain) but starts at
+/// 2000-01-01 after a power cycle, until `time set` is used.
+///
+/// # Arguments
+/// * `usart` - Monitored UART transmitter
+/// * `clock` - RTC clock chosen by `config::clocks`
+/// * `time` - Calendar reader of the RTC
+fn report_rtc<W: WriteAsync + WriteBlocking>(
+    usart: &mut TxMonitor<W>,
+    clock: RtcClock,
+    time: &RtcTimeProvider,
+) {
+    defmt::info!("rtc: {}", clock);
+    match rtc_now(time) {
+        Some(now) => {
+            let _ = uprintln!(usart, "rtc: {}, {}", clock.name(), now);
+        }
+        None => {
+            let _ = uprintln!(usart, "rtc: {}, not running", clock.name());
+        }
+    }
+}
+
+/// Reads the RTC calendar
+///
+/// # Returns
+/// The current UTC date and time, or `None` if the RTC could not be read.
+fn rtc_now(time: &RtcTimeProvider) -> Option<DateTime> {
+    let t = time.now().ok()?;
+    DateTime::new(
+        t.year(),
+        t.month(),
+        t.day(),
+        t.hour(),
+        t.minute(),
+        t.second(),
+    )
+    .ok()
+}
+
+/// Writes a date and time (within `clock::RTC_YEARS`) to the RTC calendar
+fn rtc_set(rtc: &mut Rtc, time: &DateTime) -> Result<(), RtcError> {
+    let weekday = match time.weekday() {
+        1 => DayOfWeek::Monday,
+        2 => DayOfWeek::Tuesday,
+        3 => DayOfWeek::Wednesday,
+        4 => DayOfWeek::Thursday,
+        5 => DayOfWeek::Friday,
+        6 => DayOfWeek::Saturday,
+        _ => DayOfWeek::Sunday,
+    };
+    let t = rtc::DateTime::from(
+        time.year(),
+        time.month(),
+        time.day(),
+        weekday,
+        time.hour(),
+        time.minute(),
+        time.second(),
+        0,
+    )
+    .map_err(RtcError::InvalidDateTime)?;
+    rtc.set_datetime(t)
+}
+
+/// Confirms a freshly installed image and reports how the last update went
+///
+/// Runs once the application has come up far enough to talk over the UART;
+/// an image that resets before this point is rolled back by the bootloader.
+///
+/// # Arguments
+/// * `usart` - UART to write the report to
+/// * `flash` - Flash driver holding the boot state log
+/// * `parts` - Bootloader partitions from `config::BOOT_PARTITIONS`
+fn report_update<W: WriteAsync + WriteBlocking, F: SettingsFlash>(
+    usart: &mut TxMonitor<W>,
+    flash: &mut F,
+    parts: &Partitions,
+) {
+    match boot::confirm(flash, parts) {
+        Ok(BootState::Testing { .. }) => {
+            defmt::info!("update: new image confirmed");
+            let _ = uprintln!(usart, "update: new image confirmed");
+        }
+        Ok(BootState::RolledBack) => {
+            defmt::warn!("update: new image failed to start, previous image restored");
+            let _ = uprintln!(
+                usart,
+                "update: new image failed to start, previous image restored"
+            );
+        }
+        Ok(_) => {}
+        Err(err) => {
+            defmt::warn!("update: boot state not saved: {}", err);
+            let _ = uprintln!(usart, "update: could not confirm this image");
+        }
+    }
+}
+
+/// Writes the crash report left by the previous run
+///
+/// Panics report their message and location; HardFaults report the stacked
+/// PC/LR/xPSR and the SCB fault status and address registers.
+///
+/// # Arguments
+/// * `usart` - Monitored UART transmitter
+/// * `record` - Valid crash record returned by `crash::take`
+fn report_crash<W: WriteAsync + WriteBlocking>(usart: &mut TxMonitor<W>, record: &CrashRecord) {
+    defmt::warn!("previous run crashed: {}", record.kind());
+    match record.kind() {
+        CrashKind::Panic => {
+            let _ = uprintln!(usart, "crash: panic: {}", record.message());
+            if let Some((file, line, column)) = record.location() {
+                let _ = uprintln!(usart, "crash:   at {}:{}:{}", file, line, column);
+            }
+        }
+        CrashKind::HardFault => {
+            let r = record.registers();
+            let _ = uprintln!(
+                usart,
+                "crash: HardFault at PC {:#010x}, LR {:#010x}, xPSR {:#010x}",
+                r.pc,
+                r.lr,
+                r.xpsr
+            );
+            let _ = uprintln!(
+                usart,
+                "crash:   CFSR {:#010x}, HFSR {:#010x}, MMFAR {:#010x}, BFAR {:#010x}",
+                r.cfsr,
+                r.hfsr,
+                r.mmfar,
+                r.bfar
+            );
+        }
+    }
+}
+
+/// Executes a parsed shell command and queues its response
+///
+/// # Arguments
+/// * `cmd` - Result of `shell::parse` for the received line
+/// * `led` - LED state to act on
+/// * `persisted` - Settings updated by `led blink`, `led pattern`, `schedule` and `baud`,
+///   and the flash `update` writes the boot state to
+/// * `rtc` - Real-time clock, read by `time` and written by `time set`
+async fn execute<P: LedPin, F: SettingsFlash>(
+    cmd: Result<Command, ParseError>,
+    led: &mut Led<P>,
+    persisted: &mut Persisted<F>,
+    rtc: &mut Rtc,
+) {
+    let reply = match cmd {
+        Ok(Command::Help) => messages::HELP,
+        Ok(Command::LedOn) => {
+            led.hold(true);
+            messages::OK
+        }
+        Ok(Command::LedOff) => {
+            led.hold(false);
+            messages::OK
+        }
+        Ok(Command::LedBlink(ms)) => {
+            led.play(PatternPlayer::square(ms));
+            persisted.update(|s| {
+                s.pattern = PATTERN_CUSTOM;
+                s.blink_ms = ms;
+            });
+            messages::OK
+        }
+        Ok(Command::LedPattern(index)) => {
+            led.select_preset(index);
+            persisted.update(|s| s.pattern = index as u8);
+            messages::OK
+        }
+        Ok(Command::LedEffect(effect)) => {
+            led.fade(effect);
+            messages::OK
+        }
+        Ok(Command::Morse(text)) => match led.send_morse(text.unwrap_or(messages::MORSE_BEACON)) {
+            Ok(()) => messages::OK,
+            Err(_) => messages::ERR_INVALID,
+        },
+        Ok(Command::Wpm(timing)) => {
+            led.set_morse_timing(timing);
+            messages::OK
+        }
+        Ok(Command::Status) => {
+            let text = status_report(led, persisted.settings());
+            OUTPUT.send(Output::Notice(text)).await;
+            return;
+        }
+        Ok(Command::Version) => {
+            // The features may not leave room for the line end in one string
+            let text = uformat!(OUTPUT_TEXT, "{} {}", env!("CARGO_PKG_NAME"), BUILD_INFO);
+            OUTPUT.send(Output::Text(text)).await;
+            messages::NEWLINE
+        }
+        Ok(Command::TimeGet) => match rtc_now(&rtc.time_provider()) {
+            Some(now) => {
+                OUTPUT
+                    .send(Output::Text(uformat!(OUTPUT_TEXT, "{}", now)))
+                    .await;
+                messages::NEWLINE
+            }
+            None => messages::ERR_RTC,
+        },
+        Ok(Command::TimeSet(time)) => match rtc_set(rtc, &time) {
+            Ok(()) => {
+                defmt::info!("time set to {}", time);
+                messages::OK
+            }
+            Err(_) => messages::ERR_RTC,
+        },
+        Ok(Command::ScheduleList) => {
+            let schedule = persisted.settings().schedule;
+            for slot in 0..MAX_RULES {
+                let text = match schedule.rule(slot) {
+                    Some(rule) => uformat!(OUTPUT_TEXT, "rule {}: {}\r\n", slot + 1, rule),
+                    None => uformat!(OUTPUT_TEXT, "rule {}: off\r\n", slot + 1),
+                };
+                OUTPUT.send(Output::Text(text)).await;
+            }
+            return;
+        }
+        Ok(Command::ScheduleSet(slot, rule)) => {
+            if persisted.update(|s| s.schedule.set(slot, Some(rule))) {
+                messages::OK
+            } else {
+                messages::ERR_SAVE
+            }
+        }
+        Ok(Command::ScheduleClear(slot)) => {
+            if persisted.update(|s| s.schedule.set(slot, None)) {
+                messages::OK
+            } else {
+                messages::ERR_SAVE
+            }
+        }
+        Ok(Command::Baud(rate)) => {
+            if persisted.update(|s| s.baud_rate = rate) {
+                messages::SAVED_AFTER_RESET
+            } else {
+                messages::ERR_SAVE
+            }
+        }
+        Ok(Command::Reset) => {
+            // Give `uart_tx_task` time to drain the queue before restarting
+            OUTPUT.send(Output::Bytes(messages::RESETTING)).await;
+            Timer::after(Duration::from_millis(config::RESET_FLUSH_MS)).await;
+            cortex_m::peripheral::SCB::sys_reset();
+        }
+        Ok(Command::Update) => match &config::BOOT_PARTITIONS {
+            None => messages::ERR_NO_BOOTLOADER,
+            Some(parts) => {
+                if boot::request_update(persisted.flash(), parts).is_err() {
+                    messages::ERR_UPDATE
+                } else {
+                    // The bootloader takes over the UART after the reset
+                    OUTPUT.send(Output::Bytes(messages::UPDATING)).await;
+                    Timer::after(Duration::from_millis(config::RESET_FLUSH_MS)).await;
+                    cortex_m::peripheral::SCB::sys_reset();
+                }
+            }
+        },
+        Err(ParseError::Empty) => return,
+        Err(ParseError::UnknownCommand) => messages::ERR_UNKNOWN,
+        Err(ParseError::MissingArgument) => messages::ERR_MISSING,
+        Err(ParseError::InvalidArgument) => messages::ERR_INVALID,
+        Err(ParseError::TooManyArguments) => messages::ERR_TOO_MANY,
+        Err(ParseError::NoSuchRule) => messages::ERR_NO_SUCH_RULE,
+    };
+    OUTPUT.send(Output::Bytes(reply)).await;
+}
+
+/// Formats the one-line status report: LED level (brightness while an effect
+/// runs), drive mode, pattern, uptime, UART write and receive failures and
+/// boot count
+///
+/// # Arguments
+/// * `led` - LED state to report
+/// * `settings` - Persisted settings (boot count)
+fn status_report<P: LedPin>(led: &Led<P>, settings: &Settings) -> String<OUTPUT_TEXT> {
+    let uptime_ms = now_ms();
+    let level = match led.level() {
+        Some(level) => uformat!(8, "{}%", (u32::from(level) * 100 + 127) / 255),
+        None => uformat!(8, "{}", if led.is_on() { "ON" } else { "OFF" }),
+    };
+    let tx_errors = TX_ERRORS.lock(Cell::get).total();
+    let rx_errors = RX_ERRORS.lock(Cell::get).total();
+    let sensors = match SENSORS.lock(Cell::get) {
+        Some(reading) => uformat!(32, ", {}", reading),
+        None => String::new(),
+    };
+    uformat!(
+        OUTPUT_TEXT,
+        "LED {}, mode {}, pattern {}, uptime {}.{:03} s, tx errors {}, rx errors {}, boot #{}{}\r\n",
+        level,
+        match led.mode() {
+            LedMode::Pattern => "pattern",
+            LedMode::Effect | LedMode::Morse => led.mode_name(),
+            LedMode::Manual => "manual",
+        },
+        led.pattern_name(),
+        uptime_ms / 1000,
+        uptime_ms % 1000,
+        tx_errors,
+        rx_errors,
+        settings.boot_count,
+        sensors,
+    )
+}
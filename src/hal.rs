@@ -0,0 +1,85 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Bindings from the library traits to the embassy-stm32 drivers
+//!
+//! This module is only compiled for the board (`target_os = "none"`):
+//! - `WriteBlocking` / `WriteAsync` for `UartTx`
+//! - `ReadAsync` for `RingBufferedUartRx`
+//! - `SettingsFlash` for the blocking `Flash` driver
+//!
+//! # Design Philosophy
+//! Keeping every embassy type behind these impls lets the rest of the library
+//! build and run its tests on a host, where the HAL is not available.
+
+use embassy_stm32::flash::{Blocking as FlashBlocking, Flash};
+use embassy_stm32::mode::{Async, Mode};
+use embassy_stm32::usart::{self, RingBufferedUartRx, UartTx};
+use embassy_time::{with_timeout, Duration, TimeoutError};
+
+use crate::serial::{ReadAsync, UartError, WriteAsync, WriteBlocking};
+use crate::settings::{FlashError, SettingsFlash};
+
+/// Upper bound in milliseconds on a single async UART write
+///
+/// At 115200 baud a 64-byte line takes about 6 ms, so this only trips if the
+/// DMA transfer has stalled.
+pub const UART_WRITE_TIMEOUT_MS: u64 = 100;
+
+impl From<usart::Error> for UartError {
+    fn from(err: usart::Error) -> Self {
+        match err {
+            usart::Error::Framing => Self::Framing,
+            usart::Error::Noise => Self::Noise,
+            usart::Error::Overrun => Self::Overrun,
+            usart::Error::Parity => Self::Parity,
+            usart::Error::BufferTooLong => Self::BufferFull,
+            // `usart::Error` is non-exhaustive; treat unknown faults as line noise
+            _ => Self::Noise,
+        }
+    }
+}
+
+impl<M: Mode> WriteBlocking for UartTx<'static, M> {
+    fn blocking_write(&mut self, bytes: &[u8]) -> Result<(), UartError> {
+        // Use the inherent blocking_write method, available in every UartTx mode
+        self.blocking_write(bytes).map_err(UartError::from)
+    }
+}
+
+impl WriteAsync for UartTx<'static, Async> {
+    async fn write(&mut self, bytes: &[u8]) -> Result<(), UartError> {
+        // Use the inherent DMA-driven write method provided by the async UartTx,
+        // bounded so a stalled transfer cannot hang the caller forever
+        let timeout = Duration::from_millis(UART_WRITE_TIMEOUT_MS);
+        match with_timeout(timeout, UartTx::write(self, bytes)).await {
+            Ok(result) => result.map_err(UartError::from),
+            Err(TimeoutError) => Err(UartError::Timeout),
+        }
+    }
+}
+
+impl ReadAsync for RingBufferedUartRx<'static> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, UartError> {
+        // Use the inherent read method, which waits for the DMA half/full
+        // transfer or an idle line; after an error it restarts reception
+        RingBufferedUartRx::read(self, buf)
+            .await
+            .map_err(UartError::from)
+    }
+}
+
+impl SettingsFlash for Flash<'static, FlashBlocking> {
+    fn read(&mut self, offset: u32, buf: &mut [u8]) -> Result<(), FlashError> {
+        self.blocking_read(offset, buf).map_err(|_| FlashError)
+    }
+
+    fn write(&mut self, offset: u32, data: &[u8]) -> Result<(), FlashError> {
+        self.blocking_write(offset, data).map_err(|_| FlashError)
+    }
+
+    fn erase(&mut self, offset: u32, len: u32) -> Result<(), FlashError> {
+        self.blocking_erase(offset, offset + len)
+            .map_err(|_| FlashError)
+    }
+}
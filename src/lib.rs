@@ -0,0 +1,67 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Hardware-independent application logic for the STM32F303RE blink firmware
+//!
+//! The firmware binary (`src/main.rs`) owns the peripherals and the executor;
+//! everything it decides is implemented here:
+//! - `app` - LED driving, gesture handling and persisted settings glue
+//! - `event` - events and output passed between the firmware tasks
+//! - `pattern`, `button`, `shell` - blink patterns, gestures and command parsing
+//! - `brightness` - gamma-corrected PWM brightness effects
+//! - `morse` - Morse code tables, timing and message keying
+//! - `settings`, `crc` - wear-levelled settings records in flash and checksums
+//! - `serial`, `monitor`, `uprint`, `messages` - UART traits, error accounting and output
+//! - `telemetry` - COBS-framed, CRC-checked binary status records
+//! - `supervisor`, `crash` - watchdog supervision and crash records
+//! - `power` - STOP mode policy, duty cycle and current estimate
+//! - `sensors` - die temperature and VDDA from the internal ADC channels
+//! - `clock` - calendar dates and ISO-8601 times for the real-time clock
+//! - `schedule` - time-of-day rules that switch the blink pattern
+//! - `ccm` - statics placed in core-coupled RAM (`ccm!`)
+//! - `build_info` - git commit, build time, profile and features of the image
+//! - `update`, `boot` - serial bootloader protocol, slot swap and rollback
+//! - `mock` - recording pin, serial and flash implementations for host tests
+//! - `hal` - trait impls for the embassy-stm32 drivers (board builds only)
+//!
+//! # Design Philosophy
+//! The library is written against `embedded-hal` and the local traits in `serial`
+//! and `settings`, never against embassy types directly. Building for the board
+//! (`target_os = "none"`) adds the `hal` bindings and defmt formatting; on any
+//! other target the library is plain `no_std` Rust, so
+//! `cargo test --target x86_64-unknown-linux-gnu` runs the integration tests in
+//! `tests/` against the mocks.
+
+#![no_std]
+
+pub mod app;
+pub mod boot;
+pub mod brightness;
+pub mod build_info;
+pub mod button;
+pub mod ccm;
+pub mod clock;
+pub mod crash;
+pub mod crc;
+pub mod event;
+#[cfg(target_os = "none")]
+pub mod hal;
+pub mod messages;
+pub mod mock;
+pub mod monitor;
+pub mod morse;
+pub mod pattern;
+pub mod power;
+pub mod schedule;
+pub mod sensors;
+pub mod serial;
+pub mod settings;
+pub mod shell;
+pub mod supervisor;
+pub mod telemetry;
+pub mod update;
+pub mod uprint;
+
+// Used by `uformat!` so callers need no direct `heapless` dependency
+#[doc(hidden)]
+pub use heapless;
@@ -0,0 +1,186 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! STOP mode entry and exit (`low-power` feature)
+//!
+//! `Stop` owns the RTC wake-up timer and puts the core into STOP for a given time:
+//! 1. Arms the RTC wake-up timer (RTCCLK / 16: 2048 Hz on the LSE, up to 32 s)
+//! 2. Selects the board's STOP mode (`board::LowPower`) and executes `WFI`
+//! 3. Restarts the PLL and switches SYSCLK back to it
+//! 4. Measures the time actually stopped on the RTC calendar
+//!
+//! # Design Philosophy
+//! embassy-stm32's low-power executor does not cover every supported family
+//! and only stops once every driver is dropped, so STOP is entered here, by the
+//! task that decides on it (`firmware::power_task`), inside a critical
+//! section. No other code runs until the clock tree is restored, so drivers
+//! never see the interval on the reset clock; `embassy_time` simply does not
+//! advance, and the firmware adds the measured time to its wall clock. The
+//! button's EXTI line stays armed, so a press ends a stop early.
+
+use embassy_stm32::interrupt::{self, InterruptExt};
+use embassy_stm32::pac::rtc::vals::Wucksel;
+use embassy_stm32::pac::{EXTI, RCC, RTC};
+use embassy_stm32::rcc::Sysclk;
+use embassy_stm32::rtc::RtcTimeProvider;
+
+use blink::power::CurrentModel;
+
+use crate::board::{Active, LowPower};
+use crate::config::ClockSource;
+
+/// Typical run and STOP currents of the board's chip
+pub const CURRENT: CurrentModel = Active::CURRENT;
+
+/// Milliseconds per day, where the RTC time of day wraps
+const DAY_MS: u32 = 86_400_000;
+
+/// The RTC wake-up timer and the clock tree to restore after STOP
+pub struct Stop {
+    time: RtcTimeProvider,
+    wakeup_hz: u64,
+    source: ClockSource,
+    sysclk: Sysclk,
+}
+
+impl Stop {
+    /// Routes the RTC wake-up event to the core
+    ///
+    /// # Arguments
+    /// * `time` - Calendar reader of the running RTC (`Rtc::time_provider`)
+    /// * `rtc_hz` - RTC clock frequency (32768 on the LSE, the LSI's otherwise)
+    /// * `source` - PLL input to restart after each stop
+    /// * `sysclk` - SYSCLK selection to switch back to (`Config::rcc.sys`)
+    pub fn new(time: RtcTimeProvider, rtc_hz: u32, source: ClockSource, sysclk: Sysclk) -> Self {
+        // The wake-up event reaches the NVIC through a rising-edge EXTI line
+        let line = Active::RTC_WAKEUP_LINE;
+        EXTI.rtsr(0).modify(|w| w.set_line(line, true));
+        EXTI.imr(0).modify(|w| w.set_line(line, true));
+        interrupt::RTC_WKUP.unpend();
+        // SAFETY: the handler below only clears the wake-up flags
+        unsafe { interrupt::RTC_WKUP.enable() };
+
+        Self {
+            time,
+            // WUCKSEL = RTCCLK / 16
+            wakeup_hz: u64::from(rtc_hz / 16),
+            source,
+            sysclk,
+        }
+    }
+
+    /// Stops the core for up to `ms` milliseconds
+    ///
+    /// Any enabled interrupt (the button EXTI, a DMA completion already
+    /// pending) ends the stop early.
+    ///
+    /// # Returns
+    /// The time spent stopped in milliseconds, as measured on the RTC.
+    pub fn enter(&mut self, ms: u32) -> u32 {
+        cortex_m::interrupt::free(|_| {
+            self.arm(ms);
+            let before = self.time_of_day_ms();
+
+            // Deep sleep with the board's STOP mode bits selected; with
+            // interrupts masked, WFI still returns once one becomes pending
+            Active::select_stop();
+            // SAFETY: SCR is only modified here, with interrupts disabled
+            let mut scb = unsafe { cortex_m::Peripherals::steal().SCB };
+            scb.set_sleepdeep();
+            cortex_m::asm::dsb();
+            cortex_m::asm::wfi();
+            scb.clear_sleepdeep();
+
+            // The core wakes on its reset clock: bring the PLL back first
+            self.restore_clocks();
+            self.disarm();
+
+            match (before, self.time_of_day_ms()) {
+                (Some(before), Some(after)) => (after + DAY_MS - before) % DAY_MS,
+                _ => ms,
+            }
+        })
+    }
+
+    /// Loads and starts the wake-up timer
+    fn arm(&self, ms: u32) {
+        let ticks = (u64::from(ms) * self.wakeup_hz / 1000).clamp(1, 1 << 16) as u32;
+        unlocked(|| {
+            // WUTR may only be written while the timer is off and WUTWF is set
+            RTC.cr().modify(|w| w.set_wute(false));
+            while !Active::wakeup_timer_writable() {}
+            Active::clear_wakeup_flag();
+            RTC.cr().modify(|w| w.set_wucksel(Wucksel::DIV16));
+            RTC.wutr().write(|w| w.set_wut((ticks - 1) as u16));
+            RTC.cr().modify(|w| {
+                w.set_wute(true);
+                w.set_wutie(true);
+            });
+        });
+    }
+
+    /// Stops the wake-up timer and clears every trace of its event
+    fn disarm(&self) {
+        unlocked(|| {
+            RTC.cr().modify(|w| {
+                w.set_wutie(false);
+                w.set_wute(false);
+            });
+            Active::clear_wakeup_flag();
+        });
+        EXTI.pr(0)
+            .modify(|w| w.set_line(Active::RTC_WAKEUP_LINE, true));
+        interrupt::RTC_WKUP.unpend();
+    }
+
+    /// Restarts the PLL input and the PLL, then switches SYSCLK back to it
+    ///
+    /// The PLL configuration survives STOP; only the oscillators are off.
+    fn restore_clocks(&self) {
+        match self.source {
+            ClockSource::HseBypass => {
+                RCC.cr().modify(|w| w.set_hseon(true));
+                while !RCC.cr().read().hserdy() {}
+            }
+            ClockSource::Hsi => {
+                RCC.cr().modify(|w| w.set_hsion(true));
+                while !RCC.cr().read().hsirdy() {}
+            }
+        }
+        RCC.cr().modify(|w| w.set_pllon(true));
+        while !RCC.cr().read().pllrdy() {}
+        RCC.cfgr().modify(|w| w.set_sw(self.sysclk));
+        while RCC.cfgr().read().sws() != self.sysclk {}
+    }
+
+    /// Returns the RTC time of day in milliseconds (256 Hz resolution)
+    fn time_of_day_ms(&self) -> Option<u32> {
+        let now = self.time.now().ok()?;
+        let secs =
+            (u32::from(now.hour()) * 60 + u32::from(now.minute())) * 60 + u32::from(now.second());
+        Some(secs * 1000 + now.microsecond() / 1000)
+    }
+}
+
+/// Runs `f` with the RTC write protection lifted (keys `0xCA`, `0x53`)
+///
+/// Backup-domain write access was enabled by the HAL when it started the LSE.
+fn unlocked<R>(f: impl FnOnce() -> R) -> R {
+    RTC.wpr().write(|w| w.set_key(0xCA));
+    RTC.wpr().write(|w| w.set_key(0x53));
+    let result = f();
+    RTC.wpr().write(|w| w.set_key(0xFF));
+    result
+}
+
+/// RTC wake-up interrupt
+///
+/// Never taken while `Stop::enter` runs (interrupts are masked and the event is
+/// cleared before unmasking); this only keeps a stray event from reaching the
+/// default handler.
+#[interrupt]
+fn RTC_WKUP() {
+    unlocked(Active::clear_wakeup_flag);
+    EXTI.pr(0)
+        .modify(|w| w.set_line(Active::RTC_WAKEUP_LINE, true));
+}
@@ -1,70 +1,91 @@
 // Copyright (c) 2025 Kevin Thomas
 // Licensed under the MIT License. See LICENSE file in the project root for full license information.
 
-//! STM32F303RE Nucleo LED Blink Example
+//! STM32 Nucleo LED Blink Example
 //!
 //! This application demonstrates basic embedded Rust development using the Embassy framework.
 //! It blinks the onboard LED (LD2 on PA5) at a configurable interval while sending status
-//! messages over UART2 to the ST-Link virtual COM port.
+//! messages over the ST-Link virtual COM port and accepting shell commands from it.
 //!
 //! # Hardware
-//! - Board: STM32F303RE Nucleo
-//! - MCU: STM32F303RET6 (ARM Cortex-M4F @ 72MHz)
-//! - LED: Green LED (LD2) on PA5
-//! - UART: USART2 on PA2 (TX) via ST-Link VCP
+//! - Board: STM32F303RE Nucleo (default), or F401RE, L476RG, G431RB via `board-*` features
+//! - MCU: STM32F303RET6 (ARM Cortex-M4F @ 72MHz) on the default board
+//! - LED: Green LED (LD2) on PA5 (TIM2_CH1 PWM)
+//! - UART: USART2 (LPUART1 on the G431RB) on PA2 (TX) and PA3 (RX) via ST-Link VCP
+//! - Button: User button (B1) on PC13 via EXTI
 //!
 //! # Features
-//! - Async/await with Embassy executor
+//! - Task queues and the main stack in CCM RAM (`blink::ccm!`), with a link-time
+//!   memory usage report
+//! - Board support for four Nucleo-64 boards, each with its own clock tree and memory descriptor
+//! - PLL clock tree at the chip's maximum (HSE bypass from ST-Link MCO, HSI fallback)
+//! - Async/await with Embassy executor: LED, UART, button, heartbeat, sensor and watchdog tasks
+//!   exchanging `AppEvent`s and `Output`s over `embassy-sync` channels
+//! - Programmable blink patterns (steady, heartbeat, double-flash, SOS)
+//! - PWM LED on TIM2_CH1 with gamma-corrected breathing, fade and dim effects
+//! - Morse code messages on the LED (WPM and Farnsworth timing), mirrored to UART
+//! - Optional COBS/CRC-16 binary telemetry frames (`telemetry` feature)
 //! - Real-time debug logging via RTT (defmt)
-//! - UART serial output at 115200 baud
-//! - Low power and optimized for embedded systems
+//! - DMA-driven UART serial output at 115200 baud (writes never stall the executor)
+//! - Formatted, allocation-free UART output (`uprintln!`, `uformat!`)
+//! - UART write failures counted and reported instead of ignored
+//! - Interactive command shell (`help`, `led on|off|blink <ms>`, `status`, `reset`)
+//! - Settings (pattern, blink period, baud rate, boot count) persisted in flash
+//! - User button gestures: short press, long press and double click change the blink mode
+//! - Independent watchdog fed only while every supervised task checks in; reset cause reported at boot
+//! - Panics and HardFaults captured in RAM and reported over UART on the next boot
+//! - Build metadata (git commit, build time, profile, features) in a `.build_info`
+//!   flash section, printed at boot, by `version` and by `blinkctl info <elf>`
+//! - Optional STOP mode between LED edges with RTC wake-up and duty-cycle and
+//!   current reports (`low-power` feature)
+//! - Die temperature and VDDA from the internal ADC channels, corrected with the
+//!   factory calibration and reported in `status`
+//! - UTC calendar on the RTC (LSE, or LSI as a fallback), read and set with
+//!   `time`, stamping every status line with an ISO-8601 time
+//! - Time-of-day schedule rules that switch the blink pattern, stored with the settings
+//! - Serial bootloader (`src/bin/bootloader.rs`, `bootloader` feature): `update`
+//!   restarts into it, `blinkctl update` uploads a CRC-checked image, and an image
+//!   that resets before confirming is rolled back
+//!
+//! # Layout
+//! - `blink` (the library, `src/lib.rs`) - hardware-independent logic and mocks
+//! - `board` - per-board clocks, pin mapping and settings location
+//! - `config` - timing constants and peripheral initialization
+//! - `firmware` - one task per peripheral, connected by channels
+//! - `fault` - panic and HardFault handlers
+//! - `low_power` - STOP entry, RTC wake-up and clock restore (`low-power` feature)
+//!
+//! On a host the binary is an empty stub, so `cargo test` for the host target can
+//! build every target of the package.
 //!
 //! # Usage
 //! Flash to board: `cargo run --release`
+//! Other boards: `cargo run --release --no-default-features --features board-f401re`
 //! Monitor serial: `screen /dev/tty.usbmodem* 115200`
+//! With the bootloader: `cargo build --release --features bootloader`, flash both ELFs
+//! Host tests: `cargo test-host`
 
-#![no_std]
-#![no_main]
+#![cfg_attr(target_os = "none", no_std)]
+#![cfg_attr(target_os = "none", no_main)]
+// Surface the linker's `--print-memory-usage` report (requested by `build.rs`)
+#![cfg_attr(target_os = "none", warn(linker_messages))]
 
+#[cfg(target_os = "none")]
+mod board;
+#[cfg(target_os = "none")]
 mod config;
+#[cfg(target_os = "none")]
+mod fault;
+#[cfg(target_os = "none")]
+mod firmware;
+#[cfg(all(target_os = "none", feature = "low-power"))]
+mod low_power;
 
-use crate::config::WriteBlocking;
-use embassy_executor::Spawner;
-use embassy_time::Timer;
-use {defmt_rtt as _, panic_probe as _};
-
-/// Main application entry point
-///
-/// Initializes the STM32 peripherals and runs an infinite loop that:
-/// 1. Turns the LED on and sends "LED ON" via UART
-/// 2. Waits for the configured interval
-/// 3. Turns the LED off and sends "LED OFF" via UART
-/// 4. Waits for the configured interval
-/// 5. Repeats
+/// Host stand-in for the firmware entry point
 ///
-/// # Arguments
-/// * `_spawner` - Embassy task spawner (unused in this simple example)
-///
-/// # Panics
-/// Never returns. Runs indefinitely until power loss or reset.
-#[embassy_executor::main]
-async fn main(_spawner: Spawner) {
-    // Initialize STM32 peripherals with default configuration
-    let p = embassy_stm32::init(Default::default());
-
-    // Initialize hardware (LED and UART)
-    let (mut led, mut usart) = config::init(p);
-
-    // Main application loop - blink LED and send UART messages
-    loop {
-        // Turn LED on and notify via UART
-        let _ = usart.blocking_write(config::messages::LED_ON);
-        led.set_high();
-        Timer::after_millis(500).await;
-
-        // Turn LED off and notify via UART
-        let _ = usart.blocking_write(config::messages::LED_OFF);
-        led.set_low();
-        Timer::after_millis(500).await;
-    }
+/// The firmware only runs on the supported Nucleo boards; this keeps `cargo test` for a
+/// host target from failing on the binary.
+#[cfg(not(target_os = "none"))]
+fn main() {
+    eprintln!("this firmware runs on STM32 Nucleo boards; use `cargo run --release` to flash it");
 }
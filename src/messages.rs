@@ -0,0 +1,88 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! UART serial message definitions
+//!
+//! Pre-formatted messages sent over UART2 to the ST-Link virtual COM port.
+//! Messages use CR+LF (\r\n) line endings for proper terminal display.
+
+/// LED state: ON - Sent when LED is activated
+pub const LED_ON: &[u8] = b"LED ON\r\n";
+
+/// LED state: OFF - Sent when LED is deactivated
+pub const LED_OFF: &[u8] = b"LED OFF\r\n";
+
+/// Shell prompt - Sent after every processed command line
+pub const PROMPT: &[u8] = b"> ";
+
+/// Line terminator echoed when the user presses Enter
+pub const NEWLINE: &[u8] = b"\r\n";
+
+/// Shell help text - Sent in response to `help`
+pub const HELP: &[u8] = b"Commands:\r\n\
+    \x20 help                  show this list\r\n\
+    \x20 led on|off            hold the LED on or off\r\n\
+    \x20 led blink <ms>        blink with a <ms> half-period\r\n\
+    \x20 led pattern <name>    steady|heartbeat|double-flash|sos\r\n\
+    \x20 led breathe <ms>      breathe with a <ms> period\r\n\
+    \x20 led fade in|out <ms>  fade in or out over <ms>\r\n\
+    \x20 led dim <percent>     hold a fixed brightness\r\n\
+    \x20 morse [text]          key text (or the beacon) in Morse\r\n\
+    \x20 wpm <wpm> [<slow>]    set Morse speed (Farnsworth <slow>)\r\n\
+    \x20 status                show LED state and uptime\r\n\
+    \x20 version               show the firmware build\r\n\
+    \x20 time [get]            show the RTC date and time (UTC)\r\n\
+    \x20 time set <iso-8601>   set the RTC, e.g. 2025-06-01T12:00:00Z\r\n\
+    \x20 schedule              list the time-of-day pattern rules\r\n\
+    \x20 schedule <n> <HH:MM-HH:MM> <name>\r\n\
+    \x20                       play a preset every day in that window\r\n\
+    \x20                       (rule 1 to 8, times in UTC)\r\n\
+    \x20 schedule <n> off      clear rule <n>\r\n\
+    \x20 baud <rate>           store a baud rate (after reset)\r\n\
+    \x20 reset                 restart the board\r\n\
+    \x20 update                restart into the bootloader (blinkctl update)\r\n";
+
+/// Beacon text keyed by `morse` without an argument
+pub const MORSE_BEACON: &str = "STM32F303RE OK";
+
+/// Shell acknowledgement - Sent when a command is accepted
+pub const OK: &[u8] = b"OK\r\n";
+
+/// Shell error: unrecognised command word
+pub const ERR_UNKNOWN: &[u8] = b"ERR unknown command (try `help`)\r\n";
+
+/// Shell error: command is missing a required argument
+pub const ERR_MISSING: &[u8] = b"ERR missing argument\r\n";
+
+/// Shell error: argument is malformed or out of range
+pub const ERR_INVALID: &[u8] = b"ERR invalid argument\r\n";
+
+/// Shell error: command given too many arguments
+pub const ERR_TOO_MANY: &[u8] = b"ERR too many arguments\r\n";
+
+/// Shell error: schedule rule number out of range (`schedule::MAX_RULES` slots)
+pub const ERR_NO_SUCH_RULE: &[u8] = b"ERR no such rule (the settings hold rules 1 to 8)\r\n";
+
+/// Shell error: input line exceeded the line buffer
+pub const ERR_TOO_LONG: &[u8] = b"ERR line too long\r\n";
+
+/// Shell notice - Sent when a setting is stored but applies only after reset
+pub const SAVED_AFTER_RESET: &[u8] = b"OK (takes effect after reset)\r\n";
+
+/// Shell error: settings could not be written to flash
+pub const ERR_SAVE: &[u8] = b"ERR could not save settings\r\n";
+
+/// Shell error: the RTC calendar could not be read or written
+pub const ERR_RTC: &[u8] = b"ERR RTC not running\r\n";
+
+/// Shell notice - Sent immediately before a software reset
+pub const RESETTING: &[u8] = b"Resetting...\r\n";
+
+/// Shell notice - Sent before resetting into the serial bootloader
+pub const UPDATING: &[u8] = b"Restarting into the bootloader...\r\n";
+
+/// Shell error: `update` in a build without the `bootloader` feature
+pub const ERR_NO_BOOTLOADER: &[u8] = b"ERR no bootloader in this build\r\n";
+
+/// Shell error: the update request could not be written to flash
+pub const ERR_UPDATE: &[u8] = b"ERR could not request an update\r\n";
@@ -0,0 +1,366 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Recording stand-ins for the board peripherals
+//!
+//! These implement the same traits as the real drivers so application logic can
+//! be exercised with `cargo test` on a host:
+//! - `MockPin` - `embedded-hal` output pin that records every level written
+//! - `MockPwm` - `embedded-hal` PWM channel that records every duty cycle written
+//! - `MockSerial` - `WriteBlocking`/`WriteAsync`/`ReadAsync` with
+//!   captured output, scripted input and injectable write and read failures
+//! - `MockFlash` - NOR-flash model implementing `SettingsFlash`
+//!
+//! # Design Philosophy
+//! The mocks are `no_std` and use fixed-capacity buffers, so they build for every
+//! target; on the board they are simply never linked.
+
+use core::convert::Infallible;
+
+use embedded_hal::digital::{ErrorType, OutputPin};
+use embedded_hal::pwm::{self, SetDutyCycle};
+use heapless::{Deque, Vec};
+
+use crate::serial::{ReadAsync, UartError, WriteAsync, WriteBlocking};
+use crate::settings::{FlashError, SettingsFlash};
+
+/// Maximum number of pin levels `MockPin` records
+pub const PIN_HISTORY: usize = 256;
+
+/// Maximum number of bytes `MockSerial` captures or queues for reading
+pub const SERIAL_CAPACITY: usize = 4096;
+
+/// Output pin that records every level written to it
+#[derive(Debug, Default)]
+pub struct MockPin {
+    high: bool,
+    history: Vec<bool, PIN_HISTORY>,
+}
+
+impl MockPin {
+    /// Creates a low pin with an empty history
+    pub const fn new() -> Self {
+        Self {
+            high: false,
+            history: Vec::new(),
+        }
+    }
+
+    /// Returns the current level, `true` when high
+    pub fn is_high(&self) -> bool {
+        self.high
+    }
+
+    /// Returns every level written so far, oldest first
+    ///
+    /// Writes beyond `PIN_HISTORY` still change the level but are not recorded.
+    pub fn history(&self) -> &[bool] {
+        &self.history
+    }
+
+    /// Forgets the recorded history, keeping the current level
+    pub fn clear(&mut self) {
+        self.history.clear();
+    }
+
+    /// Records a write of `level`
+    fn set(&mut self, level: bool) {
+        self.high = level;
+        let _ = self.history.push(level);
+    }
+}
+
+impl ErrorType for MockPin {
+    type Error = Infallible;
+}
+
+impl OutputPin for MockPin {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.set(false);
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.set(true);
+        Ok(())
+    }
+}
+
+/// PWM channel that records every duty cycle written to it
+#[derive(Debug)]
+pub struct MockPwm {
+    max_duty: u16,
+    duty: u16,
+    history: Vec<u16, PIN_HISTORY>,
+}
+
+impl MockPwm {
+    /// Creates a channel at zero duty with an empty history
+    ///
+    /// # Arguments
+    /// * `max_duty` - Duty value for 100% (the timer auto-reload value)
+    pub const fn new(max_duty: u16) -> Self {
+        Self {
+            max_duty,
+            duty: 0,
+            history: Vec::new(),
+        }
+    }
+
+    /// Returns the current duty cycle
+    pub fn duty(&self) -> u16 {
+        self.duty
+    }
+
+    /// Returns every duty cycle written so far, oldest first
+    ///
+    /// Writes beyond `PIN_HISTORY` still change the duty but are not recorded.
+    pub fn history(&self) -> &[u16] {
+        &self.history
+    }
+
+    /// Forgets the recorded history, keeping the current duty
+    pub fn clear(&mut self) {
+        self.history.clear();
+    }
+}
+
+impl pwm::ErrorType for MockPwm {
+    type Error = Infallible;
+}
+
+impl SetDutyCycle for MockPwm {
+    fn max_duty_cycle(&self) -> u16 {
+        self.max_duty
+    }
+
+    fn set_duty_cycle(&mut self, duty: u16) -> Result<(), Self::Error> {
+        self.duty = duty;
+        let _ = self.history.push(duty);
+        Ok(())
+    }
+}
+
+/// UART stand-in that captures output and replays scripted input
+#[derive(Debug, Default)]
+pub struct MockSerial {
+    output: Vec<u8, SERIAL_CAPACITY>,
+    input: Deque<u8, SERIAL_CAPACITY>,
+    failures: Deque<UartError, 16>,
+    read_failures: Deque<UartError, 16>,
+    writes: u32,
+}
+
+impl MockSerial {
+    /// Creates a serial port with no output, input or pending failures
+    pub const fn new() -> Self {
+        Self {
+            output: Vec::new(),
+            input: Deque::new(),
+            failures: Deque::new(),
+            read_failures: Deque::new(),
+            writes: 0,
+        }
+    }
+
+    /// Returns everything written so far
+    pub fn output(&self) -> &[u8] {
+        &self.output
+    }
+
+    /// Returns everything written so far as text (empty if not valid UTF-8)
+    pub fn output_str(&self) -> &str {
+        core::str::from_utf8(&self.output).unwrap_or("")
+    }
+
+    /// Forgets the captured output
+    pub fn clear(&mut self) {
+        self.output.clear();
+    }
+
+    /// Returns the number of write calls, including failed ones
+    pub fn writes(&self) -> u32 {
+        self.writes
+    }
+
+    /// Queues `bytes` to be returned by `read`
+    pub fn feed(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            let _ = self.input.push_back(b);
+        }
+    }
+
+    /// Makes the next write fail with `err` (failures queue up in order)
+    pub fn fail_next(&mut self, err: UartError) {
+        let _ = self.failures.push_back(err);
+    }
+
+    /// Makes the next read fail with `err` (failures queue up in order)
+    pub fn fail_next_read(&mut self, err: UartError) {
+        let _ = self.read_failures.push_back(err);
+    }
+
+    /// Applies the next queued failure, or captures `bytes`
+    fn record(&mut self, bytes: &[u8]) -> Result<(), UartError> {
+        self.writes += 1;
+        if let Some(err) = self.failures.pop_front() {
+            return Err(err);
+        }
+        self.output
+            .extend_from_slice(bytes)
+            .map_err(|_| UartError::BufferFull)
+    }
+}
+
+impl WriteBlocking for MockSerial {
+    fn blocking_write(&mut self, bytes: &[u8]) -> Result<(), UartError> {
+        self.record(bytes)
+    }
+}
+
+impl WriteAsync for MockSerial {
+    async fn write(&mut self, bytes: &[u8]) -> Result<(), UartError> {
+        self.record(bytes)
+    }
+}
+
+/// Returns everything queued at once, like a DMA ring buffer drained after a
+/// burst; with nothing queued it returns 0 instead of waiting
+impl ReadAsync for MockSerial {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, UartError> {
+        if let Some(err) = self.read_failures.pop_front() {
+            return Err(err);
+        }
+        let mut len = 0;
+        while len < buf.len() {
+            let Some(byte) = self.input.pop_front() else {
+                break;
+            };
+            buf[len] = byte;
+            len += 1;
+        }
+        Ok(len)
+    }
+}
+
+/// In-memory NOR flash of `N` bytes starting at offset `base`
+///
+/// Models the constraints the settings store relies on: erased bytes read as
+/// `0xFF`, programming a location that is not erased fails (the STM32F3 flags
+/// PGERR), and erases work on whole pages.
+/// Individual operations can be made to fail to exercise error paths, and a
+/// power cut can be scheduled after a number of writes and erases.
+pub struct MockFlash<const N: usize> {
+    base: u32,
+    page_size: u32,
+    data: [u8; N],
+    erases: u32,
+    fail_writes: bool,
+    power: Option<u32>,
+}
+
+impl<const N: usize> MockFlash<N> {
+    /// Creates a fully erased flash
+    ///
+    /// # Arguments
+    /// * `base` - Offset of the first byte (e.g. `Geometry::base`)
+    /// * `page_size` - Erase granularity in bytes
+    pub const fn new(base: u32, page_size: u32) -> Self {
+        Self {
+            base,
+            page_size,
+            data: [0xFF; N],
+            erases: 0,
+            fail_writes: false,
+            power: None,
+        }
+    }
+
+    /// Returns the raw contents
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the raw contents for corruption tests
+    pub fn data_mut(&mut self) -> &mut [u8] {
+        &mut self.data
+    }
+
+    /// Returns the number of page erases performed
+    pub fn erases(&self) -> u32 {
+        self.erases
+    }
+
+    /// Makes every subsequent write fail (`true`) or succeed (`false`)
+    pub fn fail_writes(&mut self, fail: bool) {
+        self.fail_writes = fail;
+    }
+
+    /// Lets `ops` more writes and erases succeed, then fails every later one
+    /// until `restore_power`, as if the supply had been cut
+    pub fn cut_power_after(&mut self, ops: u32) {
+        self.power = Some(ops);
+    }
+
+    /// Ends a power cut scheduled with `cut_power_after`
+    pub fn restore_power(&mut self) {
+        self.power = None;
+    }
+
+    /// Counts one write or erase against a scheduled power cut
+    fn powered(&mut self) -> Result<(), FlashError> {
+        match &mut self.power {
+            Some(0) => Err(FlashError),
+            Some(left) => {
+                *left -= 1;
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Maps an absolute range onto `data`
+    fn range(&self, offset: u32, len: usize) -> Result<core::ops::Range<usize>, FlashError> {
+        let start = offset.checked_sub(self.base).ok_or(FlashError)? as usize;
+        let end = start.checked_add(len).ok_or(FlashError)?;
+        if end > N {
+            return Err(FlashError);
+        }
+        Ok(start..end)
+    }
+}
+
+impl<const N: usize> SettingsFlash for MockFlash<N> {
+    fn read(&mut self, offset: u32, buf: &mut [u8]) -> Result<(), FlashError> {
+        let range = self.range(offset, buf.len())?;
+        buf.copy_from_slice(&self.data[range]);
+        Ok(())
+    }
+
+    fn write(&mut self, offset: u32, data: &[u8]) -> Result<(), FlashError> {
+        let range = self.range(offset, data.len())?;
+        if self.fail_writes {
+            return Err(FlashError);
+        }
+        self.powered()?;
+        // Only erased locations can be programmed
+        if self.data[range.clone()].iter().any(|&b| b != 0xFF) {
+            return Err(FlashError);
+        }
+        self.data[range].copy_from_slice(data);
+        Ok(())
+    }
+
+    fn erase(&mut self, offset: u32, len: u32) -> Result<(), FlashError> {
+        let range = self.range(offset, len as usize)?;
+        if !(offset - self.base).is_multiple_of(self.page_size)
+            || !len.is_multiple_of(self.page_size)
+        {
+            return Err(FlashError);
+        }
+        self.powered()?;
+        self.data[range].fill(0xFF);
+        self.erases += len / self.page_size;
+        Ok(())
+    }
+}
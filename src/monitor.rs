@@ -0,0 +1,174 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! UART failure accounting and reporting policy
+//!
+//! This module decides what happens when a UART write or read fails:
+//! - `ErrorCounters` - per-kind failure counts for every `UartError` variant
+//! - `TxMonitor` - wraps a transmitter, counting and logging every failed write
+//! - `RxMonitor` - wraps a receiver, counting and logging every line fault
+//!
+//! # Policy
+//! A failed write is never retried: status output is periodic, so the next message
+//! supersedes the lost one. A receive fault (overrun, framing, noise, parity)
+//! loses the bytes it hit; the shell rejects the garbled line and the sender
+//! repeats it. Every failure is counted, the first one and every
+//! `UART_ERROR_REPORT_EVERY`th after it are logged over defmt RTT (the UART
+//! itself may be what is broken), and the totals are included in `status` output.
+
+use crate::serial::{ReadAsync, UartError, WriteAsync, WriteBlocking};
+
+/// Number of UART failures (per direction) between repeated defmt warnings
+///
+/// The first failure is always logged; after that only every Nth is, so a dead
+/// link cannot flood the RTT channel.
+pub const UART_ERROR_REPORT_EVERY: u32 = 100;
+
+/// Saturating per-kind counters for UART failures (one set per direction)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub struct ErrorCounters {
+    pub framing: u32,
+    pub noise: u32,
+    pub overrun: u32,
+    pub parity: u32,
+    pub buffer_full: u32,
+    pub timeout: u32,
+}
+
+impl ErrorCounters {
+    /// Creates a zeroed set of counters
+    pub const fn new() -> Self {
+        Self {
+            framing: 0,
+            noise: 0,
+            overrun: 0,
+            parity: 0,
+            buffer_full: 0,
+            timeout: 0,
+        }
+    }
+
+    /// Counts one occurrence of `err`
+    ///
+    /// # Returns
+    /// The total number of failures recorded so far, including this one.
+    pub fn record(&mut self, err: UartError) -> u32 {
+        let counter = match err {
+            UartError::Framing => &mut self.framing,
+            UartError::Noise => &mut self.noise,
+            UartError::Overrun => &mut self.overrun,
+            UartError::Parity => &mut self.parity,
+            UartError::BufferFull => &mut self.buffer_full,
+            UartError::Timeout => &mut self.timeout,
+        };
+        *counter = counter.saturating_add(1);
+        self.total()
+    }
+
+    /// Returns the total number of failures across all kinds
+    pub fn total(&self) -> u32 {
+        self.framing
+            .saturating_add(self.noise)
+            .saturating_add(self.overrun)
+            .saturating_add(self.parity)
+            .saturating_add(self.buffer_full)
+            .saturating_add(self.timeout)
+    }
+}
+
+/// UART transmitter wrapper that applies the write-failure policy
+///
+/// # Usage
+/// Wrap the transmitter returned by `config::init_async` and send through `send`,
+/// which never fails from the caller's point of view.
+pub struct TxMonitor<W: WriteAsync> {
+    inner: W,
+    errors: ErrorCounters,
+}
+
+impl<W: WriteAsync> TxMonitor<W> {
+    /// Wraps `inner` with zeroed error counters
+    pub const fn new(inner: W) -> Self {
+        Self {
+            inner,
+            errors: ErrorCounters::new(),
+        }
+    }
+
+    /// Writes `bytes`, counting and (rate-limited) logging any failure
+    pub async fn send(&mut self, bytes: &[u8]) {
+        if let Err(err) = self.inner.write(bytes).await {
+            self.note(err);
+        }
+    }
+
+    /// Returns the failure counters accumulated so far
+    pub fn errors(&self) -> &ErrorCounters {
+        &self.errors
+    }
+
+    /// Counts `err` and logs it if the reporting policy says so
+    fn note(&mut self, err: UartError) {
+        let total = self.errors.record(err);
+        if total == 1 || total.is_multiple_of(UART_ERROR_REPORT_EVERY) {
+            #[cfg(target_os = "none")]
+            defmt::warn!("uart write failed: {} ({} failures so far)", err, total);
+        }
+    }
+}
+
+/// Blocking writes through the monitor are counted the same way as async ones,
+/// which lets `uprintln!` target a monitored transmitter directly.
+impl<W: WriteAsync + WriteBlocking> WriteBlocking for TxMonitor<W> {
+    fn blocking_write(&mut self, bytes: &[u8]) -> Result<(), UartError> {
+        let result = self.inner.blocking_write(bytes);
+        if let Err(err) = result {
+            self.note(err);
+        }
+        result
+    }
+}
+
+/// UART receiver wrapper that applies the failure policy to line faults
+///
+/// # Usage
+/// Wrap the receiver returned by `config::init_async` and read through
+/// `receive`, which never fails from the caller's point of view.
+pub struct RxMonitor<R: ReadAsync> {
+    inner: R,
+    errors: ErrorCounters,
+}
+
+impl<R: ReadAsync> RxMonitor<R> {
+    /// Wraps `inner` with zeroed error counters
+    pub const fn new(inner: R) -> Self {
+        Self {
+            inner,
+            errors: ErrorCounters::new(),
+        }
+    }
+
+    /// Waits for received bytes, counting and (rate-limited) logging any fault
+    ///
+    /// # Returns
+    /// The number of bytes copied into `buf`; 0 after a fault.
+    pub async fn receive(&mut self, buf: &mut [u8]) -> usize {
+        match self.inner.read(buf).await {
+            Ok(len) => len,
+            Err(err) => {
+                let total = self.errors.record(err);
+                if total == 1 || total.is_multiple_of(UART_ERROR_REPORT_EVERY) {
+                    #[cfg(target_os = "none")]
+                    defmt::warn!("uart read failed: {} ({} failures so far)", err, total);
+                }
+                0
+            }
+        }
+    }
+
+    /// Returns the fault counters accumulated so far
+    pub fn errors(&self) -> &ErrorCounters {
+        &self.errors
+    }
+}
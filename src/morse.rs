@@ -0,0 +1,388 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Morse code transmitter for the LED
+//!
+//! This module turns short ASCII messages into keyed LED on/off periods:
+//! - `code` - International Morse code table (letters, digits, punctuation)
+//! - `MorseTiming` - character speed and Farnsworth (overall) speed in WPM
+//! - `MorsePlayer` - keys a message against a millisecond clock, reporting each
+//!   character as it starts so it can be mirrored to UART
+//!
+//! # Design Philosophy
+//! Like `PatternPlayer`, the player never sleeps: the caller passes the current
+//! time in and receives the next deadline. Timing follows the PARIS standard
+//! (one dot = 1200 / WPM ms); Farnsworth spacing keeps characters at full speed
+//! but stretches the gaps between them, as recommended for learners.
+
+use heapless::Vec;
+
+use crate::pattern::LedPin;
+
+/// Longest message `MorsePlayer` accepts, in characters
+pub const MAX_MESSAGE: usize = 64;
+
+/// Slowest accepted speed in words per minute
+pub const MIN_WPM: u32 = 5;
+
+/// Fastest accepted speed in words per minute
+pub const MAX_WPM: u32 = 40;
+
+/// Returns the Morse code for `ch` as dots and dashes
+///
+/// Letters are matched case-insensitively.
+///
+/// # Returns
+/// `None` for characters without a Morse representation (including space,
+/// which is keyed as a word gap).
+pub fn code(ch: u8) -> Option<&'static str> {
+    let code = match ch.to_ascii_uppercase() {
+        b'A' => ".-",
+        b'B' => "-...",
+        b'C' => "-.-.",
+        b'D' => "-..",
+        b'E' => ".",
+        b'F' => "..-.",
+        b'G' => "--.",
+        b'H' => "....",
+        b'I' => "..",
+        b'J' => ".---",
+        b'K' => "-.-",
+        b'L' => ".-..",
+        b'M' => "--",
+        b'N' => "-.",
+        b'O' => "---",
+        b'P' => ".--.",
+        b'Q' => "--.-",
+        b'R' => ".-.",
+        b'S' => "...",
+        b'T' => "-",
+        b'U' => "..-",
+        b'V' => "...-",
+        b'W' => ".--",
+        b'X' => "-..-",
+        b'Y' => "-.--",
+        b'Z' => "--..",
+        b'0' => "-----",
+        b'1' => ".----",
+        b'2' => "..---",
+        b'3' => "...--",
+        b'4' => "....-",
+        b'5' => ".....",
+        b'6' => "-....",
+        b'7' => "--...",
+        b'8' => "---..",
+        b'9' => "----.",
+        b'.' => ".-.-.-",
+        b',' => "--..--",
+        b'?' => "..--..",
+        b'\'' => ".----.",
+        b'!' => "-.-.--",
+        b'/' => "-..-.",
+        b'(' => "-.--.",
+        b')' => "-.--.-",
+        b'&' => ".-...",
+        b':' => "---...",
+        b';' => "-.-.-.",
+        b'=' => "-...-",
+        b'+' => ".-.-.",
+        b'-' => "-....-",
+        b'_' => "..--.-",
+        b'"' => ".-..-.",
+        b'$' => "...-..-",
+        b'@' => ".--.-.",
+        _ => return None,
+    };
+    Some(code)
+}
+
+/// Reasons a message cannot be keyed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub enum MorseError {
+    /// The message contains no characters to send
+    Empty,
+    /// The message is longer than `MAX_MESSAGE` characters
+    TooLong,
+    /// The message contains a character with no Morse code
+    Unsupported(u8),
+}
+
+/// Keying speeds in words per minute (PARIS standard)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub struct MorseTiming {
+    /// Speed at which each character is keyed (sets the dot length)
+    pub wpm: u32,
+    /// Overall speed including the stretched gaps (Farnsworth); equal to `wpm`
+    /// for standard spacing, clamped to at most `wpm`
+    pub farnsworth_wpm: u32,
+}
+
+impl MorseTiming {
+    /// Returns the length of one dot (the Morse unit) in milliseconds
+    pub fn dot_ms(&self) -> u32 {
+        1200 / self.wpm.max(1)
+    }
+
+    /// Returns the length of one dash (three units) in milliseconds
+    pub fn dash_ms(&self) -> u32 {
+        3 * self.dot_ms()
+    }
+
+    /// Returns the gap between the dots and dashes of one character (one unit)
+    pub fn element_gap_ms(&self) -> u32 {
+        self.dot_ms()
+    }
+
+    /// Returns the gap between characters (three Farnsworth units)
+    pub fn char_gap_ms(&self) -> u32 {
+        self.spacing_ms(3)
+    }
+
+    /// Returns the gap between words (seven Farnsworth units)
+    pub fn word_gap_ms(&self) -> u32 {
+        self.spacing_ms(7)
+    }
+
+    /// Returns `units` of the stretched spacing unit, in milliseconds
+    ///
+    /// The word PARIS takes 31 units of characters and 19 units of spacing.
+    /// With character speed `c` and overall speed `s`, the 19 spacing units must
+    /// fill `60 / s - 31 * 1.2 / c` seconds (ARRL Farnsworth formula); with
+    /// `s == c` this reduces to the standard `1200 / c` ms unit.
+    fn spacing_ms(&self, units: u32) -> u32 {
+        let c = u64::from(self.wpm.max(1));
+        let s = u64::from(self.farnsworth_wpm.clamp(1, self.wpm.max(1)));
+        let total_ms = 60_000 * c - 37_200 * s;
+        (u64::from(units) * total_ms / (19 * s * c)) as u32
+    }
+}
+
+/// Checks that `text` can be keyed
+///
+/// Spaces separate words; leading, trailing and repeated spaces are ignored.
+///
+/// # Errors
+/// `Empty`, `TooLong` or `Unsupported` (with the first offending byte).
+pub fn validate(text: &str) -> Result<(), MorseError> {
+    normalize(text).map(|_| ())
+}
+
+/// Uppercases `text` and collapses its whitespace to single spaces
+fn normalize(text: &str) -> Result<Vec<u8, MAX_MESSAGE>, MorseError> {
+    let mut out = Vec::new();
+    for word in text.split_ascii_whitespace() {
+        if !out.is_empty() {
+            out.push(b' ').map_err(|_| MorseError::TooLong)?;
+        }
+        for &ch in word.as_bytes() {
+            if code(ch).is_none() {
+                return Err(MorseError::Unsupported(ch));
+            }
+            out.push(ch.to_ascii_uppercase())
+                .map_err(|_| MorseError::TooLong)?;
+        }
+    }
+    if out.is_empty() {
+        return Err(MorseError::Empty);
+    }
+    Ok(out)
+}
+
+/// One keyed period: the LED held at `on` for `ms` milliseconds
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub struct Element {
+    /// `true` for a dot or dash, `false` for a gap
+    pub on: bool,
+    /// Duration in milliseconds
+    pub ms: u32,
+    /// Character to mirror when this element starts: the character itself on
+    /// its first dot or dash, a space on the gap before a new word
+    pub echo: Option<u8>,
+}
+
+/// Result of advancing the player to a given point in time
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub enum MorseTick {
+    /// A new element started; call `poll` again at `until_ms`
+    Key {
+        on: bool,
+        until_ms: u64,
+        echo: Option<u8>,
+    },
+    /// Nothing changed; call `poll` again at `until_ms`
+    Wait { until_ms: u64 },
+    /// The message has been sent and the LED switched off
+    Finished,
+}
+
+/// Keys a message on the LED against caller-supplied time
+///
+/// The message is sent once, followed by a word gap so back-to-back messages
+/// stay separated.
+pub struct MorsePlayer {
+    text: Vec<u8, MAX_MESSAGE>,
+    timing: MorseTiming,
+    pos: usize,
+    mark: usize,
+    key_down: bool,
+    deadline: Option<u64>,
+    finished: bool,
+}
+
+impl MorsePlayer {
+    /// Creates a player that starts keying `text` on its first `poll`
+    ///
+    /// # Errors
+    /// See `validate`.
+    pub fn new(text: &str, timing: MorseTiming) -> Result<Self, MorseError> {
+        Ok(Self {
+            text: normalize(text)?,
+            timing,
+            pos: 0,
+            mark: 0,
+            key_down: true,
+            deadline: None,
+            finished: false,
+        })
+    }
+
+    /// Returns the message being sent (uppercased, single-spaced)
+    pub fn text(&self) -> &str {
+        // Only characters from the Morse table are stored, so this cannot fail
+        core::str::from_utf8(&self.text).unwrap_or("")
+    }
+
+    /// Returns the keying speeds
+    pub fn timing(&self) -> MorseTiming {
+        self.timing
+    }
+
+    /// Returns `true` once the whole message has been sent
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Advances keying to `now_ms`, driving `pin` on every element boundary
+    ///
+    /// # Arguments
+    /// * `now_ms` - Current time in milliseconds from any monotonic clock
+    /// * `pin` - LED output to key
+    ///
+    /// # Returns
+    /// The element started (if any) and the time at which `poll` should next be called.
+    pub fn poll<P: LedPin>(&mut self, now_ms: u64, pin: &mut P) -> MorseTick {
+        if self.finished {
+            return MorseTick::Finished;
+        }
+        let start_ms = match self.deadline {
+            // First poll: key the first element immediately
+            None => now_ms,
+            // Current element still running
+            Some(deadline) if now_ms < deadline => {
+                return MorseTick::Wait { until_ms: deadline };
+            }
+            // Element elapsed: measure from the deadline to avoid drift
+            Some(deadline) => deadline,
+        };
+        let Some(element) = self.next_element() else {
+            pin.set_off();
+            self.finished = true;
+            return MorseTick::Finished;
+        };
+        if element.on {
+            pin.set_on();
+        } else {
+            pin.set_off();
+        }
+        let until_ms = start_ms + u64::from(element.ms);
+        self.deadline = Some(until_ms);
+        MorseTick::Key {
+            on: element.on,
+            until_ms,
+            echo: element.echo,
+        }
+    }
+
+    /// Returns the next element of the message, or `None` once it is complete
+    ///
+    /// Alternates between a dot or dash and the gap that follows it; the gap
+    /// after the last mark of a character is a character or word gap.
+    fn next_element(&mut self) -> Option<Element> {
+        let &ch = self.text.get(self.pos)?;
+        // Validated by `normalize`, so every stored character has a code
+        let marks = code(ch).unwrap_or("").as_bytes();
+        if self.key_down {
+            self.key_down = false;
+            let ms = if marks.get(self.mark) == Some(&b'-') {
+                self.timing.dash_ms()
+            } else {
+                self.timing.dot_ms()
+            };
+            return Some(Element {
+                on: true,
+                ms,
+                echo: (self.mark == 0).then_some(ch),
+            });
+        }
+        self.key_down = true;
+        self.mark += 1;
+        if self.mark < marks.len() {
+            return Some(gap(self.timing.element_gap_ms(), None));
+        }
+        // Character complete: pick the gap from what follows it
+        self.mark = 0;
+        self.pos += 1;
+        match self.text.get(self.pos) {
+            Some(b' ') => {
+                self.pos += 1;
+                Some(gap(self.timing.word_gap_ms(), Some(b' ')))
+            }
+            Some(_) => Some(gap(self.timing.char_gap_ms(), None)),
+            None => Some(gap(self.timing.word_gap_ms(), None)),
+        }
+    }
+}
+
+/// Builds an LED-off element
+fn gap(ms: u32, echo: Option<u8>) -> Element {
+    Element {
+        on: false,
+        ms,
+        echo,
+    }
+}
+
+/// Printable ASCII (`0x20..=0x7E`) as static bytes, for `as_static`
+static PRINTABLE: [u8; 95] = printable();
+
+/// Builds `PRINTABLE`
+const fn printable() -> [u8; 95] {
+    let mut table = [0u8; 95];
+    let mut i = 0;
+    while i < table.len() {
+        table[i] = b' ' + i as u8;
+        i += 1;
+    }
+    table
+}
+
+/// Returns a printable ASCII character as a `'static` one-byte slice
+///
+/// Lets the main loop mirror keyed characters through the same
+/// `&'static [u8]` path as the other LED notifications.
+///
+/// # Returns
+/// An empty slice for bytes outside printable ASCII.
+pub fn as_static(ch: u8) -> &'static [u8] {
+    match ch {
+        0x20..=0x7e => {
+            let i = usize::from(ch - b' ');
+            &PRINTABLE[i..=i]
+        }
+        _ => &[],
+    }
+}
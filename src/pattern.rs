@@ -0,0 +1,312 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Programmable LED blink patterns and a hardware-independent pattern player
+//!
+//! This module describes blink behaviour as data rather than loop code:
+//! - `Step` - a single LED level held for a duration
+//! - `BlinkPattern` - a named sequence of steps with a repeat policy
+//! - `presets` - ready-made patterns (steady blink, heartbeat, double-flash, SOS)
+//! - `PatternPlayer` - plays a pattern back against a millisecond clock
+//!
+//! # Design Philosophy
+//! The player never sleeps or reads a timer itself. The caller passes the current
+//! time in milliseconds and receives the next deadline in return, so the same code
+//! runs against `embassy_time` on the board and against a fake clock on a host.
+
+use embedded_hal::digital::OutputPin;
+use heapless::Vec;
+
+/// Small trait to abstract an LED output.
+///
+/// We define a local trait and implement it for every `embedded-hal` output pin
+/// (`mock::MockPin` on a host) and for `brightness::PwmLed` (the TIM2 channel on
+/// the board), so the player can drive a mock pin when exercised off-target.
+pub trait LedPin {
+    fn set_on(&mut self);
+    fn set_off(&mut self);
+
+    /// Sets an intermediate brightness as a duty fraction of
+    /// `brightness::FULL_DUTY`
+    ///
+    /// Two-state outputs round to on or off.
+    fn set_level(&mut self, duty: u16) {
+        if duty > crate::brightness::FULL_DUTY / 2 {
+            self.set_on();
+        } else {
+            self.set_off();
+        }
+    }
+}
+
+impl<P: OutputPin> LedPin for P {
+    fn set_on(&mut self) {
+        // GPIO writes on the STM32 cannot fail (`Infallible`); a failing mock
+        // pin simply leaves the level unchanged
+        let _ = self.set_high();
+    }
+
+    fn set_off(&mut self) {
+        let _ = self.set_low();
+    }
+}
+
+/// A single pattern step: hold the LED at `on` for `ms` milliseconds
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub struct Step {
+    /// LED level during this step (`true` = lit)
+    pub on: bool,
+    /// Duration of this step in milliseconds
+    pub ms: u32,
+}
+
+impl Step {
+    /// Creates a step with the LED lit for `ms` milliseconds
+    pub const fn on(ms: u32) -> Self {
+        Self { on: true, ms }
+    }
+
+    /// Creates a step with the LED dark for `ms` milliseconds
+    pub const fn off(ms: u32) -> Self {
+        Self { on: false, ms }
+    }
+}
+
+/// How many times a pattern's step sequence is played
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub enum Repeat {
+    /// Loop the sequence until another pattern is selected
+    Forever,
+    /// Play the sequence the given number of times, then leave the LED off
+    Times(u16),
+}
+
+/// A named, repeatable sequence of LED steps
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub struct BlinkPattern {
+    /// Short identifier used in logs and status output
+    pub name: &'static str,
+    /// Steps played in order; an empty slice is treated as "LED off"
+    pub steps: &'static [Step],
+    /// Repeat policy applied once the last step completes
+    pub repeat: Repeat,
+}
+
+/// Ready-made blink patterns
+///
+/// Timings follow common status-LED conventions and use the default 500 ms
+/// half-period as the base unit where applicable.
+pub mod presets {
+    use super::{BlinkPattern, Repeat, Step};
+
+    /// Steady 1 Hz blink: 500 ms on, 500 ms off (original firmware behaviour)
+    pub const STEADY: BlinkPattern = BlinkPattern {
+        name: "steady",
+        steps: &[Step::on(500), Step::off(500)],
+        repeat: Repeat::Forever,
+    };
+
+    /// Heartbeat: two short beats followed by a long pause
+    pub const HEARTBEAT: BlinkPattern = BlinkPattern {
+        name: "heartbeat",
+        steps: &[Step::on(100), Step::off(100), Step::on(100), Step::off(700)],
+        repeat: Repeat::Forever,
+    };
+
+    /// Double-flash: two quick flashes every two seconds
+    pub const DOUBLE_FLASH: BlinkPattern = BlinkPattern {
+        name: "double-flash",
+        steps: &[Step::on(50), Step::off(150), Step::on(50), Step::off(1750)],
+        repeat: Repeat::Forever,
+    };
+
+    /// SOS in Morse code (... --- ...) with a 200 ms dot unit
+    pub const SOS: BlinkPattern = BlinkPattern {
+        name: "sos",
+        steps: &[
+            Step::on(200),
+            Step::off(200),
+            Step::on(200),
+            Step::off(200),
+            Step::on(200),
+            Step::off(600),
+            Step::on(600),
+            Step::off(200),
+            Step::on(600),
+            Step::off(200),
+            Step::on(600),
+            Step::off(600),
+            Step::on(200),
+            Step::off(200),
+            Step::on(200),
+            Step::off(200),
+            Step::on(200),
+            Step::off(1400),
+        ],
+        repeat: Repeat::Forever,
+    };
+
+    /// All presets, in the order they are cycled through
+    pub const ALL: &[BlinkPattern] = &[STEADY, HEARTBEAT, DOUBLE_FLASH, SOS];
+
+    /// Looks up a preset by its `name`
+    pub fn by_name(name: &str) -> Option<BlinkPattern> {
+        ALL.iter().copied().find(|p| p.name == name)
+    }
+}
+
+/// Result of advancing the player to a given point in time
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub enum Tick {
+    /// The LED changed level; call `poll` again at `until_ms`
+    Edge { on: bool, until_ms: u64 },
+    /// Nothing changed; call `poll` again at `until_ms`
+    Wait { until_ms: u64 },
+    /// The pattern has completed and the LED has been switched off
+    Finished,
+}
+
+/// Maximum number of steps a `PatternPlayer` can hold
+pub const MAX_STEPS: usize = 32;
+
+/// Plays a `BlinkPattern` back against caller-supplied time
+///
+/// The player keeps its own copy of the steps so patterns can also be built at
+/// runtime (for example from a shell command) without needing `'static` storage.
+///
+/// # Usage
+/// Call `poll` with the current time; apply the returned deadline to the
+/// timer of your choice and call `poll` again once it has elapsed.
+pub struct PatternPlayer {
+    name: &'static str,
+    steps: Vec<Step, MAX_STEPS>,
+    repeat: Repeat,
+    step: usize,
+    passes: u16,
+    deadline: Option<u64>,
+    finished: bool,
+}
+
+impl PatternPlayer {
+    /// Creates a player that starts `pattern` on its first `poll`
+    pub fn new(pattern: BlinkPattern) -> Self {
+        Self::from_steps(pattern.name, pattern.steps, pattern.repeat)
+    }
+
+    /// Creates a player from an arbitrary step sequence
+    ///
+    /// Steps beyond `MAX_STEPS` are dropped.
+    pub fn from_steps(name: &'static str, steps: &[Step], repeat: Repeat) -> Self {
+        let len = steps.len().min(MAX_STEPS);
+        Self {
+            name,
+            steps: Vec::from_slice(&steps[..len]).unwrap_or_default(),
+            repeat,
+            step: 0,
+            passes: 0,
+            deadline: None,
+            finished: false,
+        }
+    }
+
+    /// Creates a player for a symmetric square-wave blink
+    ///
+    /// # Arguments
+    /// * `half_period_ms` - Time the LED spends in each of the on and off states
+    pub fn square(half_period_ms: u32) -> Self {
+        Self::from_steps(
+            "blink",
+            &[Step::on(half_period_ms), Step::off(half_period_ms)],
+            Repeat::Forever,
+        )
+    }
+
+    /// Returns the name of the pattern currently loaded in the player
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Replaces the current pattern; playback restarts on the next `poll`
+    pub fn set_pattern(&mut self, pattern: BlinkPattern) {
+        *self = Self::new(pattern);
+    }
+
+    /// Returns `true` once a finite pattern has played all of its passes
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Advances playback to `now_ms`, driving `pin` on every step boundary
+    ///
+    /// # Arguments
+    /// * `now_ms` - Current time in milliseconds from any monotonic clock
+    /// * `pin` - LED output to drive
+    ///
+    /// # Returns
+    /// The edge taken (if any) and the time at which `poll` should next be called.
+    pub fn poll<P: LedPin>(&mut self, now_ms: u64, pin: &mut P) -> Tick {
+        if self.finished {
+            return Tick::Finished;
+        }
+        match self.deadline {
+            // First poll after (re)start: enter step 0 immediately
+            None => self.enter(now_ms, pin),
+            // Current step still running
+            Some(deadline) if now_ms < deadline => Tick::Wait { until_ms: deadline },
+            // Step elapsed: advance, measuring from the deadline to avoid drift
+            Some(deadline) => {
+                if self.advance() {
+                    self.enter(deadline, pin)
+                } else {
+                    self.finish(pin)
+                }
+            }
+        }
+    }
+
+    /// Moves to the next step, returning `false` once all passes are spent
+    fn advance(&mut self) -> bool {
+        self.step += 1;
+        if self.step < self.steps.len() {
+            return true;
+        }
+        self.step = 0;
+        self.passes = self.passes.saturating_add(1);
+        match self.repeat {
+            Repeat::Forever => true,
+            Repeat::Times(n) => self.passes < n,
+        }
+    }
+
+    /// Applies the current step to `pin` starting at `start_ms`
+    fn enter<P: LedPin>(&mut self, start_ms: u64, pin: &mut P) -> Tick {
+        let Some(step) = self.steps.get(self.step).copied() else {
+            return self.finish(pin);
+        };
+        if matches!(self.repeat, Repeat::Times(0)) {
+            return self.finish(pin);
+        }
+        if step.on {
+            pin.set_on();
+        } else {
+            pin.set_off();
+        }
+        let until_ms = start_ms + u64::from(step.ms);
+        self.deadline = Some(until_ms);
+        Tick::Edge {
+            on: step.on,
+            until_ms,
+        }
+    }
+
+    /// Switches the LED off and marks playback complete
+    fn finish<P: LedPin>(&mut self, pin: &mut P) -> Tick {
+        pin.set_off();
+        self.finished = true;
+        Tick::Finished
+    }
+}
@@ -0,0 +1,209 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! STOP mode policy and power accounting
+//!
+//! This module decides when the firmware may drop into STOP and reports what
+//! it saved:
+//! - `Activity` - what the other tasks are doing (UART, button, LED, shell input)
+//! - `StopPlanner` - how long to stop, given the next LED edge, the watchdog and `Activity`
+//! - `DutyCycle` - time spent running and stopped over a report window
+//! - `CurrentModel` - datasheet currents that turn a duty cycle into an average
+//! - `PowerReport` - one window's figures, formatted for the UART
+//!
+//! # Design Philosophy
+//! STOP halts every clock except the RTC's, including the timer behind
+//! `embassy_time`, so the firmware keeps two clocks: awake time (embassy) and
+//! wall time (awake time plus everything slept). Everything here works in wall
+//! milliseconds and is plain arithmetic, so the policy is tested on the host;
+//! entering STOP and restoring the clock tree is up to the board code (built
+//! with the `low-power` feature).
+
+use core::fmt;
+
+/// What the other tasks are doing, as far as STOP is concerned
+///
+/// Published by the firmware tasks as they run; STOP freezes every peripheral
+/// clock, so any of these keeps the core awake.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Activity {
+    /// A UART transfer is in progress or queued
+    pub uart_busy: bool,
+    /// The button is pressed or a gesture is still being timed
+    pub button_busy: bool,
+    /// The LED is at a partial PWM level (the timer stops in STOP)
+    pub led_dimmed: bool,
+    /// Wall time of the last received shell byte, if any
+    pub last_input_ms: Option<u64>,
+}
+
+impl Activity {
+    /// Creates an idle state: nothing busy, no input yet
+    pub const fn new() -> Self {
+        Self {
+            uart_busy: false,
+            button_busy: false,
+            led_dimmed: false,
+            last_input_ms: None,
+        }
+    }
+}
+
+/// When STOP is worth entering, and for how long
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StopPlanner {
+    /// Shortest stop worth the wake-up cost (PLL relock, clock switch)
+    pub min_ms: u32,
+    /// Longest the watchdog may go unfed before a stop must have ended
+    pub watchdog_ms: u32,
+    /// Wake this long before the next edge, to restore clocks in time
+    pub wake_margin_ms: u32,
+    /// Stay awake this long after shell input (the UART cannot wake from STOP)
+    pub input_grace_ms: u32,
+}
+
+impl StopPlanner {
+    /// Decides how long to stop
+    ///
+    /// # Arguments
+    /// * `now_ms` - Wall time
+    /// * `next_edge_ms` - Wall time of the next LED change, `None` if the LED is held
+    /// * `fed_ms` - Wall time the watchdog was last fed
+    /// * `activity` - What the other tasks are doing
+    ///
+    /// # Returns
+    /// The stop duration in milliseconds, or `None` to stay awake.
+    pub fn plan(
+        &self,
+        now_ms: u64,
+        next_edge_ms: Option<u64>,
+        fed_ms: u64,
+        activity: &Activity,
+    ) -> Option<u32> {
+        if activity.uart_busy || activity.button_busy || activity.led_dimmed {
+            return None;
+        }
+        // Someone is typing: keep the UART listening
+        if activity
+            .last_input_ms
+            .is_some_and(|t| now_ms.saturating_sub(t) < u64::from(self.input_grace_ms))
+        {
+            return None;
+        }
+        // Wake before the LED edge and before the watchdog runs out
+        let watchdog = (fed_ms + u64::from(self.watchdog_ms)).saturating_sub(now_ms);
+        let available = match next_edge_ms {
+            Some(edge) => edge
+                .saturating_sub(now_ms)
+                .saturating_sub(u64::from(self.wake_margin_ms))
+                .min(watchdog),
+            None => watchdog,
+        };
+        if available < u64::from(self.min_ms) {
+            return None;
+        }
+        Some(available as u32)
+    }
+}
+
+/// Typical supply currents of a chip, from its datasheet
+///
+/// Figures are for the MCU alone (no LED, no ST-Link), at 25 °C.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CurrentModel {
+    /// Run mode at the configured SYSCLK, from flash, in µA
+    pub run_ua: u32,
+    /// STOP mode with the RTC running on the LSE, in µA
+    pub stop_ua: u32,
+}
+
+/// Running and stopped time over the current report window
+pub struct DutyCycle {
+    start_ms: u64,
+    stopped_ms: u64,
+}
+
+impl DutyCycle {
+    /// Starts a window
+    ///
+    /// # Arguments
+    /// * `now_ms` - Wall time the window starts at
+    pub const fn new(now_ms: u64) -> Self {
+        Self {
+            start_ms: now_ms,
+            stopped_ms: 0,
+        }
+    }
+
+    /// Adds time spent in STOP
+    pub fn record_stop(&mut self, ms: u64) {
+        self.stopped_ms += ms;
+    }
+
+    /// Returns the wall time the window started at
+    pub fn start_ms(&self) -> u64 {
+        self.start_ms
+    }
+
+    /// Ends the window and starts the next one
+    ///
+    /// # Arguments
+    /// * `now_ms` - Wall time the window ends at
+    /// * `model` - Currents of the chip, for the average
+    pub fn take(&mut self, now_ms: u64, model: &CurrentModel) -> PowerReport {
+        let window_ms = now_ms.saturating_sub(self.start_ms);
+        let stopped_ms = self.stopped_ms.min(window_ms);
+        let average_ua = match window_ms {
+            0 => model.run_ua,
+            _ => {
+                let charge = u64::from(model.run_ua) * (window_ms - stopped_ms)
+                    + u64::from(model.stop_ua) * stopped_ms;
+                (charge / window_ms) as u32
+            }
+        };
+        *self = Self::new(now_ms);
+        PowerReport {
+            window_ms,
+            stopped_ms,
+            average_ua,
+        }
+    }
+}
+
+/// Figures for one report window
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PowerReport {
+    /// Length of the window in ms
+    pub window_ms: u64,
+    /// Time spent in STOP in ms
+    pub stopped_ms: u64,
+    /// Estimated average supply current in µA
+    pub average_ua: u32,
+}
+
+impl PowerReport {
+    /// Returns the share of the window spent in STOP, in tenths of a percent
+    pub fn stop_permille(&self) -> u32 {
+        match self.window_ms {
+            0 => 0,
+            window => (self.stopped_ms * 1000 / window) as u32,
+        }
+    }
+}
+
+/// `power: stop 94.8% of 10.0 s, ~1.71 mA average`
+impl fmt::Display for PowerReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let stop = self.stop_permille();
+        write!(
+            f,
+            "power: stop {}.{}% of {}.{} s, ~{}.{:02} mA average",
+            stop / 10,
+            stop % 10,
+            self.window_ms / 1000,
+            self.window_ms % 1000 / 100,
+            self.average_ua / 1000,
+            self.average_ua % 1000 / 10
+        )
+    }
+}
@@ -0,0 +1,266 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Time-of-day rules that select the blink pattern
+//!
+//! This module decides which preset should play at a given time of day:
+//! - `Rule` - "between `start` and `end` play preset X", wrapping past midnight
+//! - `Schedule` - the `MAX_RULES` rule slots kept in persisted settings
+//! - `Scheduler` - turns the active rule into switches as windows open and close
+//!
+//! # Design Philosophy
+//! Rules are evaluated against the minute of the day read from the RTC, which
+//! keeps UTC (there is no time zone or daylight saving setting), and the
+//! scheduler only acts when the active rule changes. A command or gesture given
+//! inside a window therefore stays in effect until the window closes, and when
+//! it does the LED goes back to the pattern stored in the settings. The first
+//! slot whose window contains the time wins, so the slots double as priorities.
+//! Nothing here reads the clock, so windows, wrap-around and switching are all
+//! tested on the host with plain minute numbers.
+
+use core::fmt;
+
+use crate::pattern::presets;
+
+/// Number of rule slots in `Schedule` (and in the settings record)
+///
+/// Bounded by the settings payload: 4 bytes per slot after the 13 bytes of
+/// other fields. Schema 2 records held two slots; the rest load empty.
+pub const MAX_RULES: usize = 8;
+
+/// Minutes in one day
+pub const DAY_MINUTES: u16 = 1440;
+
+/// Encoding of an empty slot in the settings payload
+const EMPTY: u32 = u32::MAX;
+
+/// Plays a preset while the time of day is in `[start, end)`
+///
+/// A window whose end is earlier than its start wraps past midnight
+/// (`22:00-06:00` covers the night).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub struct Rule {
+    start: u16,
+    end: u16,
+    preset: u8,
+}
+
+impl Rule {
+    /// Creates a rule after checking its fields
+    ///
+    /// # Arguments
+    /// * `start` - First minute of the window (0 to 1439)
+    /// * `end` - Minute the window closes (0 to 1439, not equal to `start`)
+    /// * `preset` - Index into `presets::ALL`
+    ///
+    /// # Returns
+    /// `None` if a minute is out of range, the window is empty or the preset
+    /// does not exist.
+    pub fn new(start: u16, end: u16, preset: usize) -> Option<Self> {
+        if start >= DAY_MINUTES || end >= DAY_MINUTES || start == end {
+            return None;
+        }
+        if preset >= presets::ALL.len() {
+            return None;
+        }
+        Some(Self {
+            start,
+            end,
+            preset: preset as u8,
+        })
+    }
+
+    /// Returns `true` if `minute` (of the day) falls inside the window
+    pub fn contains(&self, minute: u16) -> bool {
+        if self.start < self.end {
+            (self.start..self.end).contains(&minute)
+        } else {
+            minute >= self.start || minute < self.end
+        }
+    }
+
+    /// Returns the first minute of the window
+    pub fn start(&self) -> u16 {
+        self.start
+    }
+
+    /// Returns the minute the window closes
+    pub fn end(&self) -> u16 {
+        self.end
+    }
+
+    /// Returns the index of the preset in `presets::ALL`
+    pub fn preset(&self) -> usize {
+        usize::from(self.preset)
+    }
+
+    /// Packs the rule into 32 bits: start, end (11 bits each), preset (8 bits)
+    fn encode(&self) -> u32 {
+        u32::from(self.start) | u32::from(self.end) << 11 | u32::from(self.preset) << 22
+    }
+
+    /// Unpacks a rule written by `encode`
+    ///
+    /// # Returns
+    /// `None` for an empty slot or a value that is not a valid rule.
+    fn decode(raw: u32) -> Option<Self> {
+        let start = (raw & 0x7FF) as u16;
+        let end = (raw >> 11 & 0x7FF) as u16;
+        let preset = (raw >> 22 & 0xFF) as usize;
+        match raw {
+            EMPTY => None,
+            _ => Self::new(start, end, preset),
+        }
+    }
+}
+
+/// `22:00-06:00 heartbeat`, the form `schedule` lists
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:02}:{:02}-{:02}:{:02} {}",
+            self.start / 60,
+            self.start % 60,
+            self.end / 60,
+            self.end % 60,
+            presets::ALL[self.preset()].name
+        )
+    }
+}
+
+/// Parses a time of day written as `HH:MM` (24-hour clock)
+///
+/// # Returns
+/// The minute of the day, or `None` if the text is not a valid time.
+pub fn parse_clock(text: &str) -> Option<u16> {
+    let (hours, minutes) = text.split_once(':')?;
+    if hours.is_empty() || hours.len() > 2 || minutes.len() != 2 {
+        return None;
+    }
+    if !hours
+        .bytes()
+        .chain(minutes.bytes())
+        .all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+    let (hours, minutes): (u16, u16) = (hours.parse().ok()?, minutes.parse().ok()?);
+    (hours < 24 && minutes < 60).then_some(hours * 60 + minutes)
+}
+
+/// Parses a window written as `HH:MM-HH:MM`
+///
+/// # Returns
+/// The start and end minutes, or `None` if either time is invalid.
+pub fn parse_window(text: &str) -> Option<(u16, u16)> {
+    let (start, end) = text.split_once('-')?;
+    Some((parse_clock(start)?, parse_clock(end)?))
+}
+
+/// The rule slots, in priority order
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub struct Schedule {
+    rules: [Option<Rule>; MAX_RULES],
+}
+
+impl Schedule {
+    /// Bytes `encode` produces
+    pub const ENCODED_LEN: usize = MAX_RULES * 4;
+
+    /// Returns the rule in `slot`, if any (`None` for an unknown slot too)
+    pub fn rule(&self, slot: usize) -> Option<Rule> {
+        self.rules.get(slot).copied().flatten()
+    }
+
+    /// Stores or clears the rule in `slot`; out-of-range slots are ignored
+    pub fn set(&mut self, slot: usize, rule: Option<Rule>) {
+        if let Some(entry) = self.rules.get_mut(slot) {
+            *entry = rule;
+        }
+    }
+
+    /// Returns the first rule whose window contains `minute`, with its slot
+    pub fn active(&self, minute: u16) -> Option<(usize, Rule)> {
+        self.rules
+            .iter()
+            .enumerate()
+            .find_map(|(slot, rule)| rule.filter(|r| r.contains(minute)).map(|r| (slot, r)))
+    }
+
+    /// Encodes every slot as a little-endian `u32` (all ones when empty)
+    pub fn encode(&self) -> [u8; Self::ENCODED_LEN] {
+        let mut out = [0u8; Self::ENCODED_LEN];
+        for (chunk, rule) in out.chunks_exact_mut(4).zip(&self.rules) {
+            let raw = rule.map_or(EMPTY, |r| r.encode());
+            chunk.copy_from_slice(&raw.to_le_bytes());
+        }
+        out
+    }
+
+    /// Decodes the slots present in `bytes`
+    ///
+    /// Missing slots and values that are not valid rules come back empty.
+    pub fn decode(bytes: &[u8]) -> Self {
+        let mut schedule = Self::default();
+        for (entry, chunk) in schedule.rules.iter_mut().zip(bytes.chunks_exact(4)) {
+            *entry = Rule::decode(u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]));
+        }
+        schedule
+    }
+}
+
+/// What the LED should do after a scheduler update
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub enum Switch {
+    /// A window opened (or its rule changed): play `preset`
+    Enter {
+        /// Slot of the rule that opened
+        slot: usize,
+        /// Index into `presets::ALL`
+        preset: usize,
+    },
+    /// The last window closed: go back to the stored pattern
+    Leave,
+}
+
+/// Follows the active rule and reports when the LED has to switch
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Scheduler {
+    /// Active rule at the last update; `None` before the first one
+    current: Option<Option<Rule>>,
+}
+
+impl Scheduler {
+    /// Creates a scheduler that has not evaluated any rule yet
+    pub fn new() -> Self {
+        Self { current: None }
+    }
+
+    /// Evaluates `schedule` at `minute` of the day
+    ///
+    /// The first update only switches if a window is open; the stored pattern
+    /// is already playing otherwise.
+    ///
+    /// # Returns
+    /// `Some` if the active rule differs from the previous update's.
+    pub fn update(&mut self, schedule: &Schedule, minute: u16) -> Option<Switch> {
+        let active = schedule.active(minute);
+        let rule = active.map(|(_, rule)| rule);
+        let previous = self.current.replace(rule);
+        if previous == Some(rule) {
+            return None;
+        }
+        match active {
+            Some((slot, rule)) => Some(Switch::Enter {
+                slot,
+                preset: rule.preset(),
+            }),
+            None if previous.flatten().is_some() => Some(Switch::Leave),
+            None => None,
+        }
+    }
+}
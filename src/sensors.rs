@@ -0,0 +1,149 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Internal temperature sensor and VREFINT conversion
+//!
+//! The ADC samples two internal channels, which only mean something together
+//! with the factory calibration ST writes to system memory:
+//! - `Calibration` - TS_CAL1, TS_CAL2 and VREFINT_CAL with their conditions
+//! - `RawSample` - one conversion of each channel
+//! - `Reading` - the supply voltage (VDDA) and die temperature they imply
+//!
+//! # Design Philosophy
+//! The ADC measures against VDDA, which is whatever the regulator delivers.
+//! VREFINT is a fixed reference whose reading at a known VDDA is stored as
+//! VREFINT_CAL, so the ratio of the two gives the actual VDDA; the temperature
+//! reading is rescaled to the calibration VDDA before it is placed on the line
+//! through TS_CAL1 and TS_CAL2. Everything is integer arithmetic on the values
+//! the board reads, so it is tested on the host with datasheet-like numbers.
+
+use core::fmt;
+
+/// Factory calibration of one chip (12-bit ADC readings)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub struct Calibration {
+    /// Temperature sensor reading at `ts_cal1_c`
+    pub ts_cal1: u16,
+    /// Temperature sensor reading at `ts_cal2_c`
+    pub ts_cal2: u16,
+    /// Temperature of the first calibration point in °C (30 on every supported chip)
+    pub ts_cal1_c: i32,
+    /// Temperature of the second calibration point in °C (110, or 130 on the G4)
+    pub ts_cal2_c: i32,
+    /// VREFINT reading at `cal_vdda_mv`
+    pub vrefint_cal: u16,
+    /// VDDA the calibration values were taken at, in mV (3300 or 3000)
+    pub cal_vdda_mv: u32,
+}
+
+/// Reasons the calibration values cannot be used
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub enum CalibrationError {
+    /// A value is erased (`0xFFFF`) or zero
+    Missing,
+    /// Both temperature points have the same reading
+    Flat,
+}
+
+impl Calibration {
+    /// Checks that the values can be used for conversions
+    ///
+    /// # Errors
+    /// `Missing` or `Flat`.
+    pub fn check(&self) -> Result<(), CalibrationError> {
+        let unset = |value: u16| value == 0 || value == u16::MAX;
+        if unset(self.ts_cal1) || unset(self.ts_cal2) || unset(self.vrefint_cal) {
+            return Err(CalibrationError::Missing);
+        }
+        if self.ts_cal1 == self.ts_cal2 {
+            return Err(CalibrationError::Flat);
+        }
+        Ok(())
+    }
+
+    /// Returns the supply voltage implied by a VREFINT reading
+    ///
+    /// # Returns
+    /// VDDA in mV, or `None` for a zero reading.
+    pub fn vdda_mv(&self, vrefint: u16) -> Option<u32> {
+        match vrefint {
+            0 => None,
+            raw => Some(self.cal_vdda_mv * u32::from(self.vrefint_cal) / u32::from(raw)),
+        }
+    }
+
+    /// Returns the die temperature implied by a sensor reading
+    ///
+    /// # Arguments
+    /// * `sensor` - Temperature sensor reading
+    /// * `vdda_mv` - VDDA during the conversion (from `vdda_mv`)
+    ///
+    /// # Returns
+    /// The temperature in hundredths of a degree Celsius. The calibration must
+    /// have passed `check`.
+    pub fn temperature_centi_c(&self, sensor: u16, vdda_mv: u32) -> i32 {
+        // Rescale the reading to the calibration VDDA, then interpolate; both
+        // sides are kept multiplied by `cal_vdda_mv` to stay in integers
+        let cal_mv = i64::from(self.cal_vdda_mv);
+        let scaled = i64::from(sensor) * i64::from(vdda_mv) - i64::from(self.ts_cal1) * cal_mv;
+        let span_c = i64::from(self.ts_cal2_c - self.ts_cal1_c) * 100;
+        let span_raw = (i64::from(self.ts_cal2) - i64::from(self.ts_cal1)) * cal_mv;
+        (i64::from(self.ts_cal1_c) * 100 + scaled * span_c / span_raw) as i32
+    }
+
+    /// Converts one sample of both channels
+    ///
+    /// # Returns
+    /// VDDA and temperature, or `None` if VREFINT read as zero.
+    pub fn convert(&self, sample: RawSample) -> Option<Reading> {
+        let vdda_mv = self.vdda_mv(sample.vrefint)?;
+        Some(Reading {
+            vdda_mv,
+            temperature_centi_c: self.temperature_centi_c(sample.temperature, vdda_mv),
+        })
+    }
+}
+
+/// One ADC conversion of each internal channel
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub struct RawSample {
+    /// VREFINT channel
+    pub vrefint: u16,
+    /// Temperature sensor channel
+    pub temperature: u16,
+}
+
+/// Supply voltage and die temperature
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub struct Reading {
+    /// VDDA in mV
+    pub vdda_mv: u32,
+    /// Die temperature in hundredths of a degree Celsius
+    pub temperature_centi_c: i32,
+}
+
+/// `temp 31.4 C, vdda 3.29 V`, the form used by `status` and sensor reports
+///
+/// The temperature is rounded to tenths of a degree and VDDA to 10 mV.
+impl fmt::Display for Reading {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let tenths = match self.temperature_centi_c {
+            t if t < 0 => (t - 5) / 10,
+            t => (t + 5) / 10,
+        };
+        let volts = (self.vdda_mv + 5) / 10;
+        write!(
+            f,
+            "temp {}{}.{} C, vdda {}.{:02} V",
+            if tenths < 0 { "-" } else { "" },
+            tenths.unsigned_abs() / 10,
+            tenths.unsigned_abs() % 10,
+            volts / 100,
+            volts % 100
+        )
+    }
+}
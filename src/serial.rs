@@ -0,0 +1,71 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! UART transmitter and receiver abstractions
+//!
+//! This module defines the serial interface the application logic is written against:
+//! - `UartError` - why a UART write failed
+//! - `WriteBlocking` - blocking transmitter
+//! - `WriteAsync` - async (DMA-driven) transmitter
+//! - `ReadAsync` - async receiver backed by a DMA ring buffer
+//!
+//! # Design Philosophy
+//! The traits are implemented for the embassy `UartTx`/`UartRx` types in `hal` and
+//! for the recording mocks in `mock`, so the same shell, formatting and reporting
+//! code runs on the board and in host tests.
+
+/// Errors reported by the UART transmitter and receiver traits
+///
+/// Mirrors the embassy `usart::Error` variants so callers can tell line faults
+/// apart, and adds `Timeout` for async writes that fail to complete in time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub enum UartError {
+    /// Framing error (stop bit not detected)
+    Framing,
+    /// Noise detected on the line
+    Noise,
+    /// Receive data register overrun
+    Overrun,
+    /// Parity check failed
+    Parity,
+    /// Buffer too large for a single DMA transfer
+    BufferFull,
+    /// Write did not complete within `hal::UART_WRITE_TIMEOUT_MS`
+    Timeout,
+}
+
+/// Small trait to abstract a blocking-write-capable UART transmitter.
+///
+/// We define a local trait and implement it for the concrete `UartTx` type
+/// so the public API avoids exposing newer/unstable generic parameters.
+pub trait WriteBlocking {
+    fn blocking_write(&mut self, bytes: &[u8]) -> Result<(), UartError>;
+}
+
+/// Small trait to abstract an async, DMA-capable UART transmitter.
+///
+/// The async counterpart of `WriteBlocking`: awaiting a write yields to the
+/// executor while DMA moves the bytes, so other work (LED timing) keeps running.
+#[allow(async_fn_in_trait)]
+pub trait WriteAsync {
+    async fn write(&mut self, bytes: &[u8]) -> Result<(), UartError>;
+}
+
+/// Small trait to abstract an async UART receiver that never misses a byte.
+///
+/// The receiving counterpart of `WriteAsync`: bytes are collected in the
+/// background (by DMA into a ring buffer on the board) while the reader is
+/// busy, and awaiting a read yields to the executor until more arrive.
+#[allow(async_fn_in_trait)]
+pub trait ReadAsync {
+    /// Waits for received bytes and moves as many as fit into `buf`
+    ///
+    /// # Returns
+    /// The number of bytes copied into `buf`.
+    ///
+    /// # Errors
+    /// The line fault (overrun, framing, noise, parity) that interrupted
+    /// reception; the next call starts receiving again.
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, UartError>;
+}
@@ -0,0 +1,408 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Persistent settings with EEPROM emulation in internal flash
+//!
+//! This module keeps a small `Settings` record alive across resets:
+//! - `Settings` - the persisted values and their binary encoding
+//! - `SettingsFlash` - minimal flash access needed by the store
+//! - `SettingsStore` - wear-levelled, CRC-protected record log over two flash pages
+//!
+//! # Storage Format
+//! Two erase pages are used as an append-only log of fixed-size records:
+//!
+//! | Offset | Size | Field                                   |
+//! |--------|------|-----------------------------------------|
+//! | 0      | 2    | Magic `0x5345` (little-endian)          |
+//! | 2      | 1    | Schema version                          |
+//! | 3      | 1    | Payload length in bytes                 |
+//! | 4      | 4    | Sequence number (little-endian)         |
+//! | 8      | 54   | Payload (zero padded)                   |
+//! | 62     | 2    | CRC-16/CCITT-FALSE of bytes 0..62       |
+//!
+//! The record with the highest sequence number and a valid CRC wins. Saving
+//! appends to the active page; when it is full the other page is erased and the
+//! record is written there, so each page is erased once per
+//! `page_size / RECORD_SIZE` saves and a power loss at any point leaves the
+//! previous record intact.
+//!
+//! # Schema Migration
+//! Fields are only ever appended to the payload. A record from an older schema
+//! (shorter payload) decodes with defaults for the missing fields; a record from a
+//! newer schema (longer payload) decodes the known prefix. Either way the next
+//! save rewrites it at `SCHEMA_VERSION`.
+//!
+//! Schemas 1 and 2 used 32-byte records (a 22-byte payload, room for two
+//! schedule rules). `load` only falls back to that layout when no 64-byte record
+//! is found, and the next save then starts the other page, so the old log is
+//! never appended to and stays intact until the new one fills a page.
+
+use core::ops::RangeInclusive;
+
+use crate::crc::crc16;
+use crate::schedule::Schedule;
+
+/// Current settings schema version
+pub const SCHEMA_VERSION: u8 = 3;
+
+/// Size in bytes of one stored record (a multiple of every flash write size)
+pub const RECORD_SIZE: usize = 64;
+
+/// First schema version stored in `RECORD_SIZE` records
+const FIRST_LONG_SCHEMA: u8 = 3;
+
+/// Size in bytes of the records written by schemas 1 and 2
+pub const LEGACY_RECORD_SIZE: usize = 32;
+
+/// Record magic marking a programmed slot
+const MAGIC: u16 = 0x5345;
+
+/// Offset of the CRC within a record
+const CRC_OFFSET: usize = RECORD_SIZE - 2;
+
+/// Offset of the payload within a record
+const PAYLOAD_OFFSET: usize = 8;
+
+/// Maximum payload length that fits in a record
+const MAX_PAYLOAD: usize = CRC_OFFSET - PAYLOAD_OFFSET;
+
+/// `Settings::pattern` value selecting the custom square-wave blink
+pub const PATTERN_CUSTOM: u8 = 0xFF;
+
+/// Values that survive a reset
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub struct Settings {
+    /// Half-period in milliseconds used when `pattern` is `PATTERN_CUSTOM`
+    pub blink_ms: u32,
+    /// USART2 baud rate applied at boot
+    pub baud_rate: u32,
+    /// Index into `pattern::presets::ALL`, or `PATTERN_CUSTOM`
+    pub pattern: u8,
+    /// Number of boots since the settings were first written
+    pub boot_count: u32,
+    /// Time-of-day pattern rules (schema 2; `MAX_RULES` slots from schema 3)
+    pub schedule: Schedule,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            blink_ms: 500,
+            baud_rate: 115_200,
+            pattern: 0,
+            boot_count: 0,
+            schedule: Schedule::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// Payload length in bytes for `SCHEMA_VERSION`
+    pub const PAYLOAD_LEN: usize = {
+        let len = 13 + Schedule::ENCODED_LEN;
+        assert!(
+            len <= MAX_PAYLOAD,
+            "settings payload does not fit in a record"
+        );
+        len
+    };
+
+    /// Encodes the settings as a `SCHEMA_VERSION` payload
+    pub fn encode(&self) -> [u8; Self::PAYLOAD_LEN] {
+        let mut out = [0u8; Self::PAYLOAD_LEN];
+        out[0..4].copy_from_slice(&self.blink_ms.to_le_bytes());
+        out[4..8].copy_from_slice(&self.baud_rate.to_le_bytes());
+        out[8] = self.pattern;
+        out[9..13].copy_from_slice(&self.boot_count.to_le_bytes());
+        out[13..].copy_from_slice(&self.schedule.encode());
+        out
+    }
+
+    /// Decodes a payload written by any schema version
+    ///
+    /// Fields absent from `payload` keep their default values; bytes beyond the
+    /// fields known to this firmware are ignored.
+    pub fn decode(payload: &[u8]) -> Self {
+        let mut s = Self::default();
+        if let Some(v) = read_u32(payload, 0) {
+            s.blink_ms = v;
+        }
+        if let Some(v) = read_u32(payload, 4) {
+            s.baud_rate = v;
+        }
+        if let Some(&v) = payload.get(8) {
+            s.pattern = v;
+        }
+        if let Some(v) = read_u32(payload, 9) {
+            s.boot_count = v;
+        }
+        if let Some(rules) = payload.get(13..) {
+            s.schedule = Schedule::decode(rules);
+        }
+        s
+    }
+}
+
+/// Reads a little-endian `u32` at `at`, if the slice is long enough
+fn read_u32(bytes: &[u8], at: usize) -> Option<u32> {
+    let raw = bytes.get(at..at + 4)?;
+    Some(u32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]))
+}
+
+/// Flash access failed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub struct FlashError;
+
+/// Small trait to abstract the flash operations used by the store.
+///
+/// We define a local trait and implement it for the concrete HAL flash driver so
+/// the store can run against an in-memory flash model off-target. Offsets are
+/// absolute byte offsets from the start of flash; erased flash reads as `0xFF`.
+pub trait SettingsFlash {
+    fn read(&mut self, offset: u32, buf: &mut [u8]) -> Result<(), FlashError>;
+    fn write(&mut self, offset: u32, data: &[u8]) -> Result<(), FlashError>;
+    fn erase(&mut self, offset: u32, len: u32) -> Result<(), FlashError>;
+}
+
+/// Lets a store borrow its flash, so the same flash can be reopened (e.g. to
+/// simulate a reset in tests)
+impl<F: SettingsFlash + ?Sized> SettingsFlash for &mut F {
+    fn read(&mut self, offset: u32, buf: &mut [u8]) -> Result<(), FlashError> {
+        (**self).read(offset, buf)
+    }
+
+    fn write(&mut self, offset: u32, data: &[u8]) -> Result<(), FlashError> {
+        (**self).write(offset, data)
+    }
+
+    fn erase(&mut self, offset: u32, len: u32) -> Result<(), FlashError> {
+        (**self).erase(offset, len)
+    }
+}
+
+/// Reasons a save did not complete
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub enum StoreError {
+    /// The flash driver reported an error
+    Flash,
+    /// The record read back did not match what was written
+    Verify,
+}
+
+impl From<FlashError> for StoreError {
+    fn from(_: FlashError) -> Self {
+        Self::Flash
+    }
+}
+
+/// Location of the two settings pages in flash
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub struct Geometry {
+    /// Offset of the first settings page from the start of flash
+    pub base: u32,
+    /// Erase page size in bytes (both pages are contiguous)
+    pub page_size: u32,
+}
+
+impl Geometry {
+    /// Number of `size`-byte record slots per page
+    fn slots(&self, size: usize) -> u32 {
+        self.page_size / size as u32
+    }
+
+    /// Offset of `size`-byte record `slot` in `page`
+    fn slot_offset(&self, page: u32, slot: u32, size: usize) -> u32 {
+        self.base + page * self.page_size + slot * size as u32
+    }
+}
+
+/// Write position of the record log
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Cursor {
+    /// Page holding the newest record
+    page: u32,
+    /// First unprogrammed slot in `page` (`slots()` when full)
+    next_slot: u32,
+    /// Sequence number of the newest record
+    seq: u32,
+}
+
+/// Wear-levelled settings store over two flash pages
+pub struct SettingsStore<F: SettingsFlash> {
+    flash: F,
+    geometry: Geometry,
+    cursor: Option<Cursor>,
+    current: Settings,
+}
+
+impl<F: SettingsFlash> SettingsStore<F> {
+    /// Creates a store; call `load` before `save`
+    pub fn new(flash: F, geometry: Geometry) -> Self {
+        Self {
+            flash,
+            geometry,
+            cursor: None,
+            current: Settings::default(),
+        }
+    }
+
+    /// Scans both pages and returns the newest valid settings
+    ///
+    /// Falls back to `Settings::default()` if no valid record exists.
+    pub fn load(&mut self) -> Settings {
+        let newest = match self.scan::<RECORD_SIZE>(FIRST_LONG_SCHEMA..=u8::MAX) {
+            Some(found) => Some(found),
+            // Only an older log: continue it on the other page, in the new layout
+            None => self
+                .scan::<LEGACY_RECORD_SIZE>(1..=FIRST_LONG_SCHEMA - 1)
+                .map(|(c, s)| {
+                    let next_slot = self.geometry.slots(RECORD_SIZE);
+                    (Cursor { next_slot, ..c }, s)
+                }),
+        };
+        self.cursor = newest.map(|(c, _)| c);
+        self.current = newest.map(|(_, s)| s).unwrap_or_default();
+        self.current
+    }
+
+    /// Finds the newest valid `N`-byte record with a schema in `schemas`
+    fn scan<const N: usize>(&mut self, schemas: RangeInclusive<u8>) -> Option<(Cursor, Settings)> {
+        let mut newest: Option<(Cursor, Settings)> = None;
+        for page in 0..2 {
+            let mut next_slot = self.geometry.slots(N);
+            for slot in 0..self.geometry.slots(N) {
+                let mut rec = [0u8; N];
+                if self
+                    .flash
+                    .read(self.geometry.slot_offset(page, slot, N), &mut rec)
+                    .is_err()
+                {
+                    continue;
+                }
+                if rec.iter().all(|&b| b == 0xFF) {
+                    next_slot = slot;
+                    break;
+                }
+                if let Some((seq, settings)) = parse_record(&rec, &schemas) {
+                    if newest.is_none_or(|(c, _)| seq > c.seq) {
+                        newest = Some((
+                            Cursor {
+                                page,
+                                next_slot: 0,
+                                seq,
+                            },
+                            settings,
+                        ));
+                    }
+                }
+            }
+            if let Some((c, _)) = newest.as_mut() {
+                if c.page == page {
+                    c.next_slot = next_slot;
+                }
+            }
+        }
+        newest
+    }
+
+    /// Returns the settings most recently loaded or saved
+    pub fn current(&self) -> Settings {
+        self.current
+    }
+
+    /// Returns the flash driver, for the other partitions on the same chip
+    /// (the bootloader's state log)
+    pub fn flash(&mut self) -> &mut F {
+        &mut self.flash
+    }
+
+    /// Persists `settings`, skipping the write if nothing changed
+    ///
+    /// # Errors
+    /// Returns `StoreError` if programming or verification fails; the previously
+    /// stored record remains valid in that case.
+    pub fn save(&mut self, settings: &Settings) -> Result<(), StoreError> {
+        if self.cursor.is_some() && *settings == self.current {
+            return Ok(());
+        }
+        let (page, slot, seq) = match self.cursor {
+            Some(c) if c.next_slot < self.geometry.slots(RECORD_SIZE) => {
+                (c.page, c.next_slot, c.seq + 1)
+            }
+            Some(c) => (1 - c.page, 0, c.seq + 1),
+            None => (0, 0, 1),
+        };
+        // Starting a page: erase it first (the other page keeps the old record)
+        if slot == 0 {
+            let start = self.geometry.slot_offset(page, 0, RECORD_SIZE);
+            self.flash.erase(start, self.geometry.page_size)?;
+        }
+
+        let rec = build_record(seq, settings);
+        let offset = self.geometry.slot_offset(page, slot, RECORD_SIZE);
+        let written = self.flash.write(offset, &rec);
+
+        // Whatever happened, the slot may now be partially programmed
+        self.cursor = Some(Cursor {
+            page,
+            next_slot: slot + 1,
+            seq: self.cursor.map_or(0, |c| c.seq),
+        });
+        written?;
+
+        let mut check = [0u8; RECORD_SIZE];
+        self.flash.read(offset, &mut check)?;
+        if check != rec {
+            return Err(StoreError::Verify);
+        }
+        self.cursor = Some(Cursor {
+            page,
+            next_slot: slot + 1,
+            seq,
+        });
+        self.current = *settings;
+        Ok(())
+    }
+}
+
+/// Builds a complete record for `settings` with sequence number `seq`
+fn build_record(seq: u32, settings: &Settings) -> [u8; RECORD_SIZE] {
+    let payload = settings.encode();
+    let mut rec = [0u8; RECORD_SIZE];
+    rec[0..2].copy_from_slice(&MAGIC.to_le_bytes());
+    rec[2] = SCHEMA_VERSION;
+    rec[3] = payload.len() as u8;
+    rec[4..8].copy_from_slice(&seq.to_le_bytes());
+    rec[PAYLOAD_OFFSET..PAYLOAD_OFFSET + payload.len()].copy_from_slice(&payload);
+    let crc = crc16(&rec[..CRC_OFFSET]);
+    rec[CRC_OFFSET..].copy_from_slice(&crc.to_le_bytes());
+    rec
+}
+
+/// Validates a record of either layout and decodes its sequence number and
+/// settings
+///
+/// # Arguments
+/// * `rec` - A whole record; its CRC occupies the last two bytes
+/// * `schemas` - Schema versions stored in records of this size
+fn parse_record(rec: &[u8], schemas: &RangeInclusive<u8>) -> Option<(u32, Settings)> {
+    if u16::from_le_bytes([rec[0], rec[1]]) != MAGIC {
+        return None;
+    }
+    let crc_offset = rec.len() - 2;
+    let crc = u16::from_le_bytes([rec[crc_offset], rec[crc_offset + 1]]);
+    if crc16(&rec[..crc_offset]) != crc {
+        return None;
+    }
+    let len = usize::from(rec[3]);
+    if !schemas.contains(&rec[2]) || len > crc_offset - PAYLOAD_OFFSET {
+        return None;
+    }
+    let seq = u32::from_le_bytes([rec[4], rec[5], rec[6], rec[7]]);
+    let payload = &rec[PAYLOAD_OFFSET..PAYLOAD_OFFSET + len];
+    Some((seq, Settings::decode(payload)))
+}
@@ -0,0 +1,459 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Line-oriented command shell for the ST-Link virtual COM port
+//!
+//! This module turns raw received bytes into typed commands:
+//! - `LineBuffer` - accumulates bytes into lines with backspace and overflow handling
+//! - `tokenize` - splits a line into whitespace-separated tokens
+//! - `parse` - maps tokens onto a `Command`
+//!
+//! # Supported Commands
+//! - `help` - list commands
+//! - `led on` / `led off` - hold the LED at a fixed level
+//! - `led blink <ms>` - blink with the given half-period in milliseconds
+//! - `led pattern <name>` - play one of the preset patterns
+//! - `led breathe <ms>` - breathe (PWM) with the given period in milliseconds
+//! - `led fade in|out <ms>` - fade the LED in or out over the given time
+//! - `led dim <percent>` - hold the LED at a fixed perceived brightness
+//! - `morse [text]` - key a message (or the beacon text) in Morse code
+//! - `wpm <wpm> [<farnsworth>]` - set the Morse character and overall speed
+//! - `status` - report LED state, pattern and uptime
+//! - `version` - report the firmware version and build
+//! - `time` / `time get` - report the RTC date and time
+//! - `time set <YYYY-MM-DDTHH:MM:SSZ>` - set the RTC (UTC)
+//! - `schedule` - list the time-of-day pattern rules
+//! - `schedule <n> <HH:MM-HH:MM> <pattern>` - play a preset during a window
+//! - `schedule <n> off` - clear a rule slot
+//! - `baud <rate>` - store a new USART2 baud rate (applied after reset)
+//! - `reset` - perform a system reset
+//! - `update` - reset into the serial bootloader (`bootloader` feature)
+//!
+//! # Design Philosophy
+//! Nothing here touches hardware. The application feeds bytes in and acts on the
+//! returned `Command`, so the parser can be exercised on a host without a board.
+
+use heapless::Vec;
+
+use crate::brightness::Effect;
+use crate::clock::DateTime;
+use crate::morse::{self, MorseTiming, MAX_WPM, MIN_WPM};
+use crate::pattern::presets;
+use crate::schedule::{self, Rule, MAX_RULES};
+
+/// Maximum accepted line length in bytes (excluding the terminator)
+pub const MAX_LINE: usize = 64;
+
+/// Maximum number of tokens in a single command line
+pub const MAX_TOKENS: usize = 4;
+
+/// Shortest accepted `led blink` half-period in milliseconds
+pub const MIN_BLINK_MS: u32 = 10;
+
+/// Longest accepted `led blink` half-period in milliseconds
+pub const MAX_BLINK_MS: u32 = 60_000;
+
+/// Shortest accepted `led breathe` period or `led fade` duration in milliseconds
+pub const MIN_EFFECT_MS: u32 = 100;
+
+/// Longest accepted `led breathe` period or `led fade` duration in milliseconds
+pub const MAX_EFFECT_MS: u32 = 60_000;
+
+/// Baud rates accepted by `baud <rate>` and applied from persisted settings
+pub const BAUD_RATES: &[u32] = &[9_600, 19_200, 38_400, 57_600, 115_200, 230_400, 460_800];
+
+/// A fully parsed shell command
+///
+/// Borrows free-text arguments (`morse`) from the parsed line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub enum Command<'a> {
+    /// Print the command summary
+    Help,
+    /// Hold the LED on, pausing pattern playback
+    LedOn,
+    /// Hold the LED off, pausing pattern playback
+    LedOff,
+    /// Blink the LED with the given half-period in milliseconds
+    LedBlink(u32),
+    /// Play the preset at this index in `presets::ALL`
+    LedPattern(usize),
+    /// Play a PWM brightness effect
+    LedEffect(Effect),
+    /// Key a message in Morse code (`None` = the configured beacon text)
+    Morse(Option<&'a str>),
+    /// Set the Morse keying speeds
+    Wpm(MorseTiming),
+    /// Report current LED state, pattern and uptime
+    Status,
+    /// Report the firmware version, git commit and build details
+    Version,
+    /// Report the RTC date and time
+    TimeGet,
+    /// Set the RTC to this UTC date and time (within `clock::RTC_YEARS`)
+    TimeSet(DateTime),
+    /// List the schedule rules
+    ScheduleList,
+    /// Store a rule in a schedule slot (0-based; typed as 1 to `MAX_RULES`)
+    ScheduleSet(usize, Rule),
+    /// Clear a schedule slot (0-based)
+    ScheduleClear(usize),
+    /// Persist a new USART2 baud rate, applied on the next boot
+    Baud(u32),
+    /// Reset the microcontroller
+    Reset,
+    /// Reset into the serial bootloader to receive a new image
+    Update,
+}
+
+/// Reasons a line could not be turned into a `Command`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub enum ParseError {
+    /// The line contained no tokens
+    Empty,
+    /// The first token is not a known command
+    UnknownCommand,
+    /// A required argument is missing
+    MissingArgument,
+    /// An argument is present but not valid for the command
+    InvalidArgument,
+    /// More tokens than the command accepts
+    TooManyArguments,
+    /// A schedule rule number outside 1 to `MAX_RULES`
+    NoSuchRule,
+}
+
+/// Reasons the line buffer rejected input
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub enum LineError {
+    /// The line exceeded `MAX_LINE` bytes and was discarded
+    TooLong,
+}
+
+/// Accumulates received bytes into complete lines
+///
+/// Printable ASCII is stored, backspace/delete removes the last byte, and CR or LF
+/// terminates the line. Once a line overflows, further input is discarded until
+/// the next terminator, at which point `LineError::TooLong` is reported.
+pub struct LineBuffer<const N: usize> {
+    buf: Vec<u8, N>,
+    overflow: bool,
+    complete: bool,
+}
+
+impl<const N: usize> LineBuffer<N> {
+    /// Creates an empty line buffer
+    pub const fn new() -> Self {
+        Self {
+            buf: Vec::new(),
+            overflow: false,
+            complete: false,
+        }
+    }
+
+    /// Feeds one received byte into the buffer
+    ///
+    /// # Returns
+    /// * `Ok(Some(line))` - a non-empty line was terminated
+    /// * `Ok(None)` - more input is needed
+    /// * `Err(LineError::TooLong)` - an overlong line was terminated and dropped
+    pub fn push(&mut self, byte: u8) -> Result<Option<&str>, LineError> {
+        // The previous call handed out a completed line; start a fresh one
+        if self.complete {
+            self.buf.clear();
+            self.complete = false;
+        }
+        match byte {
+            b'\r' | b'\n' => {
+                if self.overflow {
+                    self.overflow = false;
+                    self.buf.clear();
+                    return Err(LineError::TooLong);
+                }
+                if self.buf.is_empty() {
+                    return Ok(None);
+                }
+                self.complete = true;
+                // Only printable ASCII is ever stored, so this cannot fail
+                Ok(core::str::from_utf8(&self.buf).ok())
+            }
+            0x08 | 0x7f => {
+                self.buf.pop();
+                Ok(None)
+            }
+            0x20..=0x7e => {
+                if !self.overflow && self.buf.push(byte).is_err() {
+                    self.overflow = true;
+                }
+                Ok(None)
+            }
+            // Ignore control characters and non-ASCII bytes
+            _ => Ok(None),
+        }
+    }
+}
+
+impl<const N: usize> Default for LineBuffer<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Splits `line` into whitespace-separated tokens
+///
+/// # Errors
+/// Returns `ParseError::TooManyArguments` if the line has more than `MAX_TOKENS` tokens.
+pub fn tokenize(line: &str) -> Result<Vec<&str, MAX_TOKENS>, ParseError> {
+    let mut tokens = Vec::new();
+    for token in line.split_ascii_whitespace() {
+        tokens
+            .push(token)
+            .map_err(|_| ParseError::TooManyArguments)?;
+    }
+    Ok(tokens)
+}
+
+/// Parses one command line into a `Command`
+///
+/// Command words are matched case-insensitively.
+pub fn parse(line: &str) -> Result<Command<'_>, ParseError> {
+    // `morse` takes free text, which may hold more words than `MAX_TOKENS`
+    if let Some(text) = strip_command(line, "morse") {
+        return parse_morse(text);
+    }
+    let tokens = tokenize(line)?;
+    let (&name, args) = tokens.split_first().ok_or(ParseError::Empty)?;
+    if name.eq_ignore_ascii_case("help") || name == "?" {
+        no_args(args, Command::Help)
+    } else if name.eq_ignore_ascii_case("status") {
+        no_args(args, Command::Status)
+    } else if name.eq_ignore_ascii_case("version") {
+        no_args(args, Command::Version)
+    } else if name.eq_ignore_ascii_case("reset") {
+        no_args(args, Command::Reset)
+    } else if name.eq_ignore_ascii_case("update") {
+        no_args(args, Command::Update)
+    } else if name.eq_ignore_ascii_case("led") {
+        parse_led(args)
+    } else if name.eq_ignore_ascii_case("time") {
+        parse_time(args)
+    } else if name.eq_ignore_ascii_case("schedule") {
+        parse_schedule(args)
+    } else if name.eq_ignore_ascii_case("wpm") {
+        parse_wpm(args)
+    } else if name.eq_ignore_ascii_case("baud") {
+        match args {
+            [] => Err(ParseError::MissingArgument),
+            [rate] => parse_baud(rate).map(Command::Baud),
+            _ => Err(ParseError::TooManyArguments),
+        }
+    } else {
+        Err(ParseError::UnknownCommand)
+    }
+}
+
+/// Returns the rest of `line` if its first word is `name` (case-insensitive)
+fn strip_command<'a>(line: &'a str, name: &str) -> Option<&'a str> {
+    let line = line.trim_ascii();
+    let (word, rest) = line
+        .split_once(|c: char| c.is_ascii_whitespace())
+        .unwrap_or((line, ""));
+    word.eq_ignore_ascii_case(name).then(|| rest.trim_ascii())
+}
+
+/// Parses the text of the `morse` command
+fn parse_morse(text: &str) -> Result<Command<'_>, ParseError> {
+    if text.is_empty() {
+        return Ok(Command::Morse(None));
+    }
+    morse::validate(text).map_err(|_| ParseError::InvalidArgument)?;
+    Ok(Command::Morse(Some(text)))
+}
+
+/// Parses the arguments of the `time` command
+fn parse_time(args: &[&str]) -> Result<Command<'static>, ParseError> {
+    match args {
+        [] => Ok(Command::TimeGet),
+        [action, rest @ ..] if action.eq_ignore_ascii_case("get") => {
+            no_args(rest, Command::TimeGet)
+        }
+        [action, rest @ ..] if action.eq_ignore_ascii_case("set") => match rest {
+            [] => Err(ParseError::MissingArgument),
+            [text] => match DateTime::parse(text) {
+                Ok(time) if time.fits_rtc() => Ok(Command::TimeSet(time)),
+                _ => Err(ParseError::InvalidArgument),
+            },
+            _ => Err(ParseError::TooManyArguments),
+        },
+        _ => Err(ParseError::InvalidArgument),
+    }
+}
+
+/// Parses the arguments of the `schedule` command
+fn parse_schedule(args: &[&str]) -> Result<Command<'static>, ParseError> {
+    let (&slot, rest) = match args.split_first() {
+        None => return Ok(Command::ScheduleList),
+        Some(split) => split,
+    };
+    let slot = match slot.parse::<usize>() {
+        Ok(n @ 1..=MAX_RULES) => n - 1,
+        _ => return Err(ParseError::NoSuchRule),
+    };
+    match rest {
+        [] => Err(ParseError::MissingArgument),
+        [off] if off.eq_ignore_ascii_case("off") => Ok(Command::ScheduleClear(slot)),
+        [_] => Err(ParseError::MissingArgument),
+        [window, name] => {
+            let (start, end) = schedule::parse_window(window).ok_or(ParseError::InvalidArgument)?;
+            presets::ALL
+                .iter()
+                .position(|p| p.name.eq_ignore_ascii_case(name))
+                .and_then(|preset| Rule::new(start, end, preset))
+                .map(|rule| Command::ScheduleSet(slot, rule))
+                .ok_or(ParseError::InvalidArgument)
+        }
+        _ => Err(ParseError::TooManyArguments),
+    }
+}
+
+/// Parses the arguments of the `wpm` command
+///
+/// The Farnsworth speed defaults to the character speed (standard spacing) and
+/// may not exceed it.
+fn parse_wpm(args: &[&str]) -> Result<Command<'static>, ParseError> {
+    let (wpm, farnsworth_wpm) = match args {
+        [] => return Err(ParseError::MissingArgument),
+        [wpm] => {
+            let wpm = parse_speed(wpm)?;
+            (wpm, wpm)
+        }
+        [wpm, farnsworth] => (parse_speed(wpm)?, parse_speed(farnsworth)?),
+        _ => return Err(ParseError::TooManyArguments),
+    };
+    if farnsworth_wpm > wpm {
+        return Err(ParseError::InvalidArgument);
+    }
+    Ok(Command::Wpm(MorseTiming {
+        wpm,
+        farnsworth_wpm,
+    }))
+}
+
+/// Parses and range-checks a Morse speed in words per minute
+fn parse_speed(token: &str) -> Result<u32, ParseError> {
+    let wpm: u32 = token.parse().map_err(|_| ParseError::InvalidArgument)?;
+    if (MIN_WPM..=MAX_WPM).contains(&wpm) {
+        Ok(wpm)
+    } else {
+        Err(ParseError::InvalidArgument)
+    }
+}
+
+/// Parses the arguments of the `led` command
+fn parse_led(args: &[&str]) -> Result<Command<'static>, ParseError> {
+    let (&action, rest) = args.split_first().ok_or(ParseError::MissingArgument)?;
+    if action.eq_ignore_ascii_case("on") {
+        no_args(rest, Command::LedOn)
+    } else if action.eq_ignore_ascii_case("off") {
+        no_args(rest, Command::LedOff)
+    } else if action.eq_ignore_ascii_case("blink") {
+        match rest {
+            [] => Err(ParseError::MissingArgument),
+            [ms] => parse_blink_ms(ms).map(Command::LedBlink),
+            _ => Err(ParseError::TooManyArguments),
+        }
+    } else if action.eq_ignore_ascii_case("pattern") {
+        match rest {
+            [] => Err(ParseError::MissingArgument),
+            [name] => presets::ALL
+                .iter()
+                .position(|p| p.name.eq_ignore_ascii_case(name))
+                .map(Command::LedPattern)
+                .ok_or(ParseError::InvalidArgument),
+            _ => Err(ParseError::TooManyArguments),
+        }
+    } else if action.eq_ignore_ascii_case("breathe") {
+        match rest {
+            [] => Err(ParseError::MissingArgument),
+            [ms] => parse_effect_ms(ms)
+                .map(|period_ms| Command::LedEffect(Effect::Breathe { period_ms })),
+            _ => Err(ParseError::TooManyArguments),
+        }
+    } else if action.eq_ignore_ascii_case("fade") {
+        parse_fade(rest)
+    } else if action.eq_ignore_ascii_case("dim") {
+        match rest {
+            [] => Err(ParseError::MissingArgument),
+            [percent] => parse_percent(percent).map(|p| Command::LedEffect(Effect::dim_percent(p))),
+            _ => Err(ParseError::TooManyArguments),
+        }
+    } else {
+        Err(ParseError::InvalidArgument)
+    }
+}
+
+/// Parses and range-checks a `led blink` half-period
+fn parse_blink_ms(token: &str) -> Result<u32, ParseError> {
+    let ms: u32 = token.parse().map_err(|_| ParseError::InvalidArgument)?;
+    if (MIN_BLINK_MS..=MAX_BLINK_MS).contains(&ms) {
+        Ok(ms)
+    } else {
+        Err(ParseError::InvalidArgument)
+    }
+}
+
+/// Parses the arguments of `led fade`: a direction and a duration
+fn parse_fade(args: &[&str]) -> Result<Command<'static>, ParseError> {
+    let (&direction, rest) = args.split_first().ok_or(ParseError::MissingArgument)?;
+    let ms = match rest {
+        [] => return Err(ParseError::MissingArgument),
+        [ms] => parse_effect_ms(ms)?,
+        _ => return Err(ParseError::TooManyArguments),
+    };
+    if direction.eq_ignore_ascii_case("in") {
+        Ok(Command::LedEffect(Effect::FadeIn { ms }))
+    } else if direction.eq_ignore_ascii_case("out") {
+        Ok(Command::LedEffect(Effect::FadeOut { ms }))
+    } else {
+        Err(ParseError::InvalidArgument)
+    }
+}
+
+/// Parses and range-checks a `led breathe` period or `led fade` duration
+fn parse_effect_ms(token: &str) -> Result<u32, ParseError> {
+    let ms: u32 = token.parse().map_err(|_| ParseError::InvalidArgument)?;
+    if (MIN_EFFECT_MS..=MAX_EFFECT_MS).contains(&ms) {
+        Ok(ms)
+    } else {
+        Err(ParseError::InvalidArgument)
+    }
+}
+
+/// Parses a `led dim` brightness percentage (0 to 100)
+fn parse_percent(token: &str) -> Result<u8, ParseError> {
+    match token.parse() {
+        Ok(percent @ 0..=100) => Ok(percent),
+        _ => Err(ParseError::InvalidArgument),
+    }
+}
+
+/// Parses a baud rate and checks it against `BAUD_RATES`
+fn parse_baud(token: &str) -> Result<u32, ParseError> {
+    let rate: u32 = token.parse().map_err(|_| ParseError::InvalidArgument)?;
+    if BAUD_RATES.contains(&rate) {
+        Ok(rate)
+    } else {
+        Err(ParseError::InvalidArgument)
+    }
+}
+
+/// Returns `cmd` if `args` is empty, otherwise `TooManyArguments`
+fn no_args<'a>(args: &[&str], cmd: Command<'a>) -> Result<Command<'a>, ParseError> {
+    if args.is_empty() {
+        Ok(cmd)
+    } else {
+        Err(ParseError::TooManyArguments)
+    }
+}
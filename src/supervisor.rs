@@ -0,0 +1,171 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Task liveness supervision for the independent watchdog, and reset causes
+//!
+//! This module decides whether the watchdog may be fed:
+//! - `Supervisor` - registry of supervised tasks and their check-ins
+//! - `Checkin` - per-task handle used to report "still alive"
+//! - `ResetFlags` / `ResetCause` - why the previous run ended
+//!
+//! # Design Philosophy
+//! Feeding the IWDG from a single timer task would keep the board alive even if
+//! every other task had hung. Instead each supervised task checks in on its own,
+//! and the feeder only pets the watchdog once *all* of them have checked in since
+//! the previous feed. The bookkeeping is two atomic bitmasks, so it is safe to
+//! share between tasks and can be exercised on a host without an executor.
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+/// Registry of supervised tasks (up to 32, one bit each) and their check-ins
+pub struct Supervisor {
+    registered: AtomicU32,
+    alive: AtomicU32,
+}
+
+/// Handle for one supervised task
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub struct Checkin {
+    bit: u32,
+}
+
+impl Supervisor {
+    /// Creates a supervisor with no registered tasks
+    pub const fn new() -> Self {
+        Self {
+            registered: AtomicU32::new(0),
+            alive: AtomicU32::new(0),
+        }
+    }
+
+    /// Registers a new supervised task
+    ///
+    /// # Returns
+    /// The task's check-in handle, or `None` if all 32 slots are taken.
+    pub fn register(&self) -> Option<Checkin> {
+        let mut current = self.registered.load(Ordering::Relaxed);
+        loop {
+            let free = !current;
+            if free == 0 {
+                return None;
+            }
+            let bit = free & free.wrapping_neg();
+            match self.registered.compare_exchange_weak(
+                current,
+                current | bit,
+                Ordering::AcqRel,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return Some(Checkin { bit }),
+                Err(actual) => current = actual,
+            }
+        }
+    }
+
+    /// Records that the task behind `checkin` is alive
+    pub fn check_in(&self, checkin: Checkin) {
+        self.alive.fetch_or(checkin.bit, Ordering::AcqRel);
+    }
+
+    /// Decides whether the watchdog may be fed and starts a new round
+    ///
+    /// # Returns
+    /// * `Ok(())` - every registered task checked in since the previous call
+    /// * `Err(mask)` - bitmask of tasks that did not check in
+    ///
+    /// Check-ins are only cleared on success, so a slow task that checks in late
+    /// still counts toward the next attempt.
+    pub fn poll(&self) -> Result<(), u32> {
+        let registered = self.registered.load(Ordering::Acquire);
+        let alive = self.alive.load(Ordering::Acquire);
+        let missing = registered & !alive;
+        if missing == 0 {
+            self.alive.fetch_and(!registered, Ordering::AcqRel);
+            Ok(())
+        } else {
+            Err(missing)
+        }
+    }
+}
+
+impl Default for Supervisor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Raw reset flags as latched in RCC_CSR
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub struct ResetFlags {
+    /// IWDGRSTF - independent watchdog reset
+    pub iwdg: bool,
+    /// WWDGRSTF - window watchdog reset
+    pub wwdg: bool,
+    /// SFTRSTF - software reset (`SCB::sys_reset`)
+    pub software: bool,
+    /// LPWRRSTF - illegal low-power mode entry
+    pub low_power: bool,
+    /// PINRSTF - NRST pin (set on every reset, including internal ones)
+    pub pin: bool,
+    /// PORRSTF - power-on / power-down reset
+    pub power_on: bool,
+}
+
+/// The single most specific reason for the last reset
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub enum ResetCause {
+    /// Power-on or brown-out
+    PowerOn,
+    /// Independent watchdog expired (a supervised task hung)
+    IndependentWatchdog,
+    /// Window watchdog expired
+    WindowWatchdog,
+    /// Software requested reset
+    Software,
+    /// Illegal low-power mode entry
+    LowPower,
+    /// External reset via the NRST pin (reset button or debugger)
+    Pin,
+    /// No flag was set
+    Unknown,
+}
+
+impl ResetCause {
+    /// Classifies latched reset flags
+    ///
+    /// Every internal reset also drives NRST, so `pin` only counts when no more
+    /// specific flag is set; power-on is checked first as it sets `pin` as well.
+    pub fn from_flags(flags: ResetFlags) -> Self {
+        if flags.power_on {
+            Self::PowerOn
+        } else if flags.iwdg {
+            Self::IndependentWatchdog
+        } else if flags.wwdg {
+            Self::WindowWatchdog
+        } else if flags.software {
+            Self::Software
+        } else if flags.low_power {
+            Self::LowPower
+        } else if flags.pin {
+            Self::Pin
+        } else {
+            Self::Unknown
+        }
+    }
+
+    /// Short human-readable name for boot reports
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::PowerOn => "power-on",
+            Self::IndependentWatchdog => "watchdog (IWDG)",
+            Self::WindowWatchdog => "watchdog (WWDG)",
+            Self::Software => "software",
+            Self::LowPower => "low-power",
+            Self::Pin => "reset pin",
+            Self::Unknown => "unknown",
+        }
+    }
+}
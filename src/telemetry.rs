@@ -0,0 +1,236 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Binary telemetry frames for test rigs
+//!
+//! This module encodes machine-readable status records for USART2:
+//! - `Record` - one telemetry sample (timestamps, LED state, error counters)
+//! - `Telemetry` - numbers records and frames them for sending
+//! - `cobs_encode` / `cobs_decode` - Consistent Overhead Byte Stuffing
+//!
+//! # Frame Format
+//! `0x00 | COBS(payload | CRC-16 LE) | 0x00`
+//!
+//! The payload (schema version 1, little-endian) is:
+//!
+//! | Offset | Size | Field                                            |
+//! |--------|------|--------------------------------------------------|
+//! | 0      | 1    | schema version (`SCHEMA_VERSION`)                |
+//! | 1      | 1    | LED flags: bit 0 = lit                           |
+//! | 2      | 1    | LED mode: 0 pattern, 1 manual, 2 effect, 3 morse |
+//! | 3      | 1    | perceived brightness (0-255)                     |
+//! | 4      | 4    | sequence number                                  |
+//! | 8      | 8    | timestamp of the reported event (ms since boot)  |
+//! | 16     | 8    | uptime when the record was sent (ms)             |
+//! | 24     | 24   | UART errors: framing, noise, overrun, parity, buffer-full, timeout (`u32` each) |
+//!
+//! # Design Philosophy
+//! COBS removes every zero byte from the frame, so `0x00` unambiguously
+//! delimits frames and a reader can resynchronise after any corruption or
+//! interleaved shell text. Fields are only ever appended: a decoder reads the
+//! fields it knows and ignores the rest, and `SCHEMA_VERSION` is bumped only
+//! when existing fields change meaning. The decoder lives in the
+//! `blink-telemetry` workspace crate.
+
+use heapless::Vec;
+
+use crate::app::{Led, LedMode};
+use crate::crc::crc16;
+use crate::monitor::ErrorCounters;
+use crate::pattern::LedPin;
+
+/// Payload schema version written in byte 0 of every record
+pub const SCHEMA_VERSION: u8 = 1;
+
+/// Length of a schema version 1 payload in bytes
+pub const PAYLOAD_LEN: usize = 48;
+
+/// Longest encoded frame in bytes, including both delimiters
+///
+/// Payload plus CRC, one COBS overhead byte per 254 bytes, two delimiters.
+pub const MAX_FRAME: usize = PAYLOAD_LEN + 2 + (PAYLOAD_LEN + 2) / 254 + 1 + 2;
+
+/// One telemetry sample
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub struct Record {
+    /// Time of the reported event (LED edge, or the periodic sample) in ms since boot
+    pub timestamp_ms: u64,
+    /// Time the record was produced in ms since boot
+    pub uptime_ms: u64,
+    /// Whether the LED is lit
+    pub led_on: bool,
+    /// How the LED is being driven
+    pub led_mode: LedMode,
+    /// Perceived brightness, `0` (off) to `255` (full)
+    pub brightness: u8,
+    /// UART write failures so far
+    pub errors: ErrorCounters,
+}
+
+impl Record {
+    /// Samples the current LED state
+    ///
+    /// # Arguments
+    /// * `led` - LED to report
+    /// * `timestamp_ms` - Time of the reported event
+    /// * `uptime_ms` - Current time
+    /// * `errors` - UART write failures so far
+    pub fn sample<P: LedPin>(
+        led: &Led<P>,
+        timestamp_ms: u64,
+        uptime_ms: u64,
+        errors: ErrorCounters,
+    ) -> Self {
+        let full = if led.is_on() { 255 } else { 0 };
+        Self {
+            timestamp_ms,
+            uptime_ms,
+            led_on: led.is_on(),
+            led_mode: led.mode(),
+            brightness: led.level().unwrap_or(full),
+            errors,
+        }
+    }
+
+    /// Serializes the record as a schema version 1 payload
+    ///
+    /// # Arguments
+    /// * `seq` - Sequence number assigned by `Telemetry`
+    pub fn to_payload(&self, seq: u32) -> [u8; PAYLOAD_LEN] {
+        let mut out = [0u8; PAYLOAD_LEN];
+        out[0] = SCHEMA_VERSION;
+        out[1] = u8::from(self.led_on);
+        out[2] = match self.led_mode {
+            LedMode::Pattern => 0,
+            LedMode::Manual => 1,
+            LedMode::Effect => 2,
+            LedMode::Morse => 3,
+        };
+        out[3] = self.brightness;
+        out[4..8].copy_from_slice(&seq.to_le_bytes());
+        out[8..16].copy_from_slice(&self.timestamp_ms.to_le_bytes());
+        out[16..24].copy_from_slice(&self.uptime_ms.to_le_bytes());
+        let e = &self.errors;
+        let counters = [
+            e.framing,
+            e.noise,
+            e.overrun,
+            e.parity,
+            e.buffer_full,
+            e.timeout,
+        ];
+        for (chunk, count) in out[24..].chunks_exact_mut(4).zip(counters) {
+            chunk.copy_from_slice(&count.to_le_bytes());
+        }
+        out
+    }
+}
+
+/// Numbers records and turns them into delimited frames
+///
+/// The sequence number starts at 0 on every boot and wraps, so a reader can
+/// detect dropped frames (a gap) and reboots (a restart at 0).
+pub struct Telemetry {
+    seq: u32,
+}
+
+impl Telemetry {
+    /// Creates a framer starting at sequence number 0
+    pub const fn new() -> Self {
+        Self { seq: 0 }
+    }
+
+    /// Returns the sequence number the next frame will carry
+    pub fn next_seq(&self) -> u32 {
+        self.seq
+    }
+
+    /// Encodes `record` as the next frame
+    ///
+    /// # Returns
+    /// The frame, ready to send, including the leading and trailing delimiters.
+    pub fn frame(&mut self, record: &Record) -> Vec<u8, MAX_FRAME> {
+        let payload = record.to_payload(self.seq);
+        self.seq = self.seq.wrapping_add(1);
+
+        // Payload followed by its CRC, little-endian
+        let mut body = [0u8; PAYLOAD_LEN + 2];
+        body[..PAYLOAD_LEN].copy_from_slice(&payload);
+        body[PAYLOAD_LEN..].copy_from_slice(&crc16(&payload).to_le_bytes());
+
+        // A leading delimiter terminates whatever garbage preceded the frame
+        let mut frame = [0u8; MAX_FRAME];
+        let len = cobs_encode(&body, &mut frame[1..MAX_FRAME - 1]).unwrap_or(0);
+        Vec::from_slice(&frame[..len + 2]).unwrap_or_default()
+    }
+}
+
+impl Default for Telemetry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Error returned when the output buffer is too small for the encoded data
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub struct BufferTooSmall;
+
+/// COBS-encodes `src` into `dst`
+///
+/// The output contains no zero bytes and is at most `src.len() + src.len() / 254 + 1`
+/// bytes long. No delimiter is appended.
+///
+/// # Returns
+/// The number of bytes written to `dst`.
+pub fn cobs_encode(src: &[u8], dst: &mut [u8]) -> Result<usize, BufferTooSmall> {
+    // `code_at` is where the length code of the current block goes
+    let mut code_at = 0;
+    let mut out = 1;
+    let mut code: u8 = 1;
+    for &byte in src {
+        if byte != 0 {
+            *dst.get_mut(out).ok_or(BufferTooSmall)? = byte;
+            out += 1;
+            code += 1;
+        }
+        // A zero, or a full block of 254 data bytes, closes the block
+        if byte == 0 || code == 0xFF {
+            *dst.get_mut(code_at).ok_or(BufferTooSmall)? = code;
+            code_at = out;
+            out += 1;
+            code = 1;
+        }
+    }
+    *dst.get_mut(code_at).ok_or(BufferTooSmall)? = code;
+    Ok(out)
+}
+
+/// Decodes COBS-encoded `src` (without delimiter) into `dst`
+///
+/// The inverse of `cobs_encode`, used by the bootloader for incoming frames.
+///
+/// # Returns
+/// The number of decoded bytes, or `None` if a length code points past the
+/// end of `src` or the decoded data does not fit in `dst`.
+pub fn cobs_decode(src: &[u8], dst: &mut [u8]) -> Option<usize> {
+    let mut out = 0;
+    let mut i = 0;
+    while i < src.len() {
+        let code = usize::from(src[i]);
+        if code == 0 || i + code > src.len() {
+            return None;
+        }
+        let block = &src[i + 1..i + code];
+        dst.get_mut(out..out + block.len())?.copy_from_slice(block);
+        out += block.len();
+        i += code;
+        // Every block except a full one (and the last) stands for a zero byte
+        if code < 0xFF && i < src.len() {
+            *dst.get_mut(out)? = 0;
+            out += 1;
+        }
+    }
+    Some(out)
+}
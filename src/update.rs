@@ -0,0 +1,293 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Firmware update protocol of the serial bootloader
+//!
+//! The bootloader (`src/bin/bootloader.rs`, `bootloader` feature) receives a
+//! new application image over USART2 as request and reply frames:
+//! - `FrameReader` - splits the byte stream into frames and checks their CRC
+//! - `Request` - a decoded host request
+//! - `Reply` - the bootloader's answer, encoded with `Reply::frame`
+//! - `Info` / `Nak` - reply contents: the partition geometry and error codes
+//!
+//! What each request does to the flash is decided by `boot::Bootloader`.
+//!
+//! # Frame Format
+//! `0x00 | COBS(type | payload | CRC-16 LE) | 0x00`, as for telemetry frames.
+//! Multi-byte fields are little-endian.
+//!
+//! | Type | Request  | Payload                               | Reply on success          |
+//! |------|----------|---------------------------------------|---------------------------|
+//! | 0x01 | `Hello`  | -                                     | `Info`                    |
+//! | 0x02 | `Begin`  | image length, image CRC-32 (`u32`)    | `Ack(0)` once erased      |
+//! | 0x03 | `Write`  | offset (`u32`), 1 to `MAX_CHUNK` bytes | `Ack(bytes received)`     |
+//! | 0x04 | `Finish` | -                                     | `Ack(length)` if verified |
+//! | 0x05 | `Boot`   | -                                     | `Ack(0)`, then a reset    |
+//!
+//! | Type | Reply  | Payload                                                          |
+//! |------|--------|------------------------------------------------------------------|
+//! | 0x81 | `Info` | version (`u8`), slot address, slot size, page size (`u32`), max chunk (`u16`) |
+//! | 0x82 | `Ack`  | value (`u32`)                                                    |
+//! | 0x83 | `Nak`  | error code (`u8`, see `Nak`)                                     |
+//!
+//! # Design Philosophy
+//! The host sends one request and waits for its reply before the next, so the
+//! bootloader never holds more than one frame and a lost or corrupted frame
+//! costs a single retry. Chunks are written in order; re-sending the last one
+//! (because its `Ack` was lost) is acknowledged without programming it twice.
+//! The host side of the protocol lives in `blink-cli` (`blink_cli::update`).
+
+use heapless::Vec;
+
+use crate::crc::crc16;
+use crate::telemetry::{cobs_decode, cobs_encode};
+
+/// Protocol version reported in `Info`
+pub const PROTOCOL_VERSION: u8 = 1;
+
+/// Largest number of image bytes in one `Write` request
+pub const MAX_CHUNK: usize = 128;
+
+/// Every chunk but the last must be a multiple of this many bytes
+///
+/// The largest flash write unit of the supported chips (a double word on the
+/// L4); the bootloader pads the last chunk with `0xFF` to it.
+pub const WRITE_ALIGN: usize = 8;
+
+/// Longest decoded request: type, offset, a full chunk and the CRC
+const MAX_REQUEST: usize = 1 + 4 + MAX_CHUNK + 2;
+
+/// Longest encoded request between delimiters
+pub const MAX_FRAME: usize = MAX_REQUEST + MAX_REQUEST / 254 + 1;
+
+/// Longest decoded reply (`Info`) including its CRC
+const MAX_REPLY: usize = 16 + 2;
+
+/// Longest reply frame, including both delimiters
+pub const MAX_REPLY_FRAME: usize = MAX_REPLY + 1 + 2;
+
+/// Request type codes
+const HELLO: u8 = 0x01;
+const BEGIN: u8 = 0x02;
+const WRITE: u8 = 0x03;
+const FINISH: u8 = 0x04;
+const BOOT: u8 = 0x05;
+
+/// Reply type codes
+const INFO: u8 = 0x81;
+const ACK: u8 = 0x82;
+const NAK: u8 = 0x83;
+
+/// A request from the host
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Request<'a> {
+    /// Asks for the partition geometry
+    Hello,
+    /// Starts an upload: erases the download slot
+    Begin {
+        /// Image length in bytes
+        len: u32,
+        /// CRC-32 (`crc::crc32`) of the whole image
+        crc: u32,
+    },
+    /// Programs the next chunk of the image
+    Write {
+        /// Offset of `data` in the image
+        offset: u32,
+        /// Image bytes
+        data: &'a [u8],
+    },
+    /// Checks the CRC-32 and vector table of the complete image
+    Finish,
+    /// Installs the verified image (or returns to the current one) and resets
+    Boot,
+}
+
+impl<'a> Request<'a> {
+    /// Parses a frame checked by `FrameReader`
+    ///
+    /// # Errors
+    /// `Nak::Request` for an unknown type or a payload of the wrong length.
+    pub fn decode(frame: &'a [u8]) -> Result<Self, Nak> {
+        let (&kind, payload) = frame.split_first().ok_or(Nak::Request)?;
+        let word = |at: usize| {
+            payload
+                .get(at..at + 4)
+                .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        };
+        let request = match (kind, payload.len()) {
+            (HELLO, 0) => Self::Hello,
+            (BEGIN, 8) => Self::Begin {
+                len: word(0).ok_or(Nak::Request)?,
+                crc: word(4).ok_or(Nak::Request)?,
+            },
+            (WRITE, len) if (5..=4 + MAX_CHUNK).contains(&len) => Self::Write {
+                offset: word(0).ok_or(Nak::Request)?,
+                data: &payload[4..],
+            },
+            (FINISH, 0) => Self::Finish,
+            (BOOT, 0) => Self::Boot,
+            _ => return Err(Nak::Request),
+        };
+        Ok(request)
+    }
+}
+
+/// Partition geometry reported in answer to `Hello`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub struct Info {
+    /// `PROTOCOL_VERSION` of the bootloader
+    pub version: u8,
+    /// Address the application is linked at and runs from
+    pub slot_address: u32,
+    /// Largest image the bootloader accepts
+    pub slot_len: u32,
+    /// Flash erase page size
+    pub page_size: u32,
+    /// Largest `Write` chunk (`MAX_CHUNK`)
+    pub max_chunk: u16,
+}
+
+/// Reasons a request was refused
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub enum Nak {
+    /// The frame failed its CRC or COBS decoding, or was too long
+    Frame = 1,
+    /// Unknown request type or wrong payload length
+    Request = 2,
+    /// Out of order: no `Begin`, an unexpected offset, an incomplete image
+    Sequence = 3,
+    /// The image is empty or larger than the slot
+    TooLarge = 4,
+    /// Erasing or programming the flash failed
+    Flash = 5,
+    /// The image in flash does not match the CRC-32 given to `Begin`
+    Crc = 6,
+    /// The image's vector table does not point into the slot and RAM
+    Vectors = 7,
+}
+
+impl Nak {
+    /// Short description for logs
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Frame => "corrupt frame",
+            Self::Request => "malformed request",
+            Self::Sequence => "out of sequence",
+            Self::TooLarge => "image does not fit the slot",
+            Self::Flash => "flash error",
+            Self::Crc => "image CRC mismatch",
+            Self::Vectors => "invalid vector table",
+        }
+    }
+}
+
+/// The bootloader's answer to one request
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub enum Reply {
+    /// Answer to `Hello`
+    Info(Info),
+    /// The request succeeded; the value depends on the request
+    Ack(u32),
+    /// The request was refused and had no effect
+    Nak(Nak),
+}
+
+impl Reply {
+    /// Encodes the reply as a frame
+    ///
+    /// # Returns
+    /// The frame, ready to send, including the leading and trailing delimiters.
+    pub fn frame(&self) -> Vec<u8, MAX_REPLY_FRAME> {
+        let mut body = [0u8; MAX_REPLY];
+        let len = match *self {
+            Self::Info(info) => {
+                body[0] = INFO;
+                body[1] = info.version;
+                body[2..6].copy_from_slice(&info.slot_address.to_le_bytes());
+                body[6..10].copy_from_slice(&info.slot_len.to_le_bytes());
+                body[10..14].copy_from_slice(&info.page_size.to_le_bytes());
+                body[14..16].copy_from_slice(&info.max_chunk.to_le_bytes());
+                16
+            }
+            Self::Ack(value) => {
+                body[0] = ACK;
+                body[1..5].copy_from_slice(&value.to_le_bytes());
+                5
+            }
+            Self::Nak(nak) => {
+                body[0] = NAK;
+                body[1] = nak as u8;
+                2
+            }
+        };
+        let crc = crc16(&body[..len]);
+        body[len..len + 2].copy_from_slice(&crc.to_le_bytes());
+        let body = &body[..len + 2];
+
+        // A leading delimiter terminates whatever garbage preceded the frame
+        let mut frame = [0u8; MAX_REPLY_FRAME];
+        let len = cobs_encode(body, &mut frame[1..MAX_REPLY_FRAME - 1]).unwrap_or(0);
+        Vec::from_slice(&frame[..len + 2]).unwrap_or_default()
+    }
+}
+
+/// Collects request frames from the serial byte stream
+///
+/// Bytes are buffered up to each `0x00` delimiter, then COBS-decoded and
+/// checked against their CRC-16. Empty frames (back-to-back delimiters) are
+/// skipped; text typed at the bootloader by mistake ends up as a `Frame` error.
+pub struct FrameReader {
+    raw: Vec<u8, MAX_FRAME>,
+    overflow: bool,
+    decoded: [u8; MAX_REQUEST],
+}
+
+impl FrameReader {
+    /// Creates an empty reader
+    pub const fn new() -> Self {
+        Self {
+            raw: Vec::new(),
+            overflow: false,
+            decoded: [0; MAX_REQUEST],
+        }
+    }
+
+    /// Adds one received byte
+    ///
+    /// # Returns
+    /// `None` until a delimiter completes a non-empty frame, then the frame
+    /// without its CRC (for `Request::decode`) or `Nak::Frame`.
+    pub fn push(&mut self, byte: u8) -> Option<Result<&[u8], Nak>> {
+        if byte != 0 {
+            if self.raw.push(byte).is_err() {
+                self.overflow = true;
+            }
+            return None;
+        }
+        let overflow = core::mem::replace(&mut self.overflow, false);
+        if self.raw.is_empty() && !overflow {
+            return None;
+        }
+        let len = cobs_decode(&self.raw, &mut self.decoded);
+        self.raw.clear();
+        let len = match len {
+            Some(len) if !overflow && len > 2 => len,
+            _ => return Some(Err(Nak::Frame)),
+        };
+        let (body, crc) = self.decoded[..len].split_at(len - 2);
+        if crc16(body).to_le_bytes() != crc {
+            return Some(Err(Nak::Frame));
+        }
+        Some(Ok(body))
+    }
+}
+
+impl Default for FrameReader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,152 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Formatted UART output without allocation
+//!
+//! This module bridges `core::fmt` and the local UART transmitter traits:
+//! - `FmtWriter` - `core::fmt::Write` adapter over any `WriteBlocking` transmitter
+//! - `uprint!` / `uprintln!` - `print!`-style macros writing straight to a transmitter
+//! - `uformat!` - formats into a fixed-capacity `heapless::String` for async senders
+//!
+//! # Design Philosophy
+//! Formatted fragments are staged in a small `heapless` buffer and flushed in as
+//! few UART writes as possible. Nothing is allocated; output that does not fit in
+//! a `uformat!` string is truncated rather than causing a panic.
+
+use core::fmt;
+
+use crate::serial::{UartError, WriteBlocking};
+
+/// Capacity in bytes of the `FmtWriter` staging buffer
+pub const FMT_BUFFER: usize = 64;
+
+/// `core::fmt::Write` adapter for a `WriteBlocking` transmitter
+///
+/// Fragments are collected in a `FMT_BUFFER`-byte staging buffer and written out
+/// whenever it fills up and when `finish` is called. The first UART error is kept
+/// so callers can see why formatting stopped.
+///
+/// # Usage
+/// ```ignore
+/// let mut w = FmtWriter::new(&mut usart);
+/// let _ = write!(w, "count = {}", n);
+/// w.finish()?;
+/// ```
+pub struct FmtWriter<'a, W: WriteBlocking> {
+    tx: &'a mut W,
+    buf: heapless::Vec<u8, FMT_BUFFER>,
+    error: Option<UartError>,
+}
+
+impl<'a, W: WriteBlocking> FmtWriter<'a, W> {
+    /// Wraps `tx` with an empty staging buffer
+    pub fn new(tx: &'a mut W) -> Self {
+        Self {
+            tx,
+            buf: heapless::Vec::new(),
+            error: None,
+        }
+    }
+
+    /// Writes out any staged bytes
+    ///
+    /// # Errors
+    /// Returns the first UART error seen by this writer.
+    pub fn finish(mut self) -> Result<(), UartError> {
+        self.flush();
+        match self.error {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// Sends the staged bytes, remembering the first failure
+    fn flush(&mut self) {
+        if self.buf.is_empty() || self.error.is_some() {
+            return;
+        }
+        if let Err(err) = self.tx.blocking_write(&self.buf) {
+            self.error = Some(err);
+        }
+        self.buf.clear();
+    }
+}
+
+impl<W: WriteBlocking> fmt::Write for FmtWriter<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for chunk in s.as_bytes().chunks(FMT_BUFFER) {
+            if self.buf.len() + chunk.len() > FMT_BUFFER {
+                self.flush();
+            }
+            // Cannot fail: the buffer was flushed if the chunk did not fit
+            let _ = self.buf.extend_from_slice(chunk);
+            if self.error.is_some() {
+                return Err(fmt::Error);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Formats `args` to `tx`, optionally followed by CR+LF
+///
+/// Backing function for `uprint!` and `uprintln!`.
+///
+/// # Errors
+/// Returns the first UART error encountered while writing.
+pub fn write_fmt<W: WriteBlocking>(
+    tx: &mut W,
+    args: fmt::Arguments<'_>,
+    newline: bool,
+) -> Result<(), UartError> {
+    let mut writer = FmtWriter::new(tx);
+    // A formatting error here can only come from a failed UART write,
+    // which `finish` reports with its precise cause
+    let _ = fmt::Write::write_fmt(&mut writer, args);
+    if newline {
+        let _ = fmt::Write::write_str(&mut writer, "\r\n");
+    }
+    writer.finish()
+}
+
+/// Writes formatted text to a `WriteBlocking` transmitter
+///
+/// Evaluates to `Result<(), UartError>`.
+#[macro_export]
+macro_rules! uprint {
+    ($tx:expr, $($arg:tt)*) => {
+        $crate::uprint::write_fmt($tx, format_args!($($arg)*), false)
+    };
+}
+
+/// Writes formatted text followed by CR+LF to a `WriteBlocking` transmitter
+///
+/// Evaluates to `Result<(), UartError>`.
+#[macro_export]
+macro_rules! uprintln {
+    ($tx:expr) => {
+        $crate::uprint::write_fmt($tx, format_args!(""), true)
+    };
+    ($tx:expr, $($arg:tt)*) => {
+        $crate::uprint::write_fmt($tx, format_args!($($arg)*), true)
+    };
+}
+
+/// Formats text into a `heapless::String` of the given capacity
+///
+/// Output beyond the capacity is truncated. Useful with async transmitters,
+/// which take a byte slice rather than implementing `core::fmt::Write`.
+///
+/// # Usage
+/// ```ignore
+/// let line = uformat!(96, "uptime {} ms\r\n", ms);
+/// usart.send(line.as_bytes()).await;
+/// ```
+#[macro_export]
+macro_rules! uformat {
+    ($cap:expr, $($arg:tt)*) => {{
+        let mut s: $crate::heapless::String<$cap> = $crate::heapless::String::new();
+        let _ = core::fmt::Write::write_fmt(&mut s, format_args!($($arg)*));
+        s
+    }};
+}
@@ -0,0 +1,275 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Host-side decoder for the firmware's binary telemetry frames
+//!
+//! The firmware (built with `--features telemetry`) writes one frame per LED
+//! change and one per second:
+//! - `FrameReader` - splits a raw serial byte stream into frames at `0x00`
+//! - `decode_frame` - COBS-decodes a frame, checks its CRC and parses the record
+//! - `Record` - the decoded telemetry sample
+//!
+//! See `blink::telemetry` in the firmware crate for the frame layout.
+//!
+//! # Design Philosophy
+//! The decoder has no dependencies and is `no_std`, so test rigs can embed it
+//! anywhere. Records of a newer schema that only appends fields decode with
+//! the extra bytes ignored; a frame that fails its CRC is reported and skipped
+//! rather than ending the stream.
+
+#![no_std]
+
+/// Newest payload schema version this decoder understands fully
+pub const SCHEMA_VERSION: u8 = 1;
+
+/// Length of a schema version 1 payload in bytes
+pub const PAYLOAD_LEN: usize = 48;
+
+/// Largest frame (between delimiters) `FrameReader` accepts
+pub const MAX_FRAME: usize = 256;
+
+/// Reasons a frame could not be decoded
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    /// A COBS length code points past the end of the frame
+    Cobs,
+    /// The frame exceeded `MAX_FRAME` bytes before its delimiter
+    TooLong,
+    /// The decoded frame is shorter than a version 1 payload plus CRC
+    TooShort,
+    /// The CRC-16 does not match the payload
+    Crc { expected: u16, actual: u16 },
+    /// The schema version is `0` (never written by the firmware)
+    Version(u8),
+}
+
+/// How the LED was being driven
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LedMode {
+    /// Blink pattern playback
+    Pattern,
+    /// Held on or off by a shell command or gesture
+    Manual,
+    /// PWM brightness effect
+    Effect,
+    /// Morse message
+    Morse,
+    /// A mode added by a newer firmware
+    Unknown(u8),
+}
+
+impl LedMode {
+    /// Maps the wire code onto a mode
+    fn from_code(code: u8) -> Self {
+        match code {
+            0 => Self::Pattern,
+            1 => Self::Manual,
+            2 => Self::Effect,
+            3 => Self::Morse,
+            other => Self::Unknown(other),
+        }
+    }
+}
+
+/// UART write failures counted by the firmware, per kind
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ErrorCounters {
+    pub framing: u32,
+    pub noise: u32,
+    pub overrun: u32,
+    pub parity: u32,
+    pub buffer_full: u32,
+    pub timeout: u32,
+}
+
+impl ErrorCounters {
+    /// Returns the total number of failures across all kinds
+    pub fn total(&self) -> u64 {
+        [
+            self.framing,
+            self.noise,
+            self.overrun,
+            self.parity,
+            self.buffer_full,
+            self.timeout,
+        ]
+        .iter()
+        .map(|&n| u64::from(n))
+        .sum()
+    }
+}
+
+/// One decoded telemetry sample
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Record {
+    /// Payload schema version
+    pub version: u8,
+    /// Frame sequence number (restarts at 0 on every boot)
+    pub seq: u32,
+    /// Time of the reported event in ms since boot
+    pub timestamp_ms: u64,
+    /// Time the record was produced in ms since boot
+    pub uptime_ms: u64,
+    /// Whether the LED was lit
+    pub led_on: bool,
+    /// How the LED was being driven
+    pub led_mode: LedMode,
+    /// Perceived brightness, `0` (off) to `255` (full)
+    pub brightness: u8,
+    /// UART write failures so far
+    pub errors: ErrorCounters,
+}
+
+impl Record {
+    /// Parses a payload whose CRC has already been checked
+    fn parse(payload: &[u8]) -> Result<Self, DecodeError> {
+        if payload.len() < PAYLOAD_LEN {
+            return Err(DecodeError::TooShort);
+        }
+        if payload[0] == 0 {
+            return Err(DecodeError::Version(0));
+        }
+        let u32_at = |at: usize| {
+            u32::from_le_bytes([
+                payload[at],
+                payload[at + 1],
+                payload[at + 2],
+                payload[at + 3],
+            ])
+        };
+        let u64_at = |at: usize| u64::from(u32_at(at)) | u64::from(u32_at(at + 4)) << 32;
+        Ok(Self {
+            version: payload[0],
+            led_on: payload[1] & 1 != 0,
+            led_mode: LedMode::from_code(payload[2]),
+            brightness: payload[3],
+            seq: u32_at(4),
+            timestamp_ms: u64_at(8),
+            uptime_ms: u64_at(16),
+            errors: ErrorCounters {
+                framing: u32_at(24),
+                noise: u32_at(28),
+                overrun: u32_at(32),
+                parity: u32_at(36),
+                buffer_full: u32_at(40),
+                timeout: u32_at(44),
+            },
+        })
+    }
+}
+
+/// Decodes one frame (the bytes between two `0x00` delimiters)
+///
+/// # Errors
+/// Any `DecodeError` except `TooLong`.
+pub fn decode_frame(frame: &[u8]) -> Result<Record, DecodeError> {
+    let mut buf = [0u8; MAX_FRAME];
+    let len = cobs_decode(frame, &mut buf)?;
+    let body = &buf[..len];
+    if body.len() < PAYLOAD_LEN + 2 {
+        return Err(DecodeError::TooShort);
+    }
+    let (payload, crc) = body.split_at(body.len() - 2);
+    let expected = u16::from_le_bytes([crc[0], crc[1]]);
+    let actual = crc16(payload);
+    if expected != actual {
+        return Err(DecodeError::Crc { expected, actual });
+    }
+    Record::parse(payload)
+}
+
+/// Decodes COBS-encoded `src` (without delimiter) into `dst`
+///
+/// # Returns
+/// The number of decoded bytes.
+pub fn cobs_decode(src: &[u8], dst: &mut [u8]) -> Result<usize, DecodeError> {
+    let mut out = 0;
+    let mut i = 0;
+    while i < src.len() {
+        let code = usize::from(src[i]);
+        if code == 0 || i + code > src.len() {
+            return Err(DecodeError::Cobs);
+        }
+        let block = &src[i + 1..i + code];
+        dst.get_mut(out..out + block.len())
+            .ok_or(DecodeError::TooLong)?
+            .copy_from_slice(block);
+        out += block.len();
+        i += code;
+        // Every block except a full one (and the last) stands for a zero byte
+        if code < 0xFF && i < src.len() {
+            *dst.get_mut(out).ok_or(DecodeError::TooLong)? = 0;
+            out += 1;
+        }
+    }
+    Ok(out)
+}
+
+/// CRC-16/CCITT-FALSE, as used by the firmware
+pub fn crc16(data: &[u8]) -> u16 {
+    let mut crc: u16 = 0xFFFF;
+    for &byte in data {
+        crc ^= u16::from(byte) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+/// Splits a serial byte stream into frames and decodes them
+///
+/// Anything that is not a valid frame (shell text, line noise, a frame cut off
+/// by a reset) comes back as an error for that chunk only.
+pub struct FrameReader {
+    buf: [u8; MAX_FRAME],
+    len: usize,
+    overflow: bool,
+}
+
+impl FrameReader {
+    /// Creates a reader waiting for the first delimiter-terminated frame
+    pub const fn new() -> Self {
+        Self {
+            buf: [0; MAX_FRAME],
+            len: 0,
+            overflow: false,
+        }
+    }
+
+    /// Feeds one received byte
+    ///
+    /// # Returns
+    /// `Some` with the decoded record (or the reason decoding failed) when
+    /// `byte` completes a non-empty frame, `None` otherwise.
+    pub fn push(&mut self, byte: u8) -> Option<Result<Record, DecodeError>> {
+        if byte != 0 {
+            match self.buf.get_mut(self.len) {
+                Some(slot) => {
+                    *slot = byte;
+                    self.len += 1;
+                }
+                None => self.overflow = true,
+            }
+            return None;
+        }
+        let result = match (self.len, self.overflow) {
+            (0, false) => None,
+            (_, true) => Some(Err(DecodeError::TooLong)),
+            (len, false) => Some(decode_frame(&self.buf[..len])),
+        };
+        self.len = 0;
+        self.overflow = false;
+        result
+    }
+}
+
+impl Default for FrameReader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,137 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Frames produced by the firmware library decode back to the same record
+
+use blink::app::LedMode as FirmwareMode;
+use blink::monitor::ErrorCounters as FirmwareErrors;
+use blink::telemetry::{Record as FirmwareRecord, Telemetry};
+use blink_telemetry::{
+    cobs_decode, decode_frame, DecodeError, ErrorCounters, FrameReader, LedMode, Record,
+    PAYLOAD_LEN, SCHEMA_VERSION,
+};
+
+fn sample(seq_hint: u64, mode: FirmwareMode) -> FirmwareRecord {
+    FirmwareRecord {
+        timestamp_ms: 1_000 * seq_hint,
+        uptime_ms: 1_000 * seq_hint + 3,
+        led_on: seq_hint.is_multiple_of(2),
+        led_mode: mode,
+        brightness: (seq_hint * 40) as u8,
+        errors: FirmwareErrors {
+            framing: 1,
+            noise: 2,
+            overrun: 3,
+            parity: 4,
+            buffer_full: 5,
+            timeout: u32::MAX,
+        },
+    }
+}
+
+/// Feeds `bytes` through a reader, collecting every completed frame
+fn read_all(bytes: &[u8]) -> Vec<Result<Record, DecodeError>> {
+    let mut reader = FrameReader::new();
+    bytes.iter().filter_map(|&b| reader.push(b)).collect()
+}
+
+#[test]
+fn every_field_round_trips() {
+    let mut telemetry = Telemetry::new();
+    let modes = [
+        (FirmwareMode::Pattern, LedMode::Pattern),
+        (FirmwareMode::Manual, LedMode::Manual),
+        (FirmwareMode::Effect, LedMode::Effect),
+        (FirmwareMode::Morse, LedMode::Morse),
+    ];
+    for (seq, (mode, expected_mode)) in modes.into_iter().enumerate() {
+        let frame = telemetry.frame(&sample(seq as u64, mode));
+        let record = decode_frame(&frame[1..frame.len() - 1]).unwrap();
+        assert_eq!(
+            record,
+            Record {
+                version: SCHEMA_VERSION,
+                seq: seq as u32,
+                timestamp_ms: 1_000 * seq as u64,
+                uptime_ms: 1_000 * seq as u64 + 3,
+                led_on: seq.is_multiple_of(2),
+                led_mode: expected_mode,
+                brightness: (seq * 40) as u8,
+                errors: ErrorCounters {
+                    framing: 1,
+                    noise: 2,
+                    overrun: 3,
+                    parity: 4,
+                    buffer_full: 5,
+                    timeout: u32::MAX,
+                },
+            }
+        );
+        assert_eq!(record.errors.total(), 15 + u64::from(u32::MAX));
+    }
+}
+
+#[test]
+fn reader_resynchronises_around_shell_text() {
+    let mut telemetry = Telemetry::new();
+    let mut stream = b"> status\r\nLED ON\r\n".to_vec();
+    stream.extend_from_slice(&telemetry.frame(&sample(1, FirmwareMode::Pattern)));
+    stream.extend_from_slice(b"OK\r\n");
+    stream.extend_from_slice(&telemetry.frame(&sample(2, FirmwareMode::Pattern)));
+
+    let results = read_all(&stream);
+    let records: Vec<u32> = results
+        .iter()
+        .filter_map(|r| r.ok())
+        .map(|r| r.seq)
+        .collect();
+    assert_eq!(records, [0, 1]);
+    // The shell text before each frame is reported as undecodable, not skipped silently
+    assert_eq!(results.len(), 4);
+}
+
+#[test]
+fn corrupted_frames_are_rejected() {
+    let mut telemetry = Telemetry::new();
+    let frame = telemetry.frame(&sample(1, FirmwareMode::Pattern));
+    let body = &frame[1..frame.len() - 1];
+
+    let mut flipped = body.to_vec();
+    flipped[10] ^= 0x40;
+    assert!(matches!(
+        decode_frame(&flipped),
+        Err(DecodeError::Crc { .. }) | Err(DecodeError::Cobs)
+    ));
+    assert!(decode_frame(&body[..20]).is_err());
+    assert_eq!(
+        decode_frame(&[0x03, 0x11, 0x22]),
+        Err(DecodeError::TooShort)
+    );
+
+    let oversized = vec![0x55; 300];
+    assert_eq!(
+        read_all(&[&oversized[..], &[0]].concat()),
+        [Err(DecodeError::TooLong)]
+    );
+}
+
+#[test]
+fn newer_schemas_with_appended_fields_still_decode() {
+    let mut payload = sample(3, FirmwareMode::Effect).to_payload(9).to_vec();
+    payload[0] = SCHEMA_VERSION + 1;
+    payload.extend_from_slice(&[0xAB; 6]);
+    let crc = blink_telemetry::crc16(&payload);
+    payload.extend_from_slice(&crc.to_le_bytes());
+
+    let mut encoded = vec![0; payload.len() + 2];
+    let len = blink::telemetry::cobs_encode(&payload, &mut encoded).unwrap();
+    let record = decode_frame(&encoded[..len]).unwrap();
+    assert_eq!((record.version, record.seq), (SCHEMA_VERSION + 1, 9));
+    assert_eq!(record.led_mode, LedMode::Effect);
+
+    // And COBS itself round-trips arbitrary data, zeros included
+    let mut decoded = [0u8; 64];
+    let n = cobs_decode(&encoded[..len], &mut decoded).unwrap();
+    assert_eq!(&decoded[..n], &payload[..]);
+    assert!(payload.len() > PAYLOAD_LEN);
+}
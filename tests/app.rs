@@ -0,0 +1,217 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Main-loop behaviour: blink sequencing, brightness effects, UART
+//! notifications, gestures and persisted pattern selection, driven by a fake clock
+
+use blink::app::{stored_pattern, Led, LedMode, Persisted};
+use blink::brightness::{Effect, PwmLed};
+use blink::button::Gesture;
+use blink::messages;
+use blink::mock::{MockFlash, MockPin, MockPwm, MockSerial};
+use blink::pattern::presets;
+use blink::serial::WriteBlocking;
+use blink::settings::{Geometry, Settings, SettingsStore, PATTERN_CUSTOM};
+
+/// Runs the LED from `from_ms` to `to_ms` in 1 ms ticks, forwarding every
+/// notification to `tx` the way the firmware main loop does
+fn run(led: &mut Led<MockPin>, tx: &mut MockSerial, from_ms: u64, to_ms: u64) {
+    for now in from_ms..to_ms {
+        if let Some(msg) = led.poll(now) {
+            tx.blocking_write(msg).unwrap();
+        }
+    }
+}
+
+#[test]
+fn default_pattern_blinks_and_reports_each_edge() {
+    let mut led = Led::new(MockPin::new(), presets::STEADY, &Settings::default());
+    let mut tx = MockSerial::new();
+
+    run(&mut led, &mut tx, 0, 2000);
+
+    assert_eq!(led.pin().history(), &[true, false, true, false]);
+    assert_eq!(
+        tx.output_str(),
+        "LED ON\r\nLED OFF\r\nLED ON\r\nLED OFF\r\n"
+    );
+    assert_eq!(led.next_deadline(), Some(2000));
+}
+
+#[test]
+fn manual_hold_pauses_the_pattern() {
+    let mut led = Led::new(MockPin::new(), presets::STEADY, &Settings::default());
+    let mut tx = MockSerial::new();
+    run(&mut led, &mut tx, 0, 10);
+
+    led.hold(false);
+    tx.clear();
+    run(&mut led, &mut tx, 10, 3000);
+
+    assert_eq!(led.mode(), LedMode::Manual);
+    assert_eq!(led.mode_name(), "manual (off)");
+    assert_eq!(led.next_deadline(), None);
+    assert_eq!(tx.output(), b"");
+    assert_eq!(led.pin().history(), &[true, false]);
+}
+
+#[test]
+fn gestures_cycle_presets_and_toggle_pause() {
+    let mut led = Led::new(MockPin::new(), presets::STEADY, &Settings::default());
+    let last = presets::ALL.len() - 1;
+
+    led.apply(Gesture::Short);
+    assert_eq!(led.preset(), 1);
+    led.apply(Gesture::Double);
+    led.apply(Gesture::Double);
+    assert_eq!(led.preset(), last);
+    assert_eq!(led.mode_name(), presets::ALL[last].name);
+
+    led.apply(Gesture::Long);
+    assert_eq!(led.mode(), LedMode::Manual);
+    led.apply(Gesture::Long);
+    assert_eq!(led.mode(), LedMode::Pattern);
+    assert_eq!(led.preset(), last);
+}
+
+#[test]
+fn restarting_a_pattern_starts_from_its_first_step() {
+    let mut led = Led::new(MockPin::new(), presets::STEADY, &Settings::default());
+    let mut tx = MockSerial::new();
+    run(&mut led, &mut tx, 0, 700);
+
+    led.apply(Gesture::Short);
+    tx.clear();
+    run(&mut led, &mut tx, 700, 701);
+    assert_eq!(tx.output(), messages::LED_ON);
+}
+
+#[test]
+fn stored_selection_is_restored() {
+    let heartbeat = Settings {
+        pattern: 1,
+        ..Settings::default()
+    };
+    let (player, preset) = stored_pattern(&heartbeat, presets::STEADY);
+    assert_eq!((player.name(), preset), (presets::ALL[1].name, 1));
+
+    let custom = Settings {
+        pattern: PATTERN_CUSTOM,
+        blink_ms: 1,
+        ..Settings::default()
+    };
+    let mut led = Led::new(MockPin::new(), presets::STEADY, &custom);
+    assert_eq!(led.pattern_name(), "blink");
+    // A corrupt period is clamped to the shell minimum of 10 ms
+    led.poll(0);
+    assert_eq!(led.next_deadline(), Some(10));
+
+    let unknown = Settings {
+        pattern: 200,
+        ..Settings::default()
+    };
+    let (player, preset) = stored_pattern(&unknown, presets::STEADY);
+    assert_eq!((player.name(), preset), (presets::STEADY.name, 0));
+}
+
+#[test]
+fn closing_a_schedule_window_restores_the_stored_pattern() {
+    let custom = Settings {
+        pattern: PATTERN_CUSTOM,
+        blink_ms: 250,
+        ..Settings::default()
+    };
+    let mut led = Led::new(MockPin::new(), presets::STEADY, &custom);
+    led.select_preset(3);
+    assert_eq!(led.pattern_name(), presets::ALL[3].name);
+    led.play_stored(&custom);
+    assert_eq!(
+        (led.pattern_name(), led.mode()),
+        ("blink", LedMode::Pattern)
+    );
+
+    led.hold(true);
+    led.play_stored(&Settings::default());
+    assert_eq!(
+        (led.pattern_name(), led.preset()),
+        (presets::STEADY.name, 0)
+    );
+    assert_eq!(led.mode(), LedMode::Pattern);
+}
+
+#[test]
+fn persisted_updates_reach_flash() {
+    const GEOMETRY: Geometry = Geometry {
+        base: 0,
+        page_size: 256,
+    };
+    let mut flash = MockFlash::<512>::new(0, 256);
+    let mut store = SettingsStore::new(&mut flash, GEOMETRY);
+    store.load();
+
+    let mut persisted = Persisted::new(store);
+    assert!(persisted.update(|s| s.pattern = 3));
+
+    assert_eq!(SettingsStore::new(&mut flash, GEOMETRY).load().pattern, 3);
+}
+
+#[test]
+fn breathing_effect_drives_pwm_without_uart_chatter() {
+    let mut led = Led::new(
+        PwmLed::new(MockPwm::new(1000)),
+        presets::STEADY,
+        &Settings::default(),
+    );
+    let mut tx = MockSerial::new();
+    led.fade(Effect::Breathe { period_ms: 1000 });
+
+    let mut peak = 0;
+    for now in 0..1000 {
+        if let Some(msg) = led.poll(now) {
+            tx.blocking_write(msg).unwrap();
+        }
+        peak = peak.max(led.pin().channel().duty());
+    }
+
+    assert_eq!(led.mode(), LedMode::Effect);
+    assert_eq!(led.mode_name(), "breathe");
+    assert_eq!(tx.output(), b"");
+    assert_eq!(peak, 1000);
+    // Updated every `EFFECT_STEP_MS`: the last update (990 ms) is almost dark
+    assert_eq!(led.next_deadline(), Some(1000));
+    assert_eq!(led.level(), Some(6));
+}
+
+#[test]
+fn fade_settles_and_stops_scheduling() {
+    let mut led = Led::new(
+        PwmLed::new(MockPwm::new(1000)),
+        presets::STEADY,
+        &Settings::default(),
+    );
+    led.fade(Effect::FadeOut { ms: 200 });
+    for now in 100..400 {
+        led.poll(now);
+    }
+    assert_eq!(led.level(), Some(0));
+    assert!(!led.is_on());
+    assert_eq!(led.pin().channel().duty(), 0);
+    assert_eq!(led.next_deadline(), None);
+
+    // Patterns take over at full duty
+    led.apply(Gesture::Short);
+    led.poll(400);
+    assert_eq!(led.pin().channel().duty(), 1000);
+    assert_eq!(led.level(), None);
+}
+
+#[test]
+fn effects_degrade_to_on_off_on_a_plain_pin() {
+    let mut led = Led::new(MockPin::new(), presets::STEADY, &Settings::default());
+    led.fade(Effect::dim_percent(90));
+    led.poll(0);
+    assert!(led.pin().is_high());
+    led.fade(Effect::dim_percent(10));
+    led.poll(1);
+    assert!(!led.pin().is_high());
+}
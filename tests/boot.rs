@@ -0,0 +1,325 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Boot state log, uploads, slot swap, power cuts and rollback
+
+use blink::boot::{
+    confirm, request_update, valid_vectors, BootState, Bootloader, Partitions, Start, StateLog,
+    Swap,
+};
+use blink::crc::crc32;
+use blink::mock::MockFlash;
+use blink::settings::SettingsFlash;
+use blink::update::{Nak, Reply, Request, MAX_CHUNK};
+
+/// Small pages keep the model flash small: 4 pages of bootloader, 2 of state,
+/// two 8-page slots and the scratch page
+const PARTS: Partitions = Partitions {
+    flash_origin: 0x0800_0000,
+    state: 0x400,
+    active: 0x600,
+    download: 0xE00,
+    scratch: 0x1600,
+    slot_len: 0x800,
+    page_size: 0x100,
+    ram: [(0x2000_0000, 0x1_0000), (0x1000_0000, 0x4000)],
+};
+
+type Flash = MockFlash<0x1800>;
+
+/// Builds an image of `len` bytes linked for the active slot
+fn image(len: usize, seed: u8) -> Vec<u8> {
+    let mut image: Vec<u8> = (0..len).map(|i| (i as u8).wrapping_mul(seed)).collect();
+    image[..4].copy_from_slice(&0x1000_4000u32.to_le_bytes());
+    let reset = PARTS.app_address() + 0x101;
+    image[4..8].copy_from_slice(&reset.to_le_bytes());
+    image
+}
+
+/// Returns a flash with `installed` programmed into the active slot
+fn flash_with(installed: &[u8]) -> Flash {
+    let mut flash = Flash::new(0, PARTS.page_size);
+    flash.write(PARTS.active, installed).unwrap();
+    flash
+}
+
+/// Returns `len` bytes of flash at `offset`
+fn contents(flash: &mut Flash, offset: u32, len: usize) -> Vec<u8> {
+    let mut buf = vec![0u8; len];
+    flash.read(offset, &mut buf).unwrap();
+    buf
+}
+
+/// Uploads `image` through the request handler, as the host would
+fn upload(boot: &mut Bootloader<&mut Flash>, image: &[u8]) -> Reply {
+    let len = image.len() as u32;
+    let begin = boot.handle(Request::Begin {
+        len,
+        crc: crc32(image),
+    });
+    assert_eq!(begin, Reply::Ack(0));
+    for (i, data) in image.chunks(MAX_CHUNK).enumerate() {
+        let offset = (i * MAX_CHUNK) as u32;
+        let reply = boot.handle(Request::Write { offset, data });
+        assert_eq!(reply, Reply::Ack(offset + data.len() as u32));
+    }
+    boot.handle(Request::Finish)
+}
+
+#[test]
+fn the_state_log_keeps_the_newest_record() {
+    let mut flash = Flash::new(0, PARTS.page_size);
+    let mut log = StateLog::new(&PARTS);
+    assert_eq!(log.load(&mut flash), BootState::Idle);
+
+    // Enough records to wrap around both 16-record pages
+    let mut swap = Swap::start(20, false);
+    for _ in 0..40 {
+        log.save(&mut flash, BootState::Swap(swap)).unwrap();
+        swap = swap.next().unwrap();
+    }
+    let last = Swap {
+        pages: 20,
+        page: 13,
+        step: 0,
+        revert: false,
+    };
+    assert_eq!(
+        StateLog::new(&PARTS).load(&mut flash),
+        BootState::Swap(last)
+    );
+
+    // A torn record (power cut while programming) leaves the previous state:
+    // the 40th record went to slot 7 of the first page after the wrap
+    flash.data_mut()[PARTS.state as usize + 7 * 16 + 12] ^= 1;
+    let previous = Swap {
+        step: 2,
+        page: 12,
+        ..last
+    };
+    assert_eq!(
+        StateLog::new(&PARTS).load(&mut flash),
+        BootState::Swap(previous)
+    );
+}
+
+#[test]
+fn vector_tables_must_point_into_the_slot_and_ram() {
+    let table = |sp: u32, reset: u32| {
+        let mut bytes = sp.to_le_bytes().to_vec();
+        bytes.extend_from_slice(&reset.to_le_bytes());
+        bytes
+    };
+    let app = PARTS.app_address();
+    assert!(valid_vectors(&table(0x2001_0000, app + 0x401), &PARTS));
+    assert!(valid_vectors(&table(0x1000_4000, app + 0x7FF), &PARTS));
+    // Erased flash, a reset vector without the Thumb bit or outside the slot
+    assert!(!valid_vectors(&[0xFF; 8], &PARTS));
+    assert!(!valid_vectors(&table(0x2001_0000, app + 0x400), &PARTS));
+    assert!(!valid_vectors(&table(0x2001_0000, 0x0800_0101), &PARTS));
+    assert!(!valid_vectors(&table(0x2001_0000, app + 0x801), &PARTS));
+    // A stack past RAM, misaligned, or in the unused second entry
+    assert!(!valid_vectors(&table(0x2001_0004, app + 0x401), &PARTS));
+    assert!(!valid_vectors(&table(0x2000_FFFE, app + 0x401), &PARTS));
+    let no_ccm = Partitions {
+        ram: [(0x2000_0000, 0x1_0000), (0, 0)],
+        ..PARTS
+    };
+    assert!(!valid_vectors(&table(0x1000_4000, app + 0x401), &no_ccm));
+    assert!(!valid_vectors(&[0; 4], &PARTS));
+}
+
+#[test]
+fn an_empty_or_erased_slot_waits_for_an_upload() {
+    let mut flash = Flash::new(0, PARTS.page_size);
+    assert_eq!(Bootloader::new(&mut flash, PARTS).start(), Start::Update);
+    let mut flash = flash_with(&image(600, 3));
+    assert_eq!(Bootloader::new(&mut flash, PARTS).start(), Start::Run);
+    request_update(&mut flash, &PARTS).unwrap();
+    assert_eq!(Bootloader::new(&mut flash, PARTS).start(), Start::Update);
+}
+
+#[test]
+fn an_upload_is_installed_and_confirmed() {
+    let old = image(1000, 3);
+    let new = image(1500, 7);
+    let mut flash = flash_with(&old);
+    request_update(&mut flash, &PARTS).unwrap();
+
+    let mut boot = Bootloader::new(&mut flash, PARTS);
+    assert_eq!(boot.start(), Start::Update);
+    let Reply::Info(info) = boot.handle(Request::Hello) else {
+        panic!("no info");
+    };
+    assert_eq!(
+        (info.slot_address, info.slot_len, info.page_size),
+        (0x0800_0600, 0x800, 0x100)
+    );
+    assert_eq!(upload(&mut boot, &new), Reply::Ack(1500));
+    assert!(!boot.reset_requested());
+    assert_eq!(boot.handle(Request::Boot), Reply::Ack(0));
+    assert!(boot.reset_requested());
+
+    // Reset: the bootloader swaps the slots and starts the new image on trial
+    let mut boot = Bootloader::new(&mut flash, PARTS);
+    assert_eq!(boot.start(), Start::Run);
+    assert_eq!(boot.state(), BootState::Testing { pages: 6 });
+    assert_eq!(contents(&mut flash, PARTS.active, 1500), new);
+    assert_eq!(contents(&mut flash, PARTS.download, 1000), old);
+    assert!(contents(&mut flash, PARTS.active + 1500, 548)
+        .iter()
+        .all(|&b| b == 0xFF));
+
+    // The application confirms; later resets keep running it
+    assert_eq!(
+        confirm(&mut flash, &PARTS),
+        Ok(BootState::Testing { pages: 6 })
+    );
+    assert_eq!(confirm(&mut flash, &PARTS), Ok(BootState::Idle));
+    let mut boot = Bootloader::new(&mut flash, PARTS);
+    assert_eq!(boot.start(), Start::Run);
+    assert_eq!(contents(&mut flash, PARTS.active, 1500), new);
+}
+
+#[test]
+fn an_unconfirmed_image_is_rolled_back() {
+    let old = image(1800, 3);
+    let new = image(700, 5);
+    let mut flash = flash_with(&old);
+    let mut boot = Bootloader::new(&mut flash, PARTS);
+    assert_eq!(upload(&mut boot, &new), Reply::Ack(700));
+    boot.handle(Request::Boot);
+    assert_eq!(Bootloader::new(&mut flash, PARTS).start(), Start::Run);
+    assert_eq!(contents(&mut flash, PARTS.active, 700), new);
+
+    // The new image resets before confirming: the old one comes back
+    let mut boot = Bootloader::new(&mut flash, PARTS);
+    assert_eq!(boot.start(), Start::Run);
+    assert_eq!(boot.state(), BootState::RolledBack);
+    assert_eq!(contents(&mut flash, PARTS.active, 1800), old);
+    assert_eq!(contents(&mut flash, PARTS.download, 700), new);
+
+    // The old image reports the rollback once
+    assert_eq!(confirm(&mut flash, &PARTS), Ok(BootState::RolledBack));
+    assert_eq!(confirm(&mut flash, &PARTS), Ok(BootState::Idle));
+}
+
+#[test]
+fn a_swap_survives_a_power_cut_at_any_point() {
+    let old = image(2048, 3);
+    let new = image(1100, 9);
+    for cut in 0.. {
+        let mut flash = flash_with(&old);
+        let mut boot = Bootloader::new(&mut flash, PARTS);
+        upload(&mut boot, &new);
+        boot.handle(Request::Boot);
+
+        // The first attempt loses power after `cut` flash operations
+        flash.cut_power_after(cut);
+        let mut boot = Bootloader::new(&mut flash, PARTS);
+        let mut outcome = (boot.start(), boot.state());
+        flash.restore_power();
+
+        // Whatever was interrupted, the next boot finishes the install
+        let completed = outcome.0 == Start::Run;
+        if !completed {
+            let mut boot = Bootloader::new(&mut flash, PARTS);
+            outcome = (boot.start(), boot.state());
+        }
+        let expected = (Start::Run, BootState::Testing { pages: 8 });
+        assert_eq!(outcome, expected, "cut after {cut}");
+        assert_eq!(contents(&mut flash, PARTS.active, 1100), new, "cut {cut}");
+        assert_eq!(contents(&mut flash, PARTS.download, 2048), old, "cut {cut}");
+        if completed {
+            break;
+        }
+    }
+}
+
+#[test]
+fn out_of_order_and_corrupt_uploads_are_refused() {
+    let installed = image(600, 3);
+    let new = image(300, 11);
+    let mut flash = flash_with(&installed);
+    let mut boot = Bootloader::new(&mut flash, PARTS);
+    let write = |boot: &mut Bootloader<&mut Flash>, offset: u32, data: &[u8]| {
+        boot.handle(Request::Write { offset, data })
+    };
+
+    // Nothing to write to or finish yet; sizes outside the slot
+    assert_eq!(write(&mut boot, 0, &new[..8]), Reply::Nak(Nak::Sequence));
+    assert_eq!(boot.handle(Request::Finish), Reply::Nak(Nak::Sequence));
+    for len in [0, 0x801] {
+        let begin = Request::Begin { len, crc: 0 };
+        assert_eq!(boot.handle(begin), Reply::Nak(Nak::TooLarge));
+    }
+
+    // Chunks must arrive in order; the last one may be repeated
+    let begin = Request::Begin {
+        len: 300,
+        crc: crc32(&new),
+    };
+    assert_eq!(boot.handle(begin), Reply::Ack(0));
+    assert_eq!(write(&mut boot, 8, &new[8..16]), Reply::Nak(Nak::Sequence));
+    assert_eq!(write(&mut boot, 0, &new[..5]), Reply::Nak(Nak::Request));
+    assert_eq!(write(&mut boot, 0, &new[..128]), Reply::Ack(128));
+    assert_eq!(write(&mut boot, 0, &new[..128]), Reply::Ack(128));
+    assert_eq!(write(&mut boot, 128, &new[128..256]), Reply::Ack(256));
+    assert_eq!(boot.handle(Request::Finish), Reply::Nak(Nak::Sequence));
+    assert_eq!(write(&mut boot, 256, &new[256..]), Reply::Ack(300));
+    assert_eq!(write(&mut boot, 300, &[0; 8]), Reply::Nak(Nak::Sequence));
+    assert_eq!(boot.handle(Request::Finish), Reply::Ack(300));
+
+    // A CRC that does not match what was programmed
+    let begin = Request::Begin {
+        len: 300,
+        crc: crc32(&new) ^ 1,
+    };
+    assert_eq!(boot.handle(begin), Reply::Ack(0));
+    for (i, data) in new.chunks(MAX_CHUNK).enumerate() {
+        write(&mut boot, (i * MAX_CHUNK) as u32, data);
+    }
+    assert_eq!(boot.handle(Request::Finish), Reply::Nak(Nak::Crc));
+
+    // An image linked for another address
+    let mut foreign = new.clone();
+    foreign[4..8].copy_from_slice(&0x0800_0101u32.to_le_bytes());
+    assert_eq!(upload(&mut boot, &foreign), Reply::Nak(Nak::Vectors));
+
+    // Without a verified upload, `Boot` returns to the installed image
+    assert_eq!(boot.handle(Request::Boot), Reply::Ack(0));
+    assert_eq!(boot.state(), BootState::Idle);
+    let mut boot = Bootloader::new(&mut flash, PARTS);
+    assert_eq!(boot.start(), Start::Run);
+    assert_eq!(contents(&mut flash, PARTS.active, 600), installed);
+
+    // ... unless there is none
+    let mut empty = Flash::new(0, PARTS.page_size);
+    let mut boot = Bootloader::new(&mut empty, PARTS);
+    assert_eq!(boot.handle(Request::Boot), Reply::Nak(Nak::Sequence));
+}
+
+#[test]
+fn flash_failures_during_an_upload_are_reported() {
+    let mut flash = flash_with(&image(600, 3));
+    let new = image(300, 11);
+    let mut boot = Bootloader::new(&mut flash, PARTS);
+    let begin = Request::Begin {
+        len: 300,
+        crc: crc32(&new),
+    };
+    assert_eq!(boot.handle(begin), Reply::Ack(0));
+    flash.fail_writes(true);
+    let mut boot = Bootloader::new(&mut flash, PARTS);
+    boot.handle(begin);
+    let data = &new[..128];
+    assert_eq!(
+        boot.handle(Request::Write { offset: 0, data }),
+        Reply::Nak(Nak::Flash)
+    );
+    // The upload has to start over
+    assert_eq!(
+        boot.handle(Request::Write { offset: 0, data }),
+        Reply::Nak(Nak::Sequence)
+    );
+}
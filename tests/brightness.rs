@@ -0,0 +1,73 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Gamma table, brightness curves and the PWM LED adapter
+
+use blink::brightness::{gamma, Effect, PwmLed, FULL_DUTY, GAMMA};
+use blink::mock::MockPwm;
+use blink::pattern::LedPin;
+
+#[test]
+fn gamma_spans_off_to_full_and_never_decreases() {
+    assert_eq!(gamma(0), 0);
+    assert_eq!(gamma(255), FULL_DUTY);
+    assert!(GAMMA.windows(2).all(|w| w[0] <= w[1]));
+}
+
+#[test]
+fn gamma_follows_the_cie_lightness_curve() {
+    // L* = 50 is about 18.4% luminance
+    let mid = f64::from(gamma(128)) / f64::from(FULL_DUTY);
+    assert!((0.18..0.19).contains(&mid), "{mid}");
+    // The linear segment near black keeps the first steps tiny but non-zero
+    assert!(gamma(1) > 0 && gamma(1) < 40);
+    // Both segments meet at L* = 8 without a jump
+    assert!(gamma(21) - gamma(20) < 40);
+}
+
+#[test]
+fn breathe_is_a_symmetric_triangle() {
+    let breathe = Effect::Breathe { period_ms: 2000 };
+    assert_eq!(breathe.level_at(0), 0);
+    assert_eq!(breathe.level_at(500), 127);
+    assert_eq!(breathe.level_at(1000), 255);
+    assert_eq!(breathe.level_at(1500), 128);
+    assert_eq!(breathe.level_at(2000), 0);
+    for t in 0..1000 {
+        let rising = breathe.level_at(t);
+        let falling = breathe.level_at(2000 - t);
+        assert!(rising.abs_diff(falling) <= 1, "t={t}");
+    }
+    assert_eq!(breathe.settle_ms(), None);
+}
+
+#[test]
+fn fades_ramp_then_hold() {
+    let fade_in = Effect::FadeIn { ms: 400 };
+    let fade_out = Effect::FadeOut { ms: 400 };
+    assert_eq!(fade_in.level_at(0), 0);
+    assert_eq!(fade_in.level_at(200), 127);
+    assert_eq!(fade_in.level_at(400), 255);
+    assert_eq!(fade_in.level_at(10_000), 255);
+    assert_eq!(fade_out.level_at(0), 255);
+    assert_eq!(fade_out.level_at(400), 0);
+    assert_eq!(fade_in.settle_ms(), Some(400));
+}
+
+#[test]
+fn dim_percent_maps_onto_levels() {
+    assert_eq!(Effect::dim_percent(0), Effect::Dim { level: 0 });
+    assert_eq!(Effect::dim_percent(50), Effect::Dim { level: 128 });
+    assert_eq!(Effect::dim_percent(100), Effect::Dim { level: 255 });
+    assert_eq!(Effect::dim_percent(200), Effect::Dim { level: 255 });
+    assert_eq!(Effect::dim_percent(40).level_at(12_345), 102);
+}
+
+#[test]
+fn pwm_led_scales_duty_to_the_timer() {
+    let mut led = PwmLed::new(MockPwm::new(36_000));
+    led.set_on();
+    led.set_level(FULL_DUTY / 5);
+    led.set_off();
+    assert_eq!(led.channel().history(), &[0, 36_000, 7_200, 0]);
+}
@@ -0,0 +1,93 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Build metadata record layout and formatting
+
+use blink::build_info::{BuildInfo, Iso8601, LEN, MAGIC};
+
+#[test]
+fn fields_round_trip_through_the_record() {
+    let info = BuildInfo::new(
+        "1.2.3",
+        "0123456789abcdef0123456789abcdef01234567",
+        true,
+        1_750_000_000,
+        "release",
+        "board-f303re,telemetry",
+    );
+    assert_eq!(info.version(), "1.2.3");
+    assert_eq!(info.commit(), "0123456789abcdef0123456789abcdef01234567");
+    assert!(info.dirty());
+    assert_eq!(info.timestamp(), 1_750_000_000);
+    assert_eq!(info.profile(), "release");
+    assert_eq!(info.features(), "board-f303re,telemetry");
+}
+
+#[test]
+fn record_layout_matches_the_documented_offsets() {
+    let info = BuildInfo::new("0.1.0", "abc", false, 0x0102_0304_0506_0708, "debug", "x");
+    let bytes = info.as_bytes();
+    assert_eq!(bytes.len(), LEN);
+    assert_eq!(bytes[..4], MAGIC);
+    assert_eq!(bytes[4], 1);
+    assert_eq!(bytes[5..8], [0, 0, 0]);
+    assert_eq!(bytes[8..16], [8, 7, 6, 5, 4, 3, 2, 1]);
+    assert_eq!(&bytes[16..22], b"0.1.0\0");
+    assert_eq!(&bytes[32..36], b"abc\0");
+    assert_eq!(&bytes[72..78], b"debug\0");
+    assert_eq!(&bytes[88..90], b"x\0");
+}
+
+#[test]
+fn long_text_is_cut_at_the_field_size() {
+    let features = "f".repeat(100);
+    let info = BuildInfo::new(
+        "0.1.0",
+        "abc",
+        false,
+        0,
+        "a-very-long-profile-name",
+        &features,
+    );
+    assert_eq!(info.profile(), "a-very-long-prof");
+    assert_eq!(info.features().len(), 64);
+}
+
+#[test]
+fn display_is_one_banner_line() {
+    let info = BuildInfo::new(
+        "0.1.0",
+        "0123456789abcdef0123456789abcdef01234567",
+        true,
+        1_750_000_000,
+        "release",
+        "board-f303re",
+    );
+    assert_eq!(
+        info.to_string(),
+        "v0.1.0 0123456789ab-dirty release 2025-06-15T15:06:40Z [board-f303re]"
+    );
+    let clean = BuildInfo::new("0.1.0", "unknown", false, 0, "debug", "");
+    assert_eq!(
+        clean.to_string(),
+        "v0.1.0 unknown debug 1970-01-01T00:00:00Z []"
+    );
+}
+
+#[test]
+fn timestamps_format_as_utc_dates() {
+    assert_eq!(Iso8601(0).to_string(), "1970-01-01T00:00:00Z");
+    assert_eq!(Iso8601(951_782_400).to_string(), "2000-02-29T00:00:00Z");
+    assert_eq!(Iso8601(1_735_689_599).to_string(), "2024-12-31T23:59:59Z");
+    assert_eq!(Iso8601(4_107_542_400).to_string(), "2100-03-01T00:00:00Z");
+}
+
+#[test]
+fn current_build_is_recorded_by_the_build_script() {
+    let info = BuildInfo::CURRENT;
+    assert_eq!(info.version(), env!("CARGO_PKG_VERSION"));
+    assert!(info.features().split(',').any(|f| f.starts_with("board-")));
+    assert!(!info.features().split(',').any(|f| f == "default"));
+    let commit = info.commit();
+    assert!(commit == "unknown" || commit.len() == 40, "{commit}");
+}
@@ -0,0 +1,125 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Gesture classification with a fake millisecond clock
+
+use blink::button::{ButtonTiming, Gesture, GestureDetector};
+
+const TIMING: ButtonTiming = ButtonTiming {
+    debounce_ms: 20,
+    long_press_ms: 800,
+    double_click_ms: 300,
+};
+
+/// Samples the detector every millisecond up to `end_ms`
+///
+/// `edges` lists `(time, pressed)` raw level changes in time order.
+fn run(edges: &[(u64, bool)], end_ms: u64) -> Vec<(u64, Gesture)> {
+    let mut detector = GestureDetector::new(TIMING);
+    let mut gestures = Vec::new();
+    let mut level = false;
+    let mut next = edges.iter().peekable();
+    for now in 0..=end_ms {
+        while let Some(&(_, pressed)) = next.next_if(|(at, _)| *at == now) {
+            level = pressed;
+        }
+        if let Some(gesture) = detector.update(now, level) {
+            gestures.push((now, gesture));
+        }
+    }
+    gestures
+}
+
+#[test]
+fn bouncy_tap_is_one_short_press() {
+    let gestures = run(
+        &[(100, true), (102, false), (104, true), (200, false)],
+        1000,
+    );
+    // Released at 200 (+20 debounce), reported once the double-click window ends
+    assert_eq!(gestures, vec![(520, Gesture::Short)]);
+}
+
+#[test]
+fn hold_reports_long_press_while_still_held() {
+    let gestures = run(&[(100, true), (1500, false)], 2000);
+    assert_eq!(gestures, vec![(920, Gesture::Long)]);
+}
+
+#[test]
+fn two_quick_taps_are_a_double_click() {
+    let gestures = run(
+        &[(100, true), (200, false), (300, true), (400, false)],
+        2000,
+    );
+    assert_eq!(gestures, vec![(420, Gesture::Double)]);
+}
+
+#[test]
+fn bouncy_release_is_one_edge() {
+    let gestures = run(
+        &[(100, true), (300, false), (303, true), (306, false)],
+        1000,
+    );
+    // Settled at 326, short once the double-click window closes
+    assert_eq!(gestures, vec![(626, Gesture::Short)]);
+}
+
+#[test]
+fn release_just_before_the_long_press_threshold_is_short() {
+    // Press accepted at 120, so a long press would fire at 920; released at 919
+    let gestures = run(&[(100, true), (899, false)], 2000);
+    assert_eq!(gestures, vec![(1219, Gesture::Short)]);
+}
+
+#[test]
+fn release_after_a_long_press_reports_nothing_more() {
+    let gestures = run(&[(100, true), (1000, false)], 3000);
+    assert_eq!(gestures, vec![(920, Gesture::Long)]);
+}
+
+#[test]
+fn slow_second_tap_is_two_short_presses() {
+    let gestures = run(
+        &[(100, true), (200, false), (600, true), (700, false)],
+        2000,
+    );
+    assert_eq!(
+        gestures,
+        vec![(520, Gesture::Short), (1020, Gesture::Short)]
+    );
+}
+
+#[test]
+fn bouncy_double_click_is_recognised() {
+    let gestures = run(
+        &[
+            (100, true),
+            (101, false),
+            (102, true),
+            (200, false),
+            (300, true),
+            (305, false),
+            (306, true),
+            (400, false),
+        ],
+        2000,
+    );
+    assert_eq!(gestures, vec![(420, Gesture::Double)]);
+}
+
+#[test]
+fn glitch_shorter_than_debounce_is_ignored() {
+    assert_eq!(run(&[(100, true), (110, false)], 2000), vec![]);
+}
+
+#[test]
+fn deadline_tracks_pending_work() {
+    let mut detector = GestureDetector::new(TIMING);
+    assert_eq!(detector.next_deadline(), None);
+    detector.update(100, true);
+    assert_eq!(detector.next_deadline(), Some(120));
+    detector.update(120, true);
+    assert!(detector.is_pressed());
+    assert_eq!(detector.next_deadline(), Some(920));
+}
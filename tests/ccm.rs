@@ -0,0 +1,43 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! CCM RAM placement macro and its zero-initializer check
+
+use core::cell::Cell;
+
+use blink::ccm::is_zeroed;
+
+blink::ccm! {
+    /// Copied from flash at boot on the board
+    static TABLE: [u16; 4] = [1, 2, 3, 4];
+}
+
+blink::ccm! {
+    #[zeroed]
+    /// Zero-filled at boot on the board
+    pub static COUNTER: Counter = Counter(Cell::new(0));
+}
+
+/// Interior-mutable static, as the firmware's queues are
+pub struct Counter(Cell<u32>);
+
+// SAFETY: the tests below only touch `COUNTER` from one thread
+unsafe impl Sync for Counter {}
+
+#[test]
+fn statics_keep_their_initializers() {
+    assert_eq!(TABLE, [1, 2, 3, 4]);
+    assert_eq!(COUNTER.0.get(), 0);
+    COUNTER.0.set(3);
+    assert_eq!(COUNTER.0.get(), 3);
+}
+
+#[test]
+fn zero_check_inspects_every_byte() {
+    const { assert!(is_zeroed(&[0u32; 8])) };
+    assert!(is_zeroed(&(0u8, 0u8)));
+    assert!(is_zeroed(&()));
+    assert!(!is_zeroed(&[0u8, 0, 0, 1]));
+    assert!(!is_zeroed(&0x0100_0000u32));
+    assert!(!is_zeroed(&Some(0u8)));
+}
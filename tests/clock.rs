@@ -0,0 +1,126 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Calendar validation, ISO-8601 parsing and formatting, and date arithmetic
+
+use blink::clock::{days_in_month, is_leap_year, DateTime, DateTimeError};
+
+/// Builds a date and time that is known to be valid
+fn at(year: u16, month: u8, day: u8, hour: u8, minute: u8, second: u8) -> DateTime {
+    DateTime::new(year, month, day, hour, minute, second).unwrap()
+}
+
+#[test]
+fn iso_8601_round_trips() {
+    let time = DateTime::parse("2025-06-01T12:34:56Z").unwrap();
+    assert_eq!(time, at(2025, 6, 1, 12, 34, 56));
+    assert_eq!(time.to_string(), "2025-06-01T12:34:56Z");
+    // The zone designator is optional and case is not significant
+    assert_eq!(DateTime::parse("2025-06-01t12:34:56"), Ok(time));
+    assert_eq!(DateTime::parse("2025-06-01T12:34:56z"), Ok(time));
+}
+
+#[test]
+fn malformed_text_is_a_format_error() {
+    for text in [
+        "",
+        "2025-06-01",
+        "2025-06-01 12:34:56",
+        "2025-6-01T12:34:56Z",
+        "2025-06-01T12:34:56+02:00",
+        "2025-06-01T12:34:5xZ",
+        "2025/06/01T12:34:56Z",
+        "2025-06-01T12:34:56ZZ",
+    ] {
+        assert_eq!(DateTime::parse(text), Err(DateTimeError::Format), "{text}");
+    }
+}
+
+#[test]
+fn fields_are_range_checked() {
+    for text in [
+        "2025-13-01T00:00:00Z",
+        "2025-00-01T00:00:00Z",
+        "2025-04-31T00:00:00Z",
+        "2025-02-29T00:00:00Z",
+        "2025-06-00T00:00:00Z",
+        "2025-06-01T24:00:00Z",
+        "2025-06-01T23:60:00Z",
+        "2025-06-01T23:59:60Z",
+        "1969-12-31T23:59:59Z",
+    ] {
+        assert_eq!(
+            DateTime::parse(text),
+            Err(DateTimeError::OutOfRange),
+            "{text}"
+        );
+    }
+    assert!(DateTime::parse("2024-02-29T23:59:59Z").is_ok());
+}
+
+#[test]
+fn leap_years_follow_the_gregorian_rules() {
+    assert!(is_leap_year(2024));
+    assert!(!is_leap_year(2025));
+    assert!(!is_leap_year(2100));
+    assert!(is_leap_year(2000));
+    assert_eq!(days_in_month(2024, 2), 29);
+    assert_eq!(days_in_month(2100, 2), 28);
+    assert_eq!(days_in_month(2025, 9), 30);
+    assert_eq!(days_in_month(2025, 12), 31);
+    assert_eq!(days_in_month(2025, 13), 0);
+}
+
+#[test]
+fn unix_time_converts_both_ways() {
+    assert_eq!(DateTime::from_unix(0), at(1970, 1, 1, 0, 0, 0));
+    assert_eq!(
+        DateTime::from_unix(1_750_000_000),
+        at(2025, 6, 15, 15, 6, 40)
+    );
+    assert_eq!(at(2000, 3, 1, 0, 0, 0).to_unix(), 951_868_800);
+    // Every day of a leap-year span survives the round trip
+    let start = at(2023, 12, 30, 23, 59, 59).to_unix();
+    for day in 0..800 {
+        let secs = start + day * 86_400;
+        assert_eq!(DateTime::from_unix(secs).to_unix(), secs);
+    }
+}
+
+#[test]
+fn adding_seconds_rolls_over_days_months_and_years() {
+    assert_eq!(
+        at(2024, 2, 28, 23, 59, 59).plus_secs(1),
+        at(2024, 2, 29, 0, 0, 0)
+    );
+    assert_eq!(
+        at(2025, 2, 28, 23, 59, 59).plus_secs(1),
+        at(2025, 3, 1, 0, 0, 0)
+    );
+    assert_eq!(
+        at(2099, 12, 31, 23, 0, 0).plus_secs(3_600),
+        at(2100, 1, 1, 0, 0, 0)
+    );
+    assert_eq!(
+        at(2025, 6, 1, 0, 0, 0).plus_secs(0),
+        at(2025, 6, 1, 0, 0, 0)
+    );
+}
+
+#[test]
+fn weekdays_and_times_of_day() {
+    // 1970-01-01 was a Thursday, 2000-01-01 a Saturday, 2025-06-01 a Sunday
+    assert_eq!(at(1970, 1, 1, 0, 0, 0).weekday(), 4);
+    assert_eq!(at(2000, 1, 1, 0, 0, 0).weekday(), 6);
+    assert_eq!(at(2025, 6, 1, 0, 0, 0).weekday(), 7);
+    assert_eq!(at(2025, 6, 2, 0, 0, 0).weekday(), 1);
+    assert_eq!(at(2025, 6, 1, 22, 30, 15).seconds_of_day(), 81_015);
+}
+
+#[test]
+fn only_the_rtc_century_fits_the_calendar() {
+    assert!(at(2000, 1, 1, 0, 0, 0).fits_rtc());
+    assert!(at(2099, 12, 31, 23, 59, 59).fits_rtc());
+    assert!(!at(1999, 12, 31, 23, 59, 59).fits_rtc());
+    assert!(!at(2100, 1, 1, 0, 0, 0).fits_rtc());
+}
@@ -0,0 +1,123 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Shell input as seen by the UART input task: echoes and completed lines
+
+use std::future::Future;
+use std::pin::pin;
+use std::task::{Context, Poll, Waker};
+
+use blink::event::{echo, AppEvent, Output, ShellInput, OUTPUT_TEXT};
+use blink::messages;
+use blink::mock::MockSerial;
+use blink::monitor::RxMonitor;
+use blink::shell::MAX_LINE;
+
+/// Polls a future that never waits (all mock I/O completes immediately)
+fn block_on<F: Future>(fut: F) -> F::Output {
+    let mut fut = pin!(fut);
+    let mut cx = Context::from_waker(Waker::noop());
+    match fut.as_mut().poll(&mut cx) {
+        Poll::Ready(out) => out,
+        Poll::Pending => panic!("mock future did not complete"),
+    }
+}
+
+/// Feeds `bytes`, returning everything echoed and every event produced
+fn feed(input: &mut ShellInput, bytes: &[u8]) -> (Vec<u8>, Vec<AppEvent>) {
+    let mut echoed = Vec::new();
+    let mut events = Vec::new();
+    for &b in bytes {
+        let (bytes, event) = input.push(b);
+        echoed.extend_from_slice(bytes);
+        events.extend(event);
+    }
+    (echoed, events)
+}
+
+#[test]
+fn typed_lines_are_echoed_and_become_events() {
+    let mut input = ShellInput::new();
+    let (echoed, events) = feed(&mut input, b"led onx\x7f\r");
+    assert_eq!(echoed, b"led onx\x08 \x08\r\n");
+    assert_eq!(events, [AppEvent::Line("led on".try_into().unwrap())]);
+}
+
+#[test]
+fn bare_terminators_produce_empty_lines() {
+    let mut input = ShellInput::new();
+    let (_, events) = feed(&mut input, b"status\n\r");
+    assert_eq!(
+        events,
+        [
+            AppEvent::Line("status".try_into().unwrap()),
+            AppEvent::Line("".try_into().unwrap()),
+        ]
+    );
+}
+
+#[test]
+fn crlf_ends_a_single_line() {
+    let mut input = ShellInput::new();
+    let (echoed, events) = feed(&mut input, b"help\r\n");
+    assert_eq!(echoed, b"help\r\n");
+    assert_eq!(events, [AppEvent::Line("help".try_into().unwrap())]);
+
+    // Only the LF straight after a CR is swallowed
+    let (echoed, events) = feed(&mut input, b"\r\n\n");
+    assert_eq!(echoed, b"\r\n\r\n");
+    let empty = AppEvent::Line("".try_into().unwrap());
+    assert_eq!(events, [empty.clone(), empty]);
+}
+
+#[test]
+fn bursts_from_the_receiver_become_whole_lines() {
+    // Two commands pasted at once, drained from the receiver in one read
+    let mut rx = MockSerial::new();
+    rx.feed(b"led on\rstatus\r");
+    let mut rx = RxMonitor::new(rx);
+    let mut buf = [0u8; MAX_LINE];
+    let len = block_on(rx.receive(&mut buf));
+    assert_eq!(len, 14);
+
+    let (echoed, events) = feed(&mut ShellInput::new(), &buf[..len]);
+    assert_eq!(echoed, b"led on\r\nstatus\r\n");
+    assert_eq!(
+        events,
+        [
+            AppEvent::Line("led on".try_into().unwrap()),
+            AppEvent::Line("status".try_into().unwrap()),
+        ]
+    );
+    assert_eq!(rx.errors().total(), 0);
+}
+
+#[test]
+fn overlong_lines_are_reported_once() {
+    let mut input = ShellInput::new();
+    let long = [b'x'; 100];
+    let (_, events) = feed(&mut input, &long);
+    assert!(events.is_empty());
+    let (_, events) = feed(&mut input, b"\r");
+    assert_eq!(events, [AppEvent::LineTooLong]);
+}
+
+#[test]
+fn control_bytes_are_not_echoed() {
+    assert_eq!(echo(b'\n'), messages::NEWLINE);
+    assert_eq!(echo(0x1b), b"");
+    assert_eq!(echo(0xC3), b"");
+    assert_eq!(echo(b'~'), b"~");
+}
+
+#[test]
+fn notices_copy_text_up_to_capacity() {
+    assert_eq!(
+        Output::notice(b"LED ON\r\n"),
+        Output::Notice("LED ON\r\n".try_into().unwrap())
+    );
+    let Output::Notice(line) = Output::notice(&[b'x'; 200]) else {
+        panic!("not a notice");
+    };
+    assert_eq!(line.len(), OUTPUT_TEXT);
+}
@@ -0,0 +1,340 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Memory descriptor parsing and validation (shared with `build.rs`)
+
+#[path = "../memory/layout.rs"]
+#[allow(dead_code)]
+mod layout;
+
+use layout::{Descriptor, Layout, LayoutError, Region, UpdateSlots, K};
+
+const F303RE: &str = r#"
+chip = "STM32F303RE"
+[flash]
+origin = 0x08000000
+length = "512K"
+[ram]
+origin = 0x20000000   # SRAM
+length = 65536
+[ccmram]
+origin = 0x10000000
+length = "16K"
+[settings]
+pages = 2
+page_size = "2K"
+"#;
+
+/// Parses `text` and validates it for `chip`
+fn layout(text: &str, chip: &str) -> Result<Layout, LayoutError> {
+    Layout::new(&Descriptor::parse(text)?, chip)
+}
+
+#[test]
+fn every_board_descriptor_validates() {
+    for (file, chip) in [
+        ("f303re", "STM32F303RE"),
+        ("f401re", "STM32F401RE"),
+        ("l476rg", "STM32L476RG"),
+        ("g431rb", "STM32G431RB"),
+    ] {
+        let path = format!("{}/memory/{file}.toml", env!("CARGO_MANIFEST_DIR"));
+        let text = std::fs::read_to_string(&path).unwrap();
+        let result = layout(&text, chip);
+        assert!(result.is_ok(), "{path}: {}", result.unwrap_err());
+    }
+}
+
+#[test]
+fn flash_is_split_into_app_and_settings() {
+    let layout = layout(F303RE, "STM32F303RE").unwrap();
+    assert_eq!(layout.app, Region::new(0x0800_0000, 508 * K));
+    assert_eq!(layout.settings, Region::new(0x0807_F000, 4 * K));
+    let script = layout.memory_x("f303re.toml");
+    assert!(script.contains("  FLASH : ORIGIN = 0x08000000, LENGTH = 508K\n"));
+    assert!(script.contains("  RAM : ORIGIN = 0x20000000, LENGTH = 64K\n"));
+    assert!(script.contains("  CCMRAM : ORIGIN = 0x10000000, LENGTH = 16K\n"));
+    assert!(script.contains("__settings_start = 0x0807f000;"));
+    assert!(script.contains("} > CCMRAM AT > FLASH"));
+    assert!(script.contains("KEEP(*(.build_info .build_info.*));"));
+    assert!(!script.contains("_stack_start"));
+    let constants = layout.constants("f303re.toml");
+    assert!(constants.contains("pub const SETTINGS_OFFSET: u32 = 0x7f000;"));
+    assert!(constants.contains("pub const SETTINGS_PAGE_SIZE: u32 = 0x800;"));
+}
+
+#[test]
+fn the_stack_can_move_to_ccm_ram() {
+    let text = F303RE.replace("length = \"16K\"", "length = \"16K\"\nstack = true");
+    let script = layout(&text, "STM32F303RE")
+        .unwrap()
+        .memory_x("f303re.toml");
+    assert!(script.contains("_stack_start = 0x10004000;"));
+    assert!(script.contains("_stack_end = __eccmdata;"));
+
+    // Without CCM RAM the sections fall back to RAM and there is no stack to move
+    let text = F303RE.replace("[ccmram]", "[unused]");
+    assert!(Descriptor::parse(&text).is_err());
+    let without = F303RE.replace("[ccmram]\norigin = 0x10000000\nlength = \"16K\"\n", "");
+    let script = layout(&without, "STM32F303RE")
+        .unwrap()
+        .memory_x("f303re.toml");
+    assert!(script.contains("INSERT AFTER .bss;"));
+    assert!(script.contains("__sccmbss = 0;"));
+    assert_eq!(
+        Descriptor::parse(&format!("{without}[ccmram]\nstack = true\n")).unwrap_err(),
+        LayoutError::Missing("ccmram.origin")
+    );
+}
+
+#[test]
+fn bootloader_offsets_the_application() {
+    let text = format!("{F303RE}[bootloader]\nlength = \"16K\"\n");
+    let layout = layout(&text, "STM32F303RE").unwrap();
+    assert_eq!(layout.bootloader, Region::new(0x0800_0000, 16 * K));
+    assert_eq!(layout.app, Region::new(0x0800_4000, 492 * K));
+    assert!(layout
+        .memory_x("f303re.toml")
+        .contains("FLASH : ORIGIN = 0x08004000, LENGTH = 492K"));
+
+    // Not a multiple of the 2 KB page
+    let text = format!("{F303RE}[bootloader]\nlength = 3000\n");
+    assert_eq!(
+        layout_err(&text, "STM32F303RE"),
+        LayoutError::Misaligned {
+            what: "the application",
+            offset: 3000
+        }
+    );
+}
+
+#[test]
+fn update_slots_follow_the_bootloader() {
+    let text = format!("{F303RE}[bootloader]\nlength = \"32K\"\nslot = \"232K\"\n");
+    let layout = layout(&text, "STM32F303RE").unwrap();
+    assert_eq!(layout.app, Region::new(0x0800_9000, 232 * K));
+    assert_eq!(
+        layout.update,
+        Some(UpdateSlots {
+            state: Region::new(0x0800_8000, 4 * K),
+            download: Region::new(0x0804_3000, 232 * K),
+            scratch: Region::new(0x0807_D000, 2 * K),
+            page_size: 2 * K,
+        })
+    );
+    let constants = layout.constants("f303re.toml");
+    assert!(constants.contains("pub const APP: (u32, u32) = (0x9000, 0x3a000);"));
+    assert!(constants.contains("pub const BOOT_STATE: (u32, u32) = (0x8000, 0x1000);"));
+    assert!(constants.contains("pub const SCRATCH: (u32, u32) = (0x7d000, 0x800);"));
+    assert!(constants.contains("pub const CCMRAM: (u32, u32) = (0x10000000, 0x4000);"));
+
+    // The bootloader links into its partition and the top of RAM
+    let script = layout.bootloader_memory_x("f303re.toml");
+    assert!(script.contains("  FLASH : ORIGIN = 0x08000000, LENGTH = 32K\n"));
+    assert!(script.contains("  RAM : ORIGIN = 0x2000e000, LENGTH = 8K\n"));
+    assert!(!script.contains("CCMRAM :"));
+
+    // Without a slot there are no update partitions
+    let constants = layout_without_slots().constants("f303re.toml");
+    assert!(constants.contains("pub const DOWNLOAD: (u32, u32) = (0x0, 0x0);"));
+}
+
+#[test]
+fn update_slots_need_pages_and_room() {
+    let slots = |length: &str, slot: &str| {
+        format!("{F303RE}[bootloader]\nlength = {length}\nslot = {slot}\n")
+    };
+    assert_eq!(
+        layout_err(&slots("\"32K\"", "\"233K\""), "STM32F303RE"),
+        LayoutError::Misaligned {
+            what: "the download slot",
+            offset: 0x0004_3400
+        }
+    );
+    assert_eq!(
+        layout_err(&slots("0", "\"232K\""), "STM32F303RE"),
+        LayoutError::Missing("bootloader.length")
+    );
+    assert!(matches!(
+        layout_err(&slots("\"32K\"", "\"236K\""), "STM32F303RE"),
+        LayoutError::NoRoom { .. }
+    ));
+    let text = r#"
+        chip = "STM32F401RE"
+        [flash]
+        origin = 0x08000000
+        length = "512K"
+        [ram]
+        origin = 0x20000000
+        length = "96K"
+        [bootloader]
+        length = "16K"
+        slot = "128K"
+    "#;
+    assert_eq!(layout_err(text, "STM32F401RE"), LayoutError::UnevenErase);
+}
+
+/// The F303RE test descriptor, validated
+fn layout_without_slots() -> Layout {
+    layout(F303RE, "STM32F303RE").unwrap()
+}
+
+#[test]
+fn sizes_must_match_the_selected_chip() {
+    assert!(matches!(
+        layout_err(F303RE, "STM32F401RE"),
+        LayoutError::WrongChip { .. }
+    ));
+    let text = F303RE.replace("65536", "\"80K\"");
+    let err = layout_err(&text, "STM32F303RE");
+    assert!(matches!(err, LayoutError::Mismatch { region: "ram", .. }));
+    assert_eq!(
+        err.to_string(),
+        "[ram] is 80K at 0x20000000, but the chip has 64K at 0x20000000"
+    );
+    let text = F303RE.replace("STM32F303RE", "STM32F999ZZ");
+    assert!(matches!(
+        layout_err(&text, "STM32F303RE"),
+        LayoutError::UnknownChip(_)
+    ));
+}
+
+#[test]
+fn settings_pages_must_be_erase_units() {
+    let text = F303RE.replace("\"2K\"", "\"1K\"");
+    assert_eq!(
+        layout_err(&text, "STM32F303RE"),
+        LayoutError::PageSize {
+            declared: K,
+            erase: 2 * K
+        }
+    );
+
+    // The F401 erases 16-128 KB sectors: three 128 KB pages leave sectors 0-4
+    // (128 KB) to the application, but a 64 KB page is not a whole sector there
+    let text = r#"
+        chip = "STM32F401RE"
+        [flash]
+        origin = 0x08000000
+        length = "512K"
+        [ram]
+        origin = 0x20000000
+        length = "96K"
+        [settings]
+        pages = 3
+        page_size = "128K"
+    "#;
+    assert_eq!(layout(text, "STM32F401RE").unwrap().app.length, 128 * K);
+    let text = text
+        .replace("pages = 3", "pages = 2")
+        .replace("128K", "64K");
+    assert_eq!(
+        layout_err(&text, "STM32F401RE"),
+        LayoutError::PageSize {
+            declared: 64 * K,
+            erase: 128 * K
+        }
+    );
+}
+
+#[test]
+fn settings_can_sit_between_the_vector_table_and_the_code() {
+    // The F401's 16 KB sectors 2 and 3: the code starts at sector 4
+    let text = r#"
+        chip = "STM32F401RE"
+        [flash]
+        origin = 0x08000000
+        length = "512K"
+        [ram]
+        origin = 0x20000000
+        length = "96K"
+        [settings]
+        pages = 2
+        page_size = "16K"
+        offset = "32K"
+    "#;
+    let inside = layout(text, "STM32F401RE").unwrap();
+    assert_eq!(inside.app, Region::new(0x0800_0000, 512 * K));
+    assert_eq!(inside.settings, Region::new(0x0800_8000, 32 * K));
+    assert_eq!(inside.text, Some(0x0801_0000));
+    let script = inside.memory_x("f401re.toml");
+    assert!(script.contains("  FLASH : ORIGIN = 0x08000000, LENGTH = 512K\n"));
+    assert!(script.contains("_stext = 0x08010000;"));
+    assert!(script.contains("SIZEOF(.vector_table) <= __settings_start"));
+    assert!(inside
+        .constants("f401re.toml")
+        .contains("pub const SETTINGS_OFFSET: u32 = 0x8000;"));
+
+    // Without an offset there is no gap in the code
+    let end = layout(F303RE, "STM32F303RE").unwrap();
+    assert_eq!(end.text, None);
+    assert!(!end.memory_x("f303re.toml").contains("_stext"));
+
+    // The 64 KB sector 4 is not a 16 KB page, and the vector table comes first
+    assert_eq!(
+        layout_err(&text.replace("\"32K\"", "\"64K\""), "STM32F401RE"),
+        LayoutError::PageSize {
+            declared: 16 * K,
+            erase: 64 * K
+        }
+    );
+    assert_eq!(
+        layout_err(&text.replace("\"32K\"", "0"), "STM32F401RE"),
+        LayoutError::Overlap("SETTINGS", "FLASH")
+    );
+    assert!(matches!(
+        layout_err(&text.replace("\"32K\"", "\"496K\""), "STM32F401RE"),
+        LayoutError::NoRoom { .. }
+    ));
+}
+
+#[test]
+fn settings_inside_the_application_exclude_update_slots() {
+    let text = format!(
+        "{}offset = \"32K\"\n[bootloader]\nlength = \"16K\"\nslot = \"200K\"\n",
+        F303RE
+    );
+    assert_eq!(
+        layout_err(&text, "STM32F303RE"),
+        LayoutError::Overlap("SETTINGS", "FLASH")
+    );
+    let text = format!("{F303RE}offset = \"8K\"\n[bootloader]\nlength = \"16K\"\n");
+    assert_eq!(
+        layout_err(&text, "STM32F303RE"),
+        LayoutError::Overlap("SETTINGS", "BOOTLOADER")
+    );
+}
+
+#[test]
+fn reservations_must_leave_room_for_the_application() {
+    let text = format!("{F303RE}[bootloader]\nlength = \"508K\"\n");
+    assert!(matches!(
+        layout_err(&text, "STM32F303RE"),
+        LayoutError::NoRoom { .. }
+    ));
+}
+
+#[test]
+fn malformed_descriptors_report_the_line() {
+    let err = Descriptor::parse("chip = \"STM32F303RE\"\n[flash]\norigin 0x08000000\n");
+    assert_eq!(
+        err.unwrap_err(),
+        LayoutError::Syntax {
+            line: 3,
+            message: "expected `key = value`, found `origin 0x08000000`".into()
+        }
+    );
+    assert!(matches!(
+        Descriptor::parse("[flash]\nsize = 1\n"),
+        Err(LayoutError::Syntax { line: 2, .. })
+    ));
+    assert_eq!(
+        Descriptor::parse("chip = \"STM32F303RE\"\n[flash]\norigin = 0\n").unwrap_err(),
+        LayoutError::Missing("flash.length")
+    );
+}
+
+/// Parses and validates `text`, expecting an error
+fn layout_err(text: &str, chip: &str) -> LayoutError {
+    layout(text, chip).unwrap_err()
+}
@@ -0,0 +1,158 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Morse tables, PARIS/Farnsworth timing and message keying
+
+use blink::app::Led;
+use blink::mock::{MockPin, MockSerial};
+use blink::morse::{self, code, MorseError, MorsePlayer, MorseTick, MorseTiming, MAX_MESSAGE};
+use blink::pattern::presets;
+use blink::serial::WriteBlocking;
+use blink::settings::Settings;
+
+/// Standard spacing at 20 WPM: 60 ms unit
+const STANDARD: MorseTiming = MorseTiming {
+    wpm: 20,
+    farnsworth_wpm: 20,
+};
+
+/// Keys `text` to completion, returning `(on, ms)` for every element and the
+/// mirrored characters
+fn key(text: &str, timing: MorseTiming) -> (Vec<(bool, u64)>, String) {
+    let mut player = MorsePlayer::new(text, timing).unwrap();
+    let mut pin = MockPin::new();
+    let (mut elements, mut echo) = (Vec::new(), String::new());
+    let mut now = 0;
+    while let MorseTick::Key {
+        on,
+        until_ms,
+        echo: ch,
+    } = player.poll(now, &mut pin)
+    {
+        elements.push((on, until_ms - now));
+        echo.extend(ch.map(char::from));
+        now = until_ms;
+    }
+    assert!(player.is_finished());
+    assert!(!pin.is_high());
+    (elements, echo)
+}
+
+#[test]
+fn table_covers_letters_digits_and_punctuation() {
+    assert_eq!(code(b'A'), Some(".-"));
+    assert_eq!(code(b'q'), Some("--.-"));
+    assert_eq!(code(b'0'), Some("-----"));
+    assert_eq!(code(b'9'), Some("----."));
+    assert_eq!(code(b'?'), Some("..--.."));
+    assert_eq!(code(b'@'), Some(".--.-."));
+    assert_eq!(code(b' '), None);
+    assert_eq!(code(b'#'), None);
+    for ch in (b'A'..=b'Z').chain(b'0'..=b'9') {
+        let marks = code(ch).unwrap();
+        assert!(!marks.is_empty() && marks.bytes().all(|m| m == b'.' || m == b'-'));
+    }
+}
+
+#[test]
+fn codes_are_unique() {
+    let mut all: Vec<&str> = (0u8..128)
+        .filter(|ch| !ch.is_ascii_lowercase())
+        .filter_map(code)
+        .collect();
+    let count = all.len();
+    all.sort_unstable();
+    all.dedup();
+    assert_eq!(all.len(), count);
+    assert_eq!(count, 26 + 10 + 18);
+}
+
+#[test]
+fn standard_timing_follows_paris() {
+    assert_eq!(STANDARD.dot_ms(), 60);
+    assert_eq!(STANDARD.dash_ms(), 180);
+    assert_eq!(STANDARD.element_gap_ms(), 60);
+    assert_eq!(STANDARD.char_gap_ms(), 180);
+    assert_eq!(STANDARD.word_gap_ms(), 420);
+}
+
+#[test]
+fn farnsworth_stretches_only_the_gaps() {
+    let slow = MorseTiming {
+        wpm: 20,
+        farnsworth_wpm: 10,
+    };
+    assert_eq!(slow.dot_ms(), 60);
+    assert_eq!(slow.element_gap_ms(), 60);
+    // ARRL: (60 * 20 - 37.2 * 10) / (10 * 20) = 4.14 s per 19 spacing units
+    assert_eq!(slow.char_gap_ms(), 653);
+    assert_eq!(slow.word_gap_ms(), 1525);
+
+    // PARIS plus its word gap takes one minute / 10 at the overall speed
+    let (elements, _) = key("PARIS", slow);
+    let total: u64 = elements.iter().map(|&(_, ms)| ms).sum();
+    assert!(total.abs_diff(6000) < 10, "{total}");
+
+    // A Farnsworth speed above the character speed means standard spacing
+    let fast = MorseTiming {
+        wpm: 20,
+        farnsworth_wpm: 30,
+    };
+    assert_eq!(fast.char_gap_ms(), STANDARD.char_gap_ms());
+}
+
+#[test]
+fn message_is_keyed_with_character_and_word_gaps() {
+    let (elements, echo) = key("et  a", STANDARD);
+    assert_eq!(
+        elements,
+        [
+            (true, 60),   // E .
+            (false, 180), // char gap
+            (true, 180),  // T -
+            (false, 420), // word gap
+            (true, 60),   // A .
+            (false, 60),  // element gap
+            (true, 180),  // A -
+            (false, 420), // trailing word gap
+        ]
+    );
+    assert_eq!(echo, "ET A");
+}
+
+#[test]
+fn invalid_messages_are_rejected() {
+    assert_eq!(morse::validate("   "), Err(MorseError::Empty));
+    assert_eq!(
+        morse::validate("SOS #1"),
+        Err(MorseError::Unsupported(b'#'))
+    );
+    assert_eq!(
+        morse::validate(&"E".repeat(MAX_MESSAGE + 1)),
+        Err(MorseError::TooLong)
+    );
+    let player = MorsePlayer::new("  hello   world ", STANDARD).unwrap();
+    assert_eq!(player.text(), "HELLO WORLD");
+}
+
+#[test]
+fn led_mirrors_morse_and_resumes_the_default_pattern() {
+    let mut led = Led::new(MockPin::new(), presets::STEADY, &Settings::default());
+    led.set_morse_timing(STANDARD);
+    led.send_morse("hi there").unwrap();
+    assert_eq!(led.mode_name(), "morse");
+
+    let mut tx = MockSerial::new();
+    let mut now = 0;
+    while led.mode_name() == "morse" {
+        if let Some(msg) = led.poll(now) {
+            tx.blocking_write(msg).unwrap();
+        }
+        now += 1;
+    }
+    assert_eq!(tx.output_str(), "HI THERE\r\n");
+    assert_eq!(led.pattern_name(), presets::STEADY.name);
+    assert!(led.send_morse("50%").is_err());
+    assert_eq!(led.pattern_name(), presets::STEADY.name);
+    assert_eq!(morse::as_static(b'Q'), b"Q");
+}
@@ -0,0 +1,262 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Pattern player sequencing against a fake clock and a recording pin
+
+use blink::mock::MockPin;
+use blink::pattern::{presets, BlinkPattern, PatternPlayer, Repeat, Step, Tick, MAX_STEPS};
+
+/// Drives `player` like the LED task does, from `start_ms` until `end_ms`
+///
+/// The fake clock jumps straight to each returned deadline.
+///
+/// # Returns
+/// Every edge taken as `(time, on)`, ending with `(time, false)` if the
+/// pattern finished.
+fn play(
+    player: &mut PatternPlayer,
+    pin: &mut MockPin,
+    start_ms: u64,
+    end_ms: u64,
+) -> Vec<(u64, bool)> {
+    let mut edges = Vec::new();
+    let mut now = start_ms;
+    while now < end_ms {
+        match player.poll(now, pin) {
+            Tick::Edge { on, until_ms } => {
+                edges.push((now, on));
+                now = until_ms;
+            }
+            Tick::Wait { until_ms } => now = until_ms,
+            Tick::Finished => {
+                edges.push((now, false));
+                break;
+            }
+        }
+    }
+    edges
+}
+
+/// Converts a pattern's steps into the edges of one pass starting at `start_ms`
+fn expected_pass(pattern: &BlinkPattern, start_ms: u64) -> Vec<(u64, bool)> {
+    let mut now = start_ms;
+    pattern
+        .steps
+        .iter()
+        .map(|step| {
+            let edge = (now, step.on);
+            now += u64::from(step.ms);
+            edge
+        })
+        .collect()
+}
+
+#[test]
+fn steady_alternates_every_500_ms() {
+    let mut player = PatternPlayer::new(presets::STEADY);
+    let mut pin = MockPin::new();
+
+    assert_eq!(
+        player.poll(0, &mut pin),
+        Tick::Edge {
+            on: true,
+            until_ms: 500
+        }
+    );
+    assert_eq!(player.poll(100, &mut pin), Tick::Wait { until_ms: 500 });
+    assert_eq!(
+        player.poll(500, &mut pin),
+        Tick::Edge {
+            on: false,
+            until_ms: 1000
+        }
+    );
+    assert_eq!(
+        player.poll(1000, &mut pin),
+        Tick::Edge {
+            on: true,
+            until_ms: 1500
+        }
+    );
+    assert_eq!(pin.history(), &[true, false, true]);
+}
+
+#[test]
+fn late_polls_do_not_accumulate_drift() {
+    let mut player = PatternPlayer::new(presets::STEADY);
+    let mut pin = MockPin::new();
+
+    player.poll(0, &mut pin);
+    // Woken 7 ms late: the next deadline is still on the 500 ms grid
+    assert_eq!(
+        player.poll(507, &mut pin),
+        Tick::Edge {
+            on: false,
+            until_ms: 1000
+        }
+    );
+}
+
+#[test]
+fn finite_pattern_finishes_after_its_repeats() {
+    const TWICE: BlinkPattern = BlinkPattern {
+        name: "twice",
+        steps: &[Step::on(10), Step::off(10)],
+        repeat: Repeat::Times(2),
+    };
+    let mut player = PatternPlayer::new(TWICE);
+    let mut pin = MockPin::new();
+
+    let mut now = 0;
+    while let Tick::Edge { until_ms, .. } = player.poll(now, &mut pin) {
+        now = until_ms;
+    }
+    assert_eq!(now, 40);
+    assert!(player.is_finished());
+    // Finishing always leaves the LED off
+    assert_eq!(pin.history(), &[true, false, true, false, false]);
+    assert!(!pin.is_high());
+}
+
+#[test]
+fn square_wave_uses_the_given_half_period() {
+    let mut player = PatternPlayer::square(250);
+    let mut pin = MockPin::new();
+
+    assert_eq!(
+        player.poll(0, &mut pin),
+        Tick::Edge {
+            on: true,
+            until_ms: 250
+        }
+    );
+    assert_eq!(
+        player.poll(250, &mut pin),
+        Tick::Edge {
+            on: false,
+            until_ms: 500
+        }
+    );
+}
+
+#[test]
+fn presets_are_found_by_name() {
+    for preset in presets::ALL {
+        assert_eq!(presets::by_name(preset.name), Some(*preset));
+    }
+    assert_eq!(presets::by_name("nope"), None);
+}
+
+#[test]
+fn presets_repeat_their_steps_exactly() {
+    for preset in presets::ALL {
+        let period: u64 = preset.steps.iter().map(|s| u64::from(s.ms)).sum();
+        let mut player = PatternPlayer::new(*preset);
+        let mut pin = MockPin::new();
+
+        let edges = play(&mut player, &mut pin, 1000, 1000 + 3 * period);
+        let mut expected = expected_pass(preset, 1000);
+        expected.extend(expected_pass(preset, 1000 + period));
+        expected.extend(expected_pass(preset, 1000 + 2 * period));
+        assert_eq!(edges, expected, "{}", preset.name);
+        let levels: Vec<bool> = expected.iter().map(|&(_, on)| on).collect();
+        assert_eq!(pin.history(), levels.as_slice(), "{}", preset.name);
+    }
+}
+
+#[test]
+fn sos_spells_dots_dashes_and_gaps() {
+    let mut player = PatternPlayer::new(presets::SOS);
+    let mut pin = MockPin::new();
+    let edges = play(&mut player, &mut pin, 0, 6800);
+
+    // Lengths of each lit and dark interval in one pass
+    let lengths: Vec<u64> = edges.windows(2).map(|w| w[1].0 - w[0].0).collect();
+    assert_eq!(
+        lengths,
+        [
+            200, 200, 200, 200, 200, 600, // S
+            600, 200, 600, 200, 600, 600, // O
+            200, 200, 200, 200, 200, // S, then the 1400 ms word gap
+        ]
+    );
+    assert_eq!(edges.last(), Some(&(5400, false)));
+}
+
+#[test]
+fn set_pattern_restarts_playback_from_the_next_poll() {
+    let mut player = PatternPlayer::new(presets::STEADY);
+    let mut pin = MockPin::new();
+    play(&mut player, &mut pin, 0, 750);
+    assert_eq!(player.name(), "steady");
+
+    player.set_pattern(presets::HEARTBEAT);
+    assert_eq!(player.name(), "heartbeat");
+    // Step 0 starts at the switch, not on the old pattern's grid
+    assert_eq!(
+        player.poll(800, &mut pin),
+        Tick::Edge {
+            on: true,
+            until_ms: 900
+        }
+    );
+    assert_eq!(
+        play(&mut player, &mut pin, 900, 1800),
+        [(900, false), (1000, true), (1100, false)]
+    );
+}
+
+#[test]
+fn set_pattern_revives_a_finished_player() {
+    const ONCE: BlinkPattern = BlinkPattern {
+        name: "once",
+        steps: &[Step::on(10)],
+        repeat: Repeat::Times(1),
+    };
+    let mut player = PatternPlayer::new(ONCE);
+    let mut pin = MockPin::new();
+    assert_eq!(
+        play(&mut player, &mut pin, 0, 100),
+        [(0, true), (10, false)]
+    );
+    assert_eq!(player.poll(50, &mut pin), Tick::Finished);
+
+    player.set_pattern(presets::STEADY);
+    assert!(!player.is_finished());
+    assert!(matches!(
+        player.poll(60, &mut pin),
+        Tick::Edge { on: true, .. }
+    ));
+}
+
+#[test]
+fn empty_and_zero_repeat_patterns_finish_without_lighting() {
+    let zero = PatternPlayer::from_steps("zero", &[Step::on(10)], Repeat::Times(0));
+    let empty = PatternPlayer::from_steps("empty", &[], Repeat::Forever);
+    for mut player in [zero, empty] {
+        let mut pin = MockPin::new();
+        assert_eq!(player.poll(0, &mut pin), Tick::Finished);
+        assert!(player.is_finished());
+        assert_eq!(pin.history(), &[false]);
+    }
+}
+
+#[test]
+fn steps_beyond_the_limit_are_dropped() {
+    let steps: Vec<Step> = (0..MAX_STEPS as u32 + 4)
+        .map(|i| {
+            if i % 2 == 0 {
+                Step::on(1)
+            } else {
+                Step::off(1)
+            }
+        })
+        .collect();
+    let mut player = PatternPlayer::from_steps("long", &steps, Repeat::Times(1));
+    let mut pin = MockPin::new();
+
+    let edges = play(&mut player, &mut pin, 0, 1000);
+    // One edge per kept step, then the final switch-off
+    assert_eq!(edges.len(), MAX_STEPS + 1);
+    assert_eq!(edges.last(), Some(&(MAX_STEPS as u64, false)));
+}
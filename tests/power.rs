@@ -0,0 +1,127 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! STOP planning, duty-cycle accounting and the current estimate
+
+use blink::power::{Activity, CurrentModel, DutyCycle, PowerReport, StopPlanner};
+
+const PLANNER: StopPlanner = StopPlanner {
+    min_ms: 20,
+    watchdog_ms: 1_000,
+    wake_margin_ms: 2,
+    input_grace_ms: 10_000,
+};
+
+const MODEL: CurrentModel = CurrentModel {
+    run_ua: 30_000,
+    stop_ua: 10,
+};
+
+const QUIET: Activity = Activity::new();
+
+#[test]
+fn stops_last_until_just_before_the_next_edge() {
+    assert_eq!(PLANNER.plan(1_000, Some(1_500), 1_000, &QUIET), Some(498));
+    // Capped by the watchdog, also while the LED is held
+    assert_eq!(
+        PLANNER.plan(1_000, Some(60_000), 1_000, &QUIET),
+        Some(1_000)
+    );
+    assert_eq!(PLANNER.plan(1_000, None, 1_000, &QUIET), Some(1_000));
+    assert_eq!(PLANNER.plan(1_000, None, 600, &QUIET), Some(600));
+}
+
+#[test]
+fn short_gaps_keep_the_core_awake() {
+    assert_eq!(PLANNER.plan(1_000, Some(1_021), 1_000, &QUIET), None);
+    assert_eq!(PLANNER.plan(1_000, Some(1_022), 1_000, &QUIET), Some(20));
+    assert_eq!(PLANNER.plan(1_000, Some(900), 1_000, &QUIET), None);
+    // A watchdog that is about to run out (or was never fed in time)
+    assert_eq!(PLANNER.plan(1_000, Some(1_500), 10, &QUIET), None);
+    assert_eq!(PLANNER.plan(5_000, Some(5_500), 10, &QUIET), None);
+}
+
+#[test]
+fn activity_keeps_the_core_awake() {
+    let busy = [
+        Activity {
+            uart_busy: true,
+            ..QUIET
+        },
+        Activity {
+            button_busy: true,
+            ..QUIET
+        },
+        Activity {
+            led_dimmed: true,
+            ..QUIET
+        },
+        Activity {
+            last_input_ms: Some(15_000),
+            ..QUIET
+        },
+    ];
+    for activity in &busy {
+        assert_eq!(PLANNER.plan(20_000, None, 20_000, activity), None);
+    }
+    // Shell input only holds the core awake for the grace period
+    let typed = Activity {
+        last_input_ms: Some(15_000),
+        ..QUIET
+    };
+    assert_eq!(PLANNER.plan(25_000, None, 25_000, &typed), Some(1_000));
+}
+
+#[test]
+fn duty_cycle_weights_the_average_current() {
+    let mut duty = DutyCycle::new(5_000);
+    duty.record_stop(9_000);
+    let report = duty.take(15_000, &MODEL);
+    assert_eq!(
+        report,
+        PowerReport {
+            window_ms: 10_000,
+            stopped_ms: 9_000,
+            average_ua: 3_009,
+        }
+    );
+    assert_eq!(report.stop_permille(), 900);
+
+    // The next window starts empty
+    assert_eq!(duty.start_ms(), 15_000);
+    let idle = duty.take(16_000, &MODEL);
+    assert_eq!(idle.stopped_ms, 0);
+    assert_eq!(idle.average_ua, 30_000);
+}
+
+#[test]
+fn empty_windows_report_run_current() {
+    let mut duty = DutyCycle::new(100);
+    duty.record_stop(50);
+    let report = duty.take(100, &MODEL);
+    assert_eq!(report.stopped_ms, 0);
+    assert_eq!(report.stop_permille(), 0);
+    assert_eq!(report.average_ua, 30_000);
+}
+
+#[test]
+fn reports_fit_on_one_line() {
+    let report = PowerReport {
+        window_ms: 10_049,
+        stopped_ms: 9_527,
+        average_ua: 1_712,
+    };
+    assert_eq!(
+        report.to_string(),
+        "power: stop 94.8% of 10.0 s, ~1.71 mA average"
+    );
+    let awake = PowerReport {
+        window_ms: 2_500,
+        stopped_ms: 0,
+        average_ua: 30_000,
+    };
+    assert_eq!(
+        awake.to_string(),
+        "power: stop 0.0% of 2.5 s, ~30.00 mA average"
+    );
+}
@@ -0,0 +1,144 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Time-of-day rules, their encoding and the switches the scheduler makes
+
+use blink::schedule::{parse_clock, parse_window, Rule, Schedule, Scheduler, Switch};
+
+/// Minute of the day for `hours:minutes`
+const fn at(hours: u16, minutes: u16) -> u16 {
+    hours * 60 + minutes
+}
+
+/// Night-time heartbeat (slot 1) and a lunchtime SOS (slot 2)
+fn schedule() -> Schedule {
+    let mut schedule = Schedule::default();
+    schedule.set(0, Rule::new(at(22, 0), at(6, 0), 1));
+    schedule.set(1, Rule::new(at(12, 0), at(13, 30), 3));
+    schedule
+}
+
+#[test]
+fn windows_include_the_start_and_exclude_the_end() {
+    let day = Rule::new(at(12, 0), at(13, 30), 0).unwrap();
+    assert!(!day.contains(at(11, 59)));
+    assert!(day.contains(at(12, 0)));
+    assert!(day.contains(at(13, 29)));
+    assert!(!day.contains(at(13, 30)));
+}
+
+#[test]
+fn windows_wrap_past_midnight() {
+    let night = Rule::new(at(22, 0), at(6, 0), 1).unwrap();
+    assert!(night.contains(at(22, 0)));
+    assert!(night.contains(at(23, 59)));
+    assert!(night.contains(0));
+    assert!(night.contains(at(5, 59)));
+    assert!(!night.contains(at(6, 0)));
+    assert!(!night.contains(at(21, 59)));
+}
+
+#[test]
+fn invalid_rules_are_refused() {
+    assert_eq!(Rule::new(at(8, 0), at(8, 0), 0), None);
+    assert_eq!(Rule::new(1440, 0, 0), None);
+    assert_eq!(Rule::new(0, 1440, 0), None);
+    assert_eq!(Rule::new(0, 60, 99), None);
+}
+
+#[test]
+fn times_and_windows_parse_from_the_shell_form() {
+    assert_eq!(parse_clock("22:00"), Some(at(22, 0)));
+    assert_eq!(parse_clock("6:05"), Some(at(6, 5)));
+    assert_eq!(parse_clock("00:00"), Some(0));
+    assert_eq!(parse_clock("23:59"), Some(at(23, 59)));
+    for bad in [
+        "24:00", "12:60", "12", "12:5", ":30", "123:00", "+1:00", "ab:cd",
+    ] {
+        assert_eq!(parse_clock(bad), None, "{bad}");
+    }
+    assert_eq!(parse_window("22:00-06:00"), Some((at(22, 0), at(6, 0))));
+    assert_eq!(parse_window("22:00"), None);
+    assert_eq!(parse_window("22:00-6"), None);
+}
+
+#[test]
+fn the_first_matching_slot_wins() {
+    let mut schedule = schedule();
+    assert_eq!(schedule.active(at(23, 0)).map(|(slot, _)| slot), Some(0));
+    assert_eq!(schedule.active(at(12, 15)).map(|(slot, _)| slot), Some(1));
+    assert_eq!(schedule.active(at(9, 0)), None);
+
+    // An overlapping rule in the first slot takes priority
+    schedule.set(0, Rule::new(at(11, 0), at(14, 0), 2));
+    assert_eq!(schedule.active(at(12, 15)).map(|(slot, _)| slot), Some(0));
+    schedule.set(0, None);
+    assert_eq!(schedule.active(at(23, 0)), None);
+}
+
+#[test]
+fn schedules_round_trip_through_their_encoding() {
+    let schedule = schedule();
+    assert_eq!(Schedule::decode(&schedule.encode()), schedule);
+    assert_eq!(
+        Schedule::decode(&Schedule::default().encode()),
+        Schedule::default()
+    );
+    // Erased or nonsensical slots decode as empty, short input as missing
+    assert_eq!(Schedule::decode(&[0xFF; 8]), Schedule::default());
+    assert_eq!(Schedule::decode(&[0; 8]), Schedule::default());
+    assert_eq!(Schedule::decode(&schedule.encode()[..4]).rule(1), None);
+    assert_eq!(
+        Schedule::decode(&schedule.encode()[..4]).rule(0),
+        schedule.rule(0)
+    );
+}
+
+#[test]
+fn rules_display_as_the_shell_writes_them() {
+    let rule = schedule().rule(0).unwrap();
+    assert_eq!(rule.to_string(), "22:00-06:00 heartbeat");
+}
+
+#[test]
+fn the_scheduler_switches_only_when_the_active_rule_changes() {
+    let schedule = schedule();
+    let mut scheduler = Scheduler::new();
+    // Booting outside every window leaves the stored pattern alone
+    assert_eq!(scheduler.update(&schedule, at(21, 58)), None);
+    assert_eq!(scheduler.update(&schedule, at(21, 59)), None);
+    assert_eq!(
+        scheduler.update(&schedule, at(22, 0)),
+        Some(Switch::Enter { slot: 0, preset: 1 })
+    );
+    // Staying inside the window (even across midnight) does nothing
+    assert_eq!(scheduler.update(&schedule, at(23, 59)), None);
+    assert_eq!(scheduler.update(&schedule, 0), None);
+    assert_eq!(scheduler.update(&schedule, at(6, 0)), Some(Switch::Leave));
+    assert_eq!(scheduler.update(&schedule, at(6, 1)), None);
+}
+
+#[test]
+fn the_scheduler_follows_edits_and_clock_jumps() {
+    let mut schedule = schedule();
+    let mut scheduler = Scheduler::new();
+    // Booting inside a window switches straight away
+    assert_eq!(
+        scheduler.update(&schedule, at(12, 30)),
+        Some(Switch::Enter { slot: 1, preset: 3 })
+    );
+    // Editing the active rule applies the new preset
+    schedule.set(1, Rule::new(at(12, 0), at(13, 30), 2));
+    assert_eq!(
+        scheduler.update(&schedule, at(12, 31)),
+        Some(Switch::Enter { slot: 1, preset: 2 })
+    );
+    // Jumping from one window straight into another enters the new one
+    assert_eq!(
+        scheduler.update(&schedule, at(23, 0)),
+        Some(Switch::Enter { slot: 0, preset: 1 })
+    );
+    // Clearing the active rule closes its window
+    schedule.set(0, None);
+    assert_eq!(scheduler.update(&schedule, at(23, 1)), Some(Switch::Leave));
+}
@@ -0,0 +1,99 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Temperature and VDDA conversion from the factory calibration
+
+use blink::sensors::{Calibration, CalibrationError, RawSample, Reading};
+
+/// F3/F4-style calibration: falling sensor voltage, taken at 3.3 V
+const FALLING: Calibration = Calibration {
+    ts_cal1: 1780,
+    ts_cal2: 1350,
+    ts_cal1_c: 30,
+    ts_cal2_c: 110,
+    vrefint_cal: 1490,
+    cal_vdda_mv: 3300,
+};
+
+/// L4/G4-style calibration: rising sensor voltage, taken at 3.0 V
+const RISING: Calibration = Calibration {
+    ts_cal1: 1034,
+    ts_cal2: 1373,
+    ts_cal1_c: 30,
+    ts_cal2_c: 130,
+    vrefint_cal: 1655,
+    cal_vdda_mv: 3000,
+};
+
+#[test]
+fn vrefint_gives_the_supply_voltage() {
+    assert_eq!(FALLING.vdda_mv(1490), Some(3300));
+    assert_eq!(FALLING.vdda_mv(1639), Some(3000));
+    assert_eq!(RISING.vdda_mv(1505), Some(3299));
+    assert_eq!(FALLING.vdda_mv(0), None);
+}
+
+#[test]
+fn calibration_points_map_to_their_temperatures() {
+    assert_eq!(FALLING.temperature_centi_c(1780, 3300), 3000);
+    assert_eq!(FALLING.temperature_centi_c(1350, 3300), 11_000);
+    assert_eq!(FALLING.temperature_centi_c(1565, 3300), 7000);
+    assert_eq!(RISING.temperature_centi_c(1034, 3000), 3000);
+    assert_eq!(RISING.temperature_centi_c(1373, 3000), 13_000);
+    // Below the first point the line is extrapolated
+    assert_eq!(RISING.temperature_centi_c(1013, 3000), 2381);
+}
+
+#[test]
+fn readings_are_rescaled_to_the_calibration_supply() {
+    // The same sensor voltage reads higher when VDDA is lower
+    assert_eq!(FALLING.temperature_centi_c(1720, 3000), 7025);
+    let reading = FALLING.convert(RawSample {
+        vrefint: 1639,
+        temperature: 1720,
+    });
+    assert_eq!(
+        reading,
+        Some(Reading {
+            vdda_mv: 3000,
+            temperature_centi_c: 7025,
+        })
+    );
+    assert_eq!(FALLING.convert(RawSample::default()), None);
+}
+
+#[test]
+fn unusable_calibrations_are_rejected() {
+    assert_eq!(FALLING.check(), Ok(()));
+    assert_eq!(RISING.check(), Ok(()));
+    let erased = Calibration {
+        ts_cal2: 0xFFFF,
+        ..FALLING
+    };
+    assert_eq!(erased.check(), Err(CalibrationError::Missing));
+    let blank = Calibration {
+        vrefint_cal: 0,
+        ..RISING
+    };
+    assert_eq!(blank.check(), Err(CalibrationError::Missing));
+    let flat = Calibration {
+        ts_cal2: FALLING.ts_cal1,
+        ..FALLING
+    };
+    assert_eq!(flat.check(), Err(CalibrationError::Flat));
+}
+
+#[test]
+fn readings_format_for_the_status_line() {
+    let reading = |vdda_mv, temperature_centi_c| {
+        Reading {
+            vdda_mv,
+            temperature_centi_c,
+        }
+        .to_string()
+    };
+    assert_eq!(reading(3287, 3141), "temp 31.4 C, vdda 3.29 V");
+    assert_eq!(reading(3295, 2996), "temp 30.0 C, vdda 3.30 V");
+    assert_eq!(reading(2998, -1234), "temp -12.3 C, vdda 3.00 V");
+    assert_eq!(reading(3000, -6), "temp -0.1 C, vdda 3.00 V");
+}
@@ -0,0 +1,104 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Formatted UART output and failure accounting in both directions
+
+use std::future::Future;
+use std::pin::pin;
+use std::task::{Context, Poll, Waker};
+
+use blink::mock::MockSerial;
+use blink::monitor::{RxMonitor, TxMonitor};
+use blink::serial::{ReadAsync, UartError, WriteBlocking};
+use blink::{uformat, uprint, uprintln};
+
+/// Polls a future that never waits (all mock I/O completes immediately)
+fn block_on<F: Future>(fut: F) -> F::Output {
+    let mut fut = pin!(fut);
+    let mut cx = Context::from_waker(Waker::noop());
+    match fut.as_mut().poll(&mut cx) {
+        Poll::Ready(out) => out,
+        Poll::Pending => panic!("mock future did not complete"),
+    }
+}
+
+#[test]
+fn uprintln_appends_crlf_and_handles_long_lines() {
+    let mut tx = MockSerial::new();
+    let long = "a".repeat(150);
+    uprintln!(&mut tx, "x={} {}", 5, long).unwrap();
+    uprint!(&mut tx, "> ").unwrap();
+
+    assert_eq!(tx.output_str(), format!("x=5 {long}\r\n> "));
+    // Staged in `FMT_BUFFER` chunks: four for the line, one for the prompt
+    assert_eq!(tx.writes(), 5);
+}
+
+#[test]
+fn uprintln_reports_the_uart_error() {
+    let mut tx = MockSerial::new();
+    tx.fail_next(UartError::Overrun);
+    assert_eq!(uprintln!(&mut tx, "lost"), Err(UartError::Overrun));
+}
+
+#[test]
+fn uformat_truncates_to_capacity() {
+    assert_eq!(uformat!(8, "{}-{}", 12, "abc").as_str(), "12-abc");
+    assert_eq!(uformat!(4, "{}", "abcdef").as_str(), "");
+}
+
+#[test]
+fn monitor_counts_failures_per_kind() {
+    let mut tx = MockSerial::new();
+    tx.fail_next(UartError::Timeout);
+    tx.fail_next(UartError::Timeout);
+    tx.fail_next(UartError::Framing);
+    let mut monitor = TxMonitor::new(tx);
+    for _ in 0..4 {
+        block_on(monitor.send(b"x"));
+    }
+
+    assert_eq!(monitor.errors().timeout, 2);
+    assert_eq!(monitor.errors().framing, 1);
+    assert_eq!(monitor.errors().total(), 3);
+}
+
+#[test]
+fn monitor_counts_blocking_writes_too() {
+    let mut tx = MockSerial::new();
+    tx.fail_next(UartError::Noise);
+    let mut monitor = TxMonitor::new(tx);
+    assert_eq!(monitor.blocking_write(b"x"), Err(UartError::Noise));
+    assert_eq!(monitor.errors().noise, 1);
+}
+
+#[test]
+fn mock_serial_replays_fed_input() {
+    let mut rx = MockSerial::new();
+    rx.feed(b"hi");
+    rx.fail_next_read(UartError::Overrun);
+    let mut buf = [0; 4];
+    assert_eq!(block_on(rx.read(&mut buf)), Err(UartError::Overrun));
+    assert_eq!(block_on(rx.read(&mut buf)), Ok(2));
+    assert_eq!(&buf[..2], b"hi");
+    assert_eq!(block_on(rx.read(&mut buf)), Ok(0));
+}
+
+#[test]
+fn monitor_counts_receive_faults() {
+    let mut rx = MockSerial::new();
+    rx.fail_next_read(UartError::Overrun);
+    rx.fail_next_read(UartError::Framing);
+    rx.feed(b"ok");
+    let mut monitor = RxMonitor::new(rx);
+    let mut buf = [0u8; 8];
+
+    // Each fault costs one read and is counted; then reception resumes
+    assert_eq!(block_on(monitor.receive(&mut buf)), 0);
+    assert_eq!(block_on(monitor.receive(&mut buf)), 0);
+    assert_eq!(block_on(monitor.receive(&mut buf)), 2);
+    assert_eq!(&buf[..2], b"ok");
+    assert_eq!(monitor.errors().overrun, 1);
+    assert_eq!(monitor.errors().framing, 1);
+    assert_eq!(monitor.errors().total(), 2);
+}
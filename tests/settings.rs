@@ -0,0 +1,344 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Settings record log on the in-memory flash model
+
+use blink::crc::crc16;
+use blink::mock::MockFlash;
+use blink::schedule::{Rule, Schedule, MAX_RULES};
+use blink::settings::{
+    Geometry, Settings, SettingsFlash, SettingsStore, StoreError, LEGACY_RECORD_SIZE, RECORD_SIZE,
+    SCHEMA_VERSION,
+};
+
+const PAGE: u32 = 512;
+const GEOMETRY: Geometry = Geometry {
+    base: 0x7_F000,
+    page_size: PAGE,
+};
+
+type Flash = MockFlash<{ 2 * PAGE as usize }>;
+
+fn flash() -> Flash {
+    MockFlash::new(GEOMETRY.base, PAGE)
+}
+
+/// Builds a raw `N`-byte record, as this or an older firmware would write it
+fn raw_record<const N: usize>(schema: u8, seq: u32, payload: &[u8]) -> [u8; N] {
+    let mut rec = [0u8; N];
+    rec[0..2].copy_from_slice(&0x5345u16.to_le_bytes());
+    rec[2] = schema;
+    rec[3] = payload.len() as u8;
+    rec[4..8].copy_from_slice(&seq.to_le_bytes());
+    rec[8..8 + payload.len()].copy_from_slice(payload);
+    seal(&mut rec);
+    rec
+}
+
+/// Recomputes the CRC in the last two bytes of `rec`
+fn seal(rec: &mut [u8]) {
+    let end = rec.len() - 2;
+    let crc = crc16(&rec[..end]);
+    rec[end..].copy_from_slice(&crc.to_le_bytes());
+}
+
+/// A schedule with a heartbeat rule from 22:00 to 06:00 in the second slot
+fn night_rule() -> Schedule {
+    let mut schedule = Schedule::default();
+    schedule.set(1, Rule::new(22 * 60, 6 * 60, 1));
+    schedule
+}
+
+#[test]
+fn crc_matches_the_ccitt_false_check_value() {
+    assert_eq!(crc16(b"123456789"), 0x29B1);
+}
+
+#[test]
+fn blank_flash_loads_defaults() {
+    let mut store = SettingsStore::new(flash(), GEOMETRY);
+    assert_eq!(store.load(), Settings::default());
+}
+
+#[test]
+fn saved_settings_survive_a_reload() {
+    let mut flash = flash();
+    let saved = Settings {
+        blink_ms: 250,
+        baud_rate: 9600,
+        pattern: 2,
+        boot_count: 7,
+        schedule: night_rule(),
+    };
+    SettingsStore::new(&mut flash, GEOMETRY)
+        .save(&saved)
+        .unwrap();
+
+    let mut store = SettingsStore::new(&mut flash, GEOMETRY);
+    assert_eq!(store.load(), saved);
+}
+
+#[test]
+fn unchanged_settings_are_not_rewritten() {
+    let mut flash = flash();
+    let mut store = SettingsStore::new(&mut flash, GEOMETRY);
+    store.load();
+    let settings = Settings {
+        boot_count: 1,
+        ..Settings::default()
+    };
+    store.save(&settings).unwrap();
+    store.save(&settings).unwrap();
+
+    let used = flash
+        .data()
+        .chunks(RECORD_SIZE)
+        .filter(|r| r[0] != 0xFF)
+        .count();
+    assert_eq!(used, 1);
+}
+
+#[test]
+fn log_wraps_between_pages_across_reloads() {
+    let mut flash = flash();
+    let slots = PAGE as usize / RECORD_SIZE;
+    let saves = 5 * slots as u32 + 3;
+    for boot in 0..saves {
+        let mut store = SettingsStore::new(&mut flash, GEOMETRY);
+        let mut settings = store.load();
+        assert_eq!(settings.boot_count, boot);
+        settings.boot_count += 1;
+        store.save(&settings).unwrap();
+    }
+    // One erase per page switch (plus the first page), never one per save
+    assert_eq!(flash.erases(), 6);
+}
+
+#[test]
+fn corrupt_newest_record_falls_back_to_the_previous_one() {
+    let mut flash = flash();
+    let mut store = SettingsStore::new(&mut flash, GEOMETRY);
+    store.load();
+    for boot in 1..=3 {
+        store
+            .save(&Settings {
+                boot_count: boot,
+                ..Settings::default()
+            })
+            .unwrap();
+    }
+
+    // Flip a payload bit in the third record
+    flash.data_mut()[2 * RECORD_SIZE + 10] ^= 0x01;
+    let mut store = SettingsStore::new(&mut flash, GEOMETRY);
+    assert_eq!(store.load().boot_count, 2);
+}
+
+#[test]
+fn failed_write_keeps_the_previous_settings() {
+    let mut flash = flash();
+    let mut store = SettingsStore::new(&mut flash, GEOMETRY);
+    store.load();
+    let first = Settings {
+        boot_count: 1,
+        ..Settings::default()
+    };
+    store.save(&first).unwrap();
+
+    flash.fail_writes(true);
+    let mut store = SettingsStore::new(&mut flash, GEOMETRY);
+    store.load();
+    let second = Settings {
+        boot_count: 2,
+        ..first
+    };
+    assert_eq!(store.save(&second), Err(StoreError::Flash));
+    assert_eq!(store.current(), first);
+
+    flash.fail_writes(false);
+    assert_eq!(SettingsStore::new(&mut flash, GEOMETRY).load(), first);
+}
+
+#[test]
+fn shorter_payloads_decode_with_defaults() {
+    let full = Settings {
+        blink_ms: 123,
+        baud_rate: 57_600,
+        pattern: 1,
+        boot_count: 99,
+        schedule: night_rule(),
+    };
+    let encoded = full.encode();
+    // A schema-0 record without `boot_count`
+    let old = Settings::decode(&encoded[..9]);
+    assert_eq!(
+        old,
+        Settings {
+            boot_count: Settings::default().boot_count,
+            schedule: Schedule::default(),
+            ..full
+        }
+    );
+    // A schema-1 record without the schedule
+    let old = Settings::decode(&encoded[..13]);
+    assert_eq!(
+        old,
+        Settings {
+            schedule: Schedule::default(),
+            ..full
+        }
+    );
+    // A schema-2 record with two rule slots
+    let mut many = full;
+    many.schedule.set(MAX_RULES - 1, Rule::new(0, 60, 3));
+    let old = Settings::decode(&many.encode()[..21]);
+    assert_eq!(old, full);
+    // Trailing bytes from a newer schema are ignored
+    let mut newer = encoded.to_vec();
+    newer.extend_from_slice(&[1, 2, 3]);
+    assert_eq!(Settings::decode(&newer), full);
+}
+
+#[test]
+fn old_schema_log_is_migrated_to_the_other_page() {
+    let mut flash = flash();
+    let legacy = Settings {
+        blink_ms: 300,
+        baud_rate: 9600,
+        pattern: 1,
+        boot_count: 41,
+        schedule: night_rule(),
+    };
+    // A schema-1 record, then a schema-2 one with the first two rule slots
+    let payload = legacy.encode();
+    let old = [
+        raw_record::<LEGACY_RECORD_SIZE>(1, 7, &payload[..13]),
+        raw_record::<LEGACY_RECORD_SIZE>(2, 8, &payload[..21]),
+    ];
+    for (slot, rec) in old.iter().enumerate() {
+        let offset = GEOMETRY.base + (slot * LEGACY_RECORD_SIZE) as u32;
+        flash.write(offset, rec).unwrap();
+    }
+
+    let mut store = SettingsStore::new(&mut flash, GEOMETRY);
+    let mut settings = store.load();
+    assert_eq!(settings, legacy);
+    settings.boot_count += 1;
+    store.save(&settings).unwrap();
+
+    // Written to the other page at the current schema and the next sequence;
+    // the old log is left as it was
+    let page = PAGE as usize;
+    let rec = &flash.data()[page..page + RECORD_SIZE];
+    assert_eq!(rec[2], SCHEMA_VERSION);
+    assert_eq!(usize::from(rec[3]), Settings::PAYLOAD_LEN);
+    assert_eq!(rec[4..8], 9u32.to_le_bytes());
+    assert_eq!(flash.data()[..LEGACY_RECORD_SIZE], old[0]);
+    assert_eq!(flash.erases(), 1);
+
+    let mut store = SettingsStore::new(&mut flash, GEOMETRY);
+    assert_eq!(store.load(), settings);
+    // Later saves append to the new log
+    settings.boot_count += 1;
+    store.save(&settings).unwrap();
+    assert_eq!(flash.data()[page + RECORD_SIZE + 2], SCHEMA_VERSION);
+    assert_eq!(SettingsStore::new(&mut flash, GEOMETRY).load(), settings);
+}
+
+#[test]
+fn every_rule_slot_survives_a_reload() {
+    let mut flash = flash();
+    let mut schedule = Schedule::default();
+    for slot in 0..MAX_RULES {
+        let start = slot as u16 * 60;
+        schedule.set(slot, Rule::new(start, start + 30, slot % 4));
+    }
+    let saved = Settings {
+        schedule,
+        ..Settings::default()
+    };
+    SettingsStore::new(&mut flash, GEOMETRY)
+        .save(&saved)
+        .unwrap();
+    assert_eq!(SettingsStore::new(&mut flash, GEOMETRY).load(), saved);
+}
+
+#[test]
+fn records_with_a_bad_header_are_skipped() {
+    let mut flash = flash();
+    let payload = Settings {
+        boot_count: 5,
+        ..Settings::default()
+    }
+    .encode();
+    let good = raw_record::<RECORD_SIZE>(SCHEMA_VERSION, 1, &payload);
+    // A higher sequence number that no longer matches its CRC
+    let mut bad_seq = raw_record::<RECORD_SIZE>(SCHEMA_VERSION, 2, &payload);
+    bad_seq[4] = 9;
+    // A valid CRC over a bad magic, an oversized length, and schemas that
+    // never used this record size
+    let mut bad_magic = raw_record::<RECORD_SIZE>(SCHEMA_VERSION, 3, &payload);
+    bad_magic[0] = 0;
+    seal(&mut bad_magic);
+    let mut too_long = raw_record::<RECORD_SIZE>(SCHEMA_VERSION, 4, &payload);
+    too_long[3] = (RECORD_SIZE - 9) as u8;
+    seal(&mut too_long);
+    let schema_zero = raw_record::<RECORD_SIZE>(0, 5, &payload);
+    let schema_two = raw_record::<RECORD_SIZE>(2, 6, &payload);
+    for (slot, rec) in [good, bad_seq, bad_magic, too_long, schema_zero, schema_two]
+        .iter()
+        .enumerate()
+    {
+        let offset = GEOMETRY.base + (slot * RECORD_SIZE) as u32;
+        flash.write(offset, rec).unwrap();
+    }
+
+    let mut store = SettingsStore::new(&mut flash, GEOMETRY);
+    assert_eq!(store.load().boot_count, 5);
+    // The next save goes after the skipped slots, not over them
+    store
+        .save(&Settings {
+            boot_count: 6,
+            ..Settings::default()
+        })
+        .unwrap();
+    assert_eq!(flash.data()[6 * RECORD_SIZE + 2], SCHEMA_VERSION);
+    assert_eq!(
+        SettingsStore::new(&mut flash, GEOMETRY).load().boot_count,
+        6
+    );
+}
+
+#[test]
+fn power_cut_while_switching_pages_keeps_the_last_record() {
+    let slots = PAGE as usize / RECORD_SIZE;
+    // Cut during the erase of the next page, then during the first write to it
+    for cut in 0..2 {
+        let mut flash = flash();
+        let mut store = SettingsStore::new(&mut flash, GEOMETRY);
+        store.load();
+        for boot in 1..=slots as u32 {
+            store
+                .save(&Settings {
+                    boot_count: boot,
+                    ..Settings::default()
+                })
+                .unwrap();
+        }
+
+        flash.cut_power_after(cut);
+        let mut store = SettingsStore::new(&mut flash, GEOMETRY);
+        store.load();
+        let next = Settings {
+            boot_count: slots as u32 + 1,
+            ..Settings::default()
+        };
+        assert_eq!(store.save(&next), Err(StoreError::Flash));
+
+        flash.restore_power();
+        let mut store = SettingsStore::new(&mut flash, GEOMETRY);
+        assert_eq!(store.load().boot_count, slots as u32);
+        store.save(&next).unwrap();
+        assert_eq!(SettingsStore::new(&mut flash, GEOMETRY).load(), next);
+    }
+}
@@ -0,0 +1,261 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Line assembly and command parsing for the UART shell
+
+use blink::brightness::Effect;
+use blink::clock::DateTime;
+use blink::messages;
+use blink::morse::MorseTiming;
+use blink::schedule::{Rule, MAX_RULES};
+use blink::shell::{
+    parse, tokenize, Command, LineBuffer, LineError, ParseError, MAX_LINE, MAX_TOKENS,
+};
+
+/// Feeds `bytes` and returns the line completed by the last byte, if any
+fn feed<const N: usize>(
+    buf: &mut LineBuffer<N>,
+    bytes: &[u8],
+) -> Result<Option<String>, LineError> {
+    let mut last = Ok(None);
+    for &b in bytes {
+        last = buf.push(b).map(|line| line.map(str::to_owned));
+    }
+    last
+}
+
+#[test]
+fn lines_end_on_cr_or_lf() {
+    let mut buf = LineBuffer::<16>::new();
+    assert_eq!(feed(&mut buf, b"led on\r"), Ok(Some("led on".into())));
+    // The LF of a CR+LF pair produces no empty line
+    assert_eq!(feed(&mut buf, b"\n"), Ok(None));
+    assert_eq!(feed(&mut buf, b"status\n"), Ok(Some("status".into())));
+}
+
+#[test]
+fn backspace_removes_the_last_byte() {
+    let mut buf = LineBuffer::<16>::new();
+    assert_eq!(feed(&mut buf, b"ab\x7fc\x08d\r"), Ok(Some("ad".into())));
+}
+
+#[test]
+fn overlong_lines_are_dropped_whole() {
+    let mut buf = LineBuffer::<8>::new();
+    assert_eq!(feed(&mut buf, b"123456789\r"), Err(LineError::TooLong));
+    assert_eq!(feed(&mut buf, b"help\r"), Ok(Some("help".into())));
+}
+
+#[test]
+fn lines_of_exactly_max_line_bytes_are_kept() {
+    let mut buf = LineBuffer::<MAX_LINE>::new();
+    let full = "x".repeat(MAX_LINE);
+    assert_eq!(
+        feed(&mut buf, format!("{full}\r").as_bytes()),
+        Ok(Some(full))
+    );
+
+    // One byte more drops the line, and everything up to its terminator
+    let over = "y".repeat(MAX_LINE + 1);
+    assert_eq!(feed(&mut buf, over.as_bytes()), Ok(None));
+    assert_eq!(feed(&mut buf, b"\x7fled on\r"), Err(LineError::TooLong));
+    assert_eq!(feed(&mut buf, b"led on\r"), Ok(Some("led on".into())));
+}
+
+#[test]
+fn tokens_split_on_runs_of_whitespace() {
+    assert!(tokenize(" \t ").unwrap().is_empty());
+    assert_eq!(
+        tokenize("  led \t blink   250 ").unwrap(),
+        ["led", "blink", "250"]
+    );
+    let most = ["a"; MAX_TOKENS].join(" ");
+    assert_eq!(tokenize(&most).unwrap().len(), MAX_TOKENS);
+    let extra = ["a"; MAX_TOKENS + 1].join(" ");
+    assert_eq!(tokenize(&extra), Err(ParseError::TooManyArguments));
+}
+
+#[test]
+fn unknown_commands_are_rejected_whatever_follows() {
+    for line in [
+        "leds on",
+        "statu",
+        "help2",
+        "reboot now",
+        "-",
+        "\u{e9}t\u{e9}",
+    ] {
+        assert_eq!(parse(line), Err(ParseError::UnknownCommand), "{line:?}");
+    }
+}
+
+#[test]
+fn commands_parse_case_insensitively() {
+    assert_eq!(parse(" help "), Ok(Command::Help));
+    assert_eq!(parse("?"), Ok(Command::Help));
+    assert_eq!(parse("LED On"), Ok(Command::LedOn));
+    assert_eq!(parse("led off"), Ok(Command::LedOff));
+    assert_eq!(parse("led BLINK 100"), Ok(Command::LedBlink(100)));
+    assert_eq!(parse("status"), Ok(Command::Status));
+    assert_eq!(parse("Version"), Ok(Command::Version));
+    assert_eq!(parse("baud 9600"), Ok(Command::Baud(9600)));
+    assert_eq!(parse("reset"), Ok(Command::Reset));
+    assert_eq!(parse("UPDATE"), Ok(Command::Update));
+}
+
+#[test]
+fn bad_input_maps_to_specific_errors() {
+    assert_eq!(parse("   "), Err(ParseError::Empty));
+    assert_eq!(parse("blink"), Err(ParseError::UnknownCommand));
+    assert_eq!(parse("led"), Err(ParseError::MissingArgument));
+    assert_eq!(parse("led blink"), Err(ParseError::MissingArgument));
+    assert_eq!(parse("led glow"), Err(ParseError::InvalidArgument));
+    assert_eq!(parse("led blink 5"), Err(ParseError::InvalidArgument));
+    assert_eq!(parse("led blink 60001"), Err(ParseError::InvalidArgument));
+    assert_eq!(parse("baud 12345"), Err(ParseError::InvalidArgument));
+    assert_eq!(parse("status now"), Err(ParseError::TooManyArguments));
+    assert_eq!(parse("version 2"), Err(ParseError::TooManyArguments));
+    assert_eq!(parse("update now"), Err(ParseError::TooManyArguments));
+    assert_eq!(parse("a b c d e"), Err(ParseError::TooManyArguments));
+}
+
+#[test]
+fn brightness_effects_parse_with_range_checks() {
+    assert_eq!(
+        parse("led breathe 3000"),
+        Ok(Command::LedEffect(Effect::Breathe { period_ms: 3000 }))
+    );
+    assert_eq!(
+        parse("led FADE In 500"),
+        Ok(Command::LedEffect(Effect::FadeIn { ms: 500 }))
+    );
+    assert_eq!(
+        parse("led fade out 100"),
+        Ok(Command::LedEffect(Effect::FadeOut { ms: 100 }))
+    );
+    assert_eq!(
+        parse("led dim 100"),
+        Ok(Command::LedEffect(Effect::Dim { level: 255 }))
+    );
+    assert_eq!(parse("led dim"), Err(ParseError::MissingArgument));
+    assert_eq!(parse("led dim 101"), Err(ParseError::InvalidArgument));
+    assert_eq!(parse("led breathe 99"), Err(ParseError::InvalidArgument));
+    assert_eq!(parse("led fade in"), Err(ParseError::MissingArgument));
+    assert_eq!(parse("led fade up 500"), Err(ParseError::InvalidArgument));
+    assert_eq!(
+        parse("led fade in 500 x"),
+        Err(ParseError::TooManyArguments)
+    );
+}
+
+#[test]
+fn morse_takes_free_text_and_wpm_checks_speeds() {
+    assert_eq!(
+        parse("  MORSE hello big wide world  "),
+        Ok(Command::Morse(Some("hello big wide world")))
+    );
+    assert_eq!(parse("morse"), Ok(Command::Morse(None)));
+    assert_eq!(parse("morse 100%"), Err(ParseError::InvalidArgument));
+    assert_eq!(parse("morsel"), Err(ParseError::UnknownCommand));
+    assert_eq!(
+        parse("wpm 18"),
+        Ok(Command::Wpm(MorseTiming {
+            wpm: 18,
+            farnsworth_wpm: 18
+        }))
+    );
+    assert_eq!(
+        parse("wpm 20 8"),
+        Ok(Command::Wpm(MorseTiming {
+            wpm: 20,
+            farnsworth_wpm: 8
+        }))
+    );
+    assert_eq!(parse("wpm 10 20"), Err(ParseError::InvalidArgument));
+    assert_eq!(parse("wpm 4"), Err(ParseError::InvalidArgument));
+    assert_eq!(parse("wpm"), Err(ParseError::MissingArgument));
+}
+
+#[test]
+fn presets_are_selected_by_name() {
+    assert_eq!(parse("led pattern steady"), Ok(Command::LedPattern(0)));
+    assert_eq!(parse("led pattern SOS"), Ok(Command::LedPattern(3)));
+    assert_eq!(parse("led pattern"), Err(ParseError::MissingArgument));
+    assert_eq!(parse("led pattern disco"), Err(ParseError::InvalidArgument));
+}
+
+#[test]
+fn time_reads_or_sets_the_rtc() {
+    let noon = DateTime::new(2025, 6, 1, 12, 0, 0).unwrap();
+    assert_eq!(parse("time"), Ok(Command::TimeGet));
+    assert_eq!(parse("TIME GET"), Ok(Command::TimeGet));
+    assert_eq!(
+        parse("time set 2025-06-01T12:00:00Z"),
+        Ok(Command::TimeSet(noon))
+    );
+    assert_eq!(
+        parse("time set 2025-06-01t12:00:00"),
+        Ok(Command::TimeSet(noon))
+    );
+    assert_eq!(parse("time set"), Err(ParseError::MissingArgument));
+    assert_eq!(
+        parse("time set 2025-06-01 12:00:00"),
+        Err(ParseError::TooManyArguments)
+    );
+    assert_eq!(
+        parse("time set 2025-02-30T12:00:00Z"),
+        Err(ParseError::InvalidArgument)
+    );
+    // Valid dates the RTC's two-digit year cannot hold are refused too
+    assert_eq!(
+        parse("time set 1999-12-31T23:59:59Z"),
+        Err(ParseError::InvalidArgument)
+    );
+    assert_eq!(parse("time get now"), Err(ParseError::TooManyArguments));
+    assert_eq!(parse("time zone"), Err(ParseError::InvalidArgument));
+}
+
+#[test]
+fn schedule_rules_are_set_listed_and_cleared() {
+    let night = Rule::new(22 * 60, 6 * 60, 1).unwrap();
+    assert_eq!(parse("schedule"), Ok(Command::ScheduleList));
+    assert_eq!(
+        parse("schedule 1 22:00-06:00 heartbeat"),
+        Ok(Command::ScheduleSet(0, night))
+    );
+    assert_eq!(
+        parse("SCHEDULE 2 22:00-06:00 HEARTBEAT"),
+        Ok(Command::ScheduleSet(1, night))
+    );
+    assert_eq!(parse("schedule 2 off"), Ok(Command::ScheduleClear(1)));
+    assert_eq!(parse("schedule 1"), Err(ParseError::MissingArgument));
+    assert_eq!(
+        parse("schedule 1 22:00-06:00"),
+        Err(ParseError::MissingArgument)
+    );
+    assert_eq!(parse("schedule 8 off"), Ok(Command::ScheduleClear(7)));
+    assert_eq!(parse("schedule 0 off"), Err(ParseError::NoSuchRule));
+    assert_eq!(parse("schedule 9 off"), Err(ParseError::NoSuchRule));
+    assert_eq!(
+        parse("schedule 1 22:00-22:00 sos"),
+        Err(ParseError::InvalidArgument)
+    );
+    assert_eq!(
+        parse("schedule 1 25:00-06:00 sos"),
+        Err(ParseError::InvalidArgument)
+    );
+    assert_eq!(
+        parse("schedule 1 22:00-06:00 disco"),
+        Err(ParseError::InvalidArgument)
+    );
+}
+
+#[test]
+fn help_and_errors_state_the_rule_limit() {
+    let range = format!("1 to {MAX_RULES}");
+    let help = String::from_utf8_lossy(messages::HELP);
+    let error = String::from_utf8_lossy(messages::ERR_NO_SUCH_RULE);
+    assert!(help.contains(&range), "{help}");
+    assert!(help.contains("times in UTC"));
+    assert!(error.contains(&range), "{error}");
+}
@@ -0,0 +1,91 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Task supervision, reset-cause decoding and crash records
+
+use blink::crash::{CrashKind, CrashRecord, FaultRegisters, FILE_LEN, MESSAGE_LEN};
+use blink::supervisor::{ResetCause, ResetFlags, Supervisor};
+
+#[test]
+fn supervisor_waits_for_every_registered_task() {
+    let sup = Supervisor::new();
+    let a = sup.register().unwrap();
+    let b = sup.register().unwrap();
+
+    sup.check_in(a);
+    assert_eq!(sup.poll(), Err(0b10));
+    sup.check_in(b);
+    assert_eq!(sup.poll(), Ok(()));
+    // Each round starts over
+    assert_eq!(sup.poll(), Err(0b11));
+}
+
+#[test]
+fn supervisor_limits_registrations() {
+    let sup = Supervisor::new();
+    for _ in 0..32 {
+        assert!(sup.register().is_some());
+    }
+    assert!(sup.register().is_none());
+}
+
+#[test]
+fn power_on_wins_over_other_reset_flags() {
+    let flags = ResetFlags {
+        power_on: true,
+        pin: true,
+        ..ResetFlags::default()
+    };
+    assert_eq!(ResetCause::from_flags(flags), ResetCause::PowerOn);
+
+    let watchdog = ResetFlags {
+        iwdg: true,
+        pin: true,
+        ..ResetFlags::default()
+    };
+    assert_eq!(
+        ResetCause::from_flags(watchdog),
+        ResetCause::IndependentWatchdog
+    );
+    assert_eq!(
+        ResetCause::from_flags(ResetFlags::default()),
+        ResetCause::Unknown
+    );
+}
+
+#[test]
+fn panic_record_truncates_message_and_keeps_file_tail() {
+    let long = "a".repeat(MESSAGE_LEN + 10);
+    let file = format!("{}/src/firmware.rs", "x".repeat(FILE_LEN));
+    let rec = CrashRecord::panic(format_args!("{long}"), Some((&file, 42, 7)));
+
+    assert!(rec.is_valid());
+    assert_eq!(rec.kind(), CrashKind::Panic);
+    assert_eq!(rec.message().len(), MESSAGE_LEN);
+    let (kept, line, column) = rec.location().unwrap();
+    assert!(kept.ends_with("/src/firmware.rs"));
+    assert_eq!(kept.len(), FILE_LEN);
+    assert_eq!((line, column), (42, 7));
+}
+
+#[test]
+fn truncation_never_splits_a_character() {
+    let msg = format!("{}é", "a".repeat(MESSAGE_LEN - 1));
+    let rec = CrashRecord::panic(format_args!("{msg}"), None);
+    assert_eq!(rec.message(), &msg[..MESSAGE_LEN - 1]);
+    assert_eq!(rec.location(), None);
+}
+
+#[test]
+fn fault_record_round_trips_registers() {
+    let regs = FaultRegisters {
+        pc: 0x0800_1234,
+        cfsr: 0x0000_8200,
+        bfar: 0x2000_FFFC,
+        ..FaultRegisters::default()
+    };
+    let rec = CrashRecord::fault(regs);
+    assert!(rec.is_valid());
+    assert_eq!(rec.kind(), CrashKind::HardFault);
+    assert_eq!(rec.registers(), &regs);
+}
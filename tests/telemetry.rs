@@ -0,0 +1,123 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! COBS encoding and telemetry frame layout
+
+use blink::app::{Led, LedMode};
+use blink::brightness::Effect;
+use blink::crc::crc16;
+use blink::mock::MockPin;
+use blink::monitor::ErrorCounters;
+use blink::pattern::presets;
+use blink::settings::Settings;
+use blink::telemetry::{
+    cobs_encode, BufferTooSmall, Record, Telemetry, MAX_FRAME, PAYLOAD_LEN, SCHEMA_VERSION,
+};
+
+/// Encodes `src`, returning the COBS bytes
+fn cobs(src: &[u8]) -> Vec<u8> {
+    let mut dst = vec![0; src.len() + src.len() / 254 + 1];
+    let len = cobs_encode(src, &mut dst).unwrap();
+    dst.truncate(len);
+    dst
+}
+
+fn record() -> Record {
+    Record {
+        timestamp_ms: 1_500,
+        uptime_ms: 1_502,
+        led_on: true,
+        led_mode: LedMode::Effect,
+        brightness: 200,
+        errors: ErrorCounters {
+            overrun: 3,
+            timeout: 0x0102_0304,
+            ..ErrorCounters::default()
+        },
+    }
+}
+
+#[test]
+fn cobs_matches_reference_vectors() {
+    assert_eq!(cobs(&[]), [0x01]);
+    assert_eq!(cobs(&[0x00]), [0x01, 0x01]);
+    assert_eq!(cobs(&[0x00, 0x00]), [0x01, 0x01, 0x01]);
+    assert_eq!(
+        cobs(&[0x11, 0x22, 0x00, 0x33]),
+        [0x03, 0x11, 0x22, 0x02, 0x33]
+    );
+    assert_eq!(
+        cobs(&[0x11, 0x00, 0x00, 0x00]),
+        [0x02, 0x11, 0x01, 0x01, 0x01]
+    );
+
+    // 254 non-zero bytes fill exactly one block
+    let long: Vec<u8> = (1..=254).collect();
+    let encoded = cobs(&long);
+    assert_eq!(encoded[0], 0xFF);
+    assert_eq!(&encoded[1..255], &long[..]);
+    assert_eq!(encoded[255..], [0x01]);
+}
+
+#[test]
+fn cobs_reports_a_short_buffer() {
+    let mut dst = [0u8; 4];
+    assert_eq!(cobs_encode(&[1, 2, 3, 4], &mut dst), Err(BufferTooSmall));
+}
+
+#[test]
+fn payload_layout_is_stable() {
+    let payload = record().to_payload(7);
+    assert_eq!(payload.len(), PAYLOAD_LEN);
+    assert_eq!(payload[..4], [SCHEMA_VERSION, 1, 2, 200]);
+    assert_eq!(payload[4..8], 7u32.to_le_bytes());
+    assert_eq!(payload[8..16], 1_500u64.to_le_bytes());
+    assert_eq!(payload[16..24], 1_502u64.to_le_bytes());
+    assert_eq!(payload[32..36], 3u32.to_le_bytes());
+    assert_eq!(payload[44..48], [0x04, 0x03, 0x02, 0x01]);
+}
+
+#[test]
+fn frames_are_delimited_zero_free_and_numbered() {
+    let mut telemetry = Telemetry::new();
+    let first = telemetry.frame(&record());
+    let second = telemetry.frame(&record());
+
+    assert!(first.len() <= MAX_FRAME);
+    assert_eq!((first[0], first[first.len() - 1]), (0, 0));
+    assert!(first[1..first.len() - 1].iter().all(|&b| b != 0));
+    assert_ne!(first, second);
+    assert_eq!(telemetry.next_seq(), 2);
+
+    // The CRC covers the payload: check it by undoing COBS by hand
+    let body = &first[1..first.len() - 1];
+    let mut decoded = Vec::new();
+    let mut i = 0;
+    while i < body.len() {
+        let code = usize::from(body[i]);
+        decoded.extend_from_slice(&body[i + 1..i + code]);
+        i += code;
+        if code < 0xFF && i < body.len() {
+            decoded.push(0);
+        }
+    }
+    let (payload, crc) = decoded.split_at(PAYLOAD_LEN);
+    assert_eq!(crc, crc16(payload).to_le_bytes());
+    assert_eq!(payload, record().to_payload(0));
+}
+
+#[test]
+fn samples_reflect_the_led() {
+    let mut led = Led::new(MockPin::new(), presets::STEADY, &Settings::default());
+    led.poll(0);
+    let lit = Record::sample(&led, 0, 4, ErrorCounters::default());
+    assert_eq!(
+        (lit.led_on, lit.led_mode, lit.brightness, lit.uptime_ms),
+        (true, LedMode::Pattern, 255, 4)
+    );
+
+    led.fade(Effect::dim_percent(40));
+    led.poll(10);
+    let dim = Record::sample(&led, 10, 10, ErrorCounters::default());
+    assert_eq!((dim.led_mode, dim.brightness), (LedMode::Effect, 102));
+}
@@ -0,0 +1,168 @@
+// Copyright (c) 2025 Kevin Thomas
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+
+//! Bootloader request framing, decoding and reply encoding
+
+use blink::crc::{crc16, crc32, crc32_update};
+use blink::telemetry::{cobs_decode, cobs_encode};
+use blink::update::{FrameReader, Info, Nak, Reply, Request, MAX_CHUNK, MAX_FRAME};
+
+/// Frames `body` the way the host does: delimiter, COBS(body | CRC), delimiter
+fn frame(body: &[u8]) -> Vec<u8> {
+    let mut raw = body.to_vec();
+    raw.extend_from_slice(&crc16(body).to_le_bytes());
+    let mut out = vec![0u8; raw.len() + raw.len() / 254 + 3];
+    let len = cobs_encode(&raw, &mut out[1..]).unwrap();
+    out.truncate(len + 1);
+    out.push(0);
+    out
+}
+
+/// Feeds `bytes` to `reader` and collects every completed frame
+fn read_all(reader: &mut FrameReader, bytes: &[u8]) -> Vec<Result<Vec<u8>, Nak>> {
+    bytes
+        .iter()
+        .filter_map(|&b| reader.push(b).map(|r| r.map(<[u8]>::to_vec)))
+        .collect()
+}
+
+#[test]
+fn crc32_matches_the_iso_hdlc_check_value() {
+    assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    assert_eq!(crc32(b""), 0);
+    // Chunked computation gives the same result
+    assert_eq!(crc32_update(crc32(b"1234"), b"56789"), 0xCBF4_3926);
+}
+
+#[test]
+fn cobs_decoding_inverts_encoding() {
+    for data in [
+        &[][..],
+        &[0],
+        &[0, 0, 1],
+        &[0x11, 0x22, 0x00, 0x33],
+        &[0xAA; 300],
+    ] {
+        let mut encoded = [0u8; 310];
+        let len = cobs_encode(data, &mut encoded).unwrap();
+        let mut decoded = [0u8; 310];
+        assert_eq!(cobs_decode(&encoded[..len], &mut decoded), Some(data.len()));
+        assert_eq!(&decoded[..data.len()], data);
+    }
+    // A length code past the end, and an output buffer that is too small
+    assert_eq!(cobs_decode(&[0x05, 1, 2], &mut [0u8; 8]), None);
+    assert_eq!(cobs_decode(&[0x04, 1, 2, 3], &mut [0u8; 2]), None);
+}
+
+#[test]
+fn requests_decode_from_frames() {
+    let mut reader = FrameReader::new();
+    let mut bytes = frame(&[0x01]);
+    let mut begin = vec![0x02];
+    begin.extend_from_slice(&1000u32.to_le_bytes());
+    begin.extend_from_slice(&0xDEAD_BEEFu32.to_le_bytes());
+    bytes.extend(frame(&begin));
+    let mut write = vec![0x03];
+    write.extend_from_slice(&256u32.to_le_bytes());
+    write.extend_from_slice(&[0, 1, 2, 0, 4]);
+    bytes.extend(frame(&write));
+    bytes.extend(frame(&[0x04]));
+    bytes.extend(frame(&[0x05]));
+
+    let frames: Vec<Vec<u8>> = read_all(&mut reader, &bytes)
+        .into_iter()
+        .map(Result::unwrap)
+        .collect();
+    let requests: Vec<Request> = frames.iter().map(|f| Request::decode(f).unwrap()).collect();
+    assert_eq!(
+        requests,
+        [
+            Request::Hello,
+            Request::Begin {
+                len: 1000,
+                crc: 0xDEAD_BEEF
+            },
+            Request::Write {
+                offset: 256,
+                data: &[0, 1, 2, 0, 4]
+            },
+            Request::Finish,
+            Request::Boot,
+        ]
+    );
+}
+
+#[test]
+fn malformed_requests_are_refused() {
+    let write = |data_len: usize| {
+        let mut body = vec![0x03, 0, 0, 0, 0];
+        body.resize(5 + data_len, 0xAB);
+        body
+    };
+    for body in [
+        vec![0x09],
+        vec![0x01, 0x00],
+        vec![0x02, 1, 2, 3],
+        write(0),
+        write(MAX_CHUNK + 1),
+    ] {
+        assert_eq!(Request::decode(&body), Err(Nak::Request), "{body:?}");
+    }
+    assert!(Request::decode(&write(MAX_CHUNK)).is_ok());
+}
+
+#[test]
+fn corrupt_frames_are_reported_and_skipped() {
+    let mut reader = FrameReader::new();
+
+    // A flipped bit fails the CRC; the next frame still decodes
+    let mut bad = frame(&[0x01]);
+    bad[2] ^= 0x40;
+    let mut bytes = bad;
+    bytes.extend(frame(&[0x04]));
+    assert_eq!(
+        read_all(&mut reader, &bytes),
+        [Err(Nak::Frame), Ok(vec![0x04])]
+    );
+
+    // Text typed at the bootloader, then back-to-back delimiters
+    let mut bytes = b"update\r".to_vec();
+    bytes.extend([0, 0, 0]);
+    assert_eq!(read_all(&mut reader, &bytes), [Err(Nak::Frame)]);
+
+    // Anything longer than the largest request is dropped as one bad frame
+    let mut bytes = vec![0x55; MAX_FRAME + 10];
+    bytes.push(0);
+    bytes.extend(frame(&[0x05]));
+    assert_eq!(
+        read_all(&mut reader, &bytes),
+        [Err(Nak::Frame), Ok(vec![0x05])]
+    );
+}
+
+#[test]
+fn replies_are_framed_with_a_crc() {
+    let decode = |reply: Reply| {
+        let frame = reply.frame();
+        assert_eq!((frame[0], frame[frame.len() - 1]), (0, 0));
+        assert!(!frame[1..frame.len() - 1].contains(&0));
+        let mut body = [0u8; 64];
+        let len = cobs_decode(&frame[1..frame.len() - 1], &mut body).unwrap();
+        let (body, crc) = body[..len].split_at(len - 2);
+        assert_eq!(crc, crc16(body).to_le_bytes());
+        body.to_vec()
+    };
+    assert_eq!(decode(Reply::Ack(0x0102_0304)), [0x82, 4, 3, 2, 1]);
+    assert_eq!(decode(Reply::Nak(Nak::Crc)), [0x83, 6]);
+    let info = Info {
+        version: 1,
+        slot_address: 0x0800_A000,
+        slot_len: 232 * 1024,
+        page_size: 2048,
+        max_chunk: 128,
+    };
+    assert_eq!(
+        decode(Reply::Info(info)),
+        [0x81, 1, 0x00, 0xA0, 0x00, 0x08, 0x00, 0xA0, 0x03, 0x00, 0x00, 0x08, 0x00, 0x00, 128, 0]
+    );
+}